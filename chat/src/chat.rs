@@ -4,6 +4,7 @@ mod name;
 mod podcount;
 
 use crate::logic::{get_current_version, Logic};
+use crate::podmsg::MessageStatus;
 use gpui::{
     actions, div, px, rgba, uniform_list, InteractiveElement, IntoElement, KeyBinding,
     ListSizingBehavior, ParentElement, Render, Styled, UniformListScrollHandle, ViewContext,
@@ -121,7 +122,7 @@ impl Render for Chat {
 
         let name_views: Vec<View<Name>> = messages
             .iter()
-            .map(|(pubkey, _)| cx.new_view(|cx| Name::new(cx, *pubkey, logic.clone())))
+            .map(|(pubkey, _, _)| cx.new_view(|cx| Name::new(cx, *pubkey, logic.clone())))
             .collect();
 
         if initial_sync {
@@ -201,13 +202,14 @@ impl Render for Chat {
                         move |_, visible_range, _| {
                             visible_range
                                 .map(|ix| {
-                                    let (_, message) = messages.get(ix).unwrap();
+                                    let (_, message, status) = messages.get(ix).unwrap();
                                     let name_view = &name_views[ix];
                                     div()
                                         .flex()
                                         .flex_row()
                                         .gap_1()
                                         .child(name_view.clone())
+                                        .child(status_badge(status))
                                         .child(message.clone())
                                         .into_any_element()
                                 })
@@ -222,3 +224,18 @@ impl Render for Chat {
         }
     }
 }
+
+fn status_badge(status: &Option<MessageStatus>) -> impl IntoElement {
+    let invalid = rgba(0xc0000080);
+    let (label, color) = match status {
+        Some(MessageStatus::Verified { sender }) => (
+            format!("verified {}", sender.chars().take(6).collect::<String>()),
+            rgba(0x00800080),
+        ),
+        Some(MessageStatus::BadSignature) => ("bad signature".to_string(), invalid),
+        Some(MessageStatus::WrongChannel) => ("wrong channel".to_string(), invalid),
+        Some(MessageStatus::MissingFields) => ("missing fields".to_string(), invalid),
+        None => ("unverified".to_string(), rgba(0x00000030)),
+    };
+    div().text_xs().text_color(color).child(label)
+}
@@ -6,6 +6,7 @@ mod pods;
 
 use auto_update::AutoUpdater;
 pub use auto_update::{get_app_path, get_current_version, is_dev};
+use babyjubjub_ark::PrivateKey;
 use constants::{NS, VL};
 use futures::StreamExt;
 use identity::Identities;
@@ -17,7 +18,10 @@ use iroh::net::key::{PublicKey, SecretKey};
 use iroh::{client::docs::LiveEvent, node::DiscoveryConfig};
 use message::SignedMessage;
 
-use persistence::{get_or_create_schnorr_secret_key, get_or_create_secret_key};
+use parcnet_pod::pod::Pod;
+use persistence::{
+    get_or_create_pod_key, get_or_create_schnorr_secret_key, get_or_create_secret_key,
+};
 use pod2::pod::entry::Entry;
 use pod2::pod::POD;
 use pod2::signature::schnorr::SchnorrSecretKey;
@@ -29,7 +33,9 @@ use std::{
     sync::{Arc, RwLock},
 };
 use tokio::sync::watch;
-use tracing::info;
+use tracing::{info, warn};
+
+use crate::podmsg::{check_message, message_body, sign_message, MessageStatus};
 
 pub use message::Message;
 
@@ -39,8 +45,9 @@ pub struct Logic {
     iroh: Arc<tokio::sync::RwLock<Option<IrohNode>>>,
     secret_key: SecretKey,
     schnorr_secret_key: SchnorrSecretKey,
+    pod_key: PrivateKey,
     doc0: Arc<tokio::sync::RwLock<Option<Doc>>>,
-    messages: RwLock<Vec<(PublicKey, Message)>>,
+    messages: RwLock<Vec<(PublicKey, Message, Option<MessageStatus>)>>,
     identities: Mutex<Identities>,
     pod_store: Arc<Mutex<PodStore>>,
     message_watch: (watch::Sender<()>, watch::Receiver<()>),
@@ -50,13 +57,15 @@ pub struct Logic {
     _auto_updater: AutoUpdater,
 }
 
-const _DOC0: &str = "6noafdqcxno4xv4ejf5xpma6gcl4gaw4w2gxsyvlp6kfwq3t6i2q";
+/// Also the channel chat pods are signed for.
+const DOC0: &str = "6noafdqcxno4xv4ejf5xpma6gcl4gaw4w2gxsyvlp6kfwq3t6i2q";
 const DOC0_TICKET: &str = "docaaacaxeh5eddy2cni7cuu5gail5gaxqqy2loiv6cghg5u45akcplu4skahswyqlad2rachperq7aesmyhoxycbsn7djsqwrn4m7yd7pkr3rxwaaa";
 
 impl Logic {
     pub fn new() -> Self {
         let secret_key = get_or_create_secret_key();
         let schnorr_secret_key = get_or_create_schnorr_secret_key();
+        let pod_key = get_or_create_pod_key();
 
         let message_watch = watch::channel(());
         let initial_sync_watch = watch::channel(());
@@ -68,6 +77,7 @@ impl Logic {
             iroh: Arc::new(tokio::sync::RwLock::new(None)),
             secret_key,
             schnorr_secret_key,
+            pod_key,
             doc0: Arc::new(tokio::sync::RwLock::new(None)),
             messages: RwLock::new(Vec::new()),
             identities: Mutex::new(Identities::new()),
@@ -171,7 +181,13 @@ impl Logic {
     }
 
     pub async fn send_message(&self, input: &str) -> anyhow::Result<()> {
-        let message = parse_message_input(input);
+        let message = match parse_message_input(input) {
+            Message::Chat { text, timestamp } => {
+                let pod = sign_message(&self.pod_key, DOC0, &text)?;
+                Message::new_pod_chat(text, serde_json::to_string(&pod)?, timestamp)
+            }
+            message => message,
+        };
 
         let content = SignedMessage::sign_and_encode(&self.secret_key, &message)?;
         let timestamp = *message.timestamp().unwrap();
@@ -215,15 +231,18 @@ impl Logic {
         *self.initial_sync.read().unwrap()
     }
 
-    pub fn get_messages(&self) -> Vec<(PublicKey, String)> {
-        let m: Vec<(PublicKey, String)> = self
+    /// Chat messages with the status of their pod, `None` for messages sent without one.
+    pub fn get_messages(&self) -> Vec<(PublicKey, String, Option<MessageStatus>)> {
+        let m: Vec<(PublicKey, String, Option<MessageStatus>)> = self
             .messages
             .read()
             .map(|msg| msg.clone())
             .unwrap()
             .into_iter()
             .filter_map(|m| match m {
-                (public_key, Message::Chat { timestamp: _, text }) => Some((public_key, text)),
+                (public_key, Message::Chat { timestamp: _, text }, status) => {
+                    Some((public_key, text, status))
+                }
                 _ => None,
             })
             .collect();
@@ -259,14 +278,35 @@ impl Logic {
     }
 
     fn add_message(&self, pubkey: PublicKey, message: &Message) {
+        let (message, status) = match message {
+            Message::PodChat {
+                text,
+                pod,
+                timestamp,
+            } => {
+                let status = match serde_json::from_str::<Pod>(pod) {
+                    // The pod must sign the text that is shown
+                    Ok(pod) if message_body(&pod).is_some_and(|body| body != text.as_str()) => {
+                        MessageStatus::BadSignature
+                    }
+                    Ok(pod) => check_message(&pod, DOC0),
+                    Err(e) => {
+                        warn!("invalid message pod: {}", e);
+                        MessageStatus::BadSignature
+                    }
+                };
+                (Message::new_chat_at(text.clone(), *timestamp), Some(status))
+            }
+            message => (message.clone(), None),
+        };
         self.messages
             .write()
             .unwrap()
-            .push((pubkey, message.clone()));
+            .push((pubkey, message.clone(), status));
         self.identities
             .lock()
             .unwrap()
-            .apply_message(pubkey, message);
+            .apply_message(pubkey, &message);
         self.messages
             .write()
             .unwrap()
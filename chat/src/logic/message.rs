@@ -20,12 +20,33 @@ impl SignedMessage {
         let signed_message: Self = postcard::from_bytes(bytes)?;
         let key: PublicKey = signed_message.from;
         key.verify(&signed_message.data, &signed_message.signature)?;
-        let message: Message = postcard::from_bytes(&signed_message.data)?;
+        let (message, rest): (Message, &[u8]) = postcard::take_from_bytes(&signed_message.data)?;
+        let message = match message {
+            Message::Chat { text, timestamp } if !rest.is_empty() => Message::PodChat {
+                text,
+                pod: postcard::from_bytes(rest)?,
+                timestamp,
+            },
+            message => message,
+        };
         Ok((signed_message.from, message))
     }
 
     pub fn sign_and_encode(secret_key: &SecretKey, message: &Message) -> anyhow::Result<Bytes> {
-        let data: Bytes = postcard::to_stdvec(&message)?.into();
+        let data: Bytes = match message {
+            Message::PodChat {
+                text,
+                pod,
+                timestamp,
+            } => {
+                let mut data =
+                    postcard::to_stdvec(&Message::new_chat_at(text.clone(), *timestamp))?;
+                data.extend(postcard::to_stdvec(pod)?);
+                data
+            }
+            message => postcard::to_stdvec(message)?,
+        }
+        .into();
         let signature = secret_key.sign(&data);
         let from: PublicKey = secret_key.public();
         let signed_message = Self {
@@ -53,6 +74,15 @@ pub enum Message {
         text: String,
         timestamp: DateTime<Utc>,
     },
+    /// Chat message along with a parcnet-pod signing it, see `podmsg`. It is sent as a `Chat`
+    /// followed by the pod as JSON, which clients that don't know about pods ignore, so that they
+    /// still get the text. It is never serialized on its own.
+    #[serde(skip)]
+    PodChat {
+        text: String,
+        pod: String,
+        timestamp: DateTime<Utc>,
+    },
     #[serde(other)]
     Unknown,
 }
@@ -71,11 +101,24 @@ impl Message {
         }
     }
 
+    pub fn new_chat_at(text: String, timestamp: DateTime<Utc>) -> Self {
+        Self::Chat { text, timestamp }
+    }
+
+    pub fn new_pod_chat(text: String, pod: String, timestamp: DateTime<Utc>) -> Self {
+        Self::PodChat {
+            text,
+            pod,
+            timestamp,
+        }
+    }
+
     pub fn timestamp(&self) -> Option<&DateTime<Utc>> {
         match self {
             Message::AboutMe { timestamp, .. } => Some(timestamp),
             Message::SchnorrKey { timestamp, .. } => Some(timestamp),
             Message::Chat { timestamp, .. } => Some(timestamp),
+            Message::PodChat { timestamp, .. } => Some(timestamp),
             Message::Unknown => {
                 warn!("no timestamp for unknown message");
                 None
@@ -104,9 +147,60 @@ impl Display for Message {
             Message::Chat { text, timestamp } => {
                 write!(f, "Chat: {} at {}", text, timestamp)
             }
+            Message::PodChat {
+                text,
+                pod,
+                timestamp,
+            } => {
+                write!(f, "PodChat: {} ({}) at {}", text, pod, timestamp)
+            }
             Message::Unknown => {
                 write!(f, "Unknown")
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pod_chat_encoding() -> anyhow::Result<()> {
+        let secret_key = SecretKey::generate();
+        let timestamp = Utc::now();
+        let message = Message::new_pod_chat("hello".to_string(), "{}".to_string(), timestamp);
+        let encoded = SignedMessage::sign_and_encode(&secret_key, &message)?;
+
+        let (from, decoded) = SignedMessage::verify_and_decode(&encoded)?;
+        assert_eq!(from, secret_key.public());
+        let Message::PodChat {
+            text,
+            pod,
+            timestamp: decoded_timestamp,
+        } = &decoded
+        else {
+            panic!("expected a PodChat, got {}", decoded);
+        };
+        assert_eq!(
+            (text.as_str(), pod.as_str(), *decoded_timestamp),
+            ("hello", "{}", timestamp)
+        );
+
+        // Clients that predate pods decode the message alone, which is a plain chat
+        let signed_message: SignedMessage = postcard::from_bytes(&encoded)?;
+        let old_decoded: Message = postcard::from_bytes(&signed_message.data)?;
+        assert!(matches!(old_decoded, Message::Chat { text, .. } if text == "hello"));
+
+        // Chats without a pod are unchanged
+        let encoded = SignedMessage::sign_and_encode(
+            &secret_key,
+            &Message::new_chat_at("hi".to_string(), timestamp),
+        )?;
+        assert!(matches!(
+            SignedMessage::verify_and_decode(&encoded)?.1,
+            Message::Chat { text, .. } if text == "hi"
+        ));
+        Ok(())
+    }
+}
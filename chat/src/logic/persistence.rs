@@ -1,3 +1,4 @@
+use babyjubjub_ark::PrivateKey;
 use iroh::net::key::SecretKey;
 use pod2::signature::schnorr::SchnorrSecretKey;
 use std::{fs, path::PathBuf};
@@ -5,7 +6,7 @@ use tracing::info;
 
 trait KeyOperations: Sized {
     const FILENAME: &'static str;
-    const EXPECTED_BYTES: usize;
+    const EXPECTED_BYTES: &'static [usize];
 
     fn generate_new() -> Self;
     fn from_bytes(bytes: &[u8]) -> Option<Self>;
@@ -14,7 +15,7 @@ trait KeyOperations: Sized {
 
 impl KeyOperations for SecretKey {
     const FILENAME: &'static str = "user_key.bin";
-    const EXPECTED_BYTES: usize = 32;
+    const EXPECTED_BYTES: &'static [usize] = &[32];
 
     fn generate_new() -> Self {
         Self::generate()
@@ -32,22 +33,43 @@ impl KeyOperations for SecretKey {
 
 impl KeyOperations for SchnorrSecretKey {
     const FILENAME: &'static str = "user_schnorr_key.bin";
-    const EXPECTED_BYTES: usize = 8;
+    const EXPECTED_BYTES: &'static [usize] = &[32, 8];
 
     fn generate_new() -> Self {
-        let random = rand::random::<u64>() % ((1 << 16) + 1);
-        Self { sk: random }
+        Self::from_seed(&rand::random())
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        // Older key files hold a single u64
+        match bytes.len() {
+            8 => Some(Self::from_u64(u64::from_le_bytes(bytes.try_into().ok()?))),
+            _ => Some(Self::from_le_bytes(bytes.try_into().ok()?)),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+}
+
+impl KeyOperations for PrivateKey {
+    const FILENAME: &'static str = "user_pod_key.bin";
+    const EXPECTED_BYTES: &'static [usize] = &[32];
+
+    fn generate_new() -> Self {
+        Self {
+            key: rand::random(),
+        }
     }
 
     fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        let bytes: [u8; 8] = bytes.try_into().ok()?;
         Some(Self {
-            sk: u64::from_le_bytes(bytes),
+            key: bytes.try_into().ok()?,
         })
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        self.sk.to_le_bytes().to_vec()
+        self.key.to_vec()
     }
 }
 
@@ -67,7 +89,7 @@ fn get_or_create_key<T: KeyOperations>() -> T {
     let path = get_exe_parent_dir().join(T::FILENAME);
 
     match fs::read(&path) {
-        Ok(bytes) if bytes.len() == T::EXPECTED_BYTES => {
+        Ok(bytes) if T::EXPECTED_BYTES.contains(&bytes.len()) => {
             if let Some(key) = T::from_bytes(&bytes) {
                 info!("loaded existing {}", T::FILENAME);
                 return key;
@@ -97,3 +119,7 @@ pub fn get_or_create_secret_key() -> SecretKey {
 pub fn get_or_create_schnorr_secret_key() -> SchnorrSecretKey {
     get_or_create_key()
 }
+
+pub fn get_or_create_pod_key() -> PrivateKey {
+    get_or_create_key()
+}
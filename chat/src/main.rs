@@ -1,5 +1,6 @@
 mod chat;
 mod logic;
+mod podmsg;
 
 use chat::Chat;
 use gpui::{
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use babyjubjub_ark::PrivateKey;
+use chrono::Utc;
+use parcnet_pod::pod::{Pod, PodValue};
+
+const FROM: &str = "from";
+const BODY: &str = "body";
+const TIMESTAMP: &str = "timestamp";
+const CHANNEL: &str = "channel";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageStatus {
+    /// Signed by `sender`, the hex of the signer's compressed public key.
+    Verified {
+        sender: String,
+    },
+    BadSignature,
+    WrongChannel,
+    MissingFields,
+}
+
+fn sender_hex(key: &PrivateKey) -> String {
+    hex::encode(key.public().compress())
+}
+
+/// Signs `body` as a pod with entries `{from, body, timestamp, channel}`.
+pub fn sign_message(key: &PrivateKey, channel: &str, body: &str) -> Result<Pod> {
+    let entries = vec![
+        (FROM, PodValue::String(sender_hex(key))),
+        (BODY, PodValue::String(body.to_string())),
+        (
+            TIMESTAMP,
+            PodValue::date_from_millis(Utc::now().timestamp_millis())?,
+        ),
+        (CHANNEL, PodValue::String(channel.to_string())),
+    ];
+    Ok(Pod::sign(entries, PrivateKey { key: key.key })?)
+}
+
+pub fn message_body(pod: &Pod) -> Option<&str> {
+    pod.get_string(BODY)
+}
+
+/// Checks a pod made by `sign_message`. The `from` entry must be the signer of the pod, so a
+/// pod claiming to be from someone else is reported as a bad signature.
+pub fn check_message(pod: &Pod, expected_channel: &str) -> MessageStatus {
+    let (Some(from), Some(_), Some(PodValue::Date(_)), Some(channel)) = (
+        pod.get_string(FROM),
+        pod.get_string(BODY),
+        pod.get(TIMESTAMP),
+        pod.get_string(CHANNEL),
+    ) else {
+        return MessageStatus::MissingFields;
+    };
+
+    let signer = hex::encode(pod.signer_public_key().compress());
+    if from != signer || !pod.verify().unwrap_or(false) {
+        MessageStatus::BadSignature
+    } else if channel != expected_channel {
+        MessageStatus::WrongChannel
+    } else {
+        MessageStatus::Verified { sender: signer }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> PrivateKey {
+        PrivateKey { key: [byte; 32] }
+    }
+
+    #[test]
+    fn test_sign_and_check() -> Result<()> {
+        let pod = sign_message(&key(1), "general", "hello")?;
+        assert_eq!(message_body(&pod), Some("hello"));
+        assert_eq!(
+            check_message(&pod, "general"),
+            MessageStatus::Verified {
+                sender: sender_hex(&key(1))
+            }
+        );
+
+        // The status survives the JSON the pod is sent as
+        let received: Pod = serde_json::from_str(&serde_json::to_string(&pod)?)?;
+        assert!(matches!(
+            check_message(&received, "general"),
+            MessageStatus::Verified { .. }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrong_channel() -> Result<()> {
+        let pod = sign_message(&key(1), "random", "hello")?;
+        assert_eq!(check_message(&pod, "general"), MessageStatus::WrongChannel);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_signature() -> Result<()> {
+        let pod = sign_message(&key(1), "general", "hello")?;
+        let mut json = serde_json::to_value(&pod)?;
+        *json.pointer_mut("/claim/entries/body").unwrap() =
+            serde_json::to_value(PodValue::String("goodbye".to_string()))?;
+        let tampered: Pod = serde_json::from_value(json)?;
+        assert_eq!(
+            check_message(&tampered, "general"),
+            MessageStatus::BadSignature
+        );
+
+        // Validly signed, but by someone other than the claimed sender
+        let impersonated = Pod::sign(
+            vec![
+                (FROM, PodValue::String(sender_hex(&key(1)))),
+                (BODY, PodValue::String("hello".to_string())),
+                (TIMESTAMP, PodValue::date_from_millis(0)?),
+                (CHANNEL, PodValue::String("general".to_string())),
+            ],
+            key(2),
+        )?;
+        assert_eq!(
+            check_message(&impersonated, "general"),
+            MessageStatus::BadSignature
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_fields() -> Result<()> {
+        let pod = Pod::sign(
+            vec![
+                (FROM, PodValue::String(sender_hex(&key(1)))),
+                (BODY, PodValue::String("hello".to_string())),
+                (CHANNEL, PodValue::String("general".to_string())),
+            ],
+            key(1),
+        )?;
+        assert_eq!(check_message(&pod, "general"), MessageStatus::MissingFields);
+
+        // The timestamp must be a date
+        let pod = Pod::sign(
+            vec![
+                (FROM, PodValue::String(sender_hex(&key(1)))),
+                (BODY, PodValue::String("hello".to_string())),
+                (TIMESTAMP, PodValue::Int(0)),
+                (CHANNEL, PodValue::String("general".to_string())),
+            ],
+            key(1),
+        )?;
+        assert_eq!(check_message(&pod, "general"), MessageStatus::MissingFields);
+        Ok(())
+    }
+}
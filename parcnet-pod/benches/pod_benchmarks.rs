@@ -1,6 +1,44 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use ark_bn254::Fr as Fq;
+use babyjubjub_ark::PrivateKey;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use parcnet_pod::pod::{create_pod, value::PodValue};
+use parcnet_pod::crypto::lean_imt::lean_poseidon_imt;
+use parcnet_pod::pod::{create_pod, value::PodValue, Pod, PodCreationError};
+
+/// Tracks live and peak heap usage so the content ID paths can be compared by memory as well.
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Returns the result of `f` along with the heap it needed beyond what was live beforehand.
+fn peak_heap<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let result = f();
+    (result, PEAK.load(Ordering::Relaxed) - baseline)
+}
 
 fn benchmark_create_pod(c: &mut Criterion) {
     let private_key = vec![0u8; 32];
@@ -76,5 +114,35 @@ fn benchmark_create_pod(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, benchmark_create_pod);
+/// The content ID as computed before `ImtBuilder`, collecting every leaf hash up front.
+fn collected_content_id(pod: &Pod) -> Result<Fq, PodCreationError> {
+    let hashes = pod
+        .entries()
+        .iter()
+        .flat_map(|(k, v)| [PodValue::String(k.to_string()).hash(), v.hash()])
+        .collect::<Result<Vec<_>, PodCreationError>>()?;
+    lean_poseidon_imt(&hashes).map_err(|_| PodCreationError::ImtError)
+}
+
+fn benchmark_content_id(c: &mut Criterion) {
+    let entries = (0..10_000).map(|i| (format!("entry{}", i), PodValue::Int(i)));
+    let pod =
+        Pod::sign_from_iter(entries, PrivateKey { key: [0u8; 32] }).expect("can't create pod");
+
+    let (_, collected_peak) = peak_heap(|| collected_content_id(&pod));
+    let (_, incremental_peak) = peak_heap(|| pod.content_id());
+    println!(
+        "content ID of 10k entries, peak heap: collected {} bytes, incremental {} bytes",
+        collected_peak, incremental_peak
+    );
+
+    c.bench_function("content ID 10k entries (collected)", |b| {
+        b.iter(|| collected_content_id(black_box(&pod)))
+    });
+    c.bench_function("content ID 10k entries (incremental)", |b| {
+        b.iter(|| black_box(&pod).content_id())
+    });
+}
+
+criterion_group!(benches, benchmark_create_pod, benchmark_content_id);
 criterion_main!(benches);
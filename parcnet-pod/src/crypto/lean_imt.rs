@@ -11,23 +11,163 @@ pub fn lean_poseidon_imt(inputs: &[Fq]) -> Result<Fq, &'static str> {
     let mut items = inputs.to_vec();
 
     while items.len() > 1 {
-        let mut new_items = Vec::new();
-        for chunk in items.chunks(2) {
-            if chunk.len() == 2 {
-                let hash = poseidon
-                    .hash(vec![chunk[0], chunk[1]])
-                    .map_err(|_| "Error hashing")?;
-                new_items.push(hash);
-            } else {
-                new_items.push(chunk[0]);
-            }
-        }
-        items = new_items;
+        items = next_level(&poseidon, &items)?;
     }
 
     Ok(items[0])
 }
 
+/// Hashes the nodes of a level in pairs, a last node without sibling is carried up unchanged.
+fn next_level(poseidon: &Poseidon, items: &[Fq]) -> Result<Vec<Fq>, &'static str> {
+    let mut new_items = Vec::new();
+    for chunk in items.chunks(2) {
+        if chunk.len() == 2 {
+            let hash = poseidon
+                .hash(vec![chunk[0], chunk[1]])
+                .map_err(|_| "Error hashing")?;
+            new_items.push(hash);
+        } else {
+            new_items.push(chunk[0]);
+        }
+    }
+    Ok(new_items)
+}
+
+/// Every level of the tree `lean_poseidon_imt` builds, from the leaves up to the root.
+pub fn lean_poseidon_imt_levels(inputs: &[Fq]) -> Result<Vec<Vec<Fq>>, &'static str> {
+    let poseidon = Poseidon::new();
+
+    if inputs.is_empty() {
+        return Err("At least one input is required");
+    }
+
+    let mut levels = vec![inputs.to_vec()];
+    while levels[levels.len() - 1].len() > 1 {
+        let items = next_level(&poseidon, &levels[levels.len() - 1])?;
+        levels.push(items);
+    }
+
+    Ok(levels)
+}
+
+/// Membership proof for a leaf, in the same format as zk-kit's LeanIMT: nodes carried up
+/// without a sibling have no entry in `siblings`, and bit `i` of `index` is set when the node
+/// is on the right of `siblings[i]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub siblings: Vec<Fq>,
+}
+
+pub fn lean_poseidon_imt_proof(inputs: &[Fq], index: usize) -> Result<MerkleProof, &'static str> {
+    if index >= inputs.len() {
+        return Err("Leaf index out of bounds");
+    }
+    let levels = lean_poseidon_imt_levels(inputs)?;
+
+    let mut proof = MerkleProof {
+        index: 0,
+        siblings: Vec::new(),
+    };
+    let mut node_index = index;
+    for level in &levels[..levels.len() - 1] {
+        let is_right = node_index % 2 == 1;
+        let sibling_index = if is_right {
+            node_index - 1
+        } else {
+            node_index + 1
+        };
+        if let Some(sibling) = level.get(sibling_index) {
+            proof.index |= (is_right as usize) << proof.siblings.len();
+            proof.siblings.push(*sibling);
+        }
+        node_index /= 2;
+    }
+    Ok(proof)
+}
+
+pub fn verify_lean_poseidon_imt_proof(root: Fq, leaf: Fq, proof: &MerkleProof) -> bool {
+    let poseidon = Poseidon::new();
+    let mut node = leaf;
+    for (i, sibling) in proof.siblings.iter().enumerate() {
+        let pair = if (proof.index >> i) & 1 == 1 {
+            vec![*sibling, node]
+        } else {
+            vec![node, *sibling]
+        };
+        match poseidon.hash(pair) {
+            Ok(hash) => node = hash,
+            Err(_) => return false,
+        }
+    }
+    node == root
+}
+
+/// Incremental counterpart of `lean_poseidon_imt`: leaves are pushed one at a time and only one
+/// pending node per tree level is kept, so the root is computed without the full leaf vector.
+pub struct ImtBuilder {
+    poseidon: Poseidon,
+    levels: Vec<Option<Fq>>,
+    len: usize,
+}
+
+impl ImtBuilder {
+    pub fn new() -> Self {
+        ImtBuilder {
+            poseidon: Poseidon::new(),
+            levels: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, leaf: Fq) -> Result<(), &'static str> {
+        let mut node = leaf;
+        let mut level = 0;
+        // Complete subtrees are merged like a binary counter carrying upwards.
+        while let Some(left) = self.levels.get_mut(level).and_then(Option::take) {
+            node = self
+                .poseidon
+                .hash(vec![left, node])
+                .map_err(|_| "Error hashing")?;
+            level += 1;
+        }
+        if level == self.levels.len() {
+            self.levels.push(Some(node));
+        } else {
+            self.levels[level] = Some(node);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn root(self) -> Result<Fq, &'static str> {
+        // A lone node is carried up unchanged until it meets a complete subtree to its left.
+        let mut pending = self.levels.into_iter().flatten();
+        let mut root = pending.next().ok_or("At least one input is required")?;
+        for left in pending {
+            root = self
+                .poseidon
+                .hash(vec![left, root])
+                .map_err(|_| "Error hashing")?;
+        }
+        Ok(root)
+    }
+}
+
+impl Default for ImtBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,4 +213,54 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "At least one input is required");
     }
+
+    #[test]
+    fn test_lean_imt_proofs() -> Result<(), Error> {
+        for n in 1..=9u64 {
+            let inputs = (1..=n).map(Fq::from).collect::<Vec<_>>();
+            let root = lean_poseidon_imt(&inputs)?;
+            assert_eq!(
+                *lean_poseidon_imt_levels(&inputs)?.last().unwrap(),
+                vec![root]
+            );
+            for (i, leaf) in inputs.iter().enumerate() {
+                let proof = lean_poseidon_imt_proof(&inputs, i)?;
+                assert!(verify_lean_poseidon_imt_proof(root, *leaf, &proof));
+                assert!(!verify_lean_poseidon_imt_proof(root, Fq::from(0), &proof));
+            }
+            assert!(lean_poseidon_imt_proof(&inputs, inputs.len()).is_err());
+        }
+
+        // The last of 5 leaves is carried up twice, so it only has the root's other child as
+        // sibling
+        let inputs = (1..=5u64).map(Fq::from).collect::<Vec<_>>();
+        let proof = lean_poseidon_imt_proof(&inputs, 4)?;
+        assert_eq!(proof.index, 1);
+        assert_eq!(
+            proof.siblings,
+            vec![lean_poseidon_imt_levels(&inputs)?[2][0]]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_imt_builder_matches_lean_imt() -> Result<(), Error> {
+        for n in 1..=33u64 {
+            let inputs = (1..=n).map(Fq::from).collect::<Vec<_>>();
+            let mut builder = ImtBuilder::new();
+            for input in inputs.iter() {
+                builder.push(*input)?;
+            }
+            assert_eq!(builder.len(), inputs.len());
+            assert_eq!(builder.root()?, lean_poseidon_imt(&inputs)?);
+        }
+
+        assert_eq!(
+            ImtBuilder::new().root().unwrap_err(),
+            "At least one input is required"
+        );
+
+        Ok(())
+    }
 }
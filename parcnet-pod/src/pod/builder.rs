@@ -0,0 +1,66 @@
+use babyjubjub_ark::PrivateKey;
+
+use super::{Pod, PodCreationError, PodValue};
+
+/// Collects entries one at a time, eg: `PodBuilder::new().add("name", "Alice").add("age", 30)`,
+/// and signs them all at once. Sorting and hashing are deferred to `sign`.
+#[derive(Clone, Debug, Default)]
+pub struct PodBuilder {
+    entries: Vec<(String, PodValue)>,
+}
+
+impl PodBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry. Adding a key twice makes `sign` fail.
+    pub fn add(mut self, key: impl Into<String>, value: impl Into<PodValue>) -> Self {
+        self.entries.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn sign(self, private_key: PrivateKey) -> Result<Pod, PodCreationError> {
+        Pod::sign_from_iter(self.entries, private_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pod::{create_pod, Fq};
+
+    #[test]
+    fn test_pod_builder() -> Result<(), PodCreationError> {
+        let private_key = [3u8; 32];
+        let builder = PodBuilder::new()
+            .add("name", "Alice")
+            .add("age", 30)
+            .add(String::from("member"), true)
+            .add("owner", Fq::from(42));
+        assert_eq!(builder.len(), 4);
+        let pod = builder.sign(PrivateKey { key: private_key })?;
+
+        let expected = create_pod(
+            &private_key,
+            crate::pod_entries![
+                "member" => true,
+                "owner" => Fq::from(42),
+                "age" => 30,
+                "name" => "Alice",
+            ],
+        )?;
+        assert!(pod.content_id()? == expected.content_id()?);
+        assert!(pod.signer_public_key() == expected.signer_public_key());
+        assert!(pod.verify().unwrap());
+        Ok(())
+    }
+}
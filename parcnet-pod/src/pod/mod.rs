@@ -1,3 +1,4 @@
+pub mod builder;
 pub mod macros;
 mod serialisation;
 pub mod value;
@@ -8,23 +9,46 @@ use std::array;
 pub use ark_bn254::Fr as Fq;
 use rayon::prelude::*;
 
+use ark_ff::{BigInteger, PrimeField};
 use babyjubjub_ark::{verify, Point, PrivateKey, Signature};
 use indexmap::IndexMap;
 use serialisation::{compressed_pt_de, compressed_pt_ser, compressed_sig_de, compressed_sig_ser};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use time::OffsetDateTime;
 
 use serde::{Deserialize, Serialize};
 
+pub use builder::PodBuilder;
 use uuid::Uuid;
 pub use value::PodValue;
 
-use crate::crypto::lean_imt::lean_poseidon_imt;
+use crate::crypto::lean_imt::{
+    lean_poseidon_imt_proof, verify_lean_poseidon_imt_proof, ImtBuilder, MerkleProof,
+};
 
 pub(crate) type Error = Box<dyn std::error::Error>;
 
 pub type PodEntries = IndexMap<String, PodValue>;
 
+/// Checks on entries before they are signed, eg: entries deserialised from untrusted JSON.
+pub trait ValidateEntries {
+    fn validate(&self) -> Result<(), PodCreationError>;
+}
+
+impl ValidateEntries for PodEntries {
+    fn validate(&self) -> Result<(), PodCreationError> {
+        self.iter().try_for_each(|(key, value)| {
+            value.validate().map_err(|e| match e {
+                PodCreationError::ValueError(e) => {
+                    PodCreationError::ValueError(format!("entry {}: {}", key, e))
+                }
+                e => e,
+            })
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct PodClaim {
@@ -54,6 +78,9 @@ pub struct Pod {
 }
 
 impl Pod {
+    /// Signs the entries with `private_key`. The EdDSA nonce is derived from the private key
+    /// and the content ID, as in `@pcd/pod`, so the signature is deterministic; only the pod
+    /// ID is random. Use `sign_deterministic` for a fully reproducible pod.
     pub fn sign<K>(
         data: Vec<(K, PodValue)>,
         private_key: PrivateKey,
@@ -61,22 +88,30 @@ impl Pod {
     where
         K: Into<String> + Clone,
     {
-        let mut entry_alist = data;
-        entry_alist.sort_by(|(k1, _), (k2, _)| {
-            Into::<String>::into(k1.clone()).cmp(&Into::<String>::into(k2.clone()))
-        });
-        let entries: IndexMap<String, PodValue> = entry_alist
-            .into_iter()
-            .map(|(k, v)| (k.into(), v))
-            .collect();
+        Self::sign_from_iter(data.into_iter().map(|(k, v)| (k.into(), v)), private_key)
+    }
 
-        let hashes: Result<Vec<_>, PodCreationError> = entries
-            .par_iter()
-            .flat_map(|(k, v)| vec![PodValue::String(k.to_string()).hash(), v.hash()])
-            .collect();
-        let hashes = hashes?;
+    /// Same as `sign`, but takes the entries from an iterator. Entries are moved straight into
+    /// the claim and the content ID is computed incrementally, so no intermediate copies of
+    /// the entries or their hashes are held.
+    pub fn sign_from_iter(
+        data: impl IntoIterator<Item = (String, PodValue)>,
+        private_key: PrivateKey,
+    ) -> Result<Self, PodCreationError> {
+        let mut entries = PodEntries::new();
+        for (key, value) in data {
+            if entries.contains_key(&key) {
+                return Err(PodCreationError::DuplicateKey(key));
+            }
+            entries.insert(key, value);
+        }
+        entries.validate()?;
+        entries.sort_keys();
+        entries
+            .values_mut()
+            .for_each(PodValue::sort_dictionary_keys);
 
-        let message = lean_poseidon_imt(&hashes).map_err(|_| PodCreationError::ImtError)?;
+        let message = content_id_of(entries.iter())?;
 
         let signer_public_key = private_key.public();
         let signature = private_key
@@ -93,22 +128,101 @@ impl Pod {
         })
     }
 
-    pub fn entries(&self) -> PodEntries {
-        self.claim.entries.clone()
+    /// Same as `sign`, but signing the same entries with the same key always yields the same
+    /// pod. The signature nonce is already derived from the private key and the content ID, so
+    /// only the pod ID, which `sign` draws at random, is derived from them instead.
+    pub fn sign_deterministic<K>(
+        data: Vec<(K, PodValue)>,
+        private_key: PrivateKey,
+    ) -> Result<Self, PodCreationError>
+    where
+        K: Into<String> + Clone,
+    {
+        let mut pod = Self::sign(data, private_key)?;
+        let mut hasher = Sha256::new();
+        hasher.update(pod.content_id()?.into_bigint().to_bytes_le());
+        hasher.update(pod.claim.signer_public_key.compress());
+        let hash = hasher.finalize();
+        pod.id = uuid::Builder::from_random_bytes(array::from_fn(|i| hash[i])).into_uuid();
+        Ok(pod)
+    }
+
+    pub fn entries(&self) -> &PodEntries {
+        &self.claim.entries
+    }
+
+    /// Iterates over the entries in the order they are stored.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&String, &PodValue)> {
+        self.claim.entries.iter()
+    }
+
+    /// Iterates over the entries in the canonical key order, which is the order they are
+    /// hashed in for the content ID. Signed pods store their entries in this order already,
+    /// but deserialised ones keep the order of their JSON.
+    pub fn entries_sorted(&self) -> impl ExactSizeIterator<Item = (&String, &PodValue)> {
+        let mut entries: Vec<_> = self.claim.entries.iter().collect();
+        entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        entries.into_iter()
     }
 
     pub fn get(&self, key: &str) -> Option<&PodValue> {
         self.claim.entries.get(key)
     }
 
+    // Typed getters, which are `None` both for missing keys and values of other types.
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.get(key)? {
+            PodValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            PodValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            PodValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn get_cryptographic(&self, key: &str) -> Option<&Fq> {
+        match self.get(key)? {
+            PodValue::Cryptographic(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn get_eddsa_public_key(&self, key: &str) -> Option<&Point> {
+        match self.get(key)? {
+            PodValue::EdDSAPublicKey(pt) => Some(pt),
+            _ => None,
+        }
+    }
+
     pub fn content_id(&self) -> Result<Fq, PodCreationError> {
-        let hashes = self
-            .claim
-            .entries
+        content_id_of(self.entries_sorted())
+    }
+
+    /// Proof that the entry `key` is in the pod, against its content ID. This is the proof of
+    /// the value's leaf, whose first sibling is the leaf of the key.
+    pub fn entry_proof(&self, key: &str) -> Result<MerkleProof, Error> {
+        let entries: Vec<_> = self.entries_sorted().collect();
+        let index = entries
+            .iter()
+            .position(|(k, _)| *k == key)
+            .ok_or_else(|| format!("No entry {} in pod", key))?;
+        let hashes = entries
             .par_iter()
             .flat_map(|(k, v)| [PodValue::String(k.to_string()).hash(), v.hash()])
             .collect::<Result<Vec<_>, PodCreationError>>()?;
-        lean_poseidon_imt(&hashes).map_err(|_| PodCreationError::ImtError)
+        Ok(lean_poseidon_imt_proof(&hashes, 2 * index + 1)?)
     }
 
     pub fn signer_public_key(&self) -> Point {
@@ -129,6 +243,51 @@ impl Pod {
 
         Ok(verify(signer_public_key, signature, content_id))
     }
+
+    /// Verifies the pod and checks that it was signed by `expected`.
+    pub fn verify_with_signer(&self, expected: &Point) -> Result<bool, Error> {
+        Ok(self.claim.signer_public_key == *expected && self.verify()?)
+    }
+
+    /// Like `verify_with_signer`, with the expected signer given in compressed form.
+    pub fn verify_with_signer_bytes(&self, expected: &[u8; 32]) -> Result<bool, Error> {
+        Ok(self.claim.signer_public_key.compress() == *expected && self.verify()?)
+    }
+
+    /// Verifies the pod and checks the optional `_not_before` and `_expires_at` dates against
+    /// `now`. The pod is valid from `_not_before` included until `_expires_at` excluded.
+    pub fn verify_at(&self, now: OffsetDateTime) -> Result<VerifyOutcome, Error> {
+        let date = |key: &str| match self.get(key) {
+            None => Ok(None),
+            Some(PodValue::Date(date)) => Ok(Some(*date)),
+            Some(value) => Err(format!("{} must be a date, got {:?}", key, value)),
+        };
+        let not_before = date(NOT_BEFORE_KEY)?;
+        let expires_at = date(EXPIRES_AT_KEY)?;
+
+        Ok(if !self.verify()? {
+            VerifyOutcome::SignatureInvalid
+        } else if let Some(at) = not_before.filter(|at| now < *at) {
+            VerifyOutcome::NotYetValid { at }
+        } else if let Some(at) = expires_at.filter(|at| now >= *at) {
+            VerifyOutcome::Expired { at }
+        } else {
+            VerifyOutcome::Valid
+        })
+    }
+}
+
+/// Reserved entry holding the date a pod expires, see `Pod::verify_at`.
+pub const EXPIRES_AT_KEY: &str = "_expires_at";
+/// Reserved entry holding the date a pod becomes valid, see `Pod::verify_at`.
+pub const NOT_BEFORE_KEY: &str = "_not_before";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Valid,
+    Expired { at: OffsetDateTime },
+    NotYetValid { at: OffsetDateTime },
+    SignatureInvalid,
 }
 
 #[derive(Error, Debug)]
@@ -139,45 +298,59 @@ pub enum PodCreationError {
     SignatureError,
     #[error("Hash computation failed: {0}")]
     HashError(String),
+    #[error("Invalid value: {0}")]
+    ValueError(String),
+    #[error("Duplicate key: {0}")]
+    DuplicateKey(String),
+}
+
+/// Number of entries whose hashes are computed in parallel before being fed to the IMT.
+const CONTENT_ID_CHUNK_SIZE: usize = 1024;
+
+/// Computes the content ID of sorted entries, hashing them in bounded parallel chunks and
+/// building the IMT incrementally rather than collecting every leaf hash first.
+fn content_id_of<'a>(
+    entries: impl ExactSizeIterator<Item = (&'a String, &'a PodValue)>,
+) -> Result<Fq, PodCreationError> {
+    let mut builder = ImtBuilder::new();
+    let mut chunk = Vec::with_capacity(CONTENT_ID_CHUNK_SIZE.min(entries.len()));
+    let mut entries = entries.peekable();
+    while entries.peek().is_some() {
+        chunk.extend(entries.by_ref().take(CONTENT_ID_CHUNK_SIZE));
+        let hashes = chunk
+            .par_iter()
+            .map(|(k, v)| -> Result<_, PodCreationError> {
+                Ok([PodValue::String(k.to_string()).hash()?, v.hash()?])
+            })
+            .collect::<Result<Vec<_>, PodCreationError>>()?;
+        for hash in hashes.into_iter().flatten() {
+            builder.push(hash).map_err(|_| PodCreationError::ImtError)?;
+        }
+        chunk.clear();
+    }
+    builder.root().map_err(|_| PodCreationError::ImtError)
+}
+
+/// Checks a proof from `Pod::entry_proof` that the pod with content ID `root` has the entry
+/// `key` with value `value`.
+pub fn verify_entry_proof(root: Fq, key: &str, value: &PodValue, proof: &MerkleProof) -> bool {
+    // The value is the right leaf of the pair whose left leaf is the key
+    let Ok(value_hash) = value.hash() else {
+        return false;
+    };
+    proof.index & 1 == 1
+        && proof.siblings.first() == Some(&value::string_hash(key))
+        && verify_lean_poseidon_imt_proof(root, value_hash, proof)
 }
 
 pub fn create_pod<K>(private_key: &[u8], data: Vec<(K, PodValue)>) -> Result<Pod, PodCreationError>
 where
     K: Into<String> + Clone,
 {
-    let mut entry_alist = data;
-    entry_alist.sort_by(|(k1, _), (k2, _)| {
-        Into::<String>::into(k1.clone()).cmp(&Into::<String>::into(k2.clone()))
-    });
-    let entries: IndexMap<String, PodValue> = entry_alist
-        .into_iter()
-        .map(|(k, v)| (k.into(), v))
-        .collect();
-
-    let hashes: Result<Vec<_>, PodCreationError> = entries
-        .iter()
-        .flat_map(|(k, v)| vec![PodValue::String(k.to_string()).hash(), v.hash()])
-        .collect();
-    let hashes = hashes?;
-
-    let message = lean_poseidon_imt(&hashes).map_err(|_| PodCreationError::ImtError)?;
-
     let private_key = PrivateKey {
         key: array::from_fn(|i| private_key[i]),
     };
-    let signer_public_key = private_key.public();
-    let signature = private_key
-        .sign(message)
-        .map_err(|_| PodCreationError::SignatureError)?;
-
-    Ok(Pod {
-        id: Uuid::new_v4(),
-        claim: PodClaim {
-            entries,
-            signer_public_key,
-        },
-        proof: PodProof { signature },
-    })
+    Pod::sign(data, private_key)
 }
 
 #[cfg(test)]
@@ -188,8 +361,23 @@ mod tests {
     use time::macros::datetime;
 
     use super::*;
+    use crate::crypto::lean_imt::lean_poseidon_imt;
 
-    fn create_test_pod() -> Result<Pod, PodCreationError> {
+    /// The `publicKey` entry of the first example in test/common.ts in the @pcd/pod package.
+    fn test_public_key() -> Point {
+        Point {
+            x: Fq::from_str(
+                "13277427435165878497778222415993513565335242147425444199013288855685581939618",
+            )
+            .unwrap(),
+            y: Fq::from_str(
+                "13622229784656158136036771217484571176836296686641868549125388198837476602820",
+            )
+            .unwrap(),
+        }
+    }
+
+    fn create_test_pod_with_public_key(public_key: PodValue) -> Result<Pod, PodCreationError> {
         // Follows the example given in test/common.ts in the @pcd/pod package.
         let private_key = [
             0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8,
@@ -208,15 +396,16 @@ mod tests {
                 "H" => 8,
                 "I" => 9,
                 "J" => 10,
-                "publicKey" => PodValue::EdDSAPublicKey(Point {
-                    x: Fq::from_str("13277427435165878497778222415993513565335242147425444199013288855685581939618").unwrap(),
-                    y: Fq::from_str("13622229784656158136036771217484571176836296686641868549125388198837476602820").unwrap()
-                }),
+                "publicKey" => public_key,
                 "owner" => Fq::from_str("18711405342588116796533073928767088921854096266145046362753928030796553161041").unwrap(),
             ],
         )
     }
 
+    fn create_test_pod() -> Result<Pod, PodCreationError> {
+        create_test_pod_with_public_key(test_public_key().into())
+    }
+
     fn create_test_pod2() -> Result<Pod, PodCreationError> {
         // Follows the second example given in test/common.ts in the @pcd/pod package.
         let private_key = [
@@ -247,6 +436,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_serde_json_numeric_values() -> Result<(), Error> {
+        let entries = crate::pod_entries![
+            "G" => -7,
+            "min" => i64::MIN,
+            "max" => i64::MAX,
+            "big" => Fq::from(-1),
+            "flag" => false,
+        ];
+        let pod = Pod::sign(entries, PrivateKey { key: [9u8; 32] })?;
+
+        // Entries survive JSON without losing precision, so the content ID is unchanged
+        let serialised_entries = serde_json::to_string(pod.entries())?;
+        assert!(serialised_entries.contains(&format!("{{\"int\":{}}}", i64::MIN)));
+        let deserialised_entries: PodEntries = serde_json::from_str(&serialised_entries)?;
+        assert!(&deserialised_entries == pod.entries());
+
+        let deserialised_pod: Pod = serde_json::from_str(&serde_json::to_string(&pod)?)?;
+        assert!(deserialised_pod.content_id()? == pod.content_id()?);
+        assert!(deserialised_pod.verify()?);
+        let resigned = Pod::sign_from_iter(deserialised_entries, PrivateKey { key: [9u8; 32] })?;
+        assert!(resigned.content_id()? == pod.content_id()?);
+
+        Ok(())
+    }
+
     #[test]
     fn verify_test_pod() -> Result<(), Error> {
         let pod = create_test_pod()?;
@@ -312,6 +527,455 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_eddsa_public_key_entries() -> Result<(), Error> {
+        // Serialised as the compressed point in unpadded Base64, like @pcd/pod does
+        let json = r#"{"eddsa_pubkey":"xDP3ppa3qjpSJO+zmTuvDM2eku7O4MKaP2yCCKnoHZ4"}"#;
+        let public_key: PodValue = serde_json::from_str(json)?;
+        assert_eq!(public_key, PodValue::from(test_public_key()));
+        assert_eq!(serde_json::to_string(&public_key)?, json);
+
+        // Hashed as the Poseidon hash of its coordinates, which the reference content ID covers
+        let pod = create_test_pod_with_public_key(public_key)?;
+        assert!(
+            pod.content_id()?
+                == Fq::from_str(
+                    "18003549444852780886592139349318927700964545643704389119309344945101355208480"
+                )
+                .map_err(|e| format!("{:?}", e))?
+        );
+        assert!(pod.verify()?);
+        assert_eq!(
+            pod.get_eddsa_public_key("publicKey"),
+            Some(&test_public_key())
+        );
+        assert_eq!(pod.get_eddsa_public_key("owner"), None);
+
+        let deserialised_pod: Pod = serde_json::from_str(&serde_json::to_string(&pod)?)?;
+        assert_eq!(
+            deserialised_pod.get("publicKey"),
+            Some(&PodValue::EdDSAPublicKey(test_public_key()))
+        );
+        assert!(deserialised_pod.content_id()? == pod.content_id()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_deterministic() -> Result<(), Error> {
+        let private_key = || PrivateKey { key: [7u8; 32] };
+        let entries = || crate::pod_entries!["A" => 123, "B" => "hello"];
+
+        let pod1 = Pod::sign_deterministic(entries(), private_key())?;
+        let pod2 = Pod::sign_deterministic(entries(), private_key())?;
+        assert!(pod1 == pod2);
+        assert_eq!(serde_json::to_string(&pod1)?, serde_json::to_string(&pod2)?);
+        assert!(pod1.verify()?);
+
+        // Same signature as the random path, only the pod ID differs
+        let random_pod = Pod::sign(entries(), private_key())?;
+        assert!(random_pod.verify()?);
+        assert!(random_pod.signature() == pod1.signature());
+        assert_eq!(
+            Pod::sign(entries(), private_key())?.signature().compress(),
+            random_pod.signature().compress()
+        );
+        assert!(random_pod.content_id()? == pod1.content_id()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_from_iter_large_pod() -> Result<(), Error> {
+        let private_key = PrivateKey { key: [3u8; 32] };
+        let entries = (0..10_000).map(|i| (format!("entry{}", i), PodValue::Int(i)));
+        let pod = Pod::sign_from_iter(entries, private_key)?;
+        assert_eq!(pod.entries().len(), 10_000);
+        assert!(pod.entries().keys().is_sorted());
+
+        // Matches the content ID computed over the full leaf vector
+        let hashes = pod
+            .entries()
+            .iter()
+            .flat_map(|(k, v)| [PodValue::String(k.to_string()).hash(), v.hash()])
+            .collect::<Result<Vec<_>, PodCreationError>>()?;
+        assert!(pod.content_id()? == lean_poseidon_imt(&hashes)?);
+        assert!(pod.verify()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dictionary_hash() -> Result<(), Error> {
+        // A dictionary hashes to the content ID of a pod with the same entries, which pins it to
+        // the @pcd/pod test vector.
+        let pod = create_test_pod()?;
+        let mut entries = pod.entries().clone();
+        entries.reverse();
+        let dictionary = PodValue::Dictionary(entries);
+        assert!(
+            dictionary.hash()?
+                == Fq::from_str(
+                    "18003549444852780886592139349318927700964545643704389119309344945101355208480"
+                )
+                .map_err(|e| format!("{:?}", e))?
+        );
+
+        let empty = PodValue::Dictionary(IndexMap::new());
+        assert!(empty.hash()? == poseidon_ark::Poseidon::new().hash(vec![Fq::from(0); 2])?);
+        assert!(empty.hash()? != PodValue::Null.hash()?);
+        assert!(empty.hash()? != PodValue::Int(0).hash()?);
+
+        let nest = |depth| {
+            (0..depth).fold(PodValue::Int(1), |value, _| {
+                PodValue::Dictionary(IndexMap::from([("inner".to_string(), value)]))
+            })
+        };
+        assert!(nest(value::MAX_DICTIONARY_DEPTH).hash().is_ok());
+        assert!(nest(value::MAX_DICTIONARY_DEPTH + 1).hash().is_err());
+        assert!(nest(2).hash()? != nest(1).hash()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_with_dictionary() -> Result<(), Error> {
+        let private_key = PrivateKey { key: [5u8; 32] };
+        let inner = IndexMap::from([
+            ("z".to_string(), PodValue::Boolean(true)),
+            ("a".to_string(), PodValue::Dictionary(IndexMap::new())),
+        ]);
+        let pod = Pod::sign(
+            crate::pod_entries!["name" => "dict", "nested" => inner.clone()],
+            private_key,
+        )?;
+        assert!(pod.verify()?);
+
+        // Keys get sorted at any depth, equality doesn't depend on their order
+        match pod.get("nested") {
+            Some(PodValue::Dictionary(d)) => {
+                assert_eq!(d.keys().collect::<Vec<_>>(), ["a", "z"]);
+                assert_eq!(d, &inner);
+            }
+            value => panic!("Expected a dictionary, got {:?}", value),
+        }
+
+        let serialised_pod = serde_json::to_string(&pod)?;
+        assert!(serialised_pod.contains("\"dictionary\":{\"a\":{\"dictionary\":{}}"));
+        let deserialised_pod: Pod = serde_json::from_str(&serialised_pod)?;
+        assert!(deserialised_pod == pod);
+        assert!(deserialised_pod.verify()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_ordering() -> Result<(), Error> {
+        // Different types are ordered by variant, the same type by value
+        let mut values = vec![
+            PodValue::Null,
+            PodValue::Int(2),
+            PodValue::String("b".to_string()),
+            PodValue::Int(-1),
+            PodValue::String("a".to_string()),
+            PodValue::Boolean(false),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            [
+                PodValue::String("a".to_string()),
+                PodValue::String("b".to_string()),
+                PodValue::Int(-1),
+                PodValue::Int(2),
+                PodValue::Boolean(false),
+                PodValue::Null,
+            ]
+        );
+        assert!(PodValue::date_from_millis(0)? < PodValue::date_from_millis(1)?);
+
+        // Dictionaries compare by their entries sorted by key, whatever their insertion order
+        let dictionary = |entries: &[(&str, i64)]| {
+            PodValue::Dictionary(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), PodValue::Int(*v)))
+                    .collect(),
+            )
+        };
+        let ab = dictionary(&[("a", 1), ("b", 2)]);
+        let ba = dictionary(&[("b", 2), ("a", 1)]);
+        assert_eq!(ab.cmp(&ba), std::cmp::Ordering::Equal);
+        assert!(ab < dictionary(&[("a", 1), ("b", 3)]));
+        assert!(ab < dictionary(&[("a", 1), ("c", 0)]));
+        assert!(dictionary(&[("a", 1)]) < ab);
+        assert!(dictionary(&[]) < dictionary(&[("a", 1)]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_values() -> Result<(), Error> {
+        // Dates are hashed as the number of milliseconds since the Unix epoch, like in @pcd/pod
+        let issue_date = PodValue::date_from_millis(1_704_067_200_000)?;
+        assert_eq!(
+            issue_date,
+            PodValue::Date(datetime!(2024-01-01 00:00:00 UTC))
+        );
+        assert!(issue_date.hash()? == PodValue::Int(1_704_067_200_000).hash()?);
+        assert!(PodValue::date_from_millis(-1)?.hash()? == PodValue::Int(-1).hash()?);
+        assert!(PodValue::date_from_millis(i64::MAX).is_err());
+
+        // Precision beyond milliseconds is dropped, so dates survive serialisation unchanged
+        let date = PodValue::date(datetime!(2024-01-01 00:00:00.123_456_789 UTC));
+        assert_eq!(date, PodValue::date_from_millis(1_704_067_200_123)?);
+        let serialised_date = serde_json::to_string(&date)?;
+        assert_eq!(serialised_date, "{\"date\":1704067200123}");
+        assert_eq!(serde_json::from_str::<PodValue>(&serialised_date)?, date);
+
+        // Matches the content ID of the @pcd/pod test vector signing this date
+        let pod = create_test_pod2()?;
+        let entries = pod
+            .entries()
+            .clone()
+            .into_iter()
+            .map(|(k, v)| match k.as_str() {
+                "issueDate" => (k, issue_date.clone()),
+                _ => (k, v),
+            });
+        let private_key = PrivateKey {
+            key: array::from_fn(|i| (i % 10) as u8),
+        };
+        assert!(
+            Pod::sign_from_iter(entries, private_key)?.content_id()?
+                == Fq::from_str(
+                    "14490445713061892907571559700953246722753167030842690801373581812224357192993"
+                )
+                .map_err(|e| format!("{:?}", e))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_getters() -> Result<(), Error> {
+        let pod = create_test_pod()?;
+        assert_eq!(pod.get_int("E"), Some(-123));
+        assert_eq!(pod.get_string("C"), Some("hello"));
+        assert_eq!(pod.get_cryptographic("F"), Some(&Fq::from(-1)));
+        assert_eq!(create_test_pod2()?.get_bool("isConsumed"), Some(true));
+
+        // Other types and missing keys
+        assert_eq!(pod.get_int("C"), None);
+        assert_eq!(pod.get_string("E"), None);
+        assert_eq!(pod.get_bool("A"), None);
+        assert_eq!(pod.get_cryptographic("A"), None);
+        assert_eq!(pod.get_int("missing"), None);
+        assert_eq!(pod.get_string("missing"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_entry_proofs() -> Result<(), Error> {
+        let pod = create_test_pod()?;
+        let content_id = pod.content_id()?;
+        for (key, value) in pod.entries() {
+            let proof = pod.entry_proof(key)?;
+            assert!(verify_entry_proof(content_id, key, value, &proof));
+        }
+
+        // Tampered values, other keys and other pods don't verify
+        let proof = pod.entry_proof("A")?;
+        assert!(!verify_entry_proof(
+            content_id,
+            "A",
+            &PodValue::Int(124),
+            &proof
+        ));
+        assert!(!verify_entry_proof(
+            content_id,
+            "B",
+            &PodValue::Int(123),
+            &proof
+        ));
+        assert!(!verify_entry_proof(
+            create_test_pod2()?.content_id()?,
+            "A",
+            &PodValue::Int(123),
+            &proof
+        ));
+        // Nor does the key's leaf pass for the value's
+        let mut key_proof = proof.clone();
+        key_proof.index ^= 1;
+        assert!(!verify_entry_proof(
+            content_id,
+            "A",
+            &PodValue::Int(123),
+            &key_proof
+        ));
+
+        assert!(pod.entry_proof("missing").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_sorted() -> Result<(), Error> {
+        let mut pod = create_test_pod()?;
+        let keys: Vec<String> = pod.iter().map(|(k, _)| k.clone()).collect();
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort();
+        assert_eq!(keys, sorted_keys);
+        assert!(pod.iter().eq(pod.entries_sorted()));
+
+        // The content ID hashes the entries in the order of `entries_sorted`
+        let hashes = pod
+            .entries_sorted()
+            .flat_map(|(k, v)| [PodValue::String(k.to_string()).hash(), v.hash()])
+            .collect::<Result<Vec<_>, PodCreationError>>()?;
+        let content_id = pod.content_id()?;
+        assert_eq!(lean_poseidon_imt(&hashes)?, content_id);
+
+        // Which doesn't depend on the order entries are stored in
+        pod.claim.entries.reverse();
+        assert!(pod.iter().map(|(k, _)| k).eq(sorted_keys.iter().rev()));
+        assert!(pod.entries_sorted().map(|(k, _)| k).eq(sorted_keys.iter()));
+        assert_eq!(pod.content_id()?, content_id);
+        assert!(pod.verify()?);
+        let proof = pod.entry_proof("A")?;
+        assert!(verify_entry_proof(
+            content_id,
+            "A",
+            &PodValue::Int(123),
+            &proof
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_at() -> Result<(), Error> {
+        let private_key = [5u8; 32];
+        let not_before = datetime!(2024-01-01 0:00 UTC);
+        let expires_at = datetime!(2025-01-01 0:00 UTC);
+        let pod = create_pod(
+            &private_key,
+            crate::pod_entries![
+                "seat" => "A12",
+                NOT_BEFORE_KEY => not_before,
+                EXPIRES_AT_KEY => expires_at,
+            ],
+        )?;
+
+        let valid = datetime!(2024-06-01 0:00 UTC);
+        assert_eq!(pod.verify_at(valid)?, VerifyOutcome::Valid);
+        assert_eq!(pod.verify_at(not_before)?, VerifyOutcome::Valid);
+        assert_eq!(
+            pod.verify_at(datetime!(2023-12-31 23:59 UTC))?,
+            VerifyOutcome::NotYetValid { at: not_before }
+        );
+        assert_eq!(
+            pod.verify_at(expires_at)?,
+            VerifyOutcome::Expired { at: expires_at }
+        );
+
+        // The dates are optional
+        let pod = create_pod(&private_key, crate::pod_entries!["seat" => "A12"])?;
+        assert_eq!(pod.verify_at(valid)?, VerifyOutcome::Valid);
+
+        let mut tampered = create_pod(
+            &private_key,
+            crate::pod_entries![EXPIRES_AT_KEY => expires_at],
+        )?;
+        tampered
+            .claim
+            .entries
+            .insert(EXPIRES_AT_KEY.to_string(), PodValue::Date(valid));
+        assert_eq!(tampered.verify_at(valid)?, VerifyOutcome::SignatureInvalid);
+
+        // Dates of the wrong type are an error, not a pass
+        let pod = create_pod(&private_key, crate::pod_entries![EXPIRES_AT_KEY => 1234])?;
+        assert!(pod.verify_at(valid).is_err());
+        let pod = create_pod(&private_key, crate::pod_entries![NOT_BEFORE_KEY => "soon"])?;
+        assert!(pod.verify_at(valid).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_entries() -> Result<(), Error> {
+        // The BN254 scalar field modulus
+        let modulus =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        let entries = |value: &str| format!(r#"{{"owner": {{"cryptographic": "{}"}}}}"#, value);
+        let below: PodEntries = serde_json::from_str(&entries(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616",
+        ))?;
+        assert!(below.validate().is_ok());
+        assert_eq!(below["owner"], PodValue::Cryptographic(-Fq::from(1)));
+        assert!(serde_json::from_str::<PodEntries>(&entries(modulus)).is_err());
+        assert!(serde_json::from_str::<PodEntries>(&entries(&format!("{}0", modulus))).is_err());
+        assert!(serde_json::from_str::<PodEntries>(&entries(
+            "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001"
+        ))
+        .is_err());
+
+        // Dictionaries nested too deep are rejected before signing
+        let mut value = PodValue::Int(1);
+        for _ in 0..=value::MAX_DICTIONARY_DEPTH {
+            value = PodValue::Dictionary(IndexMap::from([("a".to_string(), value)]));
+        }
+        let deep = PodEntries::from([("deep".to_string(), value)]);
+        assert!(matches!(
+            deep.validate(),
+            Err(PodCreationError::ValueError(e)) if e.starts_with("entry deep")
+        ));
+        assert!(matches!(
+            Pod::sign_from_iter(deep, PrivateKey { key: [5u8; 32] }),
+            Err(PodCreationError::ValueError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let private_key = [5u8; 32];
+        assert!(matches!(
+            create_pod(&private_key, crate::pod_entries!["A" => 1, "B" => 2, "A" => 2]),
+            Err(PodCreationError::DuplicateKey(key)) if key == "A"
+        ));
+        assert!(matches!(
+            Pod::sign(
+                crate::pod_entries!["A" => 1, "A" => 1],
+                PrivateKey { key: private_key }
+            ),
+            Err(PodCreationError::DuplicateKey(_))
+        ));
+        assert!(matches!(
+            PodBuilder::new()
+                .add("A", 1)
+                .add("A", 2)
+                .sign(PrivateKey { key: private_key }),
+            Err(PodCreationError::DuplicateKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_with_signer() -> Result<(), Error> {
+        let pod = create_test_pod()?;
+        let expected = PrivateKey {
+            key: [
+                0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 1, 2, 3, 4, 5, 6, 7,
+                8, 9, 0, 1,
+            ],
+        }
+        .public();
+        assert!(pod.verify_with_signer(&expected)?);
+        assert!(pod.verify_with_signer_bytes(&expected.compress())?);
+
+        let other = PrivateKey { key: [7; 32] }.public();
+        assert!(!pod.verify_with_signer(&other)?);
+        assert!(!pod.verify_with_signer_bytes(&other.compress())?);
+        Ok(())
+    }
+
     #[test]
     fn test_pod_content_id() -> Result<(), PodCreationError> {
         let pod = create_test_pod()?;
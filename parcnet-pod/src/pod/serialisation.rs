@@ -35,19 +35,26 @@ where
 }
 
 /// Deserialisation procedure for elements of Fq. Parses decimal and
-/// hexadecimal strings. The latter must be prefixed with "0x".
+/// hexadecimal strings. The latter must be prefixed with "0x". Values that
+/// aren't reduced modulo the field are rejected rather than reduced.
 pub fn fq_de<'de, D>(data: D) -> Result<Fq, D::Error>
 where
     D: serde::de::Deserializer<'de>,
 {
     let s: &str = serde::de::Deserialize::deserialize(data)?;
-    if &s[0..2] == "0x" {
+    let x = if s.starts_with("0x") {
         num_bigint::BigUint::from_str_radix(&s[2..], 16)
     } else {
         num_bigint::BigUint::from_str(s)
     }
-    .map_err(serde::de::Error::custom)
-    .map(Fq::from)
+    .map_err(serde::de::Error::custom)?;
+    if x >= num_bigint::BigUint::from(Fq::MODULUS) {
+        return Err(serde::de::Error::custom(format!(
+            "{} is not below the field modulus",
+            s
+        )));
+    }
+    Ok(Fq::from(x))
 }
 
 /// Serialisation procedure for points (elements of Fq x Fq). Yields an unpadded Base64 string
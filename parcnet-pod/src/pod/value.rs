@@ -1,13 +1,19 @@
 pub use ark_bn254::Fr as Fq;
 use ark_ff::PrimeField;
 use babyjubjub_ark::Point;
+use indexmap::IndexMap;
 use poseidon_ark::Poseidon;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use time::{serde::timestamp::milliseconds, OffsetDateTime};
+use std::cmp::Ordering;
+use time::{serde::timestamp::milliseconds, Duration, OffsetDateTime};
 
 use super::serialisation::*;
 use super::PodCreationError;
+use crate::crypto::lean_imt::ImtBuilder;
+
+/// How deeply dictionaries may be nested inside an entry value.
+pub const MAX_DICTIONARY_DEPTH: usize = 16;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -31,10 +37,102 @@ pub enum PodValue {
     Date(OffsetDateTime),
     #[serde(serialize_with = "null_ser", deserialize_with = "null_de")]
     Null,
+    /// Nested entries. Like the entries of a pod, equality and ordering don't depend on the
+    /// order of the keys and the hash is taken over the entries sorted by key.
+    Dictionary(IndexMap<String, PodValue>),
+}
+
+/// Values of different types are ordered by type, in the order of the variants above. Values of
+/// the same type compare naturally: public keys by x then y coordinate, dates chronologically
+/// and dictionaries lexicographically by their entries sorted by key.
+impl Ord for PodValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sorted_entries = |d: &IndexMap<String, PodValue>| {
+            let mut entries = d.iter().collect::<Vec<_>>();
+            entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            entries
+        };
+        match (self, other) {
+            (PodValue::String(a), PodValue::String(b)) => a.cmp(b),
+            (PodValue::Int(a), PodValue::Int(b)) => a.cmp(b),
+            (PodValue::Cryptographic(a), PodValue::Cryptographic(b)) => a.cmp(b),
+            (PodValue::EdDSAPublicKey(a), PodValue::EdDSAPublicKey(b)) => {
+                (a.x, a.y).cmp(&(b.x, b.y))
+            }
+            (PodValue::Boolean(a), PodValue::Boolean(b)) => a.cmp(b),
+            (PodValue::Bytes(a), PodValue::Bytes(b)) => a.cmp(b),
+            (PodValue::Date(a), PodValue::Date(b)) => a.cmp(b),
+            (PodValue::Null, PodValue::Null) => Ordering::Equal,
+            (PodValue::Dictionary(a), PodValue::Dictionary(b)) => {
+                sorted_entries(a).cmp(&sorted_entries(b))
+            }
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for PodValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl PodValue {
+    /// Position of the variant, which orders values of different types.
+    fn type_rank(&self) -> u8 {
+        match self {
+            PodValue::String(_) => 0,
+            PodValue::Int(_) => 1,
+            PodValue::Cryptographic(_) => 2,
+            PodValue::EdDSAPublicKey(_) => 3,
+            PodValue::Boolean(_) => 4,
+            PodValue::Bytes(_) => 5,
+            PodValue::Date(_) => 6,
+            PodValue::Null => 7,
+            PodValue::Dictionary(_) => 8,
+        }
+    }
+
+    /// Date from the number of milliseconds since the Unix epoch, which is what gets hashed.
+    pub fn date_from_millis(millis: i64) -> Result<Self, PodCreationError> {
+        OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000)
+            .map(PodValue::Date)
+            .map_err(|e| PodCreationError::ValueError(format!("date out of range: {}", e)))
+    }
+
+    /// Date truncated to milliseconds, the precision dates are hashed and serialised with.
+    pub fn date(t: OffsetDateTime) -> Self {
+        let millis = t.unix_timestamp_nanos() / 1_000_000;
+        PodValue::Date(
+            t - Duration::nanoseconds((t.unix_timestamp_nanos() - millis * 1_000_000) as i64),
+        )
+    }
+
     pub fn hash(&self) -> Result<Fq, PodCreationError> {
+        self.hash_at_depth(0)
+    }
+
+    /// Checks that the value can be hashed the same way as by `@pcd/pod`. Ints are in the
+    /// signed 64-bit range and cryptographic values are reduced modulo the field by
+    /// construction (deserialisation rejects larger ones), so this checks dictionary nesting.
+    pub fn validate(&self) -> Result<(), PodCreationError> {
+        self.validate_at_depth(0)
+    }
+
+    fn validate_at_depth(&self, depth: usize) -> Result<(), PodCreationError> {
+        match self {
+            PodValue::Dictionary(_) if depth >= MAX_DICTIONARY_DEPTH => {
+                Err(PodCreationError::ValueError(format!(
+                    "dictionaries are nested more than {} deep",
+                    MAX_DICTIONARY_DEPTH
+                )))
+            }
+            PodValue::Dictionary(d) => d.values().try_for_each(|v| v.validate_at_depth(depth + 1)),
+            _ => Ok(()),
+        }
+    }
+
+    fn hash_at_depth(&self, depth: usize) -> Result<Fq, PodCreationError> {
         let hasher = |input_type, x| {
             Poseidon::new().hash(x).map_err(|e| {
                 PodCreationError::HashError(format!("{} hash failed: {}", input_type, e))
@@ -53,6 +151,37 @@ impl PodValue {
             PodValue::Null => Ok(PrimeField::from_be_bytes_mod_order(
                 &[0; 32].iter().map(|_| 0x1d).collect::<Vec<_>>(),
             )),
+            PodValue::Dictionary(_) if depth >= MAX_DICTIONARY_DEPTH => {
+                Err(PodCreationError::HashError(format!(
+                    "Dictionary hash failed: dictionaries are nested more than {} deep",
+                    MAX_DICTIONARY_DEPTH
+                )))
+            }
+            // The IMT is undefined without leaves, so the empty dictionary gets a hash of its own
+            PodValue::Dictionary(d) if d.is_empty() => {
+                hasher("Dictionary", vec![Fq::from(0), Fq::from(0)])
+            }
+            // Same as the content ID of a pod with these entries
+            PodValue::Dictionary(d) => {
+                let mut entries = d.iter().collect::<Vec<_>>();
+                entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                let mut builder = ImtBuilder::new();
+                for (k, v) in entries {
+                    let value_hash = v.hash_at_depth(depth + 1)?;
+                    for leaf in [string_hash(k), value_hash] {
+                        builder.push(leaf).map_err(|_| PodCreationError::ImtError)?;
+                    }
+                }
+                builder.root().map_err(|_| PodCreationError::ImtError)
+            }
+        }
+    }
+
+    /// Sorts the keys of dictionaries, at any depth, so equal values serialise the same way.
+    pub(crate) fn sort_dictionary_keys(&mut self) {
+        if let PodValue::Dictionary(d) = self {
+            d.sort_keys();
+            d.values_mut().for_each(PodValue::sort_dictionary_keys);
         }
     }
 }
@@ -81,6 +210,12 @@ impl From<Fq> for PodValue {
     }
 }
 
+impl From<Point> for PodValue {
+    fn from(pt: Point) -> Self {
+        PodValue::EdDSAPublicKey(pt)
+    }
+}
+
 impl From<bool> for PodValue {
     fn from(b: bool) -> Self {
         PodValue::Boolean(b)
@@ -99,6 +234,12 @@ impl From<&[u8]> for PodValue {
     }
 }
 
+impl From<IndexMap<String, PodValue>> for PodValue {
+    fn from(d: IndexMap<String, PodValue>) -> Self {
+        PodValue::Dictionary(d)
+    }
+}
+
 impl From<()> for PodValue {
     fn from(_: ()) -> Self {
         PodValue::Null
@@ -1,6 +1,25 @@
-use crate::pod::Pod;
+use std::str::FromStr;
+
+use ark_ff::PrimeField;
+use babyjubjub_ark::{decompress_point, decompress_signature};
+use base64::{
+    alphabet,
+    engine::{general_purpose, DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use num_bigint::{BigInt, BigUint};
+use num_traits::Num;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset};
 use url::Url;
+use uuid::Uuid;
+
+use crate::pod::{Fq, Pod, PodClaim, PodEntries, PodProof, PodValue};
+
+/// The PCD type of pods in Zupass.
+pub const POD_PCD_TYPE: &str = "pod-pcd";
 
 #[derive(Serialize, Deserialize)]
 struct ZupassPcdWrapper {
@@ -26,7 +45,7 @@ impl Pod {
             request_type: "Add".to_string(),
             return_url: return_url.to_string(),
             pcd: ZupassPcdWrapper {
-                pcd_type: "pod-pcd".to_string(),
+                pcd_type: POD_PCD_TYPE.to_string(),
                 pcd: pcd_json,
             },
         };
@@ -42,10 +61,280 @@ impl Pod {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum ZupassError {
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Expected a pod-pcd, got a {0}")]
+    WrongPcdType(String),
+    #[error("Invalid entry name {0}: names start with a letter or _ and only have letters, digits and _")]
+    InvalidName(String),
+    #[error("Entry {key} has a {value_type} value, which Zupass doesn't support")]
+    UnsupportedValue { key: String, value_type: String },
+    #[error("Invalid value for entry {key}: {reason}")]
+    InvalidValue { key: String, reason: String },
+    #[error("Invalid {0}")]
+    InvalidProof(&'static str),
+}
+
+/// The `pcd` field of a serialised pod PCD, as `@pcd/pod-pcd` writes it.
+#[derive(Serialize, Deserialize)]
+struct PodPcd {
+    id: Uuid,
+    #[serde(rename = "jsonPOD")]
+    json_pod: JsonPod,
+}
+
+/// `JSONPOD` in `@pcd/pod`, whose fields are in this order.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonPod {
+    entries: Map<String, Value>,
+    signature: String,
+    signer_public_key: String,
+}
+
+/// Integers up to `Number.MAX_SAFE_INTEGER` are written as JSON numbers, bigger ones as strings.
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// `@pcd/pod` accepts Base64 with or without padding.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Serialises `pod` as a pod PCD with the given PCD ID, as `@pcd/pod-pcd` does: a
+/// `{"type": "pod-pcd", "pcd": ...}` envelope whose `pcd` is the JSON string of the ID and the
+/// `JSONPOD` of the pod. Entries are sorted by name, small integers are written as numbers,
+/// and the signer's public key and the signature are compressed and in unpadded Base64.
+pub fn to_pod_pcd(pod: &Pod, id: Uuid) -> Result<String, ZupassError> {
+    let entries = pod
+        .entries_sorted()
+        .map(|(key, value)| {
+            if !is_valid_name(key) {
+                return Err(ZupassError::InvalidName(key.clone()));
+            }
+            Ok((key.clone(), value_to_json(key, value)?))
+        })
+        .collect::<Result<_, _>>()?;
+    let pcd = PodPcd {
+        id,
+        json_pod: JsonPod {
+            entries,
+            signature: general_purpose::STANDARD_NO_PAD.encode(pod.signature().compress()),
+            signer_public_key: general_purpose::STANDARD_NO_PAD
+                .encode(pod.signer_public_key().compress()),
+        },
+    };
+    Ok(serde_json::to_string(&ZupassPcdWrapper {
+        pcd_type: POD_PCD_TYPE.to_string(),
+        pcd: serde_json::to_string(&pcd)?,
+    })?)
+}
+
+/// Parses a pod PCD serialised by `to_pod_pcd` or by `@pcd/pod-pcd`. The pod gets the ID of
+/// the PCD. Like `@pcd/pod-pcd`, this doesn't check the signature, see `Pod::verify`.
+pub fn from_pod_pcd(json: &str) -> Result<Pod, ZupassError> {
+    let wrapper: ZupassPcdWrapper = serde_json::from_str(json)?;
+    if wrapper.pcd_type != POD_PCD_TYPE {
+        return Err(ZupassError::WrongPcdType(wrapper.pcd_type));
+    }
+    let PodPcd { id, json_pod } = serde_json::from_str(&wrapper.pcd)?;
+
+    let mut entries = json_pod
+        .entries
+        .iter()
+        .map(|(key, value)| {
+            if !is_valid_name(key) {
+                return Err(ZupassError::InvalidName(key.clone()));
+            }
+            Ok((key.clone(), value_from_json(key, value)?))
+        })
+        .collect::<Result<PodEntries, _>>()?;
+    entries.sort_keys();
+
+    let signer_public_key = decode_bytes(&json_pod.signer_public_key)
+        .and_then(|bytes| decompress_point(bytes).ok())
+        .ok_or(ZupassError::InvalidProof("signer public key"))?;
+    let signature = decode_bytes(&json_pod.signature)
+        .and_then(|bytes| decompress_signature(&bytes).ok())
+        .ok_or(ZupassError::InvalidProof("signature"))?;
+    Ok(Pod {
+        id,
+        claim: PodClaim {
+            entries,
+            signer_public_key,
+        },
+        proof: PodProof { signature },
+    })
+}
+
+/// Entry names must match `[A-Za-z_]\w*` in `@pcd/pod`.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Bytes in hex or Base64, the encodings `@pcd/pod` accepts for keys and signatures.
+fn decode_bytes<const N: usize>(s: &str) -> Option<[u8; N]> {
+    let bytes = if s.len() == 2 * N {
+        hex::decode(s).ok()?
+    } else {
+        BASE64.decode(s).ok()?
+    };
+    bytes.try_into().ok()
+}
+
+/// A number when it's a safe integer in JavaScript, a hex string otherwise.
+fn bigint_to_json(x: &BigInt) -> Value {
+    match i64::try_from(x) {
+        Ok(i) if i.unsigned_abs() <= MAX_SAFE_INTEGER => json!(i),
+        _ if x.sign() == num_bigint::Sign::Minus => json!(format!("-0x{:x}", x.magnitude())),
+        _ => json!(format!("0x{:x}", x)),
+    }
+}
+
+/// Integers are written as JSON numbers or as decimal or hex strings, possibly negative.
+fn bigint_from_json(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| n.as_u64().map(BigInt::from)),
+        Value::String(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s.as_str()),
+            };
+            let magnitude = match digits.strip_prefix("0x") {
+                Some(hex) => BigUint::from_str_radix(hex, 16).ok()?,
+                None => BigUint::from_str(digits).ok()?,
+            };
+            let x = BigInt::from(magnitude);
+            Some(if negative { -x } else { x })
+        }
+        _ => None,
+    }
+}
+
+fn value_to_json(key: &str, value: &PodValue) -> Result<Value, ZupassError> {
+    Ok(match value {
+        PodValue::String(s) => json!(s),
+        PodValue::Int(i) if i.unsigned_abs() <= MAX_SAFE_INTEGER => json!(i),
+        PodValue::Int(i) => json!({ "int": bigint_to_json(&BigInt::from(*i)) }),
+        PodValue::Cryptographic(c) => {
+            json!({ "cryptographic": bigint_to_json(&BigUint::from(c.into_bigint()).into()) })
+        }
+        PodValue::EdDSAPublicKey(pt) => {
+            json!({ "eddsa_pubkey": general_purpose::STANDARD_NO_PAD.encode(pt.compress()) })
+        }
+        PodValue::Boolean(b) => json!(b),
+        PodValue::Bytes(b) => json!({ "bytes": general_purpose::STANDARD.encode(b) }),
+        // `Date.toISOString`, which always has milliseconds
+        PodValue::Date(t) => {
+            let t = t.to_offset(UtcOffset::UTC);
+            if !(0..=9999).contains(&t.year()) {
+                return Err(ZupassError::InvalidValue {
+                    key: key.to_string(),
+                    reason: format!("year {} is out of range", t.year()),
+                });
+            }
+            json!({
+                "date": format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                    t.year(),
+                    u8::from(t.month()),
+                    t.day(),
+                    t.hour(),
+                    t.minute(),
+                    t.second(),
+                    t.millisecond()
+                )
+            })
+        }
+        PodValue::Null => Value::Null,
+        PodValue::Dictionary(_) => {
+            return Err(ZupassError::UnsupportedValue {
+                key: key.to_string(),
+                value_type: "dictionary".to_string(),
+            })
+        }
+    })
+}
+
+fn value_from_json(key: &str, value: &Value) -> Result<PodValue, ZupassError> {
+    let invalid = |reason: String| ZupassError::InvalidValue {
+        key: key.to_string(),
+        reason,
+    };
+    let unsupported = |value_type: &str| ZupassError::UnsupportedValue {
+        key: key.to_string(),
+        value_type: value_type.to_string(),
+    };
+    let int = |value: &Value| {
+        bigint_from_json(value)
+            .and_then(|x| i64::try_from(x).ok())
+            .map(PodValue::Int)
+            .ok_or_else(|| invalid(format!("{} is not a 64-bit integer", value)))
+    };
+
+    match value {
+        Value::String(s) => Ok(PodValue::String(s.clone())),
+        Value::Bool(b) => Ok(PodValue::Boolean(*b)),
+        Value::Null => Ok(PodValue::Null),
+        Value::Number(_) => int(value),
+        Value::Array(_) => Err(unsupported("array")),
+        Value::Object(object) => {
+            let mut fields = object.iter();
+            let (Some((value_type, inner)), None) = (fields.next(), fields.next()) else {
+                return Err(invalid(format!("{} should have exactly one field", value)));
+            };
+            match value_type.as_str() {
+                "int" => int(inner),
+                "cryptographic" => bigint_from_json(inner)
+                    .and_then(|x| x.to_biguint())
+                    .filter(|x| *x < BigUint::from(Fq::MODULUS))
+                    .map(|x| PodValue::Cryptographic(Fq::from(x)))
+                    .ok_or_else(|| invalid(format!("{} is not a field element", inner))),
+                "eddsa_pubkey" => inner
+                    .as_str()
+                    .and_then(decode_bytes)
+                    .and_then(|bytes| decompress_point(bytes).ok())
+                    .map(PodValue::EdDSAPublicKey)
+                    .ok_or_else(|| invalid(format!("{} is not a public key", inner))),
+                "bytes" => inner
+                    .as_str()
+                    .and_then(|s| BASE64.decode(s).ok())
+                    .map(PodValue::Bytes)
+                    .ok_or_else(|| invalid(format!("{} is not Base64", inner))),
+                "date" => inner
+                    .as_str()
+                    .and_then(|s| OffsetDateTime::parse(s, &Rfc3339).ok())
+                    .map(PodValue::date)
+                    .ok_or_else(|| invalid(format!("{} is not an ISO 8601 date", inner))),
+                value_type => Err(unsupported(value_type)),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use babyjubjub_ark::PrivateKey;
+    use indexmap::IndexMap;
+    use time::macros::datetime;
+
     use super::*;
-    use crate::pod::{create_pod, PodValue};
+    use crate::pod::{create_pod, Error, PodValue};
+
+    /// Pod PCDs in the `@pcd/pod-pcd` format with the entries and signatures of the two examples
+    /// in test/common.ts in the @pcd/pod package. These are reference vectors rather than PCDs
+    /// exported from Zupass, so their PCD IDs are nil.
+    const SAMPLE1: &str = include_str!("../../fixtures/zupass/sample1.json");
+    const SAMPLE2: &str = include_str!("../../fixtures/zupass/sample2.json");
 
     #[test]
     fn test_make_zupass_url_two_value_failing_zupass_verification() {
@@ -110,4 +399,188 @@ mod tests {
         );
         assert_eq!(parsed_pod.get("attack"), Some(&PodValue::Int(7)));
     }
+
+    #[test]
+    fn test_pod_pcd_fixtures() -> Result<(), Error> {
+        const SIGNER_PUBLIC_KEY: &str = "xDP3ppa3qjpSJO+zmTuvDM2eku7O4MKaP2yCCKnoHZ4";
+        let owner = Fq::from_str(
+            "18711405342588116796533073928767088921854096266145046362753928030796553161041",
+        )
+        .map_err(|e| format!("{:?}", e))?;
+        let public_key = decode_bytes(SIGNER_PUBLIC_KEY)
+            .and_then(|bytes| decompress_point(bytes).ok())
+            .ok_or("invalid public key")?;
+
+        // The signatures and entries @pcd/pod gives the examples
+        for (fixture, signature, entries) in [
+            (
+                SAMPLE1,
+                "Jp3i2PnnRoLCmVPzgM6Bowchg44jz3fKuMQPzXQqWy4jzPFpZx2KwLuaIYaeYbd7Ah4FusEht2VhsVf3I81AAg",
+                crate::pod_entries![
+                    "A" => 123,
+                    "B" => 321,
+                    "C" => "hello",
+                    "D" => "foobar",
+                    "E" => -123,
+                    "F" => Fq::from(-1),
+                    "G" => 7,
+                    "H" => 8,
+                    "I" => 9,
+                    "J" => 10,
+                    "owner" => owner,
+                    "publicKey" => public_key,
+                ],
+            ),
+            (
+                SAMPLE2,
+                "XsPL63NJKkq59CiO8VC3vDFNGPeNfnDsN3ugn68aOQjOvAMLiRqE2ISEBQSJlAxb9eokyyauUuKlGyD98FeSBQ",
+                crate::pod_entries![
+                    "attendee" => owner,
+                    "eventID" => Fq::from(456),
+                    "image" => [1u8, 2, 3].as_slice(),
+                    "isConsumed" => true,
+                    "issueDate" => datetime!(2024-01-01 00:00:00 UTC),
+                    "ticketID" => Fq::from(999),
+                    "vipStatus" => (),
+                ],
+            ),
+        ] {
+            let pod = from_pod_pcd(fixture)?;
+            assert_eq!(
+                general_purpose::STANDARD_NO_PAD.encode(pod.signature().compress()),
+                signature
+            );
+            assert_eq!(
+                general_purpose::STANDARD_NO_PAD.encode(pod.signer_public_key().compress()),
+                SIGNER_PUBLIC_KEY
+            );
+            assert_eq!(
+                pod.entries_sorted().collect::<Vec<_>>(),
+                entries.iter().map(|(key, value)| (key, value)).collect::<Vec<_>>()
+            );
+            assert!(pod.verify()?);
+
+            // Exported field by field as in the fixture, whatever the key order and spacing
+            let json_pod = |json: &str| -> Result<Value, Error> {
+                let wrapper: ZupassPcdWrapper = serde_json::from_str(json)?;
+                let pcd: Value = serde_json::from_str(&wrapper.pcd)?;
+                Ok(pcd["jsonPOD"].clone())
+            };
+            let (expected, exported) = (json_pod(fixture)?, json_pod(&to_pod_pcd(&pod, pod.id)?)?);
+            for field in ["signature", "signerPublicKey", "entries"] {
+                assert_eq!(exported[field], expected[field], "{}", field);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pod_pcd_round_trip() -> Result<(), Error> {
+        let pod = create_pod(
+            &[3u8; 32],
+            crate::pod_entries![
+                "small" => -7,
+                "min" => i64::MIN,
+                "safe" => (1i64 << 53) - 1,
+                "unsafe" => 1i64 << 53,
+                "zero" => Fq::from(0),
+                "big" => Fq::from(-1),
+                "name" => "Zoë",
+                "flag" => false,
+                "key" => PrivateKey { key: [1u8; 32] }.public(),
+                "when" => PodValue::date_from_millis(1_704_067_200_123)?,
+                "blob" => [0u8, 255].as_slice(),
+                "nothing" => (),
+            ],
+        )?;
+
+        // Integers beyond Number.MAX_SAFE_INTEGER are written as hex strings
+        let json = to_pod_pcd(&pod, pod.id)?;
+        for expected in [
+            r#"\"small\":-7"#,
+            r#"\"min\":{\"int\":\"-0x8000000000000000\"}"#,
+            r#"\"safe\":9007199254740991"#,
+            r#"\"unsafe\":{\"int\":\"0x20000000000000\"}"#,
+            r#"\"zero\":{\"cryptographic\":0}"#,
+            r#"\"when\":{\"date\":\"2024-01-01T00:00:00.123Z\"}"#,
+            r#"\"blob\":{\"bytes\":\"AP8=\"}"#,
+            r#"\"nothing\":null"#,
+        ] {
+            assert!(json.contains(expected), "{} not in {}", expected, json);
+        }
+
+        let imported = from_pod_pcd(&json)?;
+        assert!(imported == pod);
+        assert!(imported.content_id()? == pod.content_id()?);
+        assert!(imported.verify()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pod_pcd_errors() -> Result<(), Error> {
+        let private_key = [3u8; 32];
+        let nested = create_pod(
+            &private_key,
+            crate::pod_entries![
+                "nested" => IndexMap::from([("a".to_string(), PodValue::Int(1))])
+            ],
+        )?;
+        assert!(matches!(
+            to_pod_pcd(&nested, Uuid::nil()),
+            Err(ZupassError::UnsupportedValue { key, .. }) if key == "nested"
+        ));
+        let pod = create_pod(&private_key, crate::pod_entries!["a:b" => 1])?;
+        assert!(matches!(
+            to_pod_pcd(&pod, Uuid::nil()),
+            Err(ZupassError::InvalidName(_))
+        ));
+
+        // The first example with an entry replaced
+        let with_entry = |key: &str, value: Value| -> Result<String, Error> {
+            let wrapper: ZupassPcdWrapper = serde_json::from_str(SAMPLE1)?;
+            let mut pcd: Value = serde_json::from_str(&wrapper.pcd)?;
+            pcd["jsonPOD"]["entries"][key] = value;
+            Ok(serde_json::to_string(&ZupassPcdWrapper {
+                pcd: pcd.to_string(),
+                ..wrapper
+            })?)
+        };
+        // Integers may be decimal or hex, and negative
+        let pod = from_pod_pcd(&with_entry("E", json!({ "int": "-0x7b" }))?)?;
+        assert_eq!(pod.get_int("E"), Some(-123));
+        assert!(pod.verify()?);
+
+        for (value, value_type) in [
+            (json!({ "dictionary": {} }), "dictionary"),
+            (json!([1, 2]), "array"),
+        ] {
+            assert!(matches!(
+                from_pod_pcd(&with_entry("A", value)?),
+                Err(ZupassError::UnsupportedValue { value_type: t, .. }) if t == value_type
+            ));
+        }
+        for value in [
+            json!(1.5),
+            json!({ "int": "0x8000000000000000" }),
+            json!({ "cryptographic": "-1" }),
+            json!({ "cryptographic": "21888242871839275222246405745257275088548364400416034343698204186575808495617" }),
+            json!({ "eddsa_pubkey": "not a key" }),
+            json!({ "date": "yesterday" }),
+            json!({ "int": 1, "cryptographic": 1 }),
+        ] {
+            assert!(matches!(
+                from_pod_pcd(&with_entry("A", value)?),
+                Err(ZupassError::InvalidValue { key, .. }) if key == "A"
+            ));
+        }
+        assert!(matches!(
+            from_pod_pcd(&with_entry("a:b", json!(1))?),
+            Err(ZupassError::InvalidName(_))
+        ));
+        assert!(matches!(
+            from_pod_pcd(&SAMPLE1.replacen(POD_PCD_TYPE, "semaphore-identity-pcd", 1)),
+            Err(ZupassError::WrongPcdType(_))
+        ));
+        Ok(())
+    }
 }
@@ -0,0 +1,66 @@
+use std::sync::{Arc, Mutex};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pex::{eval, Env, InMemoryStore, MyPods, Value};
+use pod2::signature::schnorr::SchnorrSecretKey;
+use tokio::runtime::Runtime;
+
+fn setup_env() -> (Env, Arc<Mutex<MyPods>>) {
+    let pod_store = Arc::new(Mutex::new(MyPods::default()));
+    let env = Env::new(
+        "bench_user".to_string(),
+        Arc::new(InMemoryStore::new()),
+        pod_store.clone(),
+        Some(SchnorrSecretKey::from_u64(42)),
+        None,
+        None,
+    );
+    (env, pod_store)
+}
+
+fn benchmark_createpod(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let (env, _) = setup_env();
+    let script = "[createpod bench
+        name \"Alice\"
+        x 10
+        y 20
+        sum [+ x y]
+        product [* x y]
+        bigger [> y x]]";
+
+    c.bench_function("createpod script", |b| {
+        b.iter(|| rt.block_on(eval(black_box(script), env.clone())).unwrap())
+    });
+}
+
+fn benchmark_pod_query(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let (env, pod_store) = setup_env();
+    for i in 0..100 {
+        let script = format!("[createpod p{} x {} y {}]", i, i, 2 * i);
+        match rt.block_on(eval(&script, env.clone())).unwrap() {
+            Value::PodRef(pod) => pod_store.lock().unwrap().add_pod(pod),
+            _ => panic!("createpod didn't return a pod"),
+        }
+    }
+
+    c.bench_function("pod? query over 100 pods", |b| {
+        b.iter(|| {
+            rt.block_on(eval(black_box("[pod? [x 57] [y]]"), env.clone()))
+                .unwrap()
+        })
+    });
+    c.bench_function("createpod from a query over 100 pods", |b| {
+        b.iter(|| {
+            rt.block_on(eval(
+                black_box("[createpod derived total [+ [pod? [x 57]] 1]]"),
+                env.clone(),
+            ))
+            .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, benchmark_createpod, benchmark_pod_query);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use pex::{
+    server::{serve, ServerConfig, ServerState},
+    store::iroh::IrohStore,
+};
+use tokio::net::TcpListener;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+
+fn arg(args: &[String], name: &str) -> Result<Option<String>> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|i| {
+            args.get(i + 1)
+                .cloned()
+                .ok_or_else(|| anyhow!("{} requires a value", name))
+        })
+        .transpose()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let config_path = arg(&args, "--config")?
+        .ok_or_else(|| anyhow!("usage: pex-server --config path [--addr host:port]"))?;
+    let addr = arg(&args, "--addr")?.unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let config = ServerConfig::load(Path::new(&config_path))?;
+
+    // Users of the server share pods and values with each other and with peers
+    let shared = Arc::new(IrohStore::new(iroh::net::key::SecretKey::generate()));
+    let (sync_tx, sync_rx) = tokio::sync::oneshot::channel();
+    let init_shared = shared.clone();
+    tokio::spawn(async move {
+        if let Err(e) = init_shared.initialize(sync_tx).await {
+            println!("Error during initialization: {}", e);
+        }
+    });
+    if sync_rx.await.is_err() {
+        return Err(anyhow!("Initialization failed"));
+    }
+
+    let state = ServerState::new(config, shared)?;
+    let listener = TcpListener::bind(&addr).await?;
+    println!("Serving pex on http://{}", listener.local_addr()?);
+    serve(listener, Arc::new(state)).await
+}
@@ -0,0 +1,186 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use pod2::signature::schnorr::SchnorrSecretKey;
+use rand::Rng;
+
+// Key files are a single line, either
+//   pex-key-v2:plain:<secret key hex>
+//   pex-key-v2:encrypted:<salt hex>:<nonce hex>:<ciphertext hex>
+// where the secret key is 32 bytes. Encrypted keys use ChaCha20-Poly1305, keyed by Argon2id
+// applied to the passphrase and salt; the ciphertext ends with the 16 byte tag.
+const KEY_FILE_HEADER: &str = "pex-key-v2";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Derives the encryption key from the passphrase with Argon2id (default parameters).
+fn cipher(passphrase: &str, salt: &[u8; SALT_LEN]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Could not derive key from passphrase: {}", e))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn decode_hex<const LEN: usize>(s: &str, what: &str) -> Result<[u8; LEN]> {
+    let bytes = hex::decode(s).map_err(|e| anyhow!("Corrupt key file, invalid {}: {}", what, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Corrupt key file, invalid {} length", what))
+}
+
+/// Serializes the secret key, encrypting it if a passphrase is given.
+pub fn encode_key(sk: &SchnorrSecretKey, passphrase: Option<&str>) -> Result<String> {
+    let sk_bytes = sk.to_le_bytes();
+    match passphrase {
+        None => Ok(format!(
+            "{}:plain:{}",
+            KEY_FILE_HEADER,
+            hex::encode(sk_bytes)
+        )),
+        Some(passphrase) => {
+            let salt: [u8; SALT_LEN] = rand::thread_rng().gen();
+            let nonce: [u8; NONCE_LEN] = rand::thread_rng().gen();
+            let encrypted = cipher(passphrase, &salt)?
+                .encrypt(Nonce::from_slice(&nonce), sk_bytes.as_ref())
+                .map_err(|_| anyhow!("Could not encrypt secret key"))?;
+            Ok(format!(
+                "{}:encrypted:{}:{}:{}",
+                KEY_FILE_HEADER,
+                hex::encode(salt),
+                hex::encode(nonce),
+                hex::encode(encrypted)
+            ))
+        }
+    }
+}
+
+pub fn decode_key(contents: &str, passphrase: Option<&str>) -> Result<SchnorrSecretKey> {
+    let parts: Vec<&str> = contents.trim().split(':').collect();
+    match parts.as_slice() {
+        [KEY_FILE_HEADER, "plain", sk] => Ok(SchnorrSecretKey::from_le_bytes(&decode_hex(
+            sk,
+            "secret key",
+        )?)),
+        [KEY_FILE_HEADER, "encrypted", salt, nonce, encrypted] => Ok(
+            SchnorrSecretKey::from_le_bytes(&decrypt(passphrase, salt, nonce, encrypted)?),
+        ),
+        _ => Err(anyhow!("Corrupt key file, unrecognized format")),
+    }
+}
+
+fn decrypt(passphrase: Option<&str>, salt: &str, nonce: &str, encrypted: &str) -> Result<[u8; 32]> {
+    let passphrase =
+        passphrase.ok_or_else(|| anyhow!("Key file is encrypted, a passphrase is required"))?;
+    let salt: [u8; SALT_LEN] = decode_hex(salt, "salt")?;
+    let nonce: [u8; NONCE_LEN] = decode_hex(nonce, "nonce")?;
+    let encrypted = hex::decode(encrypted)
+        .map_err(|e| anyhow!("Corrupt key file, invalid secret key: {}", e))?;
+
+    cipher(passphrase, &salt)?
+        .decrypt(Nonce::from_slice(&nonce), encrypted.as_ref())
+        .map_err(|_| anyhow!("Wrong passphrase or corrupt key file"))?
+        .try_into()
+        .map_err(|_| anyhow!("Corrupt key file, invalid secret key length"))
+}
+
+/// Writes the key file through a temporary file, which is created readable only by the current
+/// user so that the key is never exposed, and renamed over `path` once fully written.
+pub fn save_key(path: &Path, sk: &SchnorrSecretKey, passphrase: Option<&str>) -> Result<()> {
+    let contents = encode_key(sk, passphrase)? + "\n";
+    let tmp_path = path.with_extension("tmp");
+    // A leftover from an interrupted save could have other permissions
+    let _ = std::fs::remove_file(&tmp_path);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&tmp_path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&tmp_path);
+            anyhow!("Could not write key file {}: {}", path.display(), e)
+        })
+}
+
+pub fn load_key(path: &Path, passphrase: Option<&str>) -> Result<SchnorrSecretKey> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read key file {}: {}", path.display(), e))?;
+    decode_key(&contents, passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_key_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pex-key-{}", rand::thread_rng().gen::<u64>()))
+    }
+
+    #[test]
+    fn test_plain_key_roundtrip() -> Result<()> {
+        let sk = SchnorrSecretKey::from_u64(4242);
+        let path = temp_key_path();
+        save_key(&path, &sk, None)?;
+        let loaded = load_key(&path, None);
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::metadata(&path)?.permissions().mode()
+        };
+        std::fs::remove_file(&path)?;
+        assert_eq!(loaded?, sk);
+        #[cfg(unix)]
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!path.with_extension("tmp").exists());
+
+        let seeded = SchnorrSecretKey::from_seed(&[7; 32]);
+        assert_eq!(decode_key(&encode_key(&seeded, None)?, None)?, seeded);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_key_roundtrip() -> Result<()> {
+        let sk = SchnorrSecretKey::from_u64(u64::MAX - 7);
+        let path = temp_key_path();
+        save_key(&path, &sk, Some("hunter2"))?;
+        let contents = std::fs::read_to_string(&path)?;
+        let loaded = load_key(&path, Some("hunter2"));
+        std::fs::remove_file(&path)?;
+
+        assert!(!contents.contains(&hex::encode(sk.to_le_bytes())));
+        assert_eq!(loaded?, sk);
+        assert!(decode_key(&contents, Some("wrong")).is_err());
+        assert!(decode_key(&contents, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_key_file() -> Result<()> {
+        assert!(decode_key("", None).is_err());
+        assert!(decode_key("pex-key-v2:plain:zz", None).is_err());
+        assert!(decode_key("pex-key-v1:plain:0102030405060708", None).is_err());
+        assert!(decode_key("pex-key-v2:plain:0000000000000000", None).is_err());
+        assert!(decode_key("pex-key-v3:plain:0000000000000000", None).is_err());
+
+        let encoded = encode_key(&SchnorrSecretKey::from_u64(1), Some("pass"))?;
+        let last = if encoded.ends_with('0') { "1" } else { "0" };
+        let tampered = format!("{}{}", &encoded[..encoded.len() - 1], last);
+        assert!(decode_key(&tampered, Some("pass")).is_err());
+
+        assert!(load_key(&temp_key_path(), None).is_err());
+        Ok(())
+    }
+}
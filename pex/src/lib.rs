@@ -1,16 +1,23 @@
 mod macros;
 mod pex_constants;
 use constants::{L, M, N, NS, VL};
+pub mod keystore;
+pub mod podfile;
 pub mod repl;
+pub mod server;
 pub mod store;
 
 use async_trait::async_trait;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Ordering,
     collections::{HashMap, HashSet},
+    fmt,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, SystemTime},
 };
+use tokio::sync::Notify;
 
 use pex_constants::*;
 
@@ -19,21 +26,27 @@ use tracing::info;
 use anyhow::{anyhow, Result};
 use async_recursion::async_recursion;
 use plonky2::{
-    field::{goldilocks_field::GoldilocksField, types::PrimeField64},
+    field::{
+        goldilocks_field::GoldilocksField,
+        types::{Field, PrimeField64},
+    },
     plonk::config::GenericHashOut,
 };
 
 use pod2::{
     pod::{
         entry::Entry,
-        gadget::{plonky_pod::ProverParams, GadgetID},
+        gadget::{plonky_pod::ProverParams, GadgetID, PlonkyButNotPlonkyGadget},
         origin::Origin,
         payload::HashablePayload,
-        statement::{AnchoredKey, StatementRef},
+        statement::{AnchoredKey, StatementId, StatementRef},
+        util::hash_string_to_field,
         value::ScalarOrVec,
-        GPGInput, Op, OpCmd, Statement, POD,
+        GPGInput, Op, OpCmd, Statement, POD, SIGNER_PK_KEY,
     },
+    recursion::{traits::IntroducerCircuitTrait, traits_examples::ExampleIntroducer},
     signature::schnorr::SchnorrSecretKey,
+    NUM_BITS,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -81,14 +94,14 @@ impl From<Origin> for ORef {
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct SRef(pub ORef, pub String);
+pub struct SRef(pub ORef, pub StatementId);
 
 impl SRef {
-    pub fn new(pod_id: impl Into<String>, statement_id: impl Into<String>) -> Self {
+    pub fn new(pod_id: impl Into<String>, statement_id: impl Into<StatementId>) -> Self {
         Self(ORef::P(pod_id.into()), statement_id.into())
     }
 
-    pub fn self_ref(statement_id: impl Into<String>) -> Self {
+    pub fn self_ref(statement_id: impl Into<StatementId>) -> Self {
         Self(ORef::S, statement_id.into())
     }
 }
@@ -105,14 +118,96 @@ impl<'a> From<&'a SRef> for StatementRef {
     }
 }
 
+/// What we know about a pod beyond its statements
+#[derive(Clone, Debug, Default)]
+pub struct PodMetadata {
+    /// Strings whose hashes the pod holds, by hash
+    pub strings: HashMap<GoldilocksField, String>,
+}
+
+/// Whether `MyPods::add_pod` added the pod, pods with the same content id being the same pod
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddedPod {
+    Added,
+    AlreadyPresent,
+}
+
 #[derive(Default)]
 pub struct MyPods {
-    pub pods: Vec<POD>,
+    /// By pod id, in the order they were added
+    pub pods: IndexMap<String, POD>,
+    /// By pod id
+    pub metadata: HashMap<String, PodMetadata>,
 }
 
 impl MyPods {
-    pub fn add_pod(&mut self, pod: POD) {
-        self.pods.push(pod);
+    /// Adds the pod unless we already have one with the same content id
+    pub fn add_pod(&mut self, pod: POD) -> AddedPod {
+        match self.pods.entry(PodBuilder::pod_id(&pod)) {
+            indexmap::map::Entry::Occupied(_) => AddedPod::AlreadyPresent,
+            indexmap::map::Entry::Vacant(entry) => {
+                entry.insert(pod);
+                AddedPod::Added
+            }
+        }
+    }
+
+    pub fn set_metadata(&mut self, pod: &POD, metadata: PodMetadata) {
+        self.metadata.insert(PodBuilder::pod_id(pod), metadata);
+    }
+
+    /// The string hashed into `value`, if any of our pods stores it
+    pub fn string_preimage(&self, value: GoldilocksField) -> Option<&String> {
+        self.metadata
+            .values()
+            .find_map(|metadata| metadata.strings.get(&value))
+    }
+
+    /// Finds a pod by id, also accepting ids in the format used before content IDs
+    pub fn get_pod(&self, id: &str) -> Option<&POD> {
+        self.pods.get(id).or_else(|| {
+            self.pods
+                .values()
+                .find(|pod| PodBuilder::matches_pod_id(pod, id))
+        })
+    }
+
+    /// Like `get_pod`, but also accepts an unambiguous prefix of a pod id
+    pub fn find_pod(&self, id: &str) -> Result<&POD> {
+        if let Some(pod) = self.get_pod(id) {
+            return Ok(pod);
+        }
+        let matches = self
+            .pods
+            .iter()
+            .filter(|(pod_id, _)| pod_id.starts_with(id))
+            .map(|(_, pod)| pod)
+            .collect::<Vec<_>>();
+        match matches.len() {
+            0 => Err(anyhow!("No POD with id {}", id)),
+            1 => Ok(matches[0]),
+            n => Err(anyhow!(
+                "POD id prefix {} is ambiguous, it matches {} PODs",
+                id,
+                n
+            )),
+        }
+    }
+
+    pub fn remove_pod(&mut self, id: &str) -> bool {
+        let Some(pod_id) = self.get_pod(id).map(PodBuilder::pod_id) else {
+            return false;
+        };
+        self.metadata.remove(&pod_id);
+        self.pods.shift_remove(&pod_id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pods.is_empty()
     }
 }
 
@@ -132,75 +227,373 @@ impl ScriptId {
     }
 }
 
+/// How long `from` waits for the other party's value by default
+pub const DEFAULT_REMOTE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Why waiting for a value or a pod in a SharedStore failed
+#[derive(Clone, Debug, PartialEq)]
+pub enum WaitError {
+    /// It didn't show up in time, but it may still come
+    Timeout(Duration),
+    /// It was removed, or its script cleared, so it won't come
+    Removed,
+    /// It is there, but only for other recipients
+    Unauthorized,
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitError::Timeout(timeout) => write!(f, "timed out after {:?}", timeout),
+            WaitError::Removed => write!(f, "removed from the store"),
+            WaitError::Unauthorized => write!(f, "not authorized to read it"),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+/// Waits until `get` finds something, `removed` holds or the timeout expires. Stores notify
+/// `notify` waiters whenever the result of either may have changed.
+pub(crate) async fn wait_for<T>(
+    notify: &Notify,
+    timeout: Duration,
+    get: impl Fn() -> Option<T>,
+    removed: impl Fn() -> bool,
+) -> Result<T, WaitError> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        // Registered before checking, so that a notification in between isn't missed
+        let notified = notify.notified();
+        if let Some(value) = get() {
+            return Ok(value);
+        }
+        if removed() {
+            return Err(WaitError::Removed);
+        }
+        if tokio::time::timeout_at(deadline, notified).await.is_err() {
+            return Err(WaitError::Timeout(timeout));
+        }
+    }
+}
+
+/// Who may read a value set in a SharedStore, and for how long
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SetOptions {
+    /// Anyone may read it if None
+    pub recipients: Option<Vec<User>>,
+    /// It never expires if None
+    pub ttl: Option<Duration>,
+}
+
+/// A value in a SharedStore, along with the options it was set with
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct SharedValue {
+    value: Value,
+    recipients: Option<Vec<User>>,
+    // A wall clock time, as it's shared with peers
+    expires_at: Option<SystemTime>,
+}
+
+impl SharedValue {
+    pub(crate) fn new(value: Value, options: SetOptions) -> Self {
+        Self {
+            value,
+            recipients: options.recipients,
+            expires_at: options.ttl.map(|ttl| SystemTime::now() + ttl),
+        }
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= SystemTime::now())
+    }
+
+    /// The value as seen by `reader`, None once it expired
+    pub(crate) fn read(&self, reader: &User) -> Option<Result<Value, WaitError>> {
+        if self.is_expired() {
+            return None;
+        }
+        Some(match &self.recipients {
+            Some(recipients) if !recipients.contains(reader) => Err(WaitError::Unauthorized),
+            _ => Ok(self.value.clone()),
+        })
+    }
+}
+
 #[async_trait]
 pub trait SharedStore: Send + Sync {
-    async fn get_value(&self, script_id: &ScriptId, aid: u64) -> Option<Value>;
-    fn set_value(&self, script_id: &ScriptId, aid: u64, value: Value);
-    async fn get_pod(&self, id: &String) -> Option<POD>;
+    /// Waits for a value set by another party for `reader`, see WaitError. Expired values are
+    /// waited for like missing ones.
+    async fn get_value(
+        &self,
+        script_id: &ScriptId,
+        aid: u64,
+        reader: &User,
+        timeout: Duration,
+    ) -> Result<Value, WaitError>;
+    fn set_value(&self, script_id: &ScriptId, aid: u64, value: Value, options: SetOptions);
+    /// Removes expired values, returns how many there were
+    fn purge_expired(&self) -> usize;
+    async fn get_pod(&self, id: &String, timeout: Duration) -> Result<POD, WaitError>;
+    /// Stores a pod and returns its id. Storing a pod we already have does nothing.
     fn store_pod(&self, pod: POD) -> String;
+    /// Removes a pod, returns whether it was stored. Pending get_pod calls for it give up.
+    fn remove_pod(&self, id: &String) -> bool;
+    /// Removes all the values of a script. Pending get_value calls for it give up.
+    fn clear_script(&self, script_id: &ScriptId);
+    fn list_pod_ids(&self) -> Vec<String>;
+    /// Sync status with peers. Stores without peers are always in sync.
+    fn status(&self) -> StoreStatus {
+        StoreStatus::Synced {
+            peers: 0,
+            last_sync: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoreStatus {
+    /// Not connected to the network yet
+    Initializing,
+    Synced {
+        peers: usize,
+        last_sync: Option<SystemTime>,
+    },
+    /// No peers, or the last sync with a peer failed
+    Degraded {
+        peers: usize,
+        last_sync: Option<SystemTime>,
+    },
+}
+
+impl fmt::Display for StoreStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (state, peers, last_sync) = match self {
+            StoreStatus::Initializing => return write!(f, "initializing"),
+            StoreStatus::Synced { peers, last_sync } => ("synced", peers, last_sync),
+            StoreStatus::Degraded { peers, last_sync } => ("degraded", peers, last_sync),
+        };
+        write!(f, "{}, {} peer(s)", state, peers)?;
+        match last_sync.and_then(|t| t.elapsed().ok()) {
+            Some(elapsed) => write!(f, ", last sync {}s ago", elapsed.as_secs()),
+            None => write!(f, ", never synced"),
+        }
+    }
 }
 
+/// A SharedStore kept in memory. FileStore and IrohStore keep their values and pods in one as
+/// well and only add persistence or sync on top, so that every store applies the same expiry,
+/// authorization and removal rules.
 pub struct InMemoryStore {
-    values: Arc<Mutex<HashMap<(ScriptId, u64), Value>>>,
-    pods: Arc<Mutex<HashMap<String, POD>>>,
+    values: Mutex<HashMap<(ScriptId, u64), SharedValue>>,
+    pods: Mutex<HashMap<String, POD>>,
+    // Removed pods and cleared scripts, until they are stored/set again, so that peers don't
+    // bring them back either
+    removed_pods: Mutex<HashSet<String>>,
+    cleared_scripts: Mutex<HashSet<ScriptId>>,
+    changed: Notify,
 }
 
 impl InMemoryStore {
     pub fn new() -> Self {
+        Self::with_contents(HashMap::new(), HashMap::new())
+    }
+
+    pub(crate) fn with_contents(
+        values: HashMap<(ScriptId, u64), SharedValue>,
+        pods: HashMap<String, POD>,
+    ) -> Self {
         Self {
-            values: Arc::new(Mutex::new(HashMap::new())),
-            pods: Arc::new(Mutex::new(HashMap::new())),
+            values: Mutex::new(values),
+            pods: Mutex::new(pods),
+            removed_pods: Mutex::new(HashSet::new()),
+            cleared_scripts: Mutex::new(HashSet::new()),
+            changed: Notify::new(),
+        }
+    }
+
+    pub(crate) fn insert_value(&self, script_id: &ScriptId, id: u64, value: SharedValue) {
+        self.cleared_scripts.lock().unwrap().remove(script_id);
+        self.values
+            .lock()
+            .unwrap()
+            .insert((script_id.clone(), id), value);
+        self.changed.notify_waiters();
+    }
+
+    /// Stores the pod unless we already have it. Returns its id and whether it is new.
+    pub(crate) fn insert_pod(&self, pod: POD) -> (String, bool) {
+        let id = PodBuilder::pod_id(&pod);
+        if self.pods.lock().unwrap().contains_key(&id) {
+            return (id, false);
         }
+        self.removed_pods.lock().unwrap().remove(&id);
+        self.pods.lock().unwrap().insert(id.clone(), pod);
+        self.changed.notify_waiters();
+        (id, true)
+    }
+
+    /// Removes the expired values and returns their keys
+    pub(crate) fn take_expired(&self) -> Vec<(ScriptId, u64)> {
+        let mut values = self.values.lock().unwrap();
+        let expired = values
+            .iter()
+            .filter(|(_, value)| value.is_expired())
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in &expired {
+            values.remove(key);
+        }
+        expired
+    }
+
+    /// A pod stored by a peer, ignored if we removed it
+    pub(crate) fn sync_pod(&self, pod: POD) {
+        let id = PodBuilder::pod_id(&pod);
+        if self.removed_pods.lock().unwrap().contains(&id) {
+            return;
+        }
+        if self.pods.lock().unwrap().insert(id, pod).is_none() {
+            self.changed.notify_waiters();
+        }
+    }
+
+    /// A value set by a peer, ignored if it expired or we cleared its script
+    pub(crate) fn sync_value(&self, script_id: ScriptId, id: u64, value: SharedValue) {
+        if value.is_expired() || self.cleared_scripts.lock().unwrap().contains(&script_id) {
+            return;
+        }
+        self.values.lock().unwrap().insert((script_id, id), value);
+        self.changed.notify_waiters();
+    }
+
+    pub(crate) fn values(&self) -> Vec<((ScriptId, u64), SharedValue)> {
+        self.values
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    pub(crate) fn pods(&self) -> Vec<POD> {
+        self.pods.lock().unwrap().values().cloned().collect()
     }
 }
 
 #[async_trait]
 impl SharedStore for InMemoryStore {
-    async fn get_value(&self, script_id: &ScriptId, id: u64) -> Option<Value> {
-        let mut counter = 0;
-        loop {
-            if let Some(v) = self
-                .values
-                .lock()
-                .unwrap()
-                .get(&(script_id.clone(), id))
-                .cloned()
-            {
-                return Some(v);
-            }
-            tokio::time::sleep(Duration::from_millis(10)).await;
-            counter += 1;
-            if counter > 10 {
-                return None;
-            }
-        }
+    async fn get_value(
+        &self,
+        script_id: &ScriptId,
+        id: u64,
+        reader: &User,
+        timeout: Duration,
+    ) -> Result<Value, WaitError> {
+        wait_for(
+            &self.changed,
+            timeout,
+            || {
+                let mut values = self.values.lock().unwrap();
+                let key = (script_id.clone(), id);
+                let read = values.get(&key)?.read(reader);
+                if read.is_none() {
+                    values.remove(&key);
+                }
+                read
+            },
+            || self.cleared_scripts.lock().unwrap().contains(script_id),
+        )
+        .await?
+    }
+
+    fn set_value(&self, script_id: &ScriptId, id: u64, value: Value, options: SetOptions) {
+        self.insert_value(script_id, id, SharedValue::new(value, options));
+    }
+
+    fn purge_expired(&self) -> usize {
+        self.take_expired().len()
+    }
+
+    async fn get_pod(&self, id: &String, timeout: Duration) -> Result<POD, WaitError> {
+        wait_for(
+            &self.changed,
+            timeout,
+            || self.pods.lock().unwrap().get(id).cloned(),
+            || self.removed_pods.lock().unwrap().contains(id),
+        )
+        .await
+    }
+
+    fn store_pod(&self, pod: POD) -> String {
+        self.insert_pod(pod).0
+    }
+
+    fn remove_pod(&self, id: &String) -> bool {
+        self.removed_pods.lock().unwrap().insert(id.clone());
+        let removed = self.pods.lock().unwrap().remove(id).is_some();
+        self.changed.notify_waiters();
+        removed
     }
 
-    fn set_value(&self, script_id: &ScriptId, id: u64, value: Value) {
+    fn clear_script(&self, script_id: &ScriptId) {
+        self.cleared_scripts
+            .lock()
+            .unwrap()
+            .insert(script_id.clone());
         self.values
             .lock()
             .unwrap()
-            .insert((script_id.clone(), id), value);
+            .retain(|(id, _), _| id != script_id);
+        self.changed.notify_waiters();
     }
 
-    async fn get_pod(&self, id: &String) -> Option<POD> {
-        let mut counter = 0;
-        loop {
-            if let Some(v) = self.pods.lock().unwrap().get(id).cloned() {
-                return Some(v);
-            }
-            tokio::time::sleep(Duration::from_millis(10)).await;
-            counter += 1;
-            if counter > 10 {
-                return None;
-            }
-        }
+    fn list_pod_ids(&self) -> Vec<String> {
+        self.pods.lock().unwrap().keys().cloned().collect()
     }
+}
 
-    fn store_pod(&self, pod: POD) -> String {
-        let id = PodBuilder::pod_id(&pod);
-        self.pods.lock().unwrap().insert(id.clone(), pod);
-        id
+/// Prover params for plonky pods. Building them takes a while, so it's done the first time a
+/// plonky pod is created, and the params are reused for the following ones.
+#[derive(Default)]
+pub struct ProverContext {
+    params: tokio::sync::OnceCell<Arc<Mutex<ProverParams<L, M, N, NS, VL>>>>,
+}
+
+impl ProverContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.params.initialized()
+    }
+
+    /// The params, built on a blocking thread if this is the first call
+    pub async fn params(&self) -> Result<Arc<Mutex<ProverParams<L, M, N, NS, VL>>>> {
+        self.params
+            .get_or_try_init(|| async {
+                info!("building prover params, this takes a while");
+                let start = std::time::Instant::now();
+                let params = tokio::task::spawn_blocking(|| {
+                    let pod1_circuit_data = ExampleIntroducer::circuit_data()?;
+                    info!("built POD1 introducer circuit data");
+                    let circuit_data = PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::circuit_data(
+                        pod1_circuit_data.verifier_data(),
+                    )?;
+                    info!("built plonky circuit data");
+                    PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::build_prover_params(
+                        pod1_circuit_data,
+                        circuit_data,
+                    )
+                })
+                .await??;
+                info!("built prover params in {:?}", start.elapsed());
+                Ok::<_, anyhow::Error>(Arc::new(Mutex::new(params)))
+            })
+            .await
+            .cloned()
     }
 }
 
@@ -211,10 +604,44 @@ pub struct Env {
     pub current_builder: Option<Arc<Mutex<PodBuilder>>>,
     pub current_query: Option<Arc<Mutex<PodQueryBuilder>>>,
     shared: Arc<dyn SharedStore>,
-    bindings: Arc<Mutex<HashMap<String, Value>>>,
+    bindings: Arc<Scope>,
     sk: Option<SchnorrSecretKey>,
     script_id: Option<ScriptId>,
-    prover_params: Option<Arc<Mutex<ProverParams<L, M, N, NS, VL>>>>,
+    prover: Option<Arc<ProverContext>>,
+    remote_timeout: Duration,
+}
+
+/// Bindings of a lexical scope. Lookups read through to the parent scopes, while definitions
+/// go to the innermost one, so they disappear when the scope ends.
+#[derive(Default)]
+struct Scope {
+    bindings: Mutex<HashMap<String, Value>>,
+    parent: Option<Arc<Scope>>,
+}
+
+impl Scope {
+    fn get(&self, name: &str) -> Option<Value> {
+        match self.bindings.lock().unwrap().get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.get(name),
+        }
+    }
+
+    /// All visible bindings, inner ones shadowing outer ones
+    fn flatten(&self) -> HashMap<String, Value> {
+        let mut bindings = match &self.parent {
+            Some(parent) => parent.flatten(),
+            None => HashMap::new(),
+        };
+        bindings.extend(
+            self.bindings
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        bindings
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -222,20 +649,184 @@ pub struct PodBuilder {
     pub pending_operations: Vec<(String, OpCmd)>,
     pub input_pods: HashMap<String, POD>,
     pub matched_statements: Vec<SRef>,
+    /// Statements matched by pod?* queries, by pod id, added once the pod becomes an input
+    pub deferred_matched_statements: HashMap<String, Vec<SRef>>,
+    /// Ids of input pods marked by redact, whose origins get opaque names in the new pod
+    pub hidden_origins: HashSet<String>,
     pub next_origin_id: usize,
     pub next_result_key_id: usize,
     pub next_statement_id: usize,
+    /// Strings stored in entries, by the hash the entries hold instead
+    pub display_map: HashMap<GoldilocksField, String>,
+    /// Set by createpod!, for a plonky pod rather than an oracle pod when there are input pods
+    pub zk: bool,
+    /// Set by `[createpod name :schema schema ...]`, checked by finalize
+    pub schema: Option<Schema>,
+}
+
+/// What finalize would do to create a pod: which gadget proves it and with which operations
+#[derive(Clone, Debug)]
+pub struct FinalizePlan {
+    pub gadget: GadgetID,
+    /// In execution order. For Schnorr pods these are only NewEntry operations
+    pub ops: Vec<OpCmd>,
+    /// By pod id
+    pub input_pods: HashMap<String, POD>,
+    /// (pod id, origin name in that pod) to the origin name in the new pod
+    pub origin_renaming_map: HashMap<(String, String), String>,
+    /// Ids of input pods whose origins are renamed to origin_1, origin_2, ...
+    pub hidden_origins: HashSet<String>,
+    /// Statements the pod will have, which must not exceed `max_statements`
+    pub statement_count: usize,
+    pub max_statements: usize,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Value {
     String(String),
     Scalar(GoldilocksField),
+    Bool(bool),
+    Vector(Vec<GoldilocksField>),
     PodRef(POD),
     SRef(SRef),
     Operation(Box<Operation>),
     Assert(Box<Assert>),
     List(Vec<Value>),
+    Quoted(Expr),
+    Closure(Box<Closure>),
+    Schema(Schema),
+    /// The result of `explain`, which can't be sent to other users
+    #[serde(skip)]
+    Plan(Box<FinalizePlan>),
+}
+
+/// A user-defined function, with a snapshot of the bindings where it was defined
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Closure {
+    pub name: Option<String>,
+    pub params: Vec<String>,
+    pub body: Vec<Expr>,
+    pub bindings: HashMap<String, Value>,
+}
+
+/// Whether a pod entry holds a scalar or a vector. Strings and booleans are scalars.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    Scalar,
+    Vector,
+}
+
+impl EntryKind {
+    fn of(value: &ScalarOrVec) -> Self {
+        match value {
+            ScalarOrVec::Scalar(_) => EntryKind::Scalar,
+            ScalarOrVec::Vector(_) => EntryKind::Vector,
+        }
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryKind::Scalar => write!(f, "scalar"),
+            EntryKind::Vector => write!(f, "vector"),
+        }
+    }
+}
+
+/// The keys a pod must have and the kind of each, declared with defschema
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    pub name: String,
+    pub fields: Vec<(String, EntryKind)>,
+    /// Set by `:open`, to allow entries the schema doesn't declare
+    pub open: bool,
+}
+
+impl Schema {
+    /// Checks that `entries` has each declared key with its kind, and no other key unless the
+    /// schema is open. The error lists every missing, extra and mismatched key.
+    pub fn check<'a>(&self, entries: impl IntoIterator<Item = &'a Entry>) -> Result<()> {
+        let kinds: HashMap<&str, EntryKind> = entries
+            .into_iter()
+            .map(|entry| (entry.key.as_str(), EntryKind::of(&entry.value)))
+            .collect();
+        let mut problems = Vec::new();
+
+        let missing: Vec<&str> = self
+            .fields
+            .iter()
+            .filter(|(key, _)| !kinds.contains_key(key.as_str()))
+            .map(|(key, _)| key.as_str())
+            .collect();
+        if !missing.is_empty() {
+            problems.push(format!("missing keys {}", missing.join(", ")));
+        }
+
+        if !self.open {
+            let mut extra: Vec<&str> = kinds
+                .keys()
+                .filter(|key| !self.fields.iter().any(|(field, _)| field == *key))
+                .copied()
+                .collect();
+            extra.sort();
+            if !extra.is_empty() {
+                problems.push(format!("extra keys {}", extra.join(", ")));
+            }
+        }
+
+        let mismatched: Vec<String> = self
+            .fields
+            .iter()
+            .filter_map(|(key, kind)| match kinds.get(key.as_str()) {
+                Some(found) if found != kind => {
+                    Some(format!("{} (expected {}, found {})", key, kind, found))
+                }
+                _ => None,
+            })
+            .collect();
+        if !mismatched.is_empty() {
+            problems.push(format!("mismatched keys {}", mismatched.join(", ")));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "POD doesn't match schema {}: {}",
+                self.name,
+                problems.join("; ")
+            ))
+        }
+    }
+}
+
+impl Value {
+    fn to_scalar_or_vec(&self) -> Result<ScalarOrVec> {
+        match self {
+            Value::Scalar(s) => Ok(ScalarOrVec::Scalar(*s)),
+            Value::Bool(b) => Ok(ScalarOrVec::Scalar(GoldilocksField(*b as u64))),
+            Value::Vector(v) => Ok(ScalarOrVec::Vector(v.clone())),
+            // PODs can't hold strings, so they hold their hash
+            Value::String(s) => Ok(ScalarOrVec::Scalar(hash_string_to_field(s))),
+            _ => Err(anyhow!("Expected a scalar or a vector")),
+        }
+    }
+
+    /// Booleans are used as 0 or 1 in arithmetic and comparisons
+    fn bool_to_scalar(self) -> Self {
+        match self {
+            Value::Bool(b) => Value::Scalar(GoldilocksField(b as u64)),
+            value => value,
+        }
+    }
+}
+
+/// Comparisons and membership checks, which close the key-value section of a createpod
+const ASSERTION_OPS: &[&str] = &["<", ">", "=", "!=", "cmp", "contains", "!contains"];
+
+fn is_assertion_op(op: &str) -> bool {
+    ASSERTION_OPS.contains(&op)
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -258,6 +849,102 @@ impl AssertType {
     }
 }
 
+impl AssertType {
+    /// Returns the comparison that holds between the two values when `self` doesn't,
+    /// eg: a false `>` is witnessed by `<` or `=`.
+    fn negation_witness(&self, value1: GoldilocksField, value2: GoldilocksField) -> AssertType {
+        match self {
+            AssertType::Gt | AssertType::Lt
+                if value1.to_canonical_u64() == value2.to_canonical_u64() =>
+            {
+                AssertType::Eq
+            }
+            AssertType::Gt => AssertType::Lt,
+            AssertType::Lt => AssertType::Gt,
+            AssertType::Eq => AssertType::Neq,
+            AssertType::Neq => AssertType::Eq,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LogicType {
+    And,
+    Or,
+    Not,
+}
+
+impl LogicType {
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "and" => Ok(LogicType::And),
+            "or" => Ok(LogicType::Or),
+            "not" => Ok(LogicType::Not),
+            _ => Err(anyhow!("Unknown logic type: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum MembershipType {
+    Contains,
+    NotContains,
+}
+
+impl MembershipType {
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "contains" => Ok(MembershipType::Contains),
+            "!contains" => Ok(MembershipType::NotContains),
+            _ => Err(anyhow!("Unknown membership type: {}", s)),
+        }
+    }
+
+    fn holds(&self, vector: &[GoldilocksField], value: GoldilocksField) -> bool {
+        match self {
+            MembershipType::Contains => vector.contains(&value),
+            MembershipType::NotContains => !vector.contains(&value),
+        }
+    }
+
+    fn extract_operands(
+        vector: &Value,
+        value: &Value,
+        env: &Env,
+    ) -> Result<(Vec<GoldilocksField>, GoldilocksField)> {
+        let vector = match vector {
+            Value::Vector(v) => v.clone(),
+            Value::SRef(r) => match get_scalar_or_vec_from_sref(r, env)? {
+                ScalarOrVec::Vector(v) => v,
+                ScalarOrVec::Scalar(_) => {
+                    return Err(anyhow!("Membership requires a vector as first operand"))
+                }
+            },
+            _ => return Err(anyhow!("Membership requires a vector as first operand")),
+        };
+        let value = match value {
+            Value::Scalar(s) => *s,
+            Value::SRef(r) => get_value_from_sref(r, env)?,
+            _ => return Err(anyhow!("Membership requires a scalar as second operand")),
+        };
+        Ok((vector, value))
+    }
+
+    fn predicate(&self) -> String {
+        match self {
+            MembershipType::Contains => "CONTAINS".to_string(),
+            MembershipType::NotContains => "NOTCONTAINS".to_string(),
+        }
+    }
+
+    fn into_pod_op(self, vector: SRef, value: SRef) -> Op<StatementRef> {
+        match self {
+            MembershipType::Contains => Op::ContainsFromEntries(vector.into(), value.into()),
+            MembershipType::NotContains => Op::NotContainsFromEntries(vector.into(), value.into()),
+        }
+    }
+}
+
 impl From<(AssertType, Value, Value)> for Assert {
     fn from((assert_type, op1, op2): (AssertType, Value, Value)) -> Self {
         match assert_type {
@@ -281,6 +968,7 @@ impl Assert {
     fn extract_value(value: &Value, env: Option<&Env>) -> Result<GoldilocksField> {
         match value {
             Value::Scalar(s) => Ok(*s),
+            Value::Vector(_) => Err(anyhow!("Comparisons don't support vectors")),
             Value::SRef(r) if env.is_some() => get_value_from_sref(r, env.unwrap()),
             _ => Err(anyhow!("Invalid operand type")),
         }
@@ -296,40 +984,17 @@ impl Assert {
         }
     }
 
-    fn apply_assert(&self, value1: GoldilocksField, value2: GoldilocksField) -> GoldilocksField {
+    fn apply_assert(&self, value1: GoldilocksField, value2: GoldilocksField) -> bool {
+        let (value1, value2) = (value1.to_canonical_u64(), value2.to_canonical_u64());
         match self {
-            Assert::Gt(_, _) => {
-                if value1.to_canonical_u64() > value2.to_canonical_u64() {
-                    GoldilocksField(1)
-                } else {
-                    GoldilocksField(0)
-                }
-            }
-            Assert::Lt(_, _) => {
-                if value1.to_canonical_u64() < value2.to_canonical_u64() {
-                    GoldilocksField(1)
-                } else {
-                    GoldilocksField(0)
-                }
-            }
-            Assert::Eq(_, _) => {
-                if value1.to_canonical_u64() == value2.to_canonical_u64() {
-                    GoldilocksField(1)
-                } else {
-                    GoldilocksField(0)
-                }
-            }
-            Assert::Neq(_, _) => {
-                if value1.to_canonical_u64() != value2.to_canonical_u64() {
-                    GoldilocksField(1)
-                } else {
-                    GoldilocksField(0)
-                }
-            }
+            Assert::Gt(_, _) => value1 > value2,
+            Assert::Lt(_, _) => value1 < value2,
+            Assert::Eq(_, _) => value1 == value2,
+            Assert::Neq(_, _) => value1 != value2,
         }
     }
 
-    fn eval(&self) -> Result<GoldilocksField> {
+    fn eval(&self) -> Result<bool> {
         let (value1, value2) = self.evaluate_values(None)?;
         Ok(self.apply_assert(value1, value2))
     }
@@ -356,6 +1021,9 @@ pub enum OpType {
     Add,
     Multiply,
     Max,
+    Sub,
+    Div,
+    Min,
 }
 
 impl OpType {
@@ -364,9 +1032,19 @@ impl OpType {
             "+" => Ok(OpType::Add),
             "*" => Ok(OpType::Multiply),
             "max" => Ok(OpType::Max),
+            "-" => Ok(OpType::Sub),
+            "/" => Ok(OpType::Div),
+            "min" => Ok(OpType::Min),
             _ => Err(anyhow!("Unknown operation type: {}", s)),
         }
     }
+
+    fn is_commutative(&self) -> bool {
+        matches!(
+            self,
+            OpType::Add | OpType::Multiply | OpType::Max | OpType::Min
+        )
+    }
 }
 
 impl From<(OpType, Value, Value)> for Operation {
@@ -375,6 +1053,9 @@ impl From<(OpType, Value, Value)> for Operation {
             OpType::Add => Operation::Sum(op1, op2),
             OpType::Multiply => Operation::Product(op1, op2),
             OpType::Max => Operation::Max(op1, op2),
+            OpType::Sub => Operation::Sub(op1, op2),
+            OpType::Div => Operation::Div(op1, op2),
+            OpType::Min => Operation::Min(op1, op2),
         }
     }
 }
@@ -384,12 +1065,17 @@ pub enum Operation {
     Sum(Value, Value),
     Product(Value, Value),
     Max(Value, Value),
+    Sub(Value, Value),
+    Div(Value, Value),
+    Min(Value, Value),
 }
 
 impl Operation {
+    /// Operations only apply to scalars, vectors (eg: [+ [vec 1 2] 3]) are rejected.
     fn extract_value(value: &Value, env: Option<&Env>) -> Result<GoldilocksField> {
         match value {
             Value::Scalar(s) => Ok(*s),
+            Value::Vector(_) => Err(anyhow!("Arithmetic operators don't support vectors")),
             Value::SRef(r) if env.is_some() => get_value_from_sref(r, env.unwrap()),
             _ => Err(anyhow!("Invalid operand type")),
         }
@@ -397,7 +1083,12 @@ impl Operation {
 
     fn evaluate_values(&self, env: Option<&Env>) -> Result<(GoldilocksField, GoldilocksField)> {
         match self {
-            Operation::Sum(a, b) | Operation::Product(a, b) | Operation::Max(a, b) => {
+            Operation::Sum(a, b)
+            | Operation::Product(a, b)
+            | Operation::Max(a, b)
+            | Operation::Sub(a, b)
+            | Operation::Div(a, b)
+            | Operation::Min(a, b) => {
                 let value1 = Self::extract_value(a, env)?;
                 let value2 = Self::extract_value(b, env)?;
                 Ok((value1, value2))
@@ -405,34 +1096,105 @@ impl Operation {
         }
     }
 
-    fn apply_operation(&self, value1: GoldilocksField, value2: GoldilocksField) -> GoldilocksField {
+    /// Sums and products are on NUM_BITS-bit integers, the range comparisons assume, and error
+    /// when an operand or the result doesn't fit. Subtraction wraps around the Goldilocks
+    /// modulus (eg: [- 2 5] is p - 3). Division is integer division on the canonical values and
+    /// errors unless it is exact, so the quotient always satisfies q * b == a in the field.
+    fn apply_operation(
+        &self,
+        value1: GoldilocksField,
+        value2: GoldilocksField,
+    ) -> Result<GoldilocksField> {
         match self {
-            Operation::Sum(_, _) => value1 + value2,
-            Operation::Product(_, _) => value1 * value2,
+            Operation::Sum(_, _) => Self::apply_checked(value1, "+", value2, u64::checked_add),
+            Operation::Product(_, _) => Self::apply_checked(value1, "*", value2, u64::checked_mul),
             Operation::Max(_, _) => {
                 if value1.to_canonical_u64() > value2.to_canonical_u64() {
-                    value1
+                    Ok(value1)
+                } else {
+                    Ok(value2)
+                }
+            }
+            Operation::Sub(_, _) => Ok(value1 - value2),
+            Operation::Div(_, _) => {
+                let dividend = value1.to_canonical_u64();
+                let divisor = value2.to_canonical_u64();
+                if divisor == 0 {
+                    return Err(anyhow!("Division by zero"));
+                }
+                if dividend % divisor != 0 {
+                    return Err(anyhow!(
+                        "Non-exact division: {} is not divisible by {}",
+                        dividend,
+                        divisor
+                    ));
+                }
+                Ok(GoldilocksField(dividend / divisor))
+            }
+            Operation::Min(_, _) => {
+                if value1.to_canonical_u64() < value2.to_canonical_u64() {
+                    Ok(value1)
                 } else {
-                    value2
+                    Ok(value2)
                 }
             }
         }
     }
 
+    fn apply_checked(
+        value1: GoldilocksField,
+        operator: &str,
+        value2: GoldilocksField,
+        f: fn(u64, u64) -> Option<u64>,
+    ) -> Result<GoldilocksField> {
+        let (value1, value2) = (value1.to_canonical_u64(), value2.to_canonical_u64());
+        if let Some(operand) = [value1, value2].into_iter().find(|v| *v >= 1 << NUM_BITS) {
+            return Err(anyhow!(
+                "Operand {} of {} doesn't fit in {} bits",
+                operand,
+                operator,
+                NUM_BITS
+            ));
+        }
+        match f(value1, value2) {
+            Some(result) if result < 1 << NUM_BITS => Ok(GoldilocksField(result)),
+            _ => Err(anyhow!(
+                "{} {} {} overflows {} bits",
+                value1,
+                operator,
+                value2,
+                NUM_BITS
+            )),
+        }
+    }
+
     fn eval(&self) -> Result<GoldilocksField> {
         let (value1, value2) = self.evaluate_values(None)?;
-        Ok(self.apply_operation(value1, value2))
+        self.apply_operation(value1, value2)
     }
 
     fn eval_with_env(&self, env: &Env) -> Result<GoldilocksField> {
         let (value1, value2) = self.evaluate_values(Some(env))?;
-        Ok(self.apply_operation(value1, value2))
+        self.apply_operation(value1, value2)
     }
     fn into_pod_op(op_type: OpType, result_ref: SRef, op1: SRef, op2: SRef) -> Op<StatementRef> {
         match op_type {
             OpType::Add => Op::SumOf(result_ref.into(), op1.into(), op2.into()),
             OpType::Multiply => Op::ProductOf(result_ref.into(), op1.into(), op2.into()),
             OpType::Max => Op::MaxOf(result_ref.into(), op1.into(), op2.into()),
+            OpType::Sub => Op::SubOf(result_ref.into(), op1.into(), op2.into()),
+            OpType::Div => Op::DivOf(result_ref.into(), op1.into(), op2.into()),
+            OpType::Min => Op::MinOf(result_ref.into(), op1.into(), op2.into()),
+        }
+    }
+    fn pod_op_code(op_type: OpType) -> GoldilocksField {
+        match op_type {
+            OpType::Add => Op::<StatementRef>::SUM_OF,
+            OpType::Multiply => Op::<StatementRef>::PRODUCT_OF,
+            OpType::Max => Op::<StatementRef>::MAX_OF,
+            OpType::Sub => Op::<StatementRef>::SUB_OF,
+            OpType::Div => Op::<StatementRef>::DIV_OF,
+            OpType::Min => Op::<StatementRef>::MIN_OF,
         }
     }
 }
@@ -462,6 +1224,11 @@ enum QueryConstraint {
         assert_type: AssertType,
         operands: (Box<OperandConstraint>, Box<OperandConstraint>),
     },
+    Membership {
+        membership_type: MembershipType,
+        vector_key: String,
+        operand: Box<OperandConstraint>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -508,11 +1275,33 @@ impl PodQueryBuilder {
         Ok(())
     }
 
+    fn add_membership(
+        &mut self,
+        membership_type: MembershipType,
+        vector: &Value,
+        value: &Value,
+    ) -> Result<()> {
+        let vector_key = match vector {
+            Value::SRef(sref) => sref.1.name.clone(),
+            _ => return Err(anyhow!("Membership requires a vector key in pod? queries")),
+        };
+        let operand = self.add_value(value)?;
+        self.constraints.push(QueryConstraint::Membership {
+            membership_type,
+            vector_key,
+            operand: Box::new(operand),
+        });
+        Ok(())
+    }
+
     fn add_operation(&mut self, op: &Operation) -> Result<OperandConstraint> {
         let (op1, op2) = match op {
-            Operation::Sum(v1, v2) | Operation::Product(v1, v2) | Operation::Max(v1, v2) => {
-                (v1, v2)
-            }
+            Operation::Sum(v1, v2)
+            | Operation::Product(v1, v2)
+            | Operation::Max(v1, v2)
+            | Operation::Sub(v1, v2)
+            | Operation::Div(v1, v2)
+            | Operation::Min(v1, v2) => (v1, v2),
         };
 
         let op1_constraint = self.add_value(op1)?;
@@ -522,6 +1311,9 @@ impl PodQueryBuilder {
             Operation::Sum(_, _) => OpType::Add,
             Operation::Product(_, _) => OpType::Multiply,
             Operation::Max(_, _) => OpType::Max,
+            Operation::Sub(_, _) => OpType::Sub,
+            Operation::Div(_, _) => OpType::Div,
+            Operation::Min(_, _) => OpType::Min,
         };
 
         Ok(OperandConstraint::Operation(Box::new(
@@ -535,9 +1327,16 @@ impl PodQueryBuilder {
     fn add_value(&mut self, value: &Value) -> Result<OperandConstraint> {
         match value {
             Value::Scalar(s) => Ok(OperandConstraint::Constant(ScalarOrVec::Scalar(*s))),
+            Value::Bool(b) => Ok(OperandConstraint::Constant(ScalarOrVec::Scalar(
+                GoldilocksField(*b as u64),
+            ))),
+            Value::Vector(v) => Ok(OperandConstraint::Constant(ScalarOrVec::Vector(v.clone()))),
+            Value::String(s) => Ok(OperandConstraint::Constant(ScalarOrVec::Scalar(
+                hash_string_to_field(s),
+            ))),
             Value::Operation(op) => self.add_operation(op),
             Value::SRef(sref) => {
-                let key = sref.1.split(':').last().unwrap().to_string();
+                let key = sref.1.name.clone();
                 Ok(OperandConstraint::EntryRef(key))
             }
             _ => Err(anyhow!("Invalid value type")),
@@ -576,16 +1375,31 @@ impl PodBuilder {
         Self {
             pending_operations: Vec::new(),
             matched_statements: Vec::new(),
+            deferred_matched_statements: HashMap::new(),
+            hidden_origins: HashSet::new(),
             input_pods: HashMap::new(),
             next_origin_id: 2,
             next_result_key_id: 0,
             next_statement_id: 0,
+            display_map: HashMap::new(),
+            zk: false,
+            schema: None,
         }
     }
+    /// Content address of a pod: its pod2 content ID (payload hash) as lowercase hex, so pods with
+    /// the same statements share an id whatever their proof.
     pub fn pod_id(pod: &POD) -> String {
-        let pod_hash = pod.payload.hash_payload().to_vec()[0];
-        let name = format!("{}{}", POD_PREFIX, pod_hash);
-        name
+        format!("{}{}", POD_PREFIX, pod.content_id())
+    }
+
+    /// Ids used to be the first element of the payload hash in decimal
+    fn legacy_pod_id(pod: &POD) -> String {
+        format!("{}{}", POD_PREFIX, pod.payload.hash_payload().elements[0])
+    }
+
+    /// Whether `id` is the id of `pod`, in the current or the legacy format
+    pub fn matches_pod_id(pod: &POD, id: &str) -> bool {
+        id == Self::pod_id(pod) || id == Self::legacy_pod_id(pod)
     }
     pub fn register_input_pod(&mut self, pod: &POD) -> String {
         let name = PodBuilder::pod_id(pod);
@@ -593,6 +1407,9 @@ impl PodBuilder {
             name.clone()
         } else {
             self.input_pods.insert(name.clone(), pod.clone());
+            if let Some(statements) = self.deferred_matched_statements.remove(&name) {
+                self.matched_statements.extend(statements);
+            }
             name.clone()
         }
     }
@@ -618,6 +1435,24 @@ impl PodBuilder {
             .push((statement_id.clone(), OpCmd(op, statement_id)));
     }
 
+    /// Finds a pending operation with the given opcode whose operands end with `operands`,
+    /// eg: passing the two inputs of a SumOf matches it whatever its result operand is.
+    fn find_pending_operation(
+        &self,
+        code: GoldilocksField,
+        operands: &[StatementRef],
+    ) -> Option<&(String, OpCmd)> {
+        self.pending_operations.iter().find(|(_, op_cmd)| {
+            let pending_operands = op_cmd.0.operands();
+            op_cmd.0.code() == code
+                && pending_operands.len() >= operands.len()
+                && pending_operands[pending_operands.len() - operands.len()..]
+                    .iter()
+                    .zip(operands)
+                    .all(|(pending, operand)| *pending == operand)
+        })
+    }
+
     fn get_or_create_constant_ref(&mut self, value: GoldilocksField) -> SRef {
         let key = value.to_string();
 
@@ -632,7 +1467,7 @@ impl PodBuilder {
                     }
             })
         {
-            return SRef::self_ref(format!("{}:{}", PREDICATE_VALUEOF, statement_id));
+            return SRef::self_ref(StatementId::value_of(statement_id.as_str()));
         }
 
         // Create new constant if it doesn't exist
@@ -644,111 +1479,522 @@ impl PodBuilder {
             }),
             statement_name.clone(),
         );
-        SRef::self_ref(format!("{}:{}", PREDICATE_VALUEOF, statement_name))
+        SRef::self_ref(StatementId::value_of(&statement_name))
+    }
+
+    fn add_assert(&mut self, assert_type: AssertType, op1: Value, op2: Value) -> Result<SRef> {
+        // Convert operands to SRefs if they're scalars
+        let op1_sref = match op1 {
+            Value::Scalar(s) => self.get_or_create_constant_ref(s),
+            Value::SRef(r) => r,
+            _ => return Err(anyhow!("Invalid operand type")),
+        };
+
+        let op2_sref = match op2 {
+            Value::Scalar(s) => self.get_or_create_constant_ref(s),
+            Value::SRef(r) => r,
+            _ => return Err(anyhow!("Invalid operand type")),
+        };
+
+        let pod_op = Assert::into_pod_op(assert_type, op1_sref, op2_sref);
+        let op_statement_id = self.add_or_reuse_operation(pod_op);
+        Ok(SRef::self_ref(format!(
+            "{}:{}",
+            Assert::predicate_from_op(assert_type),
+            op_statement_id
+        )))
+    }
+
+    fn add_membership(
+        &mut self,
+        membership_type: MembershipType,
+        vector: SRef,
+        value: Value,
+    ) -> Result<SRef> {
+        let value_sref = match value {
+            Value::Scalar(s) => self.get_or_create_constant_ref(s),
+            Value::SRef(r) => r,
+            _ => return Err(anyhow!("Invalid operand type")),
+        };
+
+        let pod_op = membership_type.into_pod_op(vector, value_sref);
+        let op_statement_id = self.add_or_reuse_operation(pod_op);
+        Ok(SRef::self_ref(format!(
+            "{}:{}",
+            membership_type.predicate(),
+            op_statement_id
+        )))
+    }
+
+    /// Returns the statement id of an identical pending operation, or of the operation once added
+    fn add_or_reuse_operation(&mut self, pod_op: Op<StatementRef>) -> String {
+        let operands: Vec<StatementRef> = pod_op.operands().into_iter().cloned().collect();
+        match self.find_pending_operation(pod_op.code(), &operands) {
+            Some((statement_id, _)) => statement_id.clone(),
+            None => {
+                let op_statement_id = self.next_statement_id();
+                self.add_operation(pod_op, op_statement_id.clone());
+                op_statement_id
+            }
+        }
+    }
+
+    /// Value of an entry of the pod being built or of one of its input pods
+    pub fn value_of(&self, sref: &SRef) -> Result<ScalarOrVec> {
+        match &sref.0 {
+            ORef::S => {
+                match self
+                    .pending_operations
+                    .iter()
+                    .find(|(pending_id, _)| pending_id == &sref.1.name)
+                {
+                    Some((_, OpCmd(Op::NewEntry(entry), _))) => Ok(entry.value.clone()),
+                    _ => Err(anyhow!("Value not found in current pod operations")),
+                }
+            }
+            ORef::P(pod_id) => match self.input_pods.get(pod_id) {
+                Some(pod) => match pod.payload.statements_map().get(&sref.1.to_string()) {
+                    Some(Statement::ValueOf(_, value)) => Ok(value.clone()),
+                    _ => Err(anyhow!("Value not found")),
+                },
+                None => Err(anyhow!("Pod not found for ref")),
+            },
+            ORef::Q(_) => Err(anyhow!("Pod not found for ref")),
+        }
+    }
+
+    /// Adds an entry with the given key
+    pub fn add_entry(&mut self, key: &str, value: ScalarOrVec) -> SRef {
+        self.add_operation(
+            Op::NewEntry(Entry {
+                key: key.to_string(),
+                value,
+            }),
+            key.to_string(),
+        );
+        SRef::self_ref(StatementId::value_of(key))
+    }
+
+    /// Adds an entry with the given key proven equal to an entry of `pod`, eg: "VALUEOF:x"
+    pub fn add_entry_from_pod(
+        &mut self,
+        pod: &POD,
+        source_statement: &str,
+        new_key: &str,
+    ) -> Result<SRef> {
+        let pod_id = self.register_input_pod(pod);
+        self.add_entry_from_ref(new_key, SRef(ORef::P(pod_id), source_statement.into()))
+    }
+
+    /// Adds an entry with the given key holding the value `sref` refers to. Unnamed results of
+    /// this pod's operations are renamed, other entries are copied and proven equal.
+    pub fn add_entry_from_ref(&mut self, key: &str, sref: SRef) -> Result<SRef> {
+        match sref {
+            SRef(ORef::S, statement) => {
+                // Eg: [createpod x [+ 1 [pod? z]]] has a randomly named entry for the result of
+                // 1 + pod.z, which we rename to `x`
+                let statement_id = statement.name.clone();
+                let Some(index) = self
+                    .pending_operations
+                    .iter()
+                    .position(|(s, _)| s == &statement_id)
+                else {
+                    return Err(anyhow!(
+                        "No statement found with statement id {} while creating a POD entry",
+                        statement_id
+                    ));
+                };
+                let Op::NewEntry(entry) = &self.pending_operations[index].1 .0 else {
+                    return Err(anyhow!(
+                        "Found statement id {} that is not a NewEntry while creating a POD entry",
+                        statement_id
+                    ));
+                };
+                let new_entry = Entry {
+                    key: key.to_string(),
+                    value: entry.value.clone(),
+                };
+                if entry.key.starts_with(STATEMENT_PREFIX_RESULT) {
+                    self.pending_operations[index] = (
+                        statement_id.clone(),
+                        OpCmd(Op::NewEntry(new_entry), statement_id.clone()),
+                    );
+                    Ok(SRef::self_ref(StatementId::value_of(&statement_id)))
+                } else {
+                    // The entry already has a key of its own (eg: a deduplicated computation bound to another key)
+                    // so we keep it, and create a new entry that we prove equal to it
+                    let new_entry_statement_id = self.next_statement_id();
+                    self.add_operation(Op::NewEntry(new_entry), new_entry_statement_id.clone());
+                    let new_entry_sref =
+                        SRef::self_ref(StatementId::value_of(&new_entry_statement_id));
+                    let eq_statement_id = self.next_statement_id();
+                    self.add_operation(
+                        Op::EqualityFromEntries(
+                            (&new_entry_sref).into(),
+                            (&SRef::self_ref(statement)).into(),
+                        ),
+                        eq_statement_id,
+                    );
+                    Ok(new_entry_sref)
+                }
+            }
+            SRef(ORef::P(pod_id), statement) => {
+                // Eg: [createpod x [pod? z]]. We copy the value in a new entry and prove it equal,
+                // given we don't copy statements from other pods unless explicitly instructed with 'keep'
+                let source_pod = self
+                    .input_pods
+                    .get(&pod_id)
+                    .ok_or_else(|| anyhow!("Source pod not found in input pods"))?;
+                let value = source_pod
+                    .payload
+                    .statements_map()
+                    .get(&statement.to_string())
+                    .ok_or_else(|| anyhow!("Statement not found in source pod"))?
+                    .value()
+                    .map_err(|_| anyhow!("Could not extract value from source statement"))?;
+
+                let new_entry_statement_id = self.next_statement_id();
+                self.add_operation(
+                    Op::NewEntry(Entry {
+                        key: key.to_string(),
+                        value,
+                    }),
+                    new_entry_statement_id.clone(),
+                );
+                let new_entry_sref = SRef::self_ref(StatementId::value_of(&new_entry_statement_id));
+                let eq_statement_id = self.next_statement_id();
+                self.add_operation(
+                    Op::EqualityFromEntries(
+                        SRef(ORef::P(pod_id), statement).into(),
+                        (&new_entry_sref).into(),
+                    ),
+                    eq_statement_id,
+                );
+                Ok(new_entry_sref)
+            }
+            SRef(ORef::Q(_), _) => Err(anyhow!("Can't create a POD entry from a query reference")),
+        }
+    }
+
+    /// Records `op1 <op_type> op2`, whose value is `result_value`, returning a reference to an
+    /// unnamed entry holding the result. Scalar operands become constant entries.
+    fn record_operation(
+        &mut self,
+        op_type: OpType,
+        op1: Value,
+        op2: Value,
+        result_value: GoldilocksField,
+    ) -> Result<SRef> {
+        let op1_sref = match op1 {
+            Value::Scalar(s) => self.get_or_create_constant_ref(s),
+            Value::SRef(r) => r,
+            _ => return Err(anyhow!("Invalid operand type")),
+        };
+        let op2_sref = match op2 {
+            Value::Scalar(s) => self.get_or_create_constant_ref(s),
+            Value::SRef(r) => r,
+            _ => return Err(anyhow!("Invalid operand type")),
+        };
+
+        // Reuse the result of an identical pending computation rather than taking up more statement slots
+        let operands: [StatementRef; 2] = [(&op1_sref).into(), (&op2_sref).into()];
+        if let Some((_, op_cmd)) =
+            self.find_pending_operation(Operation::pod_op_code(op_type), &operands)
+        {
+            let result_ref = op_cmd.0.operands()[0];
+            return Ok(SRef::self_ref(result_ref.1.clone()));
+        }
+
+        // We need to create a new entry for the result
+        let result_key = self.next_result_key_id();
+        let new_entry_statement_id = self.next_statement_id();
+        let result_sref = SRef::self_ref(StatementId::value_of(&new_entry_statement_id));
+        let pod_op = Operation::into_pod_op(op_type, result_sref.clone(), op1_sref, op2_sref);
+        self.add_operation(
+            Op::NewEntry(Entry {
+                key: result_key,
+                value: ScalarOrVec::Scalar(result_value),
+            }),
+            new_entry_statement_id,
+        );
+        let op_statement_id = self.next_statement_id();
+        self.add_operation(pod_op, op_statement_id);
+        Ok(result_sref)
+    }
+
+    fn scalar_of(&self, value: &Value) -> Result<Value> {
+        match value {
+            Value::SRef(sref) => match self.value_of(sref)? {
+                ScalarOrVec::Scalar(s) => Ok(Value::Scalar(s)),
+                ScalarOrVec::Vector(_) => {
+                    Err(anyhow!("Entry {} is a vector, not a scalar", sref.1))
+                }
+            },
+            value => Ok(value.clone()),
+        }
+    }
+
+    /// Records `op1 <op_type> op2`, returning a reference to an unnamed entry holding the result,
+    /// which `add_entry_from_ref` can name
+    pub fn compute(&mut self, op_type: OpType, op1: Value, op2: Value) -> Result<SRef> {
+        let operation: Operation = (op_type, self.scalar_of(&op1)?, self.scalar_of(&op2)?).into();
+        let result_value = operation.eval()?;
+        self.record_operation(op_type, op1, op2, result_value)
+    }
+
+    /// Adds an entry with the given key proven to be `a + b`
+    pub fn sum(&mut self, result_key: &str, a: SRef, b: SRef) -> Result<SRef> {
+        let result = self.compute(OpType::Add, Value::SRef(a), Value::SRef(b))?;
+        self.add_entry_from_ref(result_key, result)
+    }
+
+    /// Adds an entry with the given key proven to be `a * b`
+    pub fn product(&mut self, result_key: &str, a: SRef, b: SRef) -> Result<SRef> {
+        let result = self.compute(OpType::Multiply, Value::SRef(a), Value::SRef(b))?;
+        self.add_entry_from_ref(result_key, result)
+    }
+
+    /// Records that the assertion holds, failing if it doesn't
+    pub fn assert(&mut self, assert_type: AssertType, op1: Value, op2: Value) -> Result<SRef> {
+        let assert: Assert = (assert_type, self.scalar_of(&op1)?, self.scalar_of(&op2)?).into();
+        if !assert.eval()? {
+            return Err(anyhow!(
+                "{} doesn't hold",
+                Assert::predicate_from_op(assert_type)
+            ));
+        }
+        self.add_assert(assert_type, op1, op2)
+    }
+
+    pub fn assert_gt(&mut self, a: SRef, b: SRef) -> Result<SRef> {
+        self.assert(AssertType::Gt, Value::SRef(a), Value::SRef(b))
+    }
+
+    pub fn assert_lt(&mut self, a: SRef, b: SRef) -> Result<SRef> {
+        self.assert(AssertType::Lt, Value::SRef(a), Value::SRef(b))
+    }
+
+    pub fn assert_eq(&mut self, a: SRef, b: SRef) -> Result<SRef> {
+        self.assert(AssertType::Eq, Value::SRef(a), Value::SRef(b))
+    }
+
+    pub fn assert_neq(&mut self, a: SRef, b: SRef) -> Result<SRef> {
+        self.assert(AssertType::Neq, Value::SRef(a), Value::SRef(b))
+    }
+
+    /// Works out how the pod would be created, without proving anything
+    pub fn plan(&self, env: &Env) -> FinalizePlan {
+        self.plan_for(env.sk.is_some())
     }
 
-    pub fn finalize(&mut self, env: &Env) -> Result<POD> {
+    /// Same as `plan`, given whether a secret key is available
+    fn plan_for(&self, has_sk: bool) -> FinalizePlan {
         let could_be_schnorr = self.input_pods.is_empty()
             && self
                 .pending_operations
                 .iter()
                 .all(|(_, op)| matches!(op.0, Op::NewEntry(_)))
-            && env.sk.is_some();
+            && has_sk;
 
+        let mut ops = self
+            .pending_operations
+            .iter()
+            .map(|(_, op)| op.clone())
+            .collect::<Vec<OpCmd>>();
         if could_be_schnorr {
-            // Convert pending operations into entries
-            let entries = self
-                .pending_operations
-                .iter()
-                .filter_map(|(_, op)| {
-                    if let Op::NewEntry(entry) = &op.0 {
-                        Some(entry.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>();
+            // The signer's public key entry is added by the gadget
+            let statement_count = ops.len() + 1;
+            return FinalizePlan {
+                gadget: GadgetID::SCHNORR16,
+                ops,
+                input_pods: HashMap::new(),
+                origin_renaming_map: HashMap::new(),
+                hidden_origins: HashSet::new(),
+                statement_count,
+                max_statements: NS,
+            };
+        }
 
-            Ok(POD::execute_schnorr_gadget::<NS, VL>(&entries, &env.sk.unwrap()).unwrap())
-        } else {
-            let mut origin_renaming_map = HashMap::new();
-            let mut used_origin_names = HashSet::new();
-            let mut next_id = 1;
-
-            for (pod_id, pod) in &self.input_pods {
-                // For _SELF origins, use the pod's payload hash
-                let pod_hash = format!("{}", pod.payload.hash_payload().to_vec()[0]);
-                if !used_origin_names.insert(pod_hash.clone()) {
-                    while used_origin_names.contains(&format!("origin_{}", next_id)) {
-                        next_id += 1;
-                    }
-                    let fallback_name = format!("origin_{}", next_id);
-                    origin_renaming_map.insert(
-                        (pod_id.clone(), SELF_ORIGIN_NAME.to_string()),
-                        fallback_name.clone(),
-                    );
-                    used_origin_names.insert(fallback_name);
+        let mut origin_renaming_map = HashMap::new();
+        let mut used_origin_names = HashSet::new();
+        let mut next_id = 1;
+
+        for (pod_id, pod) in &self.input_pods {
+            // For _SELF origins, use the pod's id
+            let self_origin_name = PodBuilder::pod_id(pod);
+            if !used_origin_names.insert(self_origin_name.clone()) {
+                while used_origin_names.contains(&format!("origin_{}", next_id)) {
                     next_id += 1;
-                } else {
-                    origin_renaming_map.insert(
-                        (pod_id.clone(), SELF_ORIGIN_NAME.to_string()),
-                        format!("{}{}", POD_PREFIX, pod_hash),
-                    );
                 }
+                let fallback_name = format!("origin_{}", next_id);
+                origin_renaming_map.insert(
+                    (pod_id.clone(), SELF_ORIGIN_NAME.to_string()),
+                    fallback_name.clone(),
+                );
+                used_origin_names.insert(fallback_name);
+                next_id += 1;
+            } else {
+                origin_renaming_map.insert(
+                    (pod_id.clone(), SELF_ORIGIN_NAME.to_string()),
+                    self_origin_name,
+                );
+            }
 
-                for (_, statement) in &pod.payload.statements_list {
-                    // Check all anchored keys in the statement
-                    for anchored_key in statement.anchored_keys() {
-                        if !anchored_key.0.is_self() {
-                            let origin_name = anchored_key.0.origin_name.clone();
-                            if !used_origin_names.insert(origin_name.clone()) {
-                                // Name clash - fallback to incremental ID
-                                while used_origin_names.contains(&format!("origin_{}", next_id)) {
-                                    next_id += 1;
-                                }
-                                let fallback_name = format!("origin_{}", next_id);
-                                origin_renaming_map
-                                    .insert((pod_id.clone(), origin_name), fallback_name.clone());
-                                used_origin_names.insert(fallback_name);
+            for (_, statement) in pod.payload.statements_list() {
+                // Check all anchored keys in the statement
+                for anchored_key in statement.anchored_keys() {
+                    if !anchored_key.0.is_self() {
+                        let origin_name = anchored_key.0.origin_name.clone();
+                        if !used_origin_names.insert(origin_name.clone()) {
+                            // Name clash - fallback to incremental ID
+                            while used_origin_names.contains(&format!("origin_{}", next_id)) {
                                 next_id += 1;
-                            } else {
-                                // Can keep the original name
-                                origin_renaming_map
-                                    .insert((pod_id.clone(), origin_name.clone()), origin_name);
                             }
+                            let fallback_name = format!("origin_{}", next_id);
+                            origin_renaming_map
+                                .insert((pod_id.clone(), origin_name), fallback_name.clone());
+                            used_origin_names.insert(fallback_name);
+                            next_id += 1;
+                        } else {
+                            // Can keep the original name
+                            origin_renaming_map
+                                .insert((pod_id.clone(), origin_name.clone()), origin_name);
                         }
                     }
                 }
             }
+        }
+
+        // Redacted pods get opaque origin names, which must also replace their ids in the names
+        // of the statements copied from them
+        let origin_renaming_map = GPGInput::new_redacted(
+            self.input_pods.clone(),
+            origin_renaming_map,
+            &self.hidden_origins,
+        )
+        .origin_renaming_map;
+        let hidden_origin_name = |pod_id: &str| {
+            self.hidden_origins
+                .contains(pod_id)
+                .then(|| &origin_renaming_map[&(pod_id.to_string(), SELF_ORIGIN_NAME.to_string())])
+        };
+        for OpCmd(_, statement_id) in ops.iter_mut() {
+            for pod_id in &self.hidden_origins {
+                let kept_prefix = format!("{}{}_", STATEMENT_PREFIX_KEPT, pod_id);
+                if let Some(key) = statement_id.strip_prefix(&kept_prefix) {
+                    *statement_id = format!(
+                        "{}{}_{}",
+                        STATEMENT_PREFIX_KEPT,
+                        hidden_origin_name(pod_id).unwrap(),
+                        key
+                    );
+                    break;
+                }
+            }
+        }
+
+        for matched_statement in &self.matched_statements {
+            let op = Op::CopyStatement(matched_statement.into());
+            let origin_str: String = matched_statement.0.clone().into();
+            let statement_id = format!(
+                "from_{}_{}",
+                hidden_origin_name(origin_str.as_str()).unwrap_or(&origin_str),
+                matched_statement.1.name
+            );
+            ops.push(OpCmd(op, statement_id));
+        }
+
+        let statement_count = ops.len();
+        FinalizePlan {
+            gadget: if self.zk {
+                GadgetID::PLONKY
+            } else {
+                GadgetID::ORACLE
+            },
+            ops,
+            input_pods: self.input_pods.clone(),
+            origin_renaming_map,
+            hidden_origins: self.hidden_origins.clone(),
+            statement_count,
+            max_statements: NS,
+        }
+    }
 
-            let mut copy_statements = Vec::new();
-            for matched_statement in &self.matched_statements {
-                let op = Op::CopyStatement(matched_statement.into());
-                let origin_str: String = matched_statement.0.clone().into();
-                let statement_id = format!(
-                    "from_{}_{}",
-                    origin_str,
-                    matched_statement.1.split(':').last().unwrap().to_string()
+    /// Creates the pod a plan describes. Plonky pods use the prover params of the env, which
+    /// are built first if they haven't been yet.
+    pub async fn prove(plan: FinalizePlan, env: &Env) -> Result<POD> {
+        let prover_params = match (plan.gadget, &env.prover) {
+            (GadgetID::PLONKY, Some(prover)) => Some(prover.params().await?),
+            _ => None,
+        };
+        Self::prove_with(plan, env.sk.as_ref(), prover_params.as_deref())
+    }
+
+    fn prove_with(
+        plan: FinalizePlan,
+        sk: Option<&SchnorrSecretKey>,
+        prover_params: Option<&Mutex<ProverParams<L, M, N, NS, VL>>>,
+    ) -> Result<POD> {
+        match plan.gadget {
+            GadgetID::SCHNORR16 => {
+                let entries = plan
+                    .ops
+                    .into_iter()
+                    .filter_map(|OpCmd(op, _)| match op {
+                        Op::NewEntry(entry) => Some(entry),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                let sk = sk.ok_or_else(|| anyhow!("Schnorr pods require a secret key"))?;
+                POD::execute_schnorr_gadget::<NS, VL>(&entries, sk)
+            }
+            GadgetID::PLONKY => {
+                let gpg_input = GPGInput::new_redacted(
+                    plan.input_pods,
+                    plan.origin_renaming_map,
+                    &plan.hidden_origins,
                 );
-                copy_statements.push((op, statement_id))
+                let prover_params =
+                    prover_params.ok_or_else(|| anyhow!("Plonky pods require prover params"))?;
+                let mut params = prover_params.lock().unwrap();
+                POD::execute_plonky_gadget::<L, M, N, NS, VL>(&mut params, &gpg_input, &plan.ops)
             }
-            for (op, statement_id) in copy_statements {
-                self.add_operation(op, statement_id);
+            _ => {
+                let gpg_input = GPGInput::new_redacted(
+                    plan.input_pods,
+                    plan.origin_renaming_map,
+                    &plan.hidden_origins,
+                );
+                POD::execute_oracle_gadget(&gpg_input, &plan.ops)
             }
+        }
+    }
 
-            let gpg_input = GPGInput::new(self.input_pods.clone(), origin_renaming_map);
-            let pending_ops: &[OpCmd] = &self
-                .pending_operations
-                .iter()
-                .map(|(_, ops)| ops.clone())
-                .collect::<Vec<OpCmd>>()[..];
-            if let Some(prover_params) = &env.prover_params {
-                let mut params = prover_params.lock().unwrap();
-                POD::execute_plonky_gadget::<L, M, N, NS, VL>(&mut params, &gpg_input, pending_ops)
-            } else {
-                POD::execute_oracle_gadget(&gpg_input, pending_ops)
-            }
+    pub async fn finalize(&self, env: &Env) -> Result<POD> {
+        if let Some(schema) = &self.schema {
+            schema.check(self.entries())?;
         }
+        Self::prove(self.plan(env), env).await
+    }
+
+    /// The entries the pod will have under their own key, leaving out constants and unnamed
+    /// results of operations
+    fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.pending_operations
+            .iter()
+            .filter(|(statement_id, _)| !statement_id.starts_with(STATEMENT_PREFIX_CONSTANT))
+            .filter_map(|(_, op_cmd)| match &op_cmd.0 {
+                Op::NewEntry(entry) if !entry.key.starts_with(STATEMENT_PREFIX_RESULT) => {
+                    Some(entry)
+                }
+                _ => None,
+            })
+    }
+
+    /// Creates the pod outside the interpreter: a Schnorr pod signed with `sk` when it only has
+    /// new entries and a key is given, an oracle pod otherwise
+    pub fn finalize_with_key(&self, sk: Option<&SchnorrSecretKey>) -> Result<POD> {
+        Self::prove_with(self.plan_for(sk.is_some()), sk, None)
     }
 }
 
@@ -759,7 +2005,7 @@ impl Env {
         pod_store: Arc<Mutex<MyPods>>,
         sk: Option<SchnorrSecretKey>,
         script_id: Option<ScriptId>,
-        prover_params: Option<Arc<Mutex<ProverParams<L, M, N, NS, VL>>>>,
+        prover: Option<Arc<ProverContext>>,
     ) -> Self {
         Self {
             user,
@@ -767,103 +2013,536 @@ impl Env {
             pod_store,
             current_builder: None,
             current_query: None,
-            bindings: Arc::new(Mutex::new(HashMap::new())),
+            bindings: Arc::new(Scope::default()),
             sk,
             script_id,
-            prover_params,
+            prover,
+            remote_timeout: DEFAULT_REMOTE_TIMEOUT,
         }
     }
 
+    /// A child env whose definitions are local to it, eg: for the body of a createpod
     pub fn extend(&self) -> Self {
-        // TODO: scoping
         Self {
             user: self.user.clone(),
             shared: self.shared.clone(),
             pod_store: self.pod_store.clone(),
             current_builder: self.current_builder.clone(),
             current_query: self.current_query.clone(),
-            bindings: Arc::new(Mutex::new(self.bindings.lock().unwrap().clone())),
+            bindings: Arc::new(Scope {
+                bindings: Mutex::new(HashMap::new()),
+                parent: Some(self.bindings.clone()),
+            }),
             sk: self.sk.clone(),
             script_id: self.script_id.clone(),
-            prover_params: self.prover_params.clone(),
+            prover: self.prover.clone(),
+            remote_timeout: self.remote_timeout,
         }
     }
 
-    pub async fn get_remote(&self, id: u64) -> Option<Value> {
+    pub async fn get_remote(&self, id: u64) -> Result<Value, WaitError> {
         self.shared
-            .get_value(self.script_id.as_ref().unwrap(), id)
+            .get_value(
+                self.script_id.as_ref().unwrap(),
+                id,
+                &self.user,
+                self.remote_timeout,
+            )
             .await
     }
 
-    pub fn set_remote(&self, id: u64, value: Value) {
+    pub fn set_remote(&self, id: u64, value: Value, options: SetOptions) {
         self.shared
-            .set_value(self.script_id.as_ref().unwrap(), id, value);
+            .set_value(self.script_id.as_ref().unwrap(), id, value, options);
+    }
+    pub fn set_identity(&mut self, user: User, sk: Option<SchnorrSecretKey>) {
+        self.user = user;
+        self.sk = sk;
+    }
+
+    /// How long `from` waits for values and pods of other users
+    pub fn set_remote_timeout(&mut self, timeout: Duration) {
+        self.remote_timeout = timeout;
+    }
+
+    /// Adds a pod received out of band to our pods and the shared store, once it verifies.
+    /// Pods we already have are left untouched.
+    pub fn import_pod(&self, pod: POD) -> Result<String> {
+        let id = PodBuilder::pod_id(&pod);
+        if !pod.verify::<L, M, N, NS, VL>()? {
+            return Err(anyhow!("POD {} failed verification", id));
+        }
+        let mut pod_store = self.pod_store.lock().unwrap();
+        if pod_store.get_pod(&id).is_some() {
+            return Err(anyhow!("POD {} already exists", id));
+        }
+        self.shared.store_pod(pod.clone());
+        pod_store.add_pod(pod);
+        Ok(id)
     }
+
     pub fn get_binding(&self, name: &str) -> Option<Value> {
-        self.bindings.lock().unwrap().get(name).cloned()
+        self.bindings.get(name)
     }
 
+    /// Binds the name in the innermost scope
     pub fn set_binding(&self, name: String, value: Value) {
-        self.bindings.lock().unwrap().insert(name, value);
+        self.bindings.bindings.lock().unwrap().insert(name, value);
+    }
+
+    /// The schema the name is bound to, eg: by defschema
+    fn get_schema(&self, name: &Expr) -> Result<Schema> {
+        let Expr::Atom(_, name) = name else {
+            return Err(anyhow!(":schema requires a schema name"));
+        };
+        match self.get_binding(name) {
+            Some(Value::Schema(schema)) => Ok(schema),
+            Some(_) => Err(anyhow!("{} is not a schema", name)),
+            None => Err(anyhow!("Schema {} not found", name)),
+        }
+    }
+
+    /// Makes the pod a reference points to an input of the pod being built, if it isn't one
+    /// yet, eg: a result of pod?*. Returns the reference with the id the pod is registered under.
+    fn use_input_pod(&self, sref: &SRef) -> Result<SRef> {
+        let (Some(builder), ORef::P(pod_id)) = (&self.current_builder, &sref.0) else {
+            return Ok(sref.clone());
+        };
+        if builder.lock().unwrap().input_pods.contains_key(pod_id) {
+            return Ok(sref.clone());
+        }
+        let pod = self
+            .pod_store
+            .lock()
+            .unwrap()
+            .get_pod(pod_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Pod {} not found", pod_id))?;
+        let pod_id = builder.lock().unwrap().register_input_pod(&pod);
+        Ok(SRef(ORef::P(pod_id), sref.1.clone()))
+    }
+
+    /// Same env with only the given bindings, eg: to call a closure in the pod being built
+    fn with_bindings(&self, bindings: HashMap<String, Value>) -> Self {
+        Self {
+            bindings: Arc::new(Scope {
+                bindings: Mutex::new(bindings),
+                parent: None,
+            }),
+            ..self.clone()
+        }
     }
 }
 
 type Id = u64;
 
+/// Position of a token in the source. `id` is the index of the token, `line` and `col` start at 1
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pos {
+    id: Id,
+    line: u64,
+    col: u64,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.col)
+    }
+}
+
 struct Token {
     val: String,
-    pos: Id,
+    pos: Pos,
 }
 
+/// Splits the source into tokens, skipping `;` comments up to the end of the line and `#| ... |#`
+/// block comments. An unterminated block comment runs to the end of the source.
 fn scan(source: &str) -> Vec<Token> {
-    source
-        .replace("[", " [ ")
-        .replace("]", " ] ")
-        .split_whitespace()
-        .enumerate()
-        .map(|(pos, val)| Token {
-            pos: pos as u64,
-            val: val.to_string(),
-        })
-        .collect()
-}
-
-fn parse(tokens: &mut Vec<Token>) -> Result<Expr> {
-    if tokens[0].val != "[" {
-        return Err(anyhow!("must start with ["));
-    }
-    let token = tokens.remove(0);
-    let mut list: Vec<Expr> = Vec::new();
-    while !tokens.is_empty() {
-        match tokens[0].val.as_str() {
-            "]" => {
-                tokens.remove(0);
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut in_block_comment = false;
+    for (line, text) in source.lines().enumerate() {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut col = 0;
+        while col < chars.len() {
+            let c = chars[col];
+            let next = chars.get(col + 1).copied();
+            if in_block_comment {
+                if c == '|' && next == Some('#') {
+                    in_block_comment = false;
+                    col += 1;
+                }
+                col += 1;
+                continue;
+            }
+            if c == ';' {
                 break;
             }
-            "[" => {
-                list.push(parse(tokens)?);
+            if c == '#' && next == Some('|') {
+                tokens.extend(current.take());
+                in_block_comment = true;
+                col += 2;
+                continue;
             }
-            _ => {
-                let token = tokens.remove(0);
-                list.push(Expr::Atom(token.pos, token.val));
+            let pos = Pos {
+                id: 0,
+                line: line as u64 + 1,
+                col: col as u64 + 1,
+            };
+            if c == '"' && current.is_none() {
+                // String literals are a single token, quotes included, so they can contain
+                // spaces, brackets or ;. An unterminated one runs to the end of the line.
+                let mut val = String::from(c);
+                col += 1;
+                while col < chars.len() {
+                    let c = chars[col];
+                    val.push(c);
+                    col += 1;
+                    if c == '\\' {
+                        if let Some(&escaped) = chars.get(col) {
+                            val.push(escaped);
+                            col += 1;
+                        }
+                    } else if c == '"' {
+                        break;
+                    }
+                }
+                tokens.push(Token { val, pos });
+                continue;
             }
-        }
-    }
+            if c.is_whitespace() || c == '[' || c == ']' {
+                tokens.extend(current.take());
+                if !c.is_whitespace() {
+                    tokens.push(Token {
+                        val: c.to_string(),
+                        pos,
+                    });
+                }
+            } else {
+                current
+                    .get_or_insert(Token {
+                        val: String::new(),
+                        pos,
+                    })
+                    .val
+                    .push(c);
+            }
+            col += 1;
+        }
+        tokens.extend(current.take());
+    }
+    for (id, token) in tokens.iter_mut().enumerate() {
+        token.pos.id = id as u64;
+    }
+    tokens
+}
+
+/// Decodes a string literal token (quotes included), or returns None if the token isn't a
+/// terminated string literal
+fn string_literal(token: &str) -> Option<String> {
+    let mut chars = token.strip_prefix('"')?.chars();
+    let mut s = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return chars.next().is_none().then_some(s),
+            '\\' => s.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                c => c,
+            }),
+            c => s.push(c),
+        }
+    }
+    None
+}
+
+/// Splits the arguments of `from` after the user into the options the value is shared with
+/// and its expression, eg: `:to [bob charlie] expr` shares it with bob and charlie only
+fn from_arguments(args: &[Expr]) -> Result<(SetOptions, &Expr)> {
+    match args {
+        [expr] => Ok((SetOptions::default(), expr)),
+        [Expr::Atom(_, to), Expr::List(_, recipients), expr] if to == ":to" => {
+            let recipients = recipients
+                .iter()
+                .map(|recipient| match recipient {
+                    Expr::Atom(_, user) => Ok(user.clone()),
+                    _ => Err(anyhow!("recipients of 'from' must be users")),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let options = SetOptions {
+                recipients: Some(recipients),
+                ..SetOptions::default()
+            };
+            Ok((options, expr))
+        }
+        _ => Err(anyhow!(
+            "'from' takes a user, optionally :to and a list of recipients, and an expression"
+        )),
+    }
+}
+
+/// Error returned when a script can't be parsed, `pos` is the index of the offending token and
+/// `line`, `col` where it starts
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub pos: u64,
+    pub line: u64,
+    pub col: u64,
+    pub token: String,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(token: &Token, message: impl Into<String>) -> Self {
+        Self {
+            pos: token.pos.id,
+            line: token.pos.line,
+            col: token.pos.col,
+            token: token.val.clone(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at token {} ({})", self.message, self.pos, self.token)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Evaluation failures callers may want to tell apart. They travel inside anyhow errors, so
+/// get them back with `downcast_ref::<PexError>()`, the same as ParseError.
+#[derive(Debug)]
+pub enum PexError {
+    UnknownIdentifier {
+        name: String,
+        pos: Pos,
+    },
+    NoMatchingPod {
+        constraints: usize,
+    },
+    /// An assertion in the assertion section of a createpod that doesn't hold
+    AssertionFailed {
+        assert: String,
+        left: u64,
+        right: u64,
+    },
+    /// The value `from` was waiting for (with id `aid`) didn't arrive
+    RemoteUnavailable {
+        aid: u64,
+        error: WaitError,
+    },
+    PodFinalize(anyhow::Error),
+}
+
+impl fmt::Display for PexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PexError::UnknownIdentifier { name, pos } => {
+                write!(f, "Unknown identifier: {} at {}", name, pos)
+            }
+            PexError::NoMatchingPod { constraints } => {
+                write!(f, "No matching pod found for {} constraints", constraints)
+            }
+            PexError::AssertionFailed {
+                assert,
+                left,
+                right,
+            } => write!(
+                f,
+                "Assertion {} failed (left is {}, right is {})",
+                assert, left, right
+            ),
+            PexError::RemoteUnavailable { error, .. } => {
+                write!(f, "couldn't find on the remote: {}", error)
+            }
+            PexError::PodFinalize(e) => write!(f, "Couldn't finalize the pod: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PexError::RemoteUnavailable { error, .. } => Some(error),
+            PexError::PodFinalize(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+fn parse(tokens: &mut Vec<Token>) -> Result<Expr, ParseError> {
+    let token = match tokens.first() {
+        Some(token) if token.val == "[" => tokens.remove(0),
+        Some(token) => return Err(ParseError::new(token, "must start with [")),
+        None => {
+            return Err(ParseError {
+                pos: 0,
+                line: 1,
+                col: 1,
+                token: String::new(),
+                message: "empty input".to_string(),
+            })
+        }
+    };
+    let mut list: Vec<Expr> = Vec::new();
+    loop {
+        if tokens.is_empty() {
+            return Err(ParseError::new(&token, "unterminated list"));
+        }
+        match tokens[0].val.as_str() {
+            "]" => {
+                tokens.remove(0);
+                break;
+            }
+            "[" => {
+                list.push(parse(tokens)?);
+            }
+            _ => {
+                let token = tokens.remove(0);
+                if token.val.starts_with('"') && string_literal(&token.val).is_none() {
+                    return Err(ParseError::new(&token, "unterminated string"));
+                }
+                list.push(Expr::Atom(token.pos, token.val));
+            }
+        }
+    }
     Ok(Expr::List(token.pos, list))
 }
 
+/// Parses a whole script, which must be a single list
+fn parse_script(source: &str) -> Result<Expr, ParseError> {
+    let mut tokens = scan(source);
+    let expr = parse(&mut tokens)?;
+    match tokens.first() {
+        Some(token) if token.val == "]" => Err(ParseError::new(token, "unmatched ]")),
+        Some(token) => Err(ParseError::new(
+            token,
+            "unexpected token after the end of the list",
+        )),
+        None => Ok(expr),
+    }
+}
+
 pub async fn eval(source: &str, env: Env) -> Result<Value> {
     let env = Env {
         script_id: Some(ScriptId::from_script(source)),
         ..env
     };
-    parse(&mut scan(source))?.eval(env).await
+    parse_script(source)?.eval(env).await
+}
+
+/// Parses a script file, which holds any number of lists one after the other
+fn parse_forms(source: &str) -> Result<Vec<Expr>, ParseError> {
+    let mut tokens = scan(source);
+    let mut forms = Vec::new();
+    while let Some(token) = tokens.first() {
+        if token.val == "]" {
+            return Err(ParseError::new(token, "unmatched ]"));
+        }
+        forms.push(parse(&mut tokens)?);
+    }
+    Ok(forms)
+}
+
+/// Error of a script file, at the position of the error when it has one, otherwise at the start
+/// of the form that failed
+#[derive(Debug)]
+pub struct ScriptError {
+    pub line: u64,
+    pub col: u64,
+    pub error: anyhow::Error,
+}
+
+impl ScriptError {
+    fn new(form: Option<&Expr>, error: anyhow::Error) -> Self {
+        let (line, col) = match (
+            error.downcast_ref::<ParseError>(),
+            error.downcast_ref::<PexError>(),
+            form,
+        ) {
+            (Some(e), _, _) => (e.line, e.col),
+            (_, Some(PexError::UnknownIdentifier { pos, .. }), _) => (pos.line, pos.col),
+            (_, _, Some(Expr::Atom(pos, _) | Expr::List(pos, _))) => (pos.line, pos.col),
+            _ => (1, 1),
+        };
+        Self { line, col, error }
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, col {}: ", self.line, self.col)?;
+        match (
+            self.error.downcast_ref::<ParseError>(),
+            self.error.downcast_ref::<PexError>(),
+        ) {
+            (Some(e), _) => write!(f, "{} ({})", e.message, e.token),
+            (_, Some(PexError::UnknownIdentifier { name, .. })) => {
+                write!(f, "Unknown identifier: {}", name)
+            }
+            _ => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// Evaluates the forms of a script file one after the other in the same env, and returns their
+/// values. It stops at the first error. Like in the REPL, pods created by a form are added to the
+/// pod store, so that the following forms can query them.
+pub async fn eval_script(source: &str, env: Env) -> Result<Vec<Value>, ScriptError> {
+    let forms = parse_forms(source).map_err(|e| ScriptError::new(None, e.into()))?;
+    // A single script id, token ids being unique across the forms
+    let env = Env {
+        script_id: Some(ScriptId::from_script(source)),
+        ..env
+    };
+    let mut values = Vec::new();
+    for form in forms {
+        let value = form
+            .eval(env.clone())
+            .await
+            .map_err(|e| ScriptError::new(Some(&form), e))?;
+        if let Value::PodRef(pod) = &value {
+            if !form.to_string().contains(&env.user) {
+                env.pod_store.lock().unwrap().add_pod(pod.clone());
+            }
+        }
+        values.push(value);
+    }
+    Ok(values)
 }
 
-#[derive(Debug, PartialEq)]
-enum Expr {
-    Atom(Id, String),
-    List(Id, Vec<Expr>),
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Expr {
+    Atom(Pos, String),
+    List(Pos, Vec<Expr>),
+}
+
+/// Writes the expression back as source, eg: to report which assertion failed
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Atom(_, a) => write!(f, "{}", a),
+            Expr::List(_, exprs) => {
+                write!(f, "[")?;
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{}", expr)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
 }
 
 impl Expr {
@@ -875,11 +2554,21 @@ impl Expr {
                     return Err(anyhow!("Empty expression"));
                 }
                 match &exprs[0] {
-                    Expr::Atom(aid, op) => {
+                    Expr::Atom(apos, op) => {
+                        // Handle boolean logic over asserts
+                        if LogicType::from_str(op).is_ok() {
+                            return self.eval_logic(env).await;
+                        }
                         // Handle asserts which can be tracked inside PODs
-                        if let Ok(assert_type) = AssertType::from_str(op) {
+                        else if let Ok(assert_type) = AssertType::from_str(op) {
                             return self.eval_assert(assert_type, &exprs[1..], env).await;
                         }
+                        // Handle vector membership which can be tracked inside PODs
+                        else if let Ok(membership_type) = MembershipType::from_str(op) {
+                            return self
+                                .eval_membership(membership_type, &exprs[1..], env)
+                                .await;
+                        }
                         // Handle operation which can be tracked inside PODs
                         else if let Ok(op_type) = OpType::from_str(op) {
                             return self.eval_operation(op_type, &exprs[1..], env).await;
@@ -892,14 +2581,18 @@ impl Expr {
                                         None
                                     };
                                     if let Some(user) = user_name {
+                                        let (options, expr) = from_arguments(&exprs[2..])?;
                                         if user == &env.user {
-                                            let res = exprs[2].eval(env.clone()).await?;
-                                            env.set_remote(*aid, res.clone());
+                                            let res = expr.eval(env.clone()).await?;
+                                            env.set_remote(apos.id, res.clone(), options);
                                             Ok(res)
                                         } else {
-                                            let remote_value =
-                                                env.get_remote(*aid).await.ok_or_else(|| {
-                                                    anyhow!("couldn't find on the remote")
+                                            let remote_value = env
+                                                .get_remote(apos.id)
+                                                .await
+                                                .map_err(|error| PexError::RemoteUnavailable {
+                                                    aid: apos.id,
+                                                    error,
                                                 })?;
 
                                             match &remote_value {
@@ -912,8 +2605,10 @@ impl Expr {
                                                 // }
                                                 Value::SRef(sref) => {
                                                     if let ORef::P(pod_id) = &sref.0 {
-                                                        if let Some(pod) =
-                                                            env.shared.get_pod(pod_id).await
+                                                        if let Ok(pod) = env
+                                                            .shared
+                                                            .get_pod(pod_id, env.remote_timeout)
+                                                            .await
                                                         {
                                                             env.pod_store
                                                                 .lock()
@@ -935,8 +2630,13 @@ impl Expr {
                                                     for value in values {
                                                         if let Value::SRef(sref) = value {
                                                             if let ORef::P(pod_id) = &sref.0 {
-                                                                if let Some(pod) =
-                                                                    env.shared.get_pod(pod_id).await
+                                                                if let Ok(pod) = env
+                                                                    .shared
+                                                                    .get_pod(
+                                                                        pod_id,
+                                                                        env.remote_timeout,
+                                                                    )
+                                                                    .await
                                                                 {
                                                                     env.pod_store
                                                                         .lock()
@@ -967,17 +2667,64 @@ impl Expr {
                                         ));
                                     }
                                 }
-                                "createpod" => {
+                                "createpod" | "createpod!" => {
                                     if exprs.len() < 2 {
-                                        return Err(anyhow!("createpod requires a body"));
+                                        return Err(anyhow!("{} requires a body", op));
                                     }
-                                    self.eval_create_pod(&exprs[1..], env).await
+                                    self.eval_create_pod(&exprs[1..], env, op == "createpod!")
+                                        .await
                                 }
-                                "pod?" => {
+                                "explain" => match exprs.get(1) {
+                                    Some(Expr::List(_, createpod))
+                                        if exprs.len() == 2
+                                            && createpod.len() >= 2
+                                            && matches!(
+                                                createpod.first(),
+                                                Some(Expr::Atom(_, op))
+                                                    if op == "createpod" || op == "createpod!"
+                                            ) =>
+                                    {
+                                        let zk = matches!(
+                                            createpod.first(),
+                                            Some(Expr::Atom(_, op)) if op == "createpod!"
+                                        );
+                                        let builder = self
+                                            .build_pod(&createpod[1..], env.clone(), zk)
+                                            .await?;
+                                        Ok(Value::Plan(Box::new(builder.plan(&env))))
+                                    }
+                                    _ => Err(anyhow!("explain requires a createpod")),
+                                },
+                                "pod?" | "pod?*" => {
                                     if exprs.len() < 2 {
-                                        return Err(anyhow!("pod? requires at least one argument"));
+                                        return Err(anyhow!(
+                                            "{} requires at least one argument",
+                                            op
+                                        ));
+                                    }
+                                    self.eval_pod_query(&exprs[1..], op == "pod?*", env).await
+                                }
+                                "keep" => {
+                                    if exprs.len() != 2 {
+                                        return Err(anyhow!("keep requires exactly one argument"));
+                                    }
+                                    self.eval_keep(&exprs[1], env).await
+                                }
+                                "redact" => {
+                                    if exprs.len() != 2 {
+                                        return Err(anyhow!(
+                                            "redact requires exactly one argument"
+                                        ));
+                                    }
+                                    self.eval_redact(&exprs[1], env).await
+                                }
+                                "if" => {
+                                    if exprs.len() != 4 {
+                                        return Err(anyhow!(
+                                            "if requires a condition, a then branch and an else branch"
+                                        ));
                                     }
-                                    self.eval_pod_query(&exprs[1..], env).await
+                                    self.eval_if(&exprs[1], &exprs[2], &exprs[3], env).await
                                 }
                                 "define" => {
                                     if exprs.len() != 3 {
@@ -1025,6 +2772,60 @@ impl Expr {
                                         }
                                     }
                                 }
+                                "let" => {
+                                    if exprs.len() < 3 {
+                                        return Err(anyhow!(
+                                            "let requires a list of bindings and a body"
+                                        ));
+                                    }
+                                    self.eval_let(&exprs[1], &exprs[2..], env).await
+                                }
+                                "lambda" => {
+                                    if exprs.len() < 3 {
+                                        return Err(anyhow!(
+                                            "lambda requires a list of parameters and a body"
+                                        ));
+                                    }
+                                    Self::make_closure(None, &exprs[1], &exprs[2..], &env)
+                                }
+                                "defun" => {
+                                    if exprs.len() < 4 {
+                                        return Err(anyhow!(
+                                            "defun requires a name, a list of parameters and a body"
+                                        ));
+                                    }
+                                    let name = match &exprs[1] {
+                                        Expr::Atom(_, name) => name.clone(),
+                                        _ => return Err(anyhow!("defun name must be an atom")),
+                                    };
+                                    let closure = Self::make_closure(
+                                        Some(name.clone()),
+                                        &exprs[2],
+                                        &exprs[3..],
+                                        &env,
+                                    )?;
+                                    env.set_binding(name, closure.clone());
+                                    Ok(closure)
+                                }
+                                "defschema" => Self::eval_defschema(&exprs[1..], &env),
+                                "cmp" => self.eval_cmp(&exprs[1..], env).await,
+                                "quote" => {
+                                    if exprs.len() != 2 {
+                                        return Err(anyhow!("quote requires exactly one argument"));
+                                    }
+                                    Ok(Value::Quoted(exprs[1].clone()))
+                                }
+                                "eval" => {
+                                    if exprs.len() != 2 {
+                                        return Err(anyhow!("eval requires exactly one argument"));
+                                    }
+                                    // Only one level of quoting is removed, other values are
+                                    // already evaluated
+                                    match exprs[1].eval(env.clone()).await? {
+                                        Value::Quoted(expr) => expr.eval(env).await,
+                                        value => Ok(value),
+                                    }
+                                }
                                 "list" => {
                                     let mut values = Vec::new();
                                     for expr in &exprs[1..] {
@@ -1032,6 +2833,36 @@ impl Expr {
                                     }
                                     Ok(Value::List(values))
                                 }
+                                "vec" => {
+                                    let mut values = Vec::new();
+                                    for expr in &exprs[1..] {
+                                        match expr.eval(env.clone()).await? {
+                                            Value::Scalar(s) => values.push(s),
+                                            _ => {
+                                                return Err(anyhow!(
+                                                    "vec requires scalar arguments"
+                                                ))
+                                            }
+                                        }
+                                    }
+                                    Ok(Value::Vector(values))
+                                }
+                                "map" => {
+                                    if exprs.len() != 4 {
+                                        return Err(anyhow!(
+                                            "map requires an operation and two lists"
+                                        ));
+                                    }
+                                    self.eval_map(&exprs[1], &exprs[2..], env).await
+                                }
+                                "fold" => {
+                                    if exprs.len() != 4 {
+                                        return Err(anyhow!(
+                                            "fold requires an operation, an initial value and a list"
+                                        ));
+                                    }
+                                    self.eval_fold(&exprs[1], &exprs[2], &exprs[3], env).await
+                                }
                                 "car" => {
                                     if exprs.len() != 2 {
                                         return Err(anyhow!("car requires exactly one argument"));
@@ -1074,20 +2905,95 @@ impl Expr {
                                         }
                                     }
                                 }
-                                op => Err(anyhow!("Unknown operation: {}", op)),
+                                "length" => {
+                                    if exprs.len() != 2 {
+                                        return Err(anyhow!(
+                                            "length requires exactly one argument"
+                                        ));
+                                    }
+                                    match exprs[1].eval(env).await? {
+                                        Value::List(values) => {
+                                            Ok(Value::Scalar(GoldilocksField(values.len() as u64)))
+                                        }
+                                        _ => Err(anyhow!("length requires a list argument")),
+                                    }
+                                }
+                                "nth" => {
+                                    if exprs.len() != 3 {
+                                        return Err(anyhow!(
+                                            "nth requires an index and a list or a vector"
+                                        ));
+                                    }
+                                    let index = match exprs[1].eval(env.clone()).await? {
+                                        Value::Scalar(s) => s.to_canonical_u64(),
+                                        _ => return Err(anyhow!("nth index must be a scalar")),
+                                    };
+                                    let values = match exprs[2].eval(env.clone()).await? {
+                                        Value::List(values) => values,
+                                        Value::Vector(values) => {
+                                            values.into_iter().map(Value::Scalar).collect()
+                                        }
+                                        // Vector entries of the pod being created or of its inputs
+                                        Value::SRef(sref) => {
+                                            match get_scalar_or_vec_from_sref(&sref, &env)? {
+                                                ScalarOrVec::Vector(values) => {
+                                                    values.into_iter().map(Value::Scalar).collect()
+                                                }
+                                                ScalarOrVec::Scalar(_) => {
+                                                    return Err(anyhow!(
+                                                        "nth requires a list or a vector argument"
+                                                    ))
+                                                }
+                                            }
+                                        }
+                                        _ => {
+                                            return Err(anyhow!(
+                                                "nth requires a list or a vector argument"
+                                            ))
+                                        }
+                                    };
+                                    usize::try_from(index)
+                                        .ok()
+                                        .and_then(|i| values.get(i).cloned())
+                                        .ok_or_else(|| {
+                                            anyhow!(
+                                                "nth index {} out of bounds for length {}",
+                                                index,
+                                                values.len()
+                                            )
+                                        })
+                                }
+                                op => match env.get_binding(op) {
+                                    Some(Value::Closure(closure)) => {
+                                        self.apply_closure(&closure, &exprs[1..], env).await
+                                    }
+                                    _ => Err(anyhow!("Unknown operation: {}", op)),
+                                },
                             }
                         }
                     }
-                    _ => Err(anyhow!("First item must be an atom")),
+                    head => match head.eval(env.clone()).await? {
+                        Value::Closure(closure) => {
+                            self.apply_closure(&closure, &exprs[1..], env).await
+                        }
+                        _ => Err(anyhow!("First item must be an atom or a function")),
+                    },
                 }
             }
-            Expr::Atom(_, a) => {
+            Expr::Atom(pos, a) => {
+                if let Some(s) = string_literal(a) {
+                    return Ok(Value::String(s));
+                }
                 // First try to resolve as binding
                 if let Some(value) = env.get_binding(a) {
                     Ok(value)
                 } else if let Ok(num) = a.parse::<u64>() {
                     // Existing number parsing
                     Ok(Value::Scalar(GoldilocksField(num)))
+                } else if let Some(num) = a.strip_prefix('-').and_then(|n| n.parse::<u64>().ok()) {
+                    // Negative numbers are the field element -num mod p. A lone "-" doesn't parse so
+                    // it can't be confused with subtraction
+                    Ok(Value::Scalar(-GoldilocksField::from_noncanonical_u64(num)))
                 } else if env.current_query.is_some() {
                     // Create an SRef to current pod being created
                     Ok(Value::SRef(SRef(
@@ -1099,17 +3005,43 @@ impl Expr {
                                 .unwrap()
                                 .current_origin_id,
                         ),
-                        format!("{}:{}", PREDICATE_VALUEOF, a),
+                        StatementId::value_of(a),
                     )))
                 } else {
-                    Err(anyhow!("Unknown identifier: {}", a))
+                    Err(PexError::UnknownIdentifier {
+                        name: a.clone(),
+                        pos: *pos,
+                    }
+                    .into())
                 }
             }
         }
     }
-    async fn eval_create_pod(&self, body: &[Expr], env: Env) -> Result<Value> {
+    async fn eval_create_pod(&self, body: &[Expr], env: Env, zk: bool) -> Result<Value> {
+        let mut builder = self.build_pod(body, env.clone(), zk).await?;
+        let pod = builder
+            .finalize(&env)
+            .await
+            .map_err(PexError::PodFinalize)?;
+        if !builder.display_map.is_empty() {
+            env.pod_store.lock().unwrap().set_metadata(
+                &pod,
+                PodMetadata {
+                    strings: std::mem::take(&mut builder.display_map),
+                },
+            );
+        }
+        Ok(Value::PodRef(pod))
+    }
+
+    /// Evaluates the body of a createpod, returning the builder ready to be finalized. `zk` is
+    /// set for createpod!
+    async fn build_pod(&self, body: &[Expr], env: Env, zk: bool) -> Result<PodBuilder> {
         let mut pod_env = env.extend();
-        let builder = Arc::new(Mutex::new(PodBuilder::new()));
+        let builder = Arc::new(Mutex::new(PodBuilder {
+            zk,
+            ..PodBuilder::new()
+        }));
         pod_env.current_builder = Some(builder.clone());
 
         let pod_name = if let Expr::Atom(_, op) = &body[0] {
@@ -1118,13 +3050,21 @@ impl Expr {
             None
         };
         info!("creating pod {}", pod_name.expect("No pod name"));
-        // First process defines
         let mut i = 1;
+        if matches!(body.get(1), Some(Expr::Atom(_, flag)) if flag == ":schema") {
+            let schema = body
+                .get(2)
+                .ok_or_else(|| anyhow!(":schema requires a schema name"))
+                .and_then(|name| env.get_schema(name))?;
+            builder.lock().unwrap().schema = Some(schema);
+            i = 3;
+        }
+        // First process defines and keeps
         while i < body.len() {
             match &body[i] {
                 Expr::List(_, exprs) => {
                     if let Some(Expr::Atom(_, op)) = exprs.first() {
-                        if op == "define" {
+                        if op == "define" || op == "keep" || op == "redact" {
                             body[i].eval(pod_env.clone()).await?;
                             i += 1;
                             continue;
@@ -1142,7 +3082,7 @@ impl Expr {
             match &body[j] {
                 Expr::List(_, exprs) => {
                     if let Some(Expr::Atom(_, op)) = exprs.first() {
-                        if matches!(op.as_str(), "<" | ">" | "=" | "!=") {
+                        if is_assertion_op(op) {
                             break;
                         }
                     }
@@ -1163,129 +3103,36 @@ impl Expr {
                 Expr::Atom(_, key) => {
                     let value = value_expr.eval(pod_env.clone()).await?;
                     match value {
-                        Value::Scalar(s) => {
-                            let entry = Entry {
-                                key: key.clone(),
-                                value: ScalarOrVec::Scalar(s),
-                            };
-
-                            let mut builder_guard = builder.lock().unwrap();
-                            builder_guard.add_operation(Op::NewEntry(entry), key.clone());
-                            // add a binding to that SRef
-                            pod_env.set_binding(
-                                key.clone(),
-                                Value::SRef(SRef::self_ref(format!(
-                                    "{}:{}",
-                                    PREDICATE_VALUEOF, key
-                                ))),
-                            );
-                        }
-                        Value::SRef(sref) => match sref {
-                            SRef(ORef::S, statement) => {
-                                // In case we are pointing to a statement on _SELF, we'll go in our list of pending operation and rename the entry to the entry the user wants to create with createpod
-                                // Eg: [createpod x [+ 1 [pod? z]]] will have a randomly named entry for the result of 1 + pod.z (where pod is the result of the query)
-                                // We will rename that entry to the key (`key` in our case)
-                                let statement_id =
-                                    statement.split(':').collect::<Vec<&str>>()[1].to_string();
-                                let mut builder_guard = builder.lock().unwrap();
-                                if let Some((index, (_, operation))) = builder_guard
-                                    .pending_operations
-                                    .iter()
-                                    .enumerate()
-                                    .find(|(_, (s, _))| s == &statement_id)
-                                {
-                                    if let Op::NewEntry(entry) = &operation.0 {
-                                        let new_entry = Entry {
-                                            key: key.clone(),
-                                            value: entry.value.clone(),
-                                        };
-                                        let op_cmd =
-                                            OpCmd(Op::NewEntry(new_entry), statement_id.clone());
-                                        builder_guard.pending_operations[index] =
-                                            (statement_id.clone(), op_cmd);
-
-                                        pod_env.set_binding(
-                                            key.clone(),
-                                            Value::SRef(SRef::self_ref(format!(
-                                                "{}:{}",
-                                                PREDICATE_VALUEOF, statement_id
-                                            ))),
-                                        );
-                                    } else {
-                                        return Err(anyhow!(format!(
-                                                "Found statement id {} that is not a NewEntry while creating a POD entry",
-                                                statement_id
-                                            )));
-                                    }
-                                } else {
-                                    return Err(anyhow!(format!("No statement found with statement id {} while creating a POD entry", statement_id)));
+                        Value::Scalar(_) | Value::Bool(_) | Value::Vector(_) | Value::String(_) => {
+                            if let Value::Vector(v) = &value {
+                                if v.len() > VL {
+                                    return Err(anyhow!(
+                                        "Vector entry {} has {} elements, more than the maximum of {}",
+                                        key,
+                                        v.len(),
+                                        VL
+                                    ));
                                 }
                             }
-                            SRef(ORef::P(pod_id), statement) => {
-                                // The user wants to create a new entry that is equal to an entry in another pod
-                                // Eg: [createpod x [pod? z]]
-                                // We will read the value from that POD's entry, copy it in our new POD, and queue an EqualityFromEntries operation
-                                // Given we don't copy entries from previous PODs unless explicitly instructed with 'keep' (TODO: this doesn't exit yet)
-                                let mut builder_guard = builder.lock().unwrap();
-
-                                if let Some(source_pod) = builder_guard.input_pods.get(&pod_id) {
-                                    if let Some(statement_value) =
-                                        source_pod.payload.statements_map.get(&statement)
-                                    {
-                                        if let Ok(value_of) = statement_value.value() {
-                                            let new_entry = Entry {
-                                                key: key.clone(),
-                                                value: value_of,
-                                            };
-
-                                            let new_entry_statement_id =
-                                                builder_guard.next_statement_id();
-                                            builder_guard.add_operation(
-                                                Op::NewEntry(new_entry),
-                                                new_entry_statement_id.clone(),
-                                            );
-
-                                            let eq_from_entries_statement_id =
-                                                builder_guard.next_statement_id();
-                                            builder_guard.add_operation(
-                                                Op::EqualityFromEntries(
-                                                    SRef(
-                                                        ORef::P(pod_id.clone()),
-                                                        statement.clone(),
-                                                    )
-                                                    .into(),
-                                                    SRef::self_ref(format!(
-                                                        "{}:{}",
-                                                        PREDICATE_VALUEOF, new_entry_statement_id
-                                                    ))
-                                                    .into(),
-                                                ),
-                                                eq_from_entries_statement_id,
-                                            );
-                                            pod_env.set_binding(
-                                                key.clone(),
-                                                Value::SRef(SRef::self_ref(format!(
-                                                    "{}:{}",
-                                                    PREDICATE_VALUEOF, new_entry_statement_id
-                                                ))),
-                                            );
-                                        } else {
-                                            return Err(anyhow!(
-                                                "Could not extract value from source statement"
-                                            ));
-                                        }
-                                    } else {
-                                        return Err(anyhow!("Statement not found in source pod"));
-                                    }
-                                } else {
-                                    return Err(anyhow!("Source pod not found in input pods"));
-                                }
+                            let entry_value = value.to_scalar_or_vec()?;
+                            let mut builder_guard = builder.lock().unwrap();
+                            if let (Value::String(s), ScalarOrVec::Scalar(hash)) =
+                                (&value, &entry_value)
+                            {
+                                builder_guard.display_map.insert(*hash, s.clone());
                             }
-                            _ => todo!(),
-                        },
+                            let entry_ref = builder_guard.add_entry(key, entry_value);
+                            pod_env.set_binding(key.clone(), Value::SRef(entry_ref));
+                        }
+                        Value::SRef(sref) => {
+                            let sref = pod_env.use_input_pod(&sref)?;
+                            let entry_ref =
+                                builder.lock().unwrap().add_entry_from_ref(key, sref)?;
+                            pod_env.set_binding(key.clone(), Value::SRef(entry_ref));
+                        }
                         _ => {
                             return Err(anyhow!(
-                                "Can't assign a non scalar or non SRef to POD entry"
+                                "Can't assign a non scalar, non vector or non SRef to POD entry"
                             ))
                         }
                     }
@@ -1296,19 +3143,67 @@ impl Expr {
         for assertion in &body[j..] {
             if let Expr::List(_, exprs) = assertion {
                 if let Some(Expr::Atom(_, op)) = exprs.first() {
-                    if matches!(op.as_str(), ">" | "=" | "!=") {
+                    if let Ok(assert_type) = AssertType::from_str(op) {
+                        if exprs.len() != 3 {
+                            return Err(anyhow!("Asserts require exactly two operands"));
+                        }
+                        let op1 = exprs[1].eval(pod_env.clone()).await?.bool_to_scalar();
+                        let op2 = exprs[2].eval(pod_env.clone()).await?.bool_to_scalar();
+                        // Checked before recording, so that a failing assertion reports its
+                        // values instead of failing later in finalize
+                        let assert: Assert = (assert_type, op1.clone(), op2.clone()).into();
+                        let (left, right) = assert.evaluate_values(Some(&pod_env))?;
+                        if !assert.apply_assert(left, right) {
+                            return Err(PexError::AssertionFailed {
+                                assert: assertion.to_string(),
+                                left: left.to_canonical_u64(),
+                                right: right.to_canonical_u64(),
+                            }
+                            .into());
+                        }
+                        Self::eval_assert_values(assert_type, op1, op2, &pod_env)?;
+                    } else if is_assertion_op(op) {
                         assertion.eval(pod_env.clone()).await?;
                     }
                 }
             }
         }
-        let pod = builder.lock().unwrap().finalize(&env)?;
-        Ok(Value::PodRef(pod))
+        let builder = builder.lock().unwrap().clone();
+        Ok(builder)
+    }
+
+    /// Replaces each `:schema name` in the arguments of a pod? with a `[key]` constraint for every
+    /// key of the schema
+    fn expand_query_schemas(args: &[Expr], env: &Env) -> Result<Vec<Expr>> {
+        let mut expanded = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg {
+                Expr::Atom(pos, flag) if flag == ":schema" => {
+                    let schema = args
+                        .next()
+                        .ok_or_else(|| anyhow!(":schema requires a schema name"))
+                        .and_then(|name| env.get_schema(name))?;
+                    expanded.extend(
+                        schema
+                            .fields
+                            .into_iter()
+                            .map(|(key, _)| Expr::List(*pos, vec![Expr::Atom(*pos, key)])),
+                    );
+                }
+                _ => expanded.push(arg.clone()),
+            }
+        }
+        Ok(expanded)
     }
 
-    async fn eval_pod_query(&self, args: &[Expr], env: Env) -> Result<Value> {
+    /// Evaluates pod?, or pod?* when `all` is set. pod? never matches pods that are already
+    /// inputs of the pod being built, pod?* only skips them given `[exclude-inputs]`.
+    async fn eval_pod_query(&self, args: &[Expr], all: bool, env: Env) -> Result<Value> {
+        let args = &Self::expand_query_schemas(args, &env)?;
+        let mut exclude_inputs = !all;
         let query_builder = Arc::new(Mutex::new(PodQueryBuilder::new()));
-        let mut query_env = env.clone();
+        let mut query_env = env.extend();
         query_env.current_query = Some(query_builder.clone());
 
         // First process all defines
@@ -1331,19 +3226,73 @@ impl Expr {
                         if op == "define" {
                             continue; // Skip defines as we've already processed them
                         }
+                        if op == "exclude-inputs" && exprs.len() == 1 {
+                            exclude_inputs = true;
+                            continue;
+                        }
 
                         // Handle assertions
-                        if matches!(op.as_str(), ">" | "=" | "!=" | "<") {
+                        if let Ok(assert_type) = AssertType::from_str(op) {
                             if exprs.len() != 3 {
                                 return Err(anyhow!("Assert requires exactly two operands"));
                             }
-                            let assert_type = AssertType::from_str(op)?;
-                            let op1 = exprs[1].eval(query_env.clone()).await?;
-                            let op2 = exprs[2].eval(query_env.clone()).await?;
+                            let op1 = exprs[1].eval(query_env.clone()).await?.bool_to_scalar();
+                            let op2 = exprs[2].eval(query_env.clone()).await?.bool_to_scalar();
                             let assert = (assert_type, op1, op2).into();
                             query_builder.lock().unwrap().add_assert(&assert)?;
                             continue;
                         }
+
+                        // Handle vector membership
+                        if let Ok(membership_type) = MembershipType::from_str(op) {
+                            if exprs.len() != 3 {
+                                return Err(anyhow!("{} requires a vector and a value", op));
+                            }
+                            let vector = exprs[1].eval(query_env.clone()).await?;
+                            let value = exprs[2].eval(query_env.clone()).await?;
+                            query_builder.lock().unwrap().add_membership(
+                                membership_type,
+                                &vector,
+                                &value,
+                            )?;
+                            continue;
+                        }
+
+                        // Handle constraints on the key the pod was signed with
+                        if op == "signer" || op == "signer-of" {
+                            if exprs.len() != 2 {
+                                return Err(anyhow!("{} requires exactly one argument", op));
+                            }
+                            if op == "signer" {
+                                let pk = match exprs[1].eval(query_env.clone()).await? {
+                                    Value::Scalar(pk) => pk,
+                                    value => {
+                                        return Err(anyhow!(
+                                            "signer requires a public key, got {:?}",
+                                            value
+                                        ))
+                                    }
+                                };
+                                query_builder.lock().unwrap().add_constraint(
+                                    SIGNER_PK_KEY.to_string(),
+                                    OperandConstraint::Constant(ScalarOrVec::Scalar(pk)),
+                                );
+                            } else {
+                                let name = match &exprs[1] {
+                                    Expr::Atom(_, name) => name.clone(),
+                                    _ => return Err(anyhow!("signer-of requires a name to bind")),
+                                };
+                                let mut builder = query_builder.lock().unwrap();
+                                let sref = SRef(
+                                    ORef::Q(builder.current_origin_id),
+                                    StatementId::value_of(SIGNER_PK_KEY),
+                                );
+                                builder.srefs.push(sref.clone());
+                                builder.add_key_constraint(SIGNER_PK_KEY.to_string());
+                                query_env.set_binding(name, Value::SRef(sref));
+                            }
+                            continue;
+                        }
                     }
 
                     // Handle key-value constraints
@@ -1357,7 +3306,7 @@ impl Expr {
                         builder.current_origin_id
                     };
 
-                    let statement_id = format!("{}:{}", PREDICATE_VALUEOF, key.clone());
+                    let statement_id = StatementId::value_of(&key);
 
                     {
                         let mut builder = query_builder.lock().unwrap();
@@ -1391,66 +3340,362 @@ impl Expr {
             builder.clone()
         };
 
-        find_matching_pod(query, env)
+        if all {
+            find_all_matching_pods(query, env, exclude_inputs)
+        } else {
+            find_matching_pod(query, env)
+        }
     }
 
-    async fn eval_operation(&self, op_type: OpType, operands: &[Expr], env: Env) -> Result<Value> {
+    /// Bindings are evaluated in the outer scope and only visible in the body, which is
+    /// evaluated in a child scope so outer ones are left untouched.
+    async fn eval_let(&self, bindings: &Expr, body: &[Expr], env: Env) -> Result<Value> {
+        let pairs = match bindings {
+            Expr::List(_, pairs) => pairs,
+            _ => return Err(anyhow!("let bindings must be a list")),
+        };
+        let scoped_env = env.extend();
+        for pair in pairs {
+            match pair {
+                Expr::List(_, pair) if pair.len() == 2 => {
+                    let name = match &pair[0] {
+                        Expr::Atom(_, name) => name.clone(),
+                        _ => return Err(anyhow!("let binding name must be an atom")),
+                    };
+                    let value = pair[1].eval(env.clone()).await?;
+                    scoped_env.set_binding(name, value);
+                }
+                _ => return Err(anyhow!("let bindings must be [name value] pairs")),
+            }
+        }
+
+        let mut result = None;
+        for expr in body {
+            result = Some(expr.eval(scoped_env.clone()).await?);
+        }
+        result.ok_or_else(|| anyhow!("let requires a body"))
+    }
+
+    fn make_closure(
+        name: Option<String>,
+        params: &Expr,
+        body: &[Expr],
+        env: &Env,
+    ) -> Result<Value> {
+        let params = match params {
+            Expr::List(_, params) => params
+                .iter()
+                .map(|param| match param {
+                    Expr::Atom(_, param) => Ok(param.clone()),
+                    _ => Err(anyhow!("Function parameters must be atoms")),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => return Err(anyhow!("Function parameters must be a list")),
+        };
+        if let Some(param) = params
+            .iter()
+            .enumerate()
+            .find_map(|(i, param)| params[..i].contains(param).then_some(param))
+        {
+            return Err(anyhow!("Duplicate function parameter: {}", param));
+        }
+        Ok(Value::Closure(Box::new(Closure {
+            name,
+            params,
+            body: body.to_vec(),
+            bindings: env.bindings.flatten(),
+        })))
+    }
+
+    /// Calls a user-defined function. Arguments are evaluated in the caller's env, and the body in
+    /// the closure's bindings, keeping the caller's builder so that a call can produce pod entries.
+    /// Named functions are bound to themselves so they can recurse.
+    async fn apply_closure(&self, closure: &Closure, args: &[Expr], env: Env) -> Result<Value> {
+        if args.len() != closure.params.len() {
+            return Err(anyhow!(
+                "{} expects {} arguments, got {}",
+                closure.name.as_deref().unwrap_or("lambda"),
+                closure.params.len(),
+                args.len()
+            ));
+        }
+        let call_env = env.with_bindings(closure.bindings.clone());
+        if let Some(name) = &closure.name {
+            call_env.set_binding(name.clone(), Value::Closure(Box::new(closure.clone())));
+        }
+        for (param, arg) in closure.params.iter().zip(args) {
+            call_env.set_binding(param.clone(), arg.eval(env.clone()).await?);
+        }
+
+        let mut result = None;
+        for expr in &closure.body {
+            result = Some(expr.eval(call_env.clone()).await?);
+        }
+        result.ok_or_else(|| anyhow!("Function requires a body"))
+    }
+
+    /// Applies a built-in operation elementwise, eg: [map + [list 1 2] [list 10 20]] is [11 22].
+    /// Inside createpod, each application is recorded like the equivalent hand-written operation.
+    async fn eval_map(&self, op: &Expr, lists: &[Expr], env: Env) -> Result<Value> {
+        let op_type = match op {
+            Expr::Atom(_, op) => OpType::from_str(op)?,
+            _ => return Err(anyhow!("map requires an operation as first argument")),
+        };
+        let mut values = Vec::new();
+        for list in lists {
+            match list.eval(env.clone()).await? {
+                Value::List(list) => values.push(list),
+                _ => return Err(anyhow!("map requires list arguments")),
+            }
+        }
+        if values[0].len() != values[1].len() {
+            return Err(anyhow!(
+                "map requires lists of the same length, got {} and {}",
+                values[0].len(),
+                values[1].len()
+            ));
+        }
+
+        values[0]
+            .iter()
+            .zip(values[1].iter())
+            .map(|(v1, v2)| Self::eval_operation_values(op_type, v1.clone(), v2.clone(), &env))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::List)
+    }
+
+    /// Applies a built-in operation from left to right, eg: [fold + 0 [list 1 2 3]] is
+    /// [+ [+ [+ 0 1] 2] 3]. Inside createpod, it records the same operations as that chain.
+    async fn eval_fold(&self, op: &Expr, init: &Expr, list: &Expr, env: Env) -> Result<Value> {
+        let op_type = match op {
+            Expr::Atom(_, op) => OpType::from_str(op)?,
+            _ => return Err(anyhow!("fold requires an operation as first argument")),
+        };
+        let init = init.eval(env.clone()).await?;
+        let values = match list.eval(env.clone()).await? {
+            Value::List(values) => values,
+            _ => return Err(anyhow!("fold requires a list as last argument")),
+        };
+
+        values.into_iter().try_fold(init, |acc, value| {
+            Self::eval_operation_values(op_type, acc, value, &env)
+        })
+    }
+
+    async fn eval_keep(&self, arg: &Expr, env: Env) -> Result<Value> {
+        let builder = env
+            .current_builder
+            .clone()
+            .ok_or_else(|| anyhow!("keep can only be used inside createpod"))?;
+        let value = arg.eval(env.clone()).await?;
+        let srefs = match &value {
+            Value::SRef(sref) => vec![sref.clone()],
+            Value::List(values) => values
+                .iter()
+                .map(|v| match v {
+                    Value::SRef(sref) => Ok(sref.clone()),
+                    _ => Err(anyhow!("keep requires statement references")),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => return Err(anyhow!("keep requires statement references")),
+        };
+
+        for sref in srefs {
+            // The reference may use a legacy id, input pods are registered under their current one
+            let sref = env.use_input_pod(&sref)?;
+            // We can only copy statements from other pods, statements on _SELF are already in the pod
+            let pod_id = match &sref.0 {
+                ORef::P(pod_id) => pod_id.clone(),
+                _ => return Err(anyhow!("keep requires a reference to a statement in a pod")),
+            };
+
+            let statement_id = format!("{}{}_{}", STATEMENT_PREFIX_KEPT, pod_id, sref.1.name);
+            // Keeping the same statement twice (eg: [keep x] after [keep [pod? [x]]]) copies it once
+            let mut builder = builder.lock().unwrap();
+            if !builder
+                .pending_operations
+                .iter()
+                .any(|(pending_id, _)| pending_id == &statement_id)
+            {
+                builder.add_operation(Op::CopyStatement((&sref).into()), statement_id);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Hides which pods the referenced statements come from: their pods' origins get opaque
+    /// names in the pod being built, eg: `[redact [pod? [age]]]`. Returns the references.
+    async fn eval_redact(&self, arg: &Expr, env: Env) -> Result<Value> {
+        let builder = env
+            .current_builder
+            .clone()
+            .ok_or_else(|| anyhow!("redact can only be used inside createpod"))?;
+        let value = arg.eval(env.clone()).await?;
+        let srefs = match &value {
+            Value::SRef(sref) => vec![sref.clone()],
+            Value::List(values) => values
+                .iter()
+                .map(|v| match v {
+                    Value::SRef(sref) => Ok(sref.clone()),
+                    _ => Err(anyhow!("redact requires statement references")),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => return Err(anyhow!("redact requires statement references")),
+        };
+
+        for sref in srefs {
+            match env.use_input_pod(&sref)?.0 {
+                ORef::P(pod_id) => {
+                    builder.lock().unwrap().hidden_origins.insert(pod_id);
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "redact requires a reference to a statement in a pod"
+                    ))
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Evaluates and/or/not to a Value::Bool. Both sides of `and` and
+    /// `or` are always evaluated (no short-circuit) so that inside createpod every comparison
+    /// ends up recorded. A POD can only attest comparisons that hold, so a false comparison is
+    /// recorded as the one that does hold instead (eg: `[> x 5]` with x = 3 records `x < 5`).
+    async fn eval_logic(&self, env: Env) -> Result<Value> {
+        if env.current_query.is_some() {
+            return Err(anyhow!("Logic operators are not supported in pod? queries"));
+        }
+        let (holds, witnesses) = Self::eval_condition(self, env.clone()).await?;
+        Self::record_witnesses(&env, witnesses)?;
+        Ok(Value::Bool(holds))
+    }
+
+    /// Only the taken branch is evaluated, so inside createpod only its operations are recorded,
+    /// along with the comparisons witnessing the condition.
+    async fn eval_if(
+        &self,
+        condition: &Expr,
+        then_branch: &Expr,
+        else_branch: &Expr,
+        env: Env,
+    ) -> Result<Value> {
+        if env.current_query.is_some() {
+            return Err(anyhow!("if is not supported in pod? queries"));
+        }
+        let (holds, witnesses) = Self::eval_condition(condition, env.clone()).await?;
+        Self::record_witnesses(&env, witnesses)?;
+        if holds {
+            then_branch.eval(env).await
+        } else {
+            else_branch.eval(env).await
+        }
+    }
+
+    fn record_witnesses(env: &Env, witnesses: Vec<(AssertType, Value, Value)>) -> Result<()> {
+        if let Some(ref builder) = env.current_builder {
+            let mut builder = builder.lock().unwrap();
+            for (assert_type, op1, op2) in witnesses {
+                // Comparisons between constants don't need to be tracked, same as eval_assert
+                if matches!(op1, Value::SRef(_)) || matches!(op2, Value::SRef(_)) {
+                    builder.add_assert(assert_type, op1, op2)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the truth value of a condition along with the comparisons that witness it,
+    /// without recording anything in the current builder.
+    #[async_recursion]
+    async fn eval_condition(
+        expr: &Expr,
+        env: Env,
+    ) -> Result<(bool, Vec<(AssertType, Value, Value)>)> {
+        if let Expr::List(_, exprs) = expr {
+            if let Some(Expr::Atom(_, op)) = exprs.first() {
+                if let Ok(logic_type) = LogicType::from_str(op) {
+                    let operands = &exprs[1..];
+                    return match logic_type {
+                        LogicType::Not => {
+                            if operands.len() != 1 {
+                                return Err(anyhow!("not requires exactly one operand"));
+                            }
+                            let (holds, witnesses) =
+                                Self::eval_condition(&operands[0], env).await?;
+                            Ok((!holds, witnesses))
+                        }
+                        LogicType::And | LogicType::Or => {
+                            if operands.len() != 2 {
+                                return Err(anyhow!("{} requires exactly two operands", op));
+                            }
+                            let (left, mut witnesses) =
+                                Self::eval_condition(&operands[0], env.clone()).await?;
+                            let (right, right_witnesses) =
+                                Self::eval_condition(&operands[1], env).await?;
+                            witnesses.extend(right_witnesses);
+                            let holds = match logic_type {
+                                LogicType::And => left && right,
+                                _ => left || right,
+                            };
+                            Ok((holds, witnesses))
+                        }
+                    };
+                }
+                if let Ok(assert_type) = AssertType::from_str(op) {
+                    if exprs.len() != 3 {
+                        return Err(anyhow!("Asserts require exactly two operands"));
+                    }
+                    let op1 = exprs[1].eval(env.clone()).await?.bool_to_scalar();
+                    let op2 = exprs[2].eval(env.clone()).await?.bool_to_scalar();
+                    let assert: Assert = (assert_type, op1.clone(), op2.clone()).into();
+                    let (value1, value2) = assert.evaluate_values(Some(&env))?;
+                    let holds = assert.apply_assert(value1, value2);
+                    let witness = if holds {
+                        assert_type
+                    } else {
+                        assert_type.negation_witness(value1, value2)
+                    };
+                    return Ok((holds, vec![(witness, op1, op2)]));
+                }
+            }
+        }
+        match expr.eval(env).await? {
+            Value::Bool(b) => Ok((b, vec![])),
+            Value::Scalar(s) => Ok((s.to_canonical_u64() != 0, vec![])),
+            _ => Err(anyhow!("Logic operators require boolean operands")),
+        }
+    }
+
+    async fn eval_operation(&self, op_type: OpType, operands: &[Expr], env: Env) -> Result<Value> {
         if operands.len() != 2 {
             return Err(anyhow!("Operations require exactly two operands"));
         }
         let op1 = operands[0].eval(env.clone()).await?;
         let op2 = operands[1].eval(env.clone()).await?;
+        Self::eval_operation_values(op_type, op1, op2, &env)
+    }
 
+    /// Applies an operation to already evaluated operands, recording it in the current builder if any
+    fn eval_operation_values(op_type: OpType, op1: Value, op2: Value, env: &Env) -> Result<Value> {
+        let (op1, op2) = (op1.bool_to_scalar(), op2.bool_to_scalar());
         let operation: Operation = (op_type, op1.clone(), op2.clone()).into();
         if let Some(ref _query) = env.current_query {
             match (&op1, &op2) {
                 (Value::Scalar(s1), Value::Scalar(s2)) => {
-                    Ok(Value::Scalar(operation.apply_operation(*s1, *s2)))
+                    Ok(Value::Scalar(operation.apply_operation(*s1, *s2)?))
                 }
                 _ => Ok(Value::Operation(Box::new(operation))),
             }
         } else if let Some(ref builder) = env.current_builder {
-            let result_value = operation.eval_with_env(&env)?;
-            let mut builder = builder.lock().unwrap();
-
-            // Create refs for any values that need tracking
+            let result_value = operation.eval_with_env(env)?;
             match (&op1, &op2) {
-                (Value::SRef(_), _) | (_, Value::SRef(_)) => {
-                    // Convert operands to SRefs if they're scalars
-                    let op1_sref = match op1 {
-                        Value::Scalar(s) => builder.get_or_create_constant_ref(s),
-                        Value::SRef(r) => r,
-                        _ => return Err(anyhow!("Invalid operand type")),
-                    };
-
-                    let op2_sref = match op2 {
-                        Value::Scalar(s) => builder.get_or_create_constant_ref(s),
-                        Value::SRef(r) => r,
-                        _ => return Err(anyhow!("Invalid operand type")),
-                    };
-
-                    // We need to create a new entry for the result
-                    let result_key = builder.next_result_key_id();
-                    let new_entry_statement_id = builder.next_statement_id();
-                    let result_sref =
-                        SRef::self_ref(format!("{}:{}", PREDICATE_VALUEOF, new_entry_statement_id));
-                    let pod_op =
-                        Operation::into_pod_op(op_type, result_sref.clone(), op1_sref, op2_sref);
-                    // Create the result entry. We use our result_key which was also used in creating the operation.
-                    builder.add_operation(
-                        Op::NewEntry(Entry {
-                            key: result_key.clone(),
-                            value: ScalarOrVec::Scalar(result_value),
-                        }),
-                        new_entry_statement_id,
-                    );
-
-                    // Then add the operation
-                    let op_statement_id = builder.next_statement_id();
-                    builder.add_operation(pod_op, op_statement_id);
-
-                    Ok(Value::SRef(result_sref))
-                }
+                (Value::SRef(_), _) | (_, Value::SRef(_)) => Ok(Value::SRef(
+                    builder
+                        .lock()
+                        .unwrap()
+                        .record_operation(op_type, op1, op2, result_value)?,
+                )),
                 _ => Ok(Value::Scalar(result_value)),
             }
         } else {
@@ -1461,6 +3706,55 @@ impl Expr {
             Ok(Value::Scalar(operation.eval()?))
         }
     }
+    /// Evaluates [contains v x] and [!contains v x] to a Value::Bool.
+    /// Inside createpod, v must be an entry and the membership must hold, as it is recorded as a
+    /// ContainsFromEntries or NotContainsFromEntries operation.
+    async fn eval_membership(
+        &self,
+        membership_type: MembershipType,
+        operands: &[Expr],
+        env: Env,
+    ) -> Result<Value> {
+        if operands.len() != 2 {
+            return Err(anyhow!("Membership requires a vector and a value"));
+        }
+        if env.current_query.is_some() {
+            return Err(anyhow!(
+                "Membership can only be used at the top level of pod? queries"
+            ));
+        }
+        let vector = operands[0].eval(env.clone()).await?;
+        let value = operands[1].eval(env.clone()).await?;
+
+        let (vector_value, scalar_value) = MembershipType::extract_operands(&vector, &value, &env)?;
+        let holds = membership_type.holds(&vector_value, scalar_value);
+        if let Some(ref builder) = env.current_builder {
+            match vector {
+                Value::SRef(vector_sref) => {
+                    if !holds {
+                        return Err(anyhow!(
+                            "{} doesn't hold for {}",
+                            membership_type.predicate(),
+                            scalar_value
+                        ));
+                    }
+                    return Ok(Value::SRef(builder.lock().unwrap().add_membership(
+                        membership_type,
+                        vector_sref,
+                        value,
+                    )?));
+                }
+                _ if matches!(value, Value::SRef(_)) => {
+                    return Err(anyhow!(
+                        "Membership requires a vector entry inside createpod"
+                    ))
+                }
+                _ => {}
+            }
+        }
+        Ok(Value::Bool(holds))
+    }
+
     async fn eval_assert(
         &self,
         assert_type: AssertType,
@@ -1470,149 +3764,248 @@ impl Expr {
         if operands.len() != 2 {
             return Err(anyhow!("Asserts require exactly two operands"));
         }
-        let op1 = operands[0].eval(env.clone()).await?;
-        let op2 = operands[1].eval(env.clone()).await?;
+        let op1 = operands[0].eval(env.clone()).await?.bool_to_scalar();
+        let op2 = operands[1].eval(env.clone()).await?.bool_to_scalar();
+        Self::eval_assert_values(assert_type, op1, op2, &env)
+    }
+
+    /// `[cmp a b]` records whichever of `>`, `<` and `=` holds between the operands in the pod
+    /// being built, returning a reference to that statement. Outside a createpod it returns the
+    /// comparison as a string, eg: ">".
+    async fn eval_cmp(&self, operands: &[Expr], env: Env) -> Result<Value> {
+        if operands.len() != 2 {
+            return Err(anyhow!("cmp requires exactly two operands"));
+        }
+        let op1 = operands[0].eval(env.clone()).await?.bool_to_scalar();
+        let op2 = operands[1].eval(env.clone()).await?.bool_to_scalar();
+        let assert: Assert = (AssertType::Eq, op1.clone(), op2.clone()).into();
+        let (value1, value2) = assert.evaluate_values(Some(&env))?;
+        let (assert_type, symbol) = match value1.to_canonical_u64().cmp(&value2.to_canonical_u64())
+        {
+            Ordering::Greater => (AssertType::Gt, ">"),
+            Ordering::Less => (AssertType::Lt, "<"),
+            Ordering::Equal => (AssertType::Eq, "="),
+        };
+        match (&env.current_builder, &env.current_query) {
+            (Some(builder), None) => Ok(Value::SRef(builder.lock().unwrap().add_assert(
+                assert_type,
+                op1,
+                op2,
+            )?)),
+            _ => Ok(Value::String(symbol.to_string())),
+        }
+    }
+
+    /// `[defschema name [key :scalar] [key :vector] ...]`, binding the schema to `name`. An `:open`
+    /// flag allows pods to have entries the schema doesn't declare.
+    fn eval_defschema(args: &[Expr], env: &Env) -> Result<Value> {
+        let Some(Expr::Atom(_, name)) = args.first() else {
+            return Err(anyhow!("defschema requires a name"));
+        };
+        let mut schema = Schema {
+            name: name.clone(),
+            fields: Vec::new(),
+            open: false,
+        };
+        for arg in &args[1..] {
+            match arg {
+                Expr::Atom(_, flag) if flag == ":open" => schema.open = true,
+                Expr::List(_, field) => {
+                    let (Some(Expr::Atom(_, key)), Some(Expr::Atom(_, kind)), 2) =
+                        (field.first(), field.get(1), field.len())
+                    else {
+                        return Err(anyhow!(
+                            "defschema fields look like [key :scalar] or [key :vector], got {}",
+                            arg
+                        ));
+                    };
+                    let kind = match kind.as_str() {
+                        ":scalar" => EntryKind::Scalar,
+                        ":vector" => EntryKind::Vector,
+                        _ => return Err(anyhow!("Unknown entry kind {} for {}", kind, key)),
+                    };
+                    if schema.fields.iter().any(|(field, _)| field == key) {
+                        return Err(anyhow!("Key {} is declared twice in schema {}", key, name));
+                    }
+                    schema.fields.push((key.clone(), kind));
+                }
+                _ => return Err(anyhow!("Invalid defschema argument {}", arg)),
+            }
+        }
+        let value = Value::Schema(schema);
+        env.set_binding(name.clone(), value.clone());
+        Ok(value)
+    }
 
+    /// Applies an assert to already evaluated operands, recording it in the current builder if any
+    fn eval_assert_values(
+        assert_type: AssertType,
+        op1: Value,
+        op2: Value,
+        env: &Env,
+    ) -> Result<Value> {
         let assert: Assert = (assert_type, op1.clone(), op2.clone()).into();
         if let Some(ref _query) = env.current_query {
             match (&op1, &op2) {
                 (Value::Scalar(s1), Value::Scalar(s2)) => {
-                    Ok(Value::Scalar(assert.apply_assert(*s1, *s2)))
+                    Ok(Value::Bool(assert.apply_assert(*s1, *s2)))
                 }
                 _ => Ok(Value::Assert(Box::new(assert))),
             }
         } else if let Some(ref builder) = env.current_builder {
-            let mut builder = builder.lock().unwrap();
-
-            // Create refs for any values that need tracking
             match (&op1, &op2) {
-                (Value::SRef(_), _) | (_, Value::SRef(_)) => {
-                    // Convert operands to SRefs if they're scalars
-                    let op1_sref = match op1 {
-                        Value::Scalar(s) => builder.get_or_create_constant_ref(s),
-                        Value::SRef(r) => r,
-                        _ => return Err(anyhow!("Invalid operand type")),
-                    };
-
-                    let op2_sref = match op2 {
-                        Value::Scalar(s) => builder.get_or_create_constant_ref(s),
-                        Value::SRef(r) => r,
-                        _ => return Err(anyhow!("Invalid operand type")),
-                    };
-
-                    // We need to create a new entry for the result
-                    let pod_op = Assert::into_pod_op(assert_type, op1_sref, op2_sref);
-                    let op_statement_id = builder.next_statement_id();
-                    builder.add_operation(pod_op, op_statement_id.clone());
-                    let assert_sref = SRef::self_ref(format!(
-                        "{}:{}",
-                        Assert::predicate_from_op(assert_type),
-                        op_statement_id.clone()
-                    ));
-
-                    Ok(Value::SRef(assert_sref))
-                }
-                _ => Ok(Value::Scalar(assert.eval()?)),
+                (Value::SRef(_), _) | (_, Value::SRef(_)) => Ok(Value::SRef(
+                    builder.lock().unwrap().add_assert(assert_type, op1, op2)?,
+                )),
+                _ => Ok(Value::Bool(assert.eval()?)),
             }
         } else {
             // Direct evaluation
-            Ok(Value::Scalar(assert.eval()?))
+            Ok(Value::Bool(assert.eval()?))
         }
     }
 }
 
 fn get_value_from_sref(sref: &SRef, env: &Env) -> Result<GoldilocksField> {
+    match get_scalar_or_vec_from_sref(sref, env)? {
+        ScalarOrVec::Scalar(value) => Ok(value),
+        ScalarOrVec::Vector(_) => Err(anyhow!("Entry {} is a vector, not a scalar", sref.1)),
+    }
+}
+
+fn get_scalar_or_vec_from_sref(sref: &SRef, env: &Env) -> Result<ScalarOrVec> {
+    let sref = &env.use_input_pod(sref)?;
     if let Some(ref builder) = env.current_builder {
-        let builder = builder.lock().unwrap();
-        if sref.0.eq(&ORef::S) {
-            if let Some((_, op_cmd)) =
-                builder.pending_operations.iter().find(|(statement_id, _)| {
-                    statement_id == &sref.1.split(':').collect::<Vec<&str>>()[1].to_string()
-                })
-            {
-                if let Op::NewEntry(entry) = &op_cmd.0 {
-                    if let ScalarOrVec::Scalar(value) = entry.value {
-                        return Ok(value);
-                    }
-                }
-            }
-            return Err(anyhow!("Value not found in current pod operations"));
-        }
-        // Look up value in input pods using origin mapping
-        let key: String = sref.0.clone().into();
-        if let Some(pod) = builder.input_pods.get(&key) {
-            if let Some(Statement::ValueOf(_, ScalarOrVec::Scalar(value))) =
-                pod.payload.statements_map.get(&sref.1)
-            {
-                Ok(*value)
-            } else {
-                Err(anyhow!("Value not found or not scalar"))
-            }
-        } else {
-            Err(anyhow!("Pod not found for ref"))
-        }
+        builder.lock().unwrap().value_of(sref)
     } else {
         // We might want to support finding the refs inside the POD store
         Err(anyhow!("No active pod builder"))
     }
 }
 
-fn find_matching_pod(query: PodQueryBuilder, env: Env) -> Result<Value> {
-    let constraints = query.build_constraints();
-    let store = env.pod_store.lock().unwrap();
-    for pod in store.pods.iter() {
-        let pod_id = PodBuilder::pod_id(pod);
-
-        if let Some(ref builder) = env.current_builder {
-            if builder.lock().unwrap().input_pods.contains_key(&pod_id) {
-                continue;
-            }
+/// Schnorr pods pad vectors to VL by repeating their first element, so vectors are compared
+/// padded the same way
+fn padded(value: &ScalarOrVec) -> ScalarOrVec {
+    match value {
+        ScalarOrVec::Vector(v) if !v.is_empty() && v.len() < VL => {
+            ScalarOrVec::Vector([v.clone(), vec![v[0]; VL - v.len()]].concat())
         }
-        if let Some(matched_statements) = matches_constraints(pod, &constraints) {
-            if let Some(ref builder) = env.current_builder {
-                builder.lock().unwrap().register_input_pod(pod);
-            }
-            if let Some(ref builder) = env.current_builder {
-                builder
-                    .lock()
-                    .unwrap()
-                    .extend_matched_statements(matched_statements);
-            }
-            env.shared.store_pod(pod.clone());
+        _ => value.clone(),
+    }
+}
 
-            let refs: Vec<Value> = query
-                .srefs
-                .iter()
-                .map(|sref| {
-                    // Find the actual statement ID in the pod for this key
-                    let key = sref.1.split(':').last().unwrap();
-                    let statement_id = pod
-                        .payload
-                        .statements_list
-                        .iter()
-                        .find(|(_, stmt)| {
-                            if let Statement::ValueOf(ak, _) = stmt {
-                                ak.1 == key
-                            } else {
-                                false
-                            }
-                        })
-                        .map(|(id, _)| id.clone())
-                        .ok_or_else(|| anyhow!("Statement not found in pod for key {}", key))?;
+/// Pods matching the query with the statements they implicitly contribute, ordered by content
+/// id so that the results don't depend on the order pods were added in.
+fn matching_pods(
+    constraints: &[QueryConstraint],
+    env: &Env,
+    exclude_inputs: bool,
+) -> Vec<(POD, Vec<SRef>)> {
+    let store = env.pod_store.lock().unwrap();
+    let mut matches: Vec<(String, POD, Vec<SRef>)> = store
+        .pods
+        .values()
+        .filter(|pod| {
+            !exclude_inputs
+                || env.current_builder.as_ref().map_or(true, |builder| {
+                    !builder
+                        .lock()
+                        .unwrap()
+                        .input_pods
+                        .contains_key(&PodBuilder::pod_id(pod))
+                })
+        })
+        .filter_map(|pod| {
+            matches_constraints(pod, constraints)
+                .map(|matched_statements| (pod.content_id(), pod.clone(), matched_statements))
+        })
+        .collect();
+    matches.sort_by(|(id1, _, _), (id2, _, _)| id1.cmp(id2));
+    matches.dedup_by(|(id1, _, _), (id2, _, _)| id1 == id2);
+    matches
+        .into_iter()
+        .map(|(_, pod, matched_statements)| (pod, matched_statements))
+        .collect()
+}
 
-                    Ok(Value::SRef(SRef(ORef::P(pod_id.clone()), statement_id)))
+/// The references to the statements of `pod` that the query asked for: a single one, or a list
+fn query_refs(query: &PodQueryBuilder, pod: &POD) -> Result<Value> {
+    let pod_id = PodBuilder::pod_id(pod);
+    let refs: Vec<Value> = query
+        .srefs
+        .iter()
+        .map(|sref| {
+            // Find the actual statement ID in the pod for this key
+            let key = &sref.1.name;
+            let statement_id = pod
+                .payload
+                .statements_list()
+                .iter()
+                .find(|(_, stmt)| {
+                    if let Statement::ValueOf(ak, _) = stmt {
+                        is_entry_key(ak, key)
+                    } else {
+                        false
+                    }
                 })
-                .collect::<Result<Vec<Value>>>()?;
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| anyhow!("Statement not found in pod for key {}", key))?;
 
-            return if refs.len() == 1 {
-                Ok(refs.into_iter().next().unwrap())
-            } else {
-                Ok(Value::List(refs))
-            };
+            Ok(Value::SRef(SRef(
+                ORef::P(pod_id.clone()),
+                statement_id.into(),
+            )))
+        })
+        .collect::<Result<Vec<Value>>>()?;
+
+    Ok(if refs.len() == 1 {
+        refs.into_iter().next().unwrap()
+    } else {
+        Value::List(refs)
+    })
+}
+
+/// The first matching pod that isn't an input of the pod being built yet, which becomes one.
+fn find_matching_pod(query: PodQueryBuilder, env: Env) -> Result<Value> {
+    let constraints = query.build_constraints();
+    let (pod, matched_statements) = matching_pods(&constraints, &env, true)
+        .into_iter()
+        .next()
+        .ok_or(PexError::NoMatchingPod {
+            constraints: constraints.len(),
+        })?;
+    if let Some(ref builder) = env.current_builder {
+        let mut builder = builder.lock().unwrap();
+        builder.register_input_pod(&pod);
+        builder.extend_matched_statements(matched_statements);
+    }
+    env.shared.store_pod(pod.clone());
+    query_refs(&query, &pod)
+}
+
+/// All the matching pods, as a list with the references of each. Unlike `find_matching_pod`,
+/// pods only become inputs of the pod being built once their references are used.
+fn find_all_matching_pods(query: PodQueryBuilder, env: Env, exclude_inputs: bool) -> Result<Value> {
+    let constraints = query.build_constraints();
+    let mut results = Vec::new();
+    for (pod, matched_statements) in matching_pods(&constraints, &env, exclude_inputs) {
+        if let Some(ref builder) = env.current_builder {
+            builder
+                .lock()
+                .unwrap()
+                .deferred_matched_statements
+                .insert(PodBuilder::pod_id(&pod), matched_statements);
         }
+        env.shared.store_pod(pod.clone());
+        results.push(query_refs(&query, &pod)?);
     }
+    Ok(Value::List(results))
+}
 
-    Err(anyhow!("No matching pod found"))
+/// Whether `ak` is the entry `key` of a pod. The signer entry only counts on the pod itself, a
+/// copy of an input pod's signer doesn't make the pod signed by that key.
+fn is_entry_key(ak: &AnchoredKey, key: &str) -> bool {
+    ak.1 == key && (key != SIGNER_PK_KEY || ak.0.origin_name == SELF_ORIGIN_NAME)
 }
 
 fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec<SRef>> {
@@ -1623,9 +4016,9 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
     for constraint in constraints {
         match constraint {
             QueryConstraint::HasKey { key } => {
-                if !pod.payload.statements_list.iter().any(|(_, stmt)| {
+                if !pod.payload.statements_list().iter().any(|(_, stmt)| {
                     if let Statement::ValueOf(ak, _) = stmt {
-                        &ak.1 == key
+                        is_entry_key(ak, key)
                     } else {
                         false
                     }
@@ -1634,9 +4027,9 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
                 }
             }
             QueryConstraint::ExactValue { key, value } => {
-                if !pod.payload.statements_list.iter().any(|(_, stmt)| {
+                if !pod.payload.statements_list().iter().any(|(_, stmt)| {
                     if let Statement::ValueOf(ak, v) = stmt {
-                        &ak.1 == key && v == value
+                        is_entry_key(ak, key) && padded(v) == padded(value)
                     } else {
                         false
                     }
@@ -1651,7 +4044,7 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
                 // Find statements that match the operation
                 let matching_ops = pod
                     .payload
-                    .statements_list
+                    .statements_list()
                     .iter()
                     .filter_map(|(id, stmt)| {
                         matches_operation_constraint(pod, operation, stmt, &mut matched_statements)
@@ -1670,8 +4063,10 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
 
                 // Add the operation statement itself
                 if let Some((stmt_id, _)) = matching_ops.first() {
-                    matched_statements
-                        .push(SRef(ORef::P(PodBuilder::pod_id(pod)), stmt_id.clone()));
+                    matched_statements.push(SRef(
+                        ORef::P(PodBuilder::pod_id(pod)),
+                        stmt_id.clone().into(),
+                    ));
                 }
             }
             QueryConstraint::Assert {
@@ -1681,7 +4076,7 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
                 // Find statements that match the assertion
                 let matching_asserts = pod
                     .payload
-                    .statements_list
+                    .statements_list()
                     .iter()
                     .filter_map(|(id, stmt)| {
                         matches_assert_constraint(
@@ -1701,8 +4096,36 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
 
                 // Add the assert statement itself
                 if let Some(stmt_id) = matching_asserts.first() {
-                    matched_statements
-                        .push(SRef(ORef::P(PodBuilder::pod_id(pod)), stmt_id.clone()));
+                    matched_statements.push(SRef(
+                        ORef::P(PodBuilder::pod_id(pod)),
+                        stmt_id.clone().into(),
+                    ));
+                }
+            }
+            QueryConstraint::Membership {
+                membership_type,
+                vector_key,
+                operand,
+            } => {
+                // The membership is either proven by a statement, which we copy like asserts,
+                // or can be checked against the vector entry itself
+                let matching_statement =
+                    pod.payload.statements_list().iter().find_map(|(id, stmt)| {
+                        matches_membership_constraint(
+                            pod,
+                            *membership_type,
+                            vector_key,
+                            operand,
+                            stmt,
+                            &mut matched_statements,
+                        )
+                        .map(|_| id.clone())
+                    });
+
+                if let Some(stmt_id) = matching_statement {
+                    matched_statements.push(SRef(ORef::P(PodBuilder::pod_id(pod)), stmt_id.into()));
+                } else if !matches_membership_entry(pod, *membership_type, vector_key, operand) {
+                    return None;
                 }
             }
         }
@@ -1710,6 +4133,66 @@ fn matches_constraints(pod: &POD, constraints: &[QueryConstraint]) -> Option<Vec
     Some(matched_statements)
 }
 
+fn matches_membership_constraint(
+    pod: &POD,
+    membership_type: MembershipType,
+    vector_key: &str,
+    operand: &OperandConstraint,
+    statement: &Statement,
+    matched_statements: &mut Vec<SRef>,
+) -> Option<bool> {
+    let (vector, value) = match (membership_type, statement) {
+        (MembershipType::Contains, Statement::Contains(v, s))
+        | (MembershipType::NotContains, Statement::NotContains(v, s)) => (v, s),
+        _ => return None,
+    };
+    if vector.1 != vector_key {
+        return None;
+    }
+
+    let value_res = matches_operand_constraint(pod, operand, value, matched_statements)?;
+    if value == &value_res {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Whether a vector entry `vector_key` of the pod satisfies the membership. An entry operand
+/// must come from the same origin as the vector, so that the vector of one pod and an entry of
+/// another pod with the same name don't match together.
+fn matches_membership_entry(
+    pod: &POD,
+    membership_type: MembershipType,
+    vector_key: &str,
+    operand: &OperandConstraint,
+) -> bool {
+    let statements = pod.payload.statements_list();
+    statements.iter().any(|(_, stmt)| {
+        let Statement::ValueOf(vector_ak, ScalarOrVec::Vector(vector)) = stmt else {
+            return false;
+        };
+        if !is_entry_key(vector_ak, vector_key) {
+            return false;
+        }
+        let value = match operand {
+            OperandConstraint::Constant(ScalarOrVec::Scalar(value)) => Some(*value),
+            OperandConstraint::EntryRef(key) => {
+                statements.iter().find_map(|(_, stmt)| match stmt {
+                    Statement::ValueOf(ak, ScalarOrVec::Scalar(value))
+                        if is_entry_key(ak, key) && ak.0 == vector_ak.0 =>
+                    {
+                        Some(*value)
+                    }
+                    _ => None,
+                })
+            }
+            _ => None,
+        };
+        value.is_some_and(|value| membership_type.holds(vector, value))
+    })
+}
+
 fn matches_assert_constraint(
     pod: &POD,
     assert_type: AssertType,
@@ -1750,7 +4233,10 @@ fn matches_operation_constraint(
     let (result, left, right) = match (op_constraint.op_type, statement) {
         (OpType::Add, Statement::SumOf(res, l, r))
         | (OpType::Multiply, Statement::ProductOf(res, l, r))
-        | (OpType::Max, Statement::MaxOf(res, l, r)) => (res, l, r),
+        | (OpType::Max, Statement::MaxOf(res, l, r))
+        | (OpType::Sub, Statement::SubOf(res, l, r))
+        | (OpType::Div, Statement::DivOf(res, l, r))
+        | (OpType::Min, Statement::MinOf(res, l, r)) => (res, l, r),
         _ => return None,
     };
 
@@ -1765,6 +4251,9 @@ fn matches_operation_constraint(
     }
 
     // Try reverse order for commutative operations
+    if !op_constraint.op_type.is_commutative() {
+        return None;
+    }
     if let Some(left_res) = matches_operand_constraint(pod, op2, left, matched_statements) {
         if let Some(right_res) = matches_operand_constraint(pod, op1, right, matched_statements) {
             if left == &left_res && right == &right_res {
@@ -1791,10 +4280,11 @@ fn matches_operand_constraint(
             }
         }
         OperandConstraint::Constant(value) => {
-            pod.payload.statements_list.iter().find_map(|(id, stmt)| {
+            pod.payload.statements_list().iter().find_map(|(id, stmt)| {
                 if let Statement::ValueOf(ak, val) = stmt {
                     if ak == operand && val == value {
-                        matched_statements.push(SRef(ORef::P(PodBuilder::pod_id(pod)), id.clone()));
+                        matched_statements
+                            .push(SRef(ORef::P(PodBuilder::pod_id(pod)), id.clone().into()));
                         Some(ak.clone())
                     } else {
                         None
@@ -1805,17 +4295,22 @@ fn matches_operand_constraint(
             })
         }
         OperandConstraint::Operation(op) => {
-            for (id, stmt) in &pod.payload.statements_list {
+            for (id, stmt) in pod.payload.statements_list() {
                 match stmt {
                     Statement::SumOf(res, _, _)
                     | Statement::ProductOf(res, _, _)
-                    | Statement::MaxOf(res, _, _) => {
+                    | Statement::MaxOf(res, _, _)
+                    | Statement::SubOf(res, _, _)
+                    | Statement::DivOf(res, _, _)
+                    | Statement::MinOf(res, _, _) => {
                         if operand == res {
                             if let Some(matched_res) =
                                 matches_operation_constraint(pod, op, stmt, matched_statements)
                             {
-                                matched_statements
-                                    .push(SRef(ORef::P(PodBuilder::pod_id(pod)), id.clone()));
+                                matched_statements.push(SRef(
+                                    ORef::P(PodBuilder::pod_id(pod)),
+                                    id.clone().into(),
+                                ));
                                 return Some(matched_res);
                             }
                         }
@@ -1831,10 +4326,11 @@ fn matches_operand_constraint(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pod2::pod::statement::AnchoredKey;
+    use pod2::pod::{payload::PODPayload, statement::AnchoredKey, PODProof};
+    use pod2::signature::schnorr::{SchnorrSignature, SchnorrSigner};
     pub fn get_self_entry_value(pod: &POD, key: &str) -> Option<ScalarOrVec> {
         pod.payload
-            .statements_list
+            .statements_list()
             .iter()
             .find(|(_, s)| {
                 if let Statement::ValueOf(AnchoredKey(origin, k), _) = s {
@@ -1845,6 +4341,13 @@ mod tests {
             })
             .and_then(|(_, s)| s.value().ok())
     }
+    pub fn get_entry_value(pod: &POD, key: &str) -> Option<ScalarOrVec> {
+        pod.payload
+            .statements_list()
+            .iter()
+            .find(|(_, s)| matches!(s, Statement::ValueOf(AnchoredKey(_, k), _) if k == key))
+            .and_then(|(_, s)| s.value().ok())
+    }
     async fn setup_env() -> (Env, Arc<Mutex<MyPods>>) {
         let shared = Arc::new(InMemoryStore::new());
         let pod_store = Arc::new(Mutex::new(MyPods::default()));
@@ -1852,40 +4355,268 @@ mod tests {
             "test_user".to_string(),
             shared,
             pod_store.clone(),
-            Some(SchnorrSecretKey { sk: 42 }),
+            Some(SchnorrSecretKey::from_u64(42)),
             None,
             None,
         );
         (env, pod_store)
     }
+    fn pos(id: Id, col: u64) -> Pos {
+        Pos { id, line: 1, col }
+    }
     #[test]
     fn test_parse() {
         assert_eq!(
             parse(&mut scan("[+ [+ 20 20] [+ 1 [1]]]")).unwrap(),
             Expr::List(
-                0,
+                pos(0, 1),
                 vec![
-                    Expr::Atom(1, String::from("+")),
+                    Expr::Atom(pos(1, 2), String::from("+")),
                     Expr::List(
-                        2,
+                        pos(2, 4),
                         vec![
-                            Expr::Atom(3, String::from("+")),
-                            Expr::Atom(4, String::from("20")),
-                            Expr::Atom(5, String::from("20")),
+                            Expr::Atom(pos(3, 5), String::from("+")),
+                            Expr::Atom(pos(4, 7), String::from("20")),
+                            Expr::Atom(pos(5, 10), String::from("20")),
                         ]
                     ),
                     Expr::List(
-                        7,
+                        pos(7, 14),
                         vec![
-                            Expr::Atom(8, String::from("+")),
-                            Expr::Atom(9, String::from("1")),
-                            Expr::List(10, vec![Expr::Atom(11, String::from("1")),]),
+                            Expr::Atom(pos(8, 15), String::from("+")),
+                            Expr::Atom(pos(9, 17), String::from("1")),
+                            Expr::List(
+                                pos(10, 19),
+                                vec![Expr::Atom(pos(11, 20), String::from("1")),]
+                            ),
                         ]
                     )
                 ]
             )
         )
     }
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_script("[+ 1 [* 2 3]").unwrap_err(),
+            ParseError {
+                pos: 0,
+                line: 1,
+                col: 1,
+                token: "[".to_string(),
+                message: "unterminated list".to_string(),
+            }
+        );
+        assert_eq!(parse_script("[+ 1 [* 2 3").unwrap_err().pos, 3);
+
+        let error = parse_script("[+ 1 2]]").unwrap_err();
+        assert_eq!((error.pos, error.message.as_str()), (5, "unmatched ]"));
+        assert_eq!(error.to_string(), "unmatched ] at token 5 (])");
+
+        let error = parse_script("+ 1 2").unwrap_err();
+        assert_eq!((error.pos, error.token.as_str()), (0, "+"));
+        assert_eq!(parse_script("  ").unwrap_err().message, "empty input");
+        assert_eq!(parse_script("[+ 1 2] [+ 3 4]").unwrap_err().pos, 5);
+
+        let error = parse_script("[+ 1\n  [* 2 3]").unwrap_err();
+        assert_eq!((error.line, error.col), (1, 1));
+        let error = parse_script("[+ 1\n  2]]").unwrap_err();
+        assert_eq!((error.pos, error.line, error.col), (5, 2, 5));
+    }
+    #[test]
+    fn test_comments() {
+        let expected = Expr::List(
+            pos(0, 1),
+            vec![
+                Expr::Atom(pos(1, 2), String::from("+")),
+                Expr::Atom(pos(2, 4), String::from("1")),
+                Expr::Atom(pos(3, 6), String::from("2")),
+                Expr::Atom(
+                    Pos {
+                        id: 4,
+                        line: 2,
+                        col: 2,
+                    },
+                    String::from("3"),
+                ),
+            ],
+        );
+        assert_eq!(
+            parse_script("[+ 1 2 ; ignore me [ ]\n 3]").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_script("[+ 1 2#| ignore\n me ] |#3]").unwrap(),
+            Expr::List(
+                pos(0, 1),
+                vec![
+                    Expr::Atom(pos(1, 2), String::from("+")),
+                    Expr::Atom(pos(2, 4), String::from("1")),
+                    Expr::Atom(pos(3, 6), String::from("2")),
+                    Expr::Atom(
+                        Pos {
+                            id: 4,
+                            line: 2,
+                            col: 9,
+                        },
+                        String::from("3"),
+                    ),
+                ],
+            )
+        );
+
+        // A script that is only comments is like an empty one
+        assert_eq!(
+            parse_script("; [+ 1 2]\n  #| [+ 3 4] |#")
+                .unwrap_err()
+                .message,
+            "empty input"
+        );
+        assert_eq!(
+            parse_script("#| never closed [+ 1 2]").unwrap_err().message,
+            "empty input"
+        );
+    }
+    #[test]
+    fn test_string_literals() {
+        assert_eq!(
+            parse_script(r#"[f "a [b] ; c" x]"#).unwrap(),
+            Expr::List(
+                pos(0, 1),
+                vec![
+                    Expr::Atom(pos(1, 2), String::from("f")),
+                    Expr::Atom(pos(2, 4), String::from(r#""a [b] ; c""#)),
+                    Expr::Atom(pos(3, 16), String::from("x")),
+                ],
+            )
+        );
+        assert_eq!(
+            string_literal(r#""say \"hi\"\n""#),
+            Some(String::from("say \"hi\"\n"))
+        );
+        assert_eq!(
+            parse_script(r#"[f "never closed]"#).unwrap_err().message,
+            "unterminated string"
+        );
+    }
+    #[tokio::test]
+    async fn test_string_entries() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let pod = match eval(
+            r#"[createpod profile name "Alice" bio "likes [brackets] and spaces"]"#,
+            env.clone(),
+        )
+        .await?
+        {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        let name_hash = hash_string_to_field("Alice");
+        assert_eq!(
+            get_self_entry_value(&pod, "name").unwrap(),
+            ScalarOrVec::Scalar(name_hash)
+        );
+        let bio_hash = hash_string_to_field("likes [brackets] and spaces");
+        assert_eq!(
+            get_self_entry_value(&pod, "bio").unwrap(),
+            ScalarOrVec::Scalar(bio_hash)
+        );
+        pod_store.lock().unwrap().add_pod(pod);
+        {
+            let pod_store = pod_store.lock().unwrap();
+            assert_eq!(
+                pod_store.string_preimage(name_hash),
+                Some(&String::from("Alice"))
+            );
+            assert_eq!(
+                pod_store.string_preimage(bio_hash),
+                Some(&String::from("likes [brackets] and spaces"))
+            );
+        }
+
+        let result = eval(
+            r#"[pod? [name "Alice"] [bio "likes [brackets] and spaces"]]"#,
+            env.clone(),
+        )
+        .await?;
+        assert!(matches!(result, Value::List(_)));
+        let result = eval(r#"[pod? [name "Bob"]]"#, env).await;
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<PexError>(),
+            Some(PexError::NoMatchingPod { .. })
+        ));
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_unknown_identifier_position() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let error = eval("[+ 1\n   [* 2 oops]]", env).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown identifier: oops at line 2, column 9"
+        );
+        assert!(matches!(
+            error.downcast_ref::<PexError>(),
+            Some(PexError::UnknownIdentifier { name, pos }) if name == "oops" && pos.line == 2
+        ));
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_eval_script() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        // Forms are evaluated in order in the same env, so later ones see earlier definitions
+        // and pods
+        let script = "; a script\n[define x 1] ; the first form\n\n[createpod p\n  y [+ x 1] ; y is 2\n  #| z 3 |#]\n[pod? [y]]\n[+ x ; ]\n 2]\n";
+        let values = eval_script(script, env.clone()).await?;
+        assert_eq!(values.len(), 4);
+        assert!(matches!(values[1], Value::PodRef(_)));
+        assert!(matches!(values[2], Value::SRef(_)));
+        assert!(matches!(values[3], Value::Scalar(GoldilocksField(3))));
+        assert_eq!(pod_store.lock().unwrap().len(), 1);
+
+        assert!(eval_script(" ; nothing to do\n", env.clone())
+            .await?
+            .is_empty());
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_eval_script_errors() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        // Stops at the first error, at the position of the unknown identifier
+        let script = "[define balance 10]\n[define a 1]\n\n[+ 1\n       blance]\n[define b 2]";
+        let error = eval_script(script, env.clone()).await.unwrap_err();
+        assert_eq!((error.line, error.col), (5, 8));
+        assert_eq!(
+            error.to_string(),
+            "line 5, col 8: Unknown identifier: blance"
+        );
+        assert!(env.get_binding("a").is_some());
+        assert!(env.get_binding("b").is_none());
+
+        // Other errors are at the start of the form
+        let error = eval_script("[+ 1 2]\n  [/ 1 0]", env.clone())
+            .await
+            .unwrap_err();
+        assert_eq!((error.line, error.col), (2, 3));
+        assert!(error.to_string().starts_with("line 2, col 3: "));
+
+        // Nothing is evaluated if the script doesn't parse
+        let error = eval_script("[define c 1]\n[+ 1 2]]", env.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "line 2, col 8: unmatched ] (])");
+        assert!(env.get_binding("c").is_none());
+        let error = eval_script("[define c 1]\n3", env.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "line 2, col 1: must start with [ (3)");
+        Ok(())
+    }
     #[tokio::test]
     async fn test_create_pod_simple() -> Result<()> {
         let (env, _) = setup_env().await;
@@ -1911,7 +4642,7 @@ mod tests {
     #[tokio::test]
     async fn test_create_pod_with_pod_basic() -> Result<()> {
         let (env, pod_store) = setup_env().await;
-        let first_pod_eval = eval("[createpod test_pod x [+ 40 2] y 12", env.clone()).await?;
+        let first_pod_eval = eval("[createpod test_pod x [+ 40 2] y 12]", env.clone()).await?;
         let first_pod = match first_pod_eval {
             Value::PodRef(pod) => pod,
             _ => panic!("Expected PodRef"),
@@ -2018,7 +4749,10 @@ mod tests {
 
             assert!(result.is_err());
             if let Err(e) = result {
-                assert!(e.to_string().contains("No matching pod found"));
+                assert!(matches!(
+                    e.downcast_ref::<PexError>(),
+                    Some(PexError::NoMatchingPod { .. })
+                ));
             }
             Ok(())
         } else {
@@ -2055,10 +4789,10 @@ mod tests {
             // Should NOT match when querying with wrong key
             let result = eval("[pod? [wrong_key [+ x y]]]", env.clone()).await;
             assert!(result.is_err());
-            assert!(result
-                .unwrap_err()
-                .to_string()
-                .contains("No matching pod found"));
+            assert!(matches!(
+                result.unwrap_err().downcast_ref::<PexError>(),
+                Some(PexError::NoMatchingPod { .. })
+            ));
 
             // Should match specific operation under specific key
             let result = eval("[pod? [other [+ x 5]]]", env.clone()).await?;
@@ -2067,10 +4801,10 @@ mod tests {
             // Should NOT match wrong operation under correct key
             let result = eval("[pod? [sum1 [+ x 5]]]", env.clone()).await;
             assert!(result.is_err());
-            assert!(result
-                .unwrap_err()
-                .to_string()
-                .contains("No matching pod found"));
+            assert!(matches!(
+                result.unwrap_err().downcast_ref::<PexError>(),
+                Some(PexError::NoMatchingPod { .. })
+            ));
 
             Ok(())
         } else {
@@ -2193,13 +4927,150 @@ mod tests {
         let result = eval("[pod? [x 10]]", env.clone()).await?;
         match result {
             Value::SRef(sref) => {
-                assert!(sref.1.contains("VALUEOF:x"));
+                assert_eq!(sref.1, StatementId::value_of("x"));
                 Ok(())
             }
             _ => Err(anyhow!("Expected SRef")),
         }
     }
 
+    #[tokio::test]
+    async fn test_query_keys_with_colons_and_unicode() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let pod = eval("[createpod test_pod a:b 10 âge 30]", env.clone()).await?;
+        if let Value::PodRef(pod) = pod {
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+
+        match eval("[pod? [a:b 10] [âge 30]]", env.clone()).await? {
+            Value::List(values) => match &values[..] {
+                [Value::SRef(a), Value::SRef(b)] => {
+                    assert_eq!(a.1, StatementId::value_of("a:b"));
+                    assert_eq!(b.1, StatementId::value_of("âge"));
+                }
+                _ => return Err(anyhow!("Expected two SRefs")),
+            },
+            _ => return Err(anyhow!("Expected List")),
+        }
+
+        // Values are copied from the input pod by statement id
+        match eval("[createpod final y [+ [pod? [a:b]] 1]]", env.clone()).await? {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "y").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(11))
+                );
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schema_createpod() -> Result<()> {
+        let (env, _) = setup_env().await;
+        eval(
+            "[defschema ticket [age :scalar] [event :scalar] [seats :vector]]",
+            env.clone(),
+        )
+        .await?;
+
+        let pod = eval(
+            "[createpod t :schema ticket age 30 event [+ 1 2] seats [vec 4 5]]",
+            env.clone(),
+        )
+        .await?;
+        assert!(matches!(pod, Value::PodRef(_)));
+
+        let err = eval(
+            "[createpod t :schema ticket age 30 seats [vec 4]]",
+            env.clone(),
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("missing keys event"));
+
+        let err = eval(
+            "[createpod t :schema ticket age 30 event 1 seats 4 zip 1234]",
+            env.clone(),
+        )
+        .await
+        .unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("extra keys zip"));
+        assert!(message.contains("mismatched keys seats (expected vector, found scalar)"));
+
+        // Open schemas allow other keys, but still require the declared ones
+        eval("[defschema open-ticket :open [age :scalar]]", env.clone()).await?;
+        eval(
+            "[createpod t :schema open-ticket age 30 zip 1234]",
+            env.clone(),
+        )
+        .await?;
+        assert!(
+            eval("[createpod t :schema open-ticket zip 1234]", env.clone())
+                .await
+                .is_err()
+        );
+
+        assert!(eval("[createpod t :schema age age 30]", env.clone())
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_schema_query() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let schema = eval(
+            "[defschema ticket [age :scalar] [event :scalar]]",
+            env.clone(),
+        )
+        .await?;
+
+        // Schemas are values, so they can be sent to other users
+        let Value::Schema(schema) = serde_json::from_str(&serde_json::to_string(&schema)?)? else {
+            return Err(anyhow!("Expected Schema"));
+        };
+        assert_eq!(
+            schema.fields,
+            vec![
+                ("age".to_string(), EntryKind::Scalar),
+                ("event".to_string(), EntryKind::Scalar)
+            ]
+        );
+
+        for pod in [
+            "[createpod other age 20]",
+            "[createpod t age 30 event 7 zip 1234]",
+        ] {
+            if let Value::PodRef(pod) = eval(pod, env.clone()).await? {
+                pod_store.lock().unwrap().add_pod(pod);
+            }
+        }
+
+        match eval("[pod? :schema ticket]", env.clone()).await? {
+            Value::List(values) => match &values[..] {
+                [Value::SRef(age), Value::SRef(event)] => {
+                    assert_eq!(age.1, StatementId::value_of("age"));
+                    assert_eq!(event.1, StatementId::value_of("event"));
+                }
+                _ => return Err(anyhow!("Expected two SRefs")),
+            },
+            _ => return Err(anyhow!("Expected List")),
+        }
+
+        // The schema can be combined with other constraints
+        assert!(eval("[pod? :schema ticket [zip 1234]]", env.clone())
+            .await
+            .is_ok());
+        assert!(eval("[pod? :schema ticket [zip 1]]", env.clone())
+            .await
+            .is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_operation_query() -> Result<()> {
         let (env, pod_store) = setup_env().await;
@@ -2222,7 +5093,10 @@ mod tests {
         let result = eval("[pod? [x 31]]", env.clone()).await;
         assert!(result.is_err());
         if let Err(e) = result {
-            assert!(e.to_string().contains("No matching pod found"));
+            assert!(matches!(
+                e.downcast_ref::<PexError>(),
+                Some(PexError::NoMatchingPod { .. })
+            ));
         }
 
         Ok(())
@@ -2412,87 +5286,237 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_pod_query_operation_matching() -> Result<()> {
-        let (env, pod_store) = setup_env().await;
-
-        let pod1 = eval(
-            "[createpod test_pod1 value1 10 value2 [+ 10 value1] value3 [* 2 value2]]",
-            env.clone(),
-        )
-        .await?;
-        if let Value::PodRef(pod1) = pod1 {
-            pod_store.lock().unwrap().add_pod(pod1);
+    async fn test_create_pod_with_sub_and_div() -> Result<()> {
+        let (env, _) = setup_env().await;
 
-            let result = eval(
-                "[createpod final
-                    [define x [pod? [value3 [* 2 [+ 10 value1]]]]]
-                    a [+ x 1]]",
-                env.clone(),
-            )
-            .await?;
+        let result = eval("[createpod p x [- 40 2] y [/ 42 6]]", env).await?;
 
-            match result {
-                Value::PodRef(pod) => {
-                    assert_eq!(
-                        get_self_entry_value(&pod, "a").unwrap(),
-                        ScalarOrVec::Scalar(GoldilocksField(41))
-                    );
-                }
-                _ => return Err(anyhow!("Expected PodRef")),
+        match result {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "x").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(38))
+                );
+                assert_eq!(
+                    get_self_entry_value(&pod, "y").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(7))
+                );
+                Ok(())
             }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
 
-            // Both of these queries should work
-            let result = eval(
-                "[createpod final
-                    [define x [pod? [value3 [* 2 value2]]]]
-                    a [+ x 1]]",
-                env.clone(),
-            )
-            .await?;
+    #[tokio::test]
+    async fn test_sub_wraps_around_modulus() -> Result<()> {
+        let (env, _) = setup_env().await;
 
-            match result {
-                Value::PodRef(pod) => {
-                    assert_eq!(
-                        get_self_entry_value(&pod, "a").unwrap(),
-                        ScalarOrVec::Scalar(GoldilocksField(41))
-                    );
-                    Ok(())
-                }
-                _ => Err(anyhow!("Expected PodRef")),
+        // Goldilocks modulus p = 2^64 - 2^32 + 1, so 2 - 5 = p - 3
+        let result = eval("[- 2 5]", env).await?;
+        match result {
+            Value::Scalar(s) => {
+                assert_eq!(s.to_canonical_u64(), 18446744069414584318);
+                Ok(())
             }
-        } else {
-            Err(anyhow!("Failed to create test pod"))
+            _ => Err(anyhow!("Expected Scalar")),
         }
     }
+
     #[tokio::test]
-    async fn test_pod_refer_to_previously_created_key() -> Result<()> {
-        let (env, pod_store) = setup_env().await;
+    async fn test_sum_and_product_stay_within_num_bits() -> Result<()> {
+        let (env, _) = setup_env().await;
 
-        // Create a pod with a single value
-        let pod1 = eval("[createpod test_pod1 value1 10]", env.clone()).await?;
-        if let Value::PodRef(pod1) = pod1 {
-            pod_store.lock().unwrap().add_pod(pod1);
+        // 2^32 - 1 is the largest result
+        match eval("[+ 4294967294 1]", env.clone()).await? {
+            Value::Scalar(s) => assert_eq!(s.to_canonical_u64(), 4294967295),
+            _ => return Err(anyhow!("Expected Scalar")),
+        }
+        assert!(eval("[+ 4294967295 1]", env.clone()).await.is_err());
+        assert!(eval("[+ 4000000000 4000000000]", env.clone())
+            .await
+            .is_err());
+        assert!(eval("[+ 4294967296 0]", env.clone()).await.is_err());
 
-            // Test querying single value returns SRef directly, not in a list
-            let result = eval(
-                "[createpod final
-                    [define x [pod? [value1]]]
-                    a 10
-                    b [+ [* x 10] a]]",
-                env.clone(),
-            )
-            .await?;
+        // Both operands fit, but only the first product does
+        match eval("[* 65535 65537]", env.clone()).await? {
+            Value::Scalar(s) => assert_eq!(s.to_canonical_u64(), 4294967295),
+            _ => return Err(anyhow!("Expected Scalar")),
+        }
+        assert!(eval("[* 65536 65536]", env.clone()).await.is_err());
 
-            match result {
-                Value::PodRef(pod) => {
-                    assert_eq!(
-                        get_self_entry_value(&pod, "b").unwrap(),
-                        ScalarOrVec::Scalar(GoldilocksField(110))
-                    );
-                    Ok(())
-                }
-                _ => Err(anyhow!("Expected PodRef")),
-            }
+        assert!(eval("[createpod p x 4294967295 y [+ x 1]]", env)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_div_by_zero_fails() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let result = eval("[/ 42 0]", env.clone()).await;
+        assert!(result.is_err());
+
+        let result = eval("[createpod p x 42 y [/ x 0]]", env).await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_non_exact_div_fails() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let result = eval("[/ 43 6]", env.clone()).await;
+        assert!(result.is_err());
+
+        let result = eval("[createpod p x 43 y [/ x 6]]", env).await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sub_operation_query() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let pod = eval(
+            "[createpod account balance 100 fee 7 diff [- balance fee]]",
+            env.clone(),
+        )
+        .await?;
+        if let Value::PodRef(pod) = pod {
+            assert_eq!(
+                get_self_entry_value(&pod, "diff").unwrap(),
+                ScalarOrVec::Scalar(GoldilocksField(93))
+            );
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+
+        let result = eval("[pod? [diff [- balance fee]]]", env.clone()).await?;
+        assert!(matches!(result, Value::SRef(_)));
+
+        // Subtraction isn't commutative
+        let result = eval("[pod? [diff [- fee balance]]]", env.clone()).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_min_operation_query() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let pod = eval(
+            "[createpod offers a 30 b 20 lowest [min a b] capped [min a 12]]",
+            env.clone(),
+        )
+        .await?;
+        if let Value::PodRef(pod) = pod {
+            assert_eq!(
+                get_self_entry_value(&pod, "lowest").unwrap(),
+                ScalarOrVec::Scalar(GoldilocksField(20))
+            );
+            assert_eq!(
+                get_self_entry_value(&pod, "capped").unwrap(),
+                ScalarOrVec::Scalar(GoldilocksField(12))
+            );
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+
+        let result = eval("[pod? [lowest [min a b]]]", env.clone()).await?;
+        assert!(matches!(result, Value::SRef(_)));
+
+        // Constants are matched in either operand order
+        let result = eval("[pod? [capped [min a 12]]]", env.clone()).await?;
+        assert!(matches!(result, Value::SRef(_)));
+        let result = eval("[pod? [capped [min 12 a]]]", env.clone()).await?;
+        assert!(matches!(result, Value::SRef(_)));
+
+        let result = eval("[pod? [capped [min a 13]]]", env.clone()).await;
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_query_operation_matching() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let pod1 = eval(
+            "[createpod test_pod1 value1 10 value2 [+ 10 value1] value3 [* 2 value2]]",
+            env.clone(),
+        )
+        .await?;
+        if let Value::PodRef(pod1) = pod1 {
+            pod_store.lock().unwrap().add_pod(pod1);
+
+            let result = eval(
+                "[createpod final
+                    [define x [pod? [value3 [* 2 [+ 10 value1]]]]]
+                    a [+ x 1]]",
+                env.clone(),
+            )
+            .await?;
+
+            match result {
+                Value::PodRef(pod) => {
+                    assert_eq!(
+                        get_self_entry_value(&pod, "a").unwrap(),
+                        ScalarOrVec::Scalar(GoldilocksField(41))
+                    );
+                }
+                _ => return Err(anyhow!("Expected PodRef")),
+            }
+
+            // Both of these queries should work
+            let result = eval(
+                "[createpod final
+                    [define x [pod? [value3 [* 2 value2]]]]
+                    a [+ x 1]]",
+                env.clone(),
+            )
+            .await?;
+
+            match result {
+                Value::PodRef(pod) => {
+                    assert_eq!(
+                        get_self_entry_value(&pod, "a").unwrap(),
+                        ScalarOrVec::Scalar(GoldilocksField(41))
+                    );
+                    Ok(())
+                }
+                _ => Err(anyhow!("Expected PodRef")),
+            }
+        } else {
+            Err(anyhow!("Failed to create test pod"))
+        }
+    }
+    #[tokio::test]
+    async fn test_pod_refer_to_previously_created_key() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        // Create a pod with a single value
+        let pod1 = eval("[createpod test_pod1 value1 10]", env.clone()).await?;
+        if let Value::PodRef(pod1) = pod1 {
+            pod_store.lock().unwrap().add_pod(pod1);
+
+            // Test querying single value returns SRef directly, not in a list
+            let result = eval(
+                "[createpod final
+                    [define x [pod? [value1]]]
+                    a 10
+                    b [+ [* x 10] a]]",
+                env.clone(),
+            )
+            .await?;
+
+            match result {
+                Value::PodRef(pod) => {
+                    assert_eq!(
+                        get_self_entry_value(&pod, "b").unwrap(),
+                        ScalarOrVec::Scalar(GoldilocksField(110))
+                    );
+                    Ok(())
+                }
+                _ => Err(anyhow!("Expected PodRef")),
+            }
         } else {
             Err(anyhow!("Failed to create test pod"))
         }
@@ -2589,6 +5613,148 @@ mod tests {
         }
     }
     #[tokio::test]
+    async fn test_keep_copies_queried_statements() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let source_pod = eval("[createpod source x 10 w 5]", env.clone()).await?;
+        if let Value::PodRef(source_pod) = source_pod {
+            pod_store.lock().unwrap().add_pod(source_pod);
+        }
+
+        let result = eval(
+            "[createpod out [keep [pod? [x 10] [w]]] y [+ 1 2]]",
+            env.clone(),
+        )
+        .await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                for (key, value) in [("x", 10), ("w", 5)] {
+                    assert!(pod.payload.statements_list().iter().any(|(_, s)| {
+                        if let Statement::ValueOf(AnchoredKey(origin, k), v) = s {
+                            k == key
+                                && !origin.is_self()
+                                && v == &ScalarOrVec::Scalar(GoldilocksField(value))
+                        } else {
+                            false
+                        }
+                    }));
+                }
+                assert_eq!(
+                    get_self_entry_value(&pod, "y").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(3))
+                );
+                assert!(pod.verify::<L, M, N, NS, VL>()?);
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keep_bound_entry() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let source_pod = eval("[createpod source x 10 w 5]", env.clone()).await?;
+        if let Value::PodRef(source_pod) = source_pod {
+            pod_store.lock().unwrap().add_pod(source_pod);
+        }
+
+        let result = eval(
+            "[createpod out [define x [pod? [x]]] [keep x] [keep x] y [+ x 1]]",
+            env.clone(),
+        )
+        .await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                // The kept entry is carried over with its original origin
+                assert!(get_self_entry_value(&pod, "x").is_none());
+                assert_eq!(
+                    get_entry_value(&pod, "x").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(10))
+                );
+                assert!(get_entry_value(&pod, "w").is_none());
+                assert_eq!(
+                    pod.payload
+                        .statements_list()
+                        .iter()
+                        .filter(|(_, s)| matches!(s, Statement::ValueOf(AnchoredKey(_, k), _) if k == "x"))
+                        .count(),
+                    1
+                );
+                assert_eq!(
+                    get_self_entry_value(&pod, "y").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(11))
+                );
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redact_hides_source_pod() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let source_id = match eval("[createpod source age 30 name 7]", env.clone()).await? {
+            Value::PodRef(pod) => {
+                let source_id = PodBuilder::pod_id(&pod);
+                pod_store.lock().unwrap().add_pod(pod);
+                source_id
+            }
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+
+        let result = eval(
+            "[createpod out
+                [define age [pod? [age]]]
+                [redact age]
+                [keep age]
+                checked 1
+                [> age 18]]",
+            env.clone(),
+        )
+        .await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                assert!(pod.payload.statements_list().iter().any(|(_, s)| matches!(
+                    s,
+                    Statement::ValueOf(AnchoredKey(origin, k), _)
+                        if k == "age" && !origin.is_self()
+                )));
+                for (name, statement) in pod.payload.statements_list() {
+                    assert!(!name.contains(&source_id));
+                    for AnchoredKey(origin, _) in statement.anchored_keys() {
+                        assert!(origin.is_self() || origin.origin_name.starts_with("origin_"));
+                    }
+                }
+                assert!(pod.verify::<L, M, N, NS, VL>()?);
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keep_outside_createpod_fails() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let source_pod = eval("[createpod source x 10]", env.clone()).await?;
+        if let Value::PodRef(source_pod) = source_pod {
+            pod_store.lock().unwrap().add_pod(source_pod);
+        }
+
+        let result = eval("[keep [pod? [x]]]", env.clone()).await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keep_non_sref_fails() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let result = eval("[createpod out [keep 42] y 1]", env.clone()).await;
+        assert!(result.is_err());
+        Ok(())
+    }
+    #[tokio::test]
     async fn test_pod_with_assertions_after_kv() -> Result<()> {
         let (env, _) = setup_env().await;
 
@@ -2640,9 +5806,52 @@ mod tests {
         )
         .await;
 
+        match result.unwrap_err().downcast_ref::<PexError>() {
+            Some(PexError::AssertionFailed {
+                assert,
+                left,
+                right,
+            }) => {
+                assert_eq!(assert, "[> y z]");
+                assert_eq!((*left, *right), (15, 20));
+            }
+            e => return Err(anyhow!("Expected AssertionFailed, got {:?}", e)),
+        }
+
+        // Assertions over constants are checked too
+        let result = eval("[createpod test x 1 [> 1 2]]", env).await;
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<PexError>(),
+            Some(PexError::AssertionFailed {
+                left: 1,
+                right: 2,
+                ..
+            })
+        ));
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_pod_with_lt_assertion() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[createpod t x 5 y 10 [< x y]]", env.clone()).await?;
+        match result {
+            Value::PodRef(pod) => {
+                assert!(pod
+                    .payload
+                    .statements_list()
+                    .iter()
+                    .any(|(_, s)| matches!(s, Statement::Lt(_, _))));
+            }
+            _ => return Err(anyhow!("Expected PodRef")),
+        }
+
+        // x=10, y=5, so this assertion fails
+        let result = eval("[createpod t x 10 y 5 [< x y]]", env.clone()).await;
         assert!(result.is_err());
         Ok(())
     }
+
     #[tokio::test]
     async fn test_pod_query_with_operation_and_assert() -> Result<()> {
         let (env, pod_store) = setup_env().await;
@@ -2676,13 +5885,50 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_pod_query_with_failing_assert() -> Result<()> {
+    async fn test_pod_query_with_lt_assert() -> Result<()> {
         let (env, pod_store) = setup_env().await;
 
-        // Create a pod that won't match the assertion
         let source_pod = eval(
             "[createpod source
-                y 5
+                y 3
+                x [+ y 2]
+                [< y 10]]",
+            env.clone(),
+        )
+        .await?;
+        if let Value::PodRef(source_pod) = source_pod {
+            pod_store.lock().unwrap().add_pod(source_pod);
+        }
+
+        let result = eval(
+            "[pod?
+                [x [+ y 2]]
+                [< y 10]]",
+            env.clone(),
+        )
+        .await?;
+        assert!(matches!(result, Value::SRef(_)));
+
+        // The pod only proves y < 10, not y > 10
+        let result = eval(
+            "[pod?
+                [x [+ y 2]]
+                [> y 10]]",
+            env.clone(),
+        )
+        .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_query_with_failing_assert() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        // Create a pod that won't match the assertion
+        let source_pod = eval(
+            "[createpod source
+                y 5
                 x [+ y 2]]",
             env.clone(),
         )
@@ -2701,10 +5947,10 @@ mod tests {
         .await;
 
         assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("No matching pod found"));
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<PexError>(),
+            Some(PexError::NoMatchingPod { .. })
+        ));
         Ok(())
     }
     #[tokio::test]
@@ -2716,7 +5962,7 @@ mod tests {
             "alice".to_string(),
             shared.clone(),
             Arc::new(Mutex::new(MyPods::default())),
-            Some(SchnorrSecretKey { sk: 42 }),
+            Some(SchnorrSecretKey::from_u64(42)),
             None,
             None,
         );
@@ -2726,7 +5972,7 @@ mod tests {
             "bob".to_string(),
             shared.clone(),
             Arc::new(Mutex::new(MyPods::default())),
-            Some(SchnorrSecretKey { sk: 43 }),
+            Some(SchnorrSecretKey::from_u64(43)),
             None,
             None,
         );
@@ -2750,7 +5996,7 @@ mod tests {
             "alice".to_string(),
             shared.clone(),
             alice_pod_store.clone(),
-            Some(SchnorrSecretKey { sk: 42 }),
+            Some(SchnorrSecretKey::from_u64(42)),
             None,
             None,
         );
@@ -2759,7 +6005,7 @@ mod tests {
             "bob".to_string(),
             shared.clone(),
             bob_pod_store.clone(),
-            Some(SchnorrSecretKey { sk: 43 }),
+            Some(SchnorrSecretKey::from_u64(43)),
             None,
             None,
         );
@@ -2803,23 +6049,61 @@ mod tests {
     async fn test_from_wrong_user() -> Result<()> {
         let shared = Arc::new(InMemoryStore::new());
 
-        let alice_env = Env::new(
+        let mut alice_env = Env::new(
             "alice".to_string(),
             shared.clone(),
             Arc::new(Mutex::new(MyPods::default())),
-            Some(SchnorrSecretKey { sk: 42 }),
+            Some(SchnorrSecretKey::from_u64(42)),
             None,
             None,
         );
+        alice_env.set_remote_timeout(Duration::from_millis(100));
 
         // Try to get a value from Bob that doesn't exist
         let result = eval("[from bob 42]", alice_env.clone()).await;
         assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("couldn't find on the remote"));
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<PexError>(),
+            Some(PexError::RemoteUnavailable {
+                aid: _,
+                error: WaitError::Timeout(_)
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_from_to_recipients() -> Result<()> {
+        let shared = Arc::new(InMemoryStore::new());
+        let env = |user: &str, sk| {
+            Env::new(
+                user.to_string(),
+                shared.clone(),
+                Arc::new(Mutex::new(MyPods::default())),
+                Some(SchnorrSecretKey::from_u64(sk)),
+                None,
+                None,
+            )
+        };
+
+        let script = "[from alice :to [bob] 42]";
+        eval(script, env("alice", 42)).await?;
+        let result = eval(script, env("bob", 43)).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(42)));
+
+        let result = eval(script, env("carol", 44)).await;
+        assert!(matches!(
+            result.unwrap_err().downcast_ref::<PexError>(),
+            Some(PexError::RemoteUnavailable {
+                aid: _,
+                error: WaitError::Unauthorized
+            })
+        ));
 
+        assert!(eval("[from alice :to bob 42]", env("alice", 42))
+            .await
+            .is_err());
         Ok(())
     }
 
@@ -2833,7 +6117,7 @@ mod tests {
             "alice".to_string(),
             shared.clone(),
             alice_pod_store.clone(),
-            Some(SchnorrSecretKey { sk: 42 }),
+            Some(SchnorrSecretKey::from_u64(42)),
             None,
             None,
         );
@@ -2842,7 +6126,7 @@ mod tests {
             "bob".to_string(),
             shared.clone(),
             bob_pod_store.clone(),
-            Some(SchnorrSecretKey { sk: 43 }),
+            Some(SchnorrSecretKey::from_u64(43)),
             None,
             None,
         );
@@ -3005,6 +6289,48 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_length() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[length [list]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(0)));
+        let result = eval("[length [list 1 [+ 1 1] 3]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(3)));
+
+        let error = eval("[length 5]", env).await.unwrap_err();
+        assert_eq!(error.to_string(), "length requires a list argument");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nth() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[nth 0 [list 10 20 30]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(10)));
+        let result = eval("[nth [+ 1 1] [list 10 20 30]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(30)));
+
+        let error = eval("[nth 3 [list 10 20 30]]", env.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "nth index 3 out of bounds for length 3");
+        // -1 is p - 1 in the field, so it's out of bounds rather than the last element
+        assert!(eval("[nth -1 [list 10 20 30]]", env.clone()).await.is_err());
+        assert!(eval("[nth 18446744069414584320 [list 10]]", env.clone())
+            .await
+            .is_err());
+
+        let error = eval("[nth 0 5]", env.clone()).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "nth requires a list or a vector argument"
+        );
+        assert!(eval("[nth [list 1] [list 1]]", env).await.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_list_with_arithmetic() -> Result<()> {
         let (env, _) = setup_env().await;
@@ -3022,4 +6348,1662 @@ mod tests {
             _ => Err(anyhow!("Expected List, got something else")),
         }
     }
+
+    #[tokio::test]
+    async fn test_comparison_values() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let cases = [
+            ("[= 3 3]", true),
+            ("[= 3 4]", false),
+            ("[!= 3 4]", true),
+            ("[> 3 4]", false),
+            ("[< 3 4]", true),
+        ];
+        for (source, expected) in cases {
+            match eval(source, env.clone()).await? {
+                Value::Bool(b) => assert_eq!(b, expected, "{}", source),
+                _ => return Err(anyhow!("Expected Bool for {}", source)),
+            }
+        }
+
+        // Booleans count as 0 or 1 in arithmetic and comparisons, and can be used as conditions
+        let result = eval("[+ [= 3 3] [= 1 1]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(2)));
+        let result = eval("[* 5 [> 1 2]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(0)));
+        let result = eval("[= [< 1 2] 1]", env.clone()).await?;
+        assert!(matches!(result, Value::Bool(true)));
+        let result = eval("[if [= 3 3] 10 20]", env).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(10)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_logic_operators() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let cases = [
+            ("[and [> 10 5] [< 10 100]]", true),
+            ("[and [> 1 5] [< 1 100]]", false),
+            ("[or [> 1 5] [< 1 100]]", true),
+            ("[or [> 1 5] [= 1 2]]", false),
+            ("[not [= 1 2]]", true),
+            ("[not [and [> 10 5] [!= 10 10]]]", true),
+        ];
+        for (source, expected) in cases {
+            match eval(source, env.clone()).await? {
+                Value::Bool(b) => assert_eq!(b, expected, "{}", source),
+                _ => return Err(anyhow!("Expected Bool for {}", source)),
+            }
+        }
+
+        assert!(eval("[not 1 2]", env.clone()).await.is_err());
+        assert!(eval("[and [> 1 0]]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_logic_operators_in_createpod() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[createpod p x 10 either [or [> x 5] [> x 50]]]", env).await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "either").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(1))
+                );
+                // Both sides of `or` are recorded, the false `x > 50` as `x < 50`
+                let gt_count = pod
+                    .payload
+                    .statements_list()
+                    .iter()
+                    .filter(|(_, s)| matches!(s, Statement::Gt(_, _)))
+                    .count();
+                let lt_count = pod
+                    .payload
+                    .statements_list()
+                    .iter()
+                    .filter(|(_, s)| matches!(s, Statement::Lt(_, _)))
+                    .count();
+                assert_eq!(gt_count, 1);
+                assert_eq!(lt_count, 1);
+                assert!(pod.verify::<L, M, N, NS, VL>()?);
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cmp() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let key = |s: &AnchoredKey| s.1.clone();
+        for (y, expected) in [(15, ">"), (5, "<"), (10, "=")] {
+            let source = format!("[createpod p x 10 y {} [cmp y x]]", y);
+            let Value::PodRef(pod) = eval(&source, env.clone()).await? else {
+                return Err(anyhow!("Expected PodRef"));
+            };
+            let comparisons: Vec<_> = pod
+                .payload
+                .statements_list()
+                .iter()
+                .filter_map(|(_, s)| match s {
+                    Statement::Gt(l, r) => Some((">", key(l), key(r))),
+                    Statement::Lt(l, r) => Some(("<", key(l), key(r))),
+                    Statement::Equal(l, r) => Some(("=", key(l), key(r))),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                comparisons,
+                vec![(expected, "y".to_string(), "x".to_string())],
+                "{}",
+                source
+            );
+            assert!(pod.verify::<L, M, N, NS, VL>()?);
+
+            // Outside a createpod, the comparison that holds
+            let result = eval(&format!("[cmp {} 10]", y), env.clone()).await?;
+            assert!(matches!(result, Value::String(s) if s == expected));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplicated_computation_is_deduplicated() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval(
+            "[createpod t x 5 a [+ x 1] b [+ x 1] c [and [> x 1] [> x 1]]]",
+            env,
+        )
+        .await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "a").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(6))
+                );
+                assert_eq!(
+                    get_self_entry_value(&pod, "b").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(6))
+                );
+                let count = |predicate: fn(&Statement) -> bool| {
+                    pod.payload
+                        .statements_list()
+                        .iter()
+                        .filter(|(_, s)| predicate(s))
+                        .count()
+                };
+                assert_eq!(count(|s| matches!(s, Statement::SumOf(_, _, _))), 1);
+                assert_eq!(count(|s| matches!(s, Statement::Gt(_, _))), 1);
+                assert!(pod.verify::<L, M, N, NS, VL>()?);
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_if() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[if [> 3 1] 10 20]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(10)));
+
+        let result = eval("[if [< 3 1] 10 20]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(20)));
+
+        assert!(eval("[if [> 3 1] 10]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_if_in_createpod_only_records_taken_branch() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[createpod p x 10 y [if [> x 5] [+ x 1] [* x 2]]]", env).await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "y").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(11))
+                );
+                let statements = pod.payload.statements_list();
+                assert!(statements
+                    .iter()
+                    .any(|(_, s)| matches!(s, Statement::Gt(_, _))));
+                assert!(statements
+                    .iter()
+                    .any(|(_, s)| matches!(s, Statement::SumOf(_, _, _))));
+                assert!(!statements
+                    .iter()
+                    .any(|(_, s)| matches!(s, Statement::ProductOf(_, _, _))));
+                assert!(pod.verify::<L, M, N, NS, VL>()?);
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_if_records_one_comparison_for_taken_branch() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        // Comparison statements in the pod, as (is Gt, is Lt)
+        let comparisons = |pod: &POD| {
+            pod.payload
+                .statements_list()
+                .iter()
+                .filter(|(_, s)| {
+                    matches!(
+                        s,
+                        Statement::Gt(_, _)
+                            | Statement::Lt(_, _)
+                            | Statement::Equal(_, _)
+                            | Statement::NotEqual(_, _)
+                    )
+                })
+                .map(|(_, s)| {
+                    (
+                        matches!(s, Statement::Gt(_, _)),
+                        matches!(s, Statement::Lt(_, _)),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let create = |script: &'static str| {
+            let env = env.clone();
+            async move {
+                match eval(script, env).await? {
+                    Value::PodRef(pod) => Ok(pod),
+                    _ => Err(anyhow!("Expected PodRef")),
+                }
+            }
+        };
+
+        let gold = create("[createpod gold balance 150 tier [if [> balance 100] 1 2]]").await?;
+        assert_eq!(
+            get_self_entry_value(&gold, "tier").unwrap(),
+            ScalarOrVec::Scalar(GoldilocksField(1))
+        );
+        assert_eq!(comparisons(&gold), vec![(true, false)]);
+
+        // The else branch is witnessed by balance < 100
+        let silver = create("[createpod silver balance 50 tier [if [> balance 100] 1 2]]").await?;
+        assert_eq!(
+            get_self_entry_value(&silver, "tier").unwrap(),
+            ScalarOrVec::Scalar(GoldilocksField(2))
+        );
+        assert_eq!(comparisons(&silver), vec![(false, true)]);
+
+        // Conditions over constants only don't end up in the pod
+        let constant = create("[createpod p balance 50 tier [if [> 3 1] 1 2]]").await?;
+        assert!(comparisons(&constant).is_empty());
+
+        let nested =
+            create("[createpod p x 7 y [if [> x 5] [if [< x 8] [+ x 1] [* x 2]] 0]]").await?;
+        assert_eq!(
+            get_self_entry_value(&nested, "y").unwrap(),
+            ScalarOrVec::Scalar(GoldilocksField(8))
+        );
+        let mut nested_comparisons = comparisons(&nested);
+        nested_comparisons.sort();
+        assert_eq!(nested_comparisons, vec![(false, true), (true, false)]);
+        assert!(nested.verify::<L, M, N, NS, VL>()?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_query_all() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let mut ids = Vec::new();
+        for script in [
+            "[createpod a x 1]",
+            "[createpod b x 2]",
+            "[createpod c y 3]",
+        ] {
+            let pod = match eval(script, env.clone()).await? {
+                Value::PodRef(pod) => pod,
+                _ => return Err(anyhow!("Expected PodRef")),
+            };
+            ids.push((pod.content_id(), PodBuilder::pod_id(&pod)));
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+        ids.truncate(2);
+        ids.sort();
+        let expected: Vec<String> = ids.into_iter().map(|(_, id)| id).collect();
+
+        // Both pods with an x, ordered by content id whatever the store order
+        let matched_ids = |value: Value| match value {
+            Value::List(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    Value::SRef(SRef(ORef::P(id), _)) => Ok(id),
+                    _ => Err(anyhow!("Expected SRef")),
+                })
+                .collect::<Result<Vec<_>>>(),
+            _ => Err(anyhow!("Expected List")),
+        };
+        assert_eq!(
+            matched_ids(eval("[pod?* [x]]", env.clone()).await?)?,
+            expected
+        );
+        pod_store.lock().unwrap().pods.reverse();
+        assert_eq!(
+            matched_ids(eval("[pod?* [x]]", env.clone()).await?)?,
+            expected
+        );
+        let first = eval("[pod? [x]]", env.clone()).await?;
+        assert!(matches!(first, Value::SRef(SRef(ORef::P(id), _)) if id == expected[0]));
+        assert!(matches!(
+            eval("[pod?* [x 5]]", env.clone()).await?,
+            Value::List(values) if values.is_empty()
+        ));
+
+        // Only the pod whose reference gets used becomes an input
+        let script = "[createpod q z [+ [nth 1 [pod?* [x]]] 1]]";
+        let plan = match eval(&format!("[explain {}]", script), env.clone()).await? {
+            Value::Plan(plan) => plan,
+            _ => return Err(anyhow!("Expected Plan")),
+        };
+        assert_eq!(
+            plan.input_pods.keys().collect::<Vec<_>>(),
+            vec![&expected[1]]
+        );
+        assert!(matches!(eval(script, env.clone()).await?, Value::PodRef(_)));
+
+        // pod?* still matches inputs, unless they are excluded
+        let script = "[createpod r
+            x1 [pod? [x]]
+            n [length [pod?* [x]]]
+            m [length [pod?* [exclude-inputs] [x]]]]";
+        let pod = match eval(script, env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        let values: HashMap<String, ScalarOrVec> = pod
+            .payload
+            .statements_list()
+            .iter()
+            .filter_map(|(_, statement)| match statement {
+                Statement::ValueOf(ak, value) if ak.0.origin_name == SELF_ORIGIN_NAME => {
+                    Some((ak.1.clone(), value.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values["n"], ScalarOrVec::Scalar(GoldilocksField(2)));
+        assert_eq!(values["m"], ScalarOrVec::Scalar(GoldilocksField(1)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_builder_api_matches_createpod() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let source = match eval("[createpod source x 10]", env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        pod_store.lock().unwrap().add_pod(source.clone());
+        env.set_binding(
+            "source_x".to_string(),
+            Value::SRef(SRef::new(
+                PodBuilder::pod_id(&source),
+                StatementId::value_of("x"),
+            )),
+        );
+
+        let created = match eval(
+            "[createpod p a 5 b source_x s [+ a b] m [* s 2] [> s a] [= b 10]]",
+            env.clone(),
+        )
+        .await?
+        {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+
+        let mut builder = PodBuilder::new();
+        let a = builder.add_entry("a", ScalarOrVec::Scalar(GoldilocksField(5)));
+        let b = builder.add_entry_from_pod(&source, "VALUEOF:x", "b")?;
+        let s = builder.sum("s", a.clone(), b.clone())?;
+        let two = builder.compute(
+            OpType::Multiply,
+            Value::SRef(s.clone()),
+            Value::Scalar(GoldilocksField(2)),
+        )?;
+        builder.add_entry_from_ref("m", two)?;
+        builder.assert_gt(s.clone(), a.clone())?;
+        builder.assert(
+            AssertType::Eq,
+            Value::SRef(b),
+            Value::Scalar(GoldilocksField(10)),
+        )?;
+        assert!(builder.assert_lt(s, a).is_err());
+        let built = builder.finalize_with_key(Some(&SchnorrSecretKey::from_u64(42)))?;
+
+        assert_eq!(
+            built.payload.statements_list(),
+            created.payload.statements_list()
+        );
+        assert!(built.verify::<L, M, N, NS, VL>()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pod_builder_api_schnorr() -> Result<()> {
+        let sk = SchnorrSecretKey::from_u64(42);
+        let mut builder = PodBuilder::new();
+        builder.add_entry("x", ScalarOrVec::Scalar(GoldilocksField(1)));
+        assert!(matches!(
+            builder.finalize_with_key(Some(&sk))?.proof,
+            PODProof::Schnorr(_)
+        ));
+        assert!(matches!(
+            builder.finalize_with_key(None)?.proof,
+            PODProof::Oracle(_)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_explain_createpod() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        // Only new entries, so it would be a Schnorr pod
+        let plan = match eval("[explain [createpod p x 10 y 20]]", env.clone()).await? {
+            Value::Plan(plan) => plan,
+            _ => return Err(anyhow!("Expected Plan")),
+        };
+        assert_eq!(plan.gadget, GadgetID::SCHNORR16);
+        assert_eq!(plan.ops.len(), 2);
+        assert_eq!(plan.statement_count, 3);
+        assert_eq!(plan.max_statements, NS);
+
+        let source = match eval("[createpod source x 10]", env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        let source_id = PodBuilder::pod_id(&source);
+        pod_store.lock().unwrap().add_pod(source);
+
+        let script = "[createpod q z [+ [pod? [x]] 1]]";
+        let plan = match eval(&format!("[explain {}]", script), env.clone()).await? {
+            Value::Plan(plan) => plan,
+            _ => return Err(anyhow!("Expected Plan")),
+        };
+        assert_eq!(plan.gadget, GadgetID::ORACLE);
+        assert_eq!(plan.input_pods.keys().collect::<Vec<_>>(), vec![&source_id]);
+        assert_eq!(
+            plan.origin_renaming_map
+                .get(&(source_id.clone(), SELF_ORIGIN_NAME.to_string())),
+            Some(&source_id)
+        );
+        assert_eq!(plan.statement_count, plan.ops.len());
+        assert!(plan
+            .ops
+            .iter()
+            .any(|OpCmd(op, _)| matches!(op, Op::SumOf(_, _, _))));
+        assert!(plan
+            .ops
+            .iter()
+            .any(|OpCmd(op, _)| matches!(op, Op::CopyStatement(_))));
+
+        // Proving the plan gives the same statements as createpod
+        let proven = PodBuilder::prove(*plan, &env).await?;
+        let created = match eval(script, env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        assert_eq!(proven.payload, created.payload);
+
+        assert!(eval("[explain [+ 1 2]]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_createpod_zk() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let plan_gadget = |script: &str| {
+            let env = env.clone();
+            let script = format!("[explain {}]", script);
+            async move {
+                match eval(&script, env).await? {
+                    Value::Plan(plan) => Ok(plan.gadget),
+                    _ => Err(anyhow!("Expected Plan")),
+                }
+            }
+        };
+        match eval("[createpod source x 10]", env.clone()).await? {
+            Value::PodRef(pod) => {
+                pod_store.lock().unwrap().add_pod(pod);
+            }
+            _ => return Err(anyhow!("Expected PodRef")),
+        }
+
+        // Pods without input pods are still signed
+        assert_eq!(
+            plan_gadget("[createpod! p x 10]").await?,
+            GadgetID::SCHNORR16
+        );
+        let scripts = [
+            "[createpod! q z [+ [pod? [x]] 1]]",
+            "[createpod! r z [+ [pod? [x]] 2]]",
+        ];
+        assert_eq!(plan_gadget(scripts[0]).await?, GadgetID::PLONKY);
+        assert!(eval(scripts[0], env.clone()).await.is_err());
+
+        // The params are built for the first pod and reused for the second
+        let prover = Arc::new(ProverContext::new());
+        let env = Env {
+            prover: Some(prover.clone()),
+            ..env
+        };
+        for script in scripts {
+            let pod = match eval(script, env.clone()).await? {
+                Value::PodRef(pod) => pod,
+                _ => return Err(anyhow!("Expected PodRef")),
+            };
+            assert_eq!(pod.proof_type, GadgetID::PLONKY);
+            assert!(pod.verify::<L, M, N, NS, VL>()?);
+            assert!(prover.is_initialized());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_remove_pod() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let pod = match eval("[createpod p x 1]", env).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+
+        let store = InMemoryStore::new();
+        let id = store.store_pod(pod.clone());
+        assert_eq!(store.list_pod_ids(), vec![id.clone()]);
+        assert!(store.remove_pod(&id));
+        assert!(!store.remove_pod(&id));
+        assert!(store.list_pod_ids().is_empty());
+        assert_eq!(
+            store
+                .get_pod(&id, Duration::from_secs(1))
+                .await
+                .unwrap_err(),
+            WaitError::Removed
+        );
+
+        // A peer syncing it doesn't bring it back, storing it again does
+        store.sync_pod(pod.clone());
+        assert!(store.list_pod_ids().is_empty());
+        store.store_pod(pod.clone());
+        assert!(store.get_pod(&id, Duration::from_secs(1)).await.is_ok());
+
+        let mut pods = MyPods::default();
+        pods.add_pod(pod);
+        assert_eq!(pods.len(), 1);
+        assert!(pods.remove_pod(&id));
+        assert!(!pods.remove_pod(&id));
+        assert_eq!(pods.len(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_id_content_address() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let pod = match eval("[createpod p x 1 y 2]", env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+
+        // Fixed length lowercase hex, and stable for the same pod
+        let id = PodBuilder::pod_id(&pod);
+        assert_eq!(id, format!("pod_{}", pod.content_id()));
+        assert_eq!(id.len(), "pod_".len() + 64);
+        assert!(id["pod_".len()..]
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+        assert_eq!(PodBuilder::pod_id(&pod.clone()), id);
+
+        // Signing the same content again gives a different proof but the same id
+        let same_content = match eval("[createpod q x 1 y 2]", env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        assert_eq!(PodBuilder::pod_id(&same_content), id);
+        let store = InMemoryStore::new();
+        store.store_pod(pod.clone());
+        store.store_pod(same_content.clone());
+        assert_eq!(store.list_pod_ids(), vec![id.clone()]);
+
+        let other = match eval("[createpod p x 1 y 3]", env).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        assert_ne!(PodBuilder::pod_id(&other), id);
+
+        // Pods can still be looked up with their legacy id
+        let mut pods = MyPods::default();
+        pods.add_pod(pod.clone());
+        assert_eq!(pods.add_pod(same_content), AddedPod::AlreadyPresent);
+        pods.add_pod(other);
+        let legacy_id = format!("pod_{}", pod.payload.hash_payload().elements[0]);
+        assert!(pods.get_pod(&legacy_id).is_some());
+        assert!(pods.get_pod(&id).is_some());
+        assert!(pods.get_pod("pod_0").is_none());
+        assert!(pods.remove_pod(&legacy_id));
+        assert_eq!(pods.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_dedup() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let pod = match eval("[createpod p x 1]", env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        assert_eq!(
+            pod_store.lock().unwrap().add_pod(pod.clone()),
+            AddedPod::Added
+        );
+        assert_eq!(
+            pod_store.lock().unwrap().add_pod(pod.clone()),
+            AddedPod::AlreadyPresent
+        );
+        assert_eq!(pod_store.lock().unwrap().len(), 1);
+        let id = env.shared.store_pod(pod.clone());
+        assert_eq!(env.shared.store_pod(pod), id);
+        assert_eq!(env.shared.list_pod_ids(), vec![id]);
+
+        // The same entries signed by someone else are another pod
+        let mut other_env = env.clone();
+        other_env.set_identity(
+            "other_user".to_string(),
+            Some(SchnorrSecretKey::from_u64(43)),
+        );
+        match eval("[createpod p x 1]", other_env).await? {
+            Value::PodRef(pod) => {
+                assert_eq!(pod_store.lock().unwrap().add_pod(pod), AddedPod::Added);
+            }
+            _ => return Err(anyhow!("Expected PodRef")),
+        }
+        assert_eq!(pod_store.lock().unwrap().len(), 2);
+
+        // Running a script again doesn't add its pods twice, and queries still find them
+        let script = "[createpod q y 2]";
+        eval_script(script, env.clone()).await?;
+        eval_script(script, env.clone()).await?;
+        assert_eq!(pod_store.lock().unwrap().len(), 3);
+        assert!(matches!(
+            eval("[pod? [y]]", env.clone()).await?,
+            Value::SRef(_)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_pod_by_prefix() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        for script in ["[createpod p x 1]", "[createpod p x 2]"] {
+            match eval(script, env.clone()).await? {
+                Value::PodRef(pod) => {
+                    pod_store.lock().unwrap().add_pod(pod);
+                }
+                _ => return Err(anyhow!("Expected PodRef")),
+            }
+        }
+
+        let pods = pod_store.lock().unwrap();
+        let id = PodBuilder::pod_id(&pods.pods[0]);
+        let other_id = PodBuilder::pod_id(&pods.pods[1]);
+        let prefix_len = (4..id.len()).find(|&i| id[..i] != other_id[..i]).unwrap();
+        assert_eq!(pods.find_pod(&id)?, &pods.pods[0]);
+        assert_eq!(pods.find_pod(&id[..prefix_len])?, &pods.pods[0]);
+        assert!(pods
+            .find_pod("pod_")
+            .unwrap_err()
+            .to_string()
+            .contains("ambiguous"));
+        assert!(pods.find_pod("pod_x").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_import_pod() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let pod = match eval("[createpod p x 1 y \"hello\"]", env).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        let path = std::env::temp_dir().join(format!("pex-pod-{}.json", rand::random::<u64>()));
+        podfile::save_pod(&path, &pod)?;
+        let loaded = podfile::load_pod(&path);
+        std::fs::remove_file(&path)?;
+        let loaded = loaded?;
+        assert_eq!(loaded, pod);
+
+        // A pod that only reached the shared store, eg: through sync or a query, is imported
+        let (other_env, other_pods) = setup_env().await;
+        other_env.shared.store_pod(loaded.clone());
+        let id = other_env.import_pod(loaded.clone())?;
+        assert_eq!(id, PodBuilder::pod_id(&pod));
+        assert_eq!(other_pods.lock().unwrap().get_pod(&id), Some(&pod));
+        assert!(other_env.shared.list_pod_ids().contains(&id));
+
+        // Importing it again doesn't clobber the pod we have
+        assert!(other_env
+            .import_pod(loaded)
+            .unwrap_err()
+            .to_string()
+            .contains("already exists"));
+        assert_eq!(other_pods.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_import_pods() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        for script in ["[createpod p x 1]", "[createpod q y \"hello\" z [vec 1 2]]"] {
+            match eval(script, env.clone()).await? {
+                Value::PodRef(pod) => {
+                    pod_store.lock().unwrap().add_pod(pod);
+                }
+                _ => return Err(anyhow!("Expected PodRef")),
+            }
+        }
+        let pods: Vec<POD> = pod_store.lock().unwrap().pods.values().cloned().collect();
+        let path = std::env::temp_dir().join(format!("pex-pods-{}.json", rand::random::<u64>()));
+        podfile::save_pods(&path, &pods)?;
+        let loaded = podfile::load_pods(&path);
+        std::fs::write(&path, "[{\"version\": 1}]")?;
+        let invalid = podfile::load_pods(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(loaded?, pods);
+        assert!(invalid.unwrap_err().to_string().contains("POD #1"));
+        assert!(podfile::load_pods(&path)
+            .unwrap_err()
+            .to_string()
+            .contains("Could not read"));
+        assert_eq!(podfile::pods_to_json(&[])?, "[]\n");
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_import_invalid_pod() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        let mut pod = match eval("[createpod p x 1]", env.clone()).await? {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        match &mut pod.proof {
+            PODProof::Schnorr(signature) => signature.s = signature.s.wrapping_add(1),
+            proof => return Err(anyhow!("Expected a Schnorr proof, got {:?}", proof)),
+        }
+
+        // The content id still matches, so the file loads but the pod is rejected on import
+        let path = std::env::temp_dir().join(format!("pex-pod-{}.json", rand::random::<u64>()));
+        podfile::save_pod(&path, &pod)?;
+        let loaded = podfile::load_pod(&path);
+        std::fs::remove_file(&path)?;
+        let err = env.import_pod(loaded?).unwrap_err();
+        assert!(err.to_string().contains("failed verification"));
+        assert!(pod_store.lock().unwrap().is_empty());
+        assert!(env.shared.list_pod_ids().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_removal_during_wait() -> Result<()> {
+        let store = Arc::new(InMemoryStore::new());
+        let id = "pod_missing".to_string();
+        let script_id = ScriptId::from_script("[from alice 1]");
+
+        let waiting_pod = {
+            let store = store.clone();
+            let id = id.clone();
+            tokio::spawn(async move {
+                let start = std::time::Instant::now();
+                (
+                    store.get_pod(&id, Duration::from_secs(10)).await,
+                    start.elapsed(),
+                )
+            })
+        };
+        let waiting_value = {
+            let store = store.clone();
+            let script_id = script_id.clone();
+            tokio::spawn(async move {
+                let start = std::time::Instant::now();
+                (
+                    store
+                        .get_value(&script_id, 0, &"bob".to_string(), Duration::from_secs(10))
+                        .await,
+                    start.elapsed(),
+                )
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!store.remove_pod(&id));
+        store.clear_script(&script_id);
+
+        // Both give up on removal instead of waiting for their timeout
+        let (pod, elapsed) = waiting_pod.await?;
+        assert_eq!(pod.unwrap_err(), WaitError::Removed);
+        assert!(elapsed < Duration::from_secs(1));
+        let (value, elapsed) = waiting_value.await?;
+        assert_eq!(value.unwrap_err(), WaitError::Removed);
+        assert!(elapsed < Duration::from_secs(1));
+
+        // Values set after clearing are visible again
+        store.set_value(
+            &script_id,
+            0,
+            Value::Scalar(GoldilocksField(1)),
+            SetOptions::default(),
+        );
+        assert!(store
+            .get_value(&script_id, 0, &"bob".to_string(), Duration::from_secs(1))
+            .await
+            .is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_value_set_while_waiting() -> Result<()> {
+        let store = Arc::new(InMemoryStore::new());
+        let script_id = ScriptId::from_script("[from alice 1]");
+
+        let waiting_value = {
+            let store = store.clone();
+            let script_id = script_id.clone();
+            tokio::spawn(async move {
+                store
+                    .get_value(&script_id, 0, &"bob".to_string(), Duration::from_secs(10))
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        store.set_value(
+            &script_id,
+            0,
+            Value::Scalar(GoldilocksField(7)),
+            SetOptions::default(),
+        );
+        assert!(matches!(
+            waiting_value.await?,
+            Ok(Value::Scalar(s)) if s == GoldilocksField(7)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_remote_timeout() -> Result<()> {
+        let (env, _) = setup_env().await;
+        let mut env = Env {
+            script_id: Some(ScriptId::from_script("[from alice 1]")),
+            ..env
+        };
+
+        env.set_remote_timeout(Duration::from_millis(50));
+        assert_eq!(
+            env.get_remote(0).await.unwrap_err(),
+            WaitError::Timeout(Duration::from_millis(50))
+        );
+
+        // With a longer timeout, a value set after a while is still retrieved
+        env.set_remote_timeout(Duration::from_secs(10));
+        let setter = env.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            setter.set_remote(0, Value::Scalar(GoldilocksField(7)), SetOptions::default());
+        });
+        assert!(matches!(
+            env.get_remote(0).await,
+            Ok(Value::Scalar(s)) if s == GoldilocksField(7)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_timeout_and_removal_errors() -> Result<()> {
+        let store = InMemoryStore::new();
+        let script_id = ScriptId::from_script("[from alice 1]");
+        let timeout = Duration::from_millis(50);
+
+        assert_eq!(
+            store
+                .get_value(&script_id, 0, &"bob".to_string(), timeout)
+                .await
+                .unwrap_err(),
+            WaitError::Timeout(timeout)
+        );
+        assert_eq!(
+            store
+                .get_pod(&"pod_missing".to_string(), timeout)
+                .await
+                .unwrap_err(),
+            WaitError::Timeout(timeout)
+        );
+
+        // A value synced by a peer after the script is cleared stays removed
+        store.clear_script(&script_id);
+        store.sync_value(
+            script_id.clone(),
+            0,
+            SharedValue::new(Value::Scalar(GoldilocksField(1)), SetOptions::default()),
+        );
+        assert_eq!(
+            store
+                .get_value(&script_id, 0, &"bob".to_string(), timeout)
+                .await
+                .unwrap_err(),
+            WaitError::Removed
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_recipients_and_expiry() -> Result<()> {
+        let store = InMemoryStore::new();
+        let script_id = ScriptId::from_script("[from alice :to [bob] 1]");
+        let (bob, carol) = ("bob".to_string(), "carol".to_string());
+        let timeout = Duration::from_secs(10);
+
+        store.set_value(
+            &script_id,
+            0,
+            Value::Scalar(GoldilocksField(1)),
+            SetOptions {
+                recipients: Some(vec![bob.clone()]),
+                ttl: None,
+            },
+        );
+        assert!(store.get_value(&script_id, 0, &bob, timeout).await.is_ok());
+        // Not authorized right away, rather than after the timeout
+        let start = std::time::Instant::now();
+        assert_eq!(
+            store
+                .get_value(&script_id, 0, &carol, timeout)
+                .await
+                .unwrap_err(),
+            WaitError::Unauthorized
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Expired values are missing, whether they are read or purged first
+        let ttl = SetOptions {
+            recipients: None,
+            ttl: Some(Duration::from_millis(50)),
+        };
+        store.set_value(
+            &script_id,
+            1,
+            Value::Scalar(GoldilocksField(1)),
+            ttl.clone(),
+        );
+        store.set_value(&script_id, 2, Value::Scalar(GoldilocksField(2)), ttl);
+        assert!(store.get_value(&script_id, 1, &bob, timeout).await.is_ok());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let timeout = Duration::from_millis(50);
+        assert_eq!(
+            store
+                .get_value(&script_id, 1, &bob, timeout)
+                .await
+                .unwrap_err(),
+            WaitError::Timeout(timeout)
+        );
+        assert_eq!(store.purge_expired(), 1);
+        assert_eq!(
+            store
+                .get_value(&script_id, 2, &bob, timeout)
+                .await
+                .unwrap_err(),
+            WaitError::Timeout(timeout)
+        );
+        // The value without a ttl stays
+        assert_eq!(store.purge_expired(), 0);
+        assert!(store.get_value(&script_id, 0, &bob, timeout).await.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_negative_literals() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        // -7 is p - 7, which is out of range for sums
+        assert!(eval("[+ -7 10]", env.clone()).await.is_err());
+
+        let result = eval("[- 10 -7]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(17)));
+
+        match eval("[createpod p x -7]", env).await? {
+            Value::PodRef(pod) => {
+                // p - 7
+                assert_eq!(
+                    get_self_entry_value(&pod, "x").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(18446744069414584314))
+                );
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_let_scoped_bindings() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[let [[a 1] [b 2]] [+ a b]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(3)));
+
+        // Bindings don't leak out of the let
+        assert!(eval("[let [[a 1]] a]", env.clone()).await.is_ok());
+        assert!(env.get_binding("a").is_none());
+
+        // An inner binding shadows the outer one only for the duration of the let
+        eval("[define a 10]", env.clone()).await?;
+        let result = eval("[let [[a 1]] [define c 5] [+ a c]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(6)));
+        assert!(matches!(env.get_binding("a"), Some(Value::Scalar(s)) if s == GoldilocksField(10)));
+        assert!(env.get_binding("c").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scoped_defines() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        // A define inside createpod stays in the pod body
+        let pod = eval("[createpod p [define a 5] x [+ a 1]]", env.clone()).await?;
+        assert!(env.get_binding("a").is_none());
+        let pod = match pod {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected a pod")),
+        };
+        pod_store.lock().unwrap().add_pod(pod);
+
+        // Defines and keys bound by a query stay in the query
+        let result = eval("[pod? [define b 6] [x b]]", env.clone()).await?;
+        assert!(matches!(result, Value::SRef(_)));
+        assert!(env.get_binding("b").is_none());
+        assert!(env.get_binding("x").is_none());
+
+        // Shadowing only lasts as long as the scope
+        eval("[define a 10]", env.clone()).await?;
+        eval("[createpod q [define a 1] y a]", env.clone()).await?;
+        eval("[pod? [define a 6] [x a]]", env.clone()).await?;
+        assert!(matches!(env.get_binding("a"), Some(Value::Scalar(s)) if s == GoldilocksField(10)));
+
+        // Inner scopes still read outer bindings
+        let result = eval("[createpod r z [+ a 1]]", env.clone()).await?;
+        assert!(matches!(result, Value::PodRef(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_map() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        match eval("[map + [list 1 2] [list 10 20]]", env.clone()).await? {
+            Value::List(values) => {
+                assert_eq!(values.len(), 2);
+                assert!(matches!(values[0], Value::Scalar(s) if s == GoldilocksField(11)));
+                assert!(matches!(values[1], Value::Scalar(s) if s == GoldilocksField(22)));
+            }
+            _ => return Err(anyhow!("Expected List")),
+        }
+
+        assert!(eval("[map + [list 1 2] [list 10]]", env.clone())
+            .await
+            .is_err());
+        assert!(eval("[map + 1 [list 10]]", env.clone()).await.is_err());
+        assert!(eval("[map foo [list 1] [list 10]]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_map_in_createpod() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        if let Value::PodRef(pod) = eval("[createpod p x 3 y 4]", env.clone()).await? {
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+
+        let result = eval(
+            "[createpod q
+                [define [x y] [pod? [x] [y]]]
+                [define [a b] [map * [list x y] [list 2 2]]]
+                double_x a
+                double_y b]",
+            env,
+        )
+        .await?;
+        match result {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "double_x").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(6))
+                );
+                assert_eq!(
+                    get_self_entry_value(&pod, "double_y").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(8))
+                );
+                assert_eq!(
+                    pod.payload
+                        .statements_list()
+                        .iter()
+                        .filter(|(_, s)| matches!(s, Statement::ProductOf(_, _, _)))
+                        .count(),
+                    2
+                );
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRef")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fold() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[fold + 0 [list 1 2 3 4]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(10)));
+
+        let result = eval("[fold max 0 [list 3 9 4]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(9)));
+
+        // Folding an empty list returns the initial value
+        let result = eval("[fold * 1 [list]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(1)));
+
+        assert!(eval("[fold + 0 1]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fold_in_createpod_matches_chain() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        if let Value::PodRef(pod) = eval("[createpod p a 1 b 2 c 3]", env.clone()).await? {
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+
+        let folded = eval(
+            "[createpod q [define [a b c] [pod? [a] [b] [c]]] total [fold + 0 [list a b c]]]",
+            env.clone(),
+        )
+        .await?;
+        let chained = eval(
+            "[createpod q [define [a b c] [pod? [a] [b] [c]]] total [+ [+ [+ 0 a] b] c]]",
+            env,
+        )
+        .await?;
+
+        match (folded, chained) {
+            (Value::PodRef(folded), Value::PodRef(chained)) => {
+                assert_eq!(
+                    get_self_entry_value(&folded, "total").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(6))
+                );
+                assert_eq!(
+                    folded.payload.statements_list(),
+                    chained.payload.statements_list()
+                );
+                Ok(())
+            }
+            _ => Err(anyhow!("Expected PodRefs")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vec_in_createpod() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let result = eval("[vec 1 2 [+ 1 2]]", env.clone()).await?;
+        assert!(matches!(result, Value::Vector(ref v) if v == &vec![
+            GoldilocksField(1),
+            GoldilocksField(2),
+            GoldilocksField(3)
+        ]));
+
+        match eval("[createpod p coords [vec 1 2 3] x 4]", env.clone()).await? {
+            Value::PodRef(pod) => {
+                // Padded to VL with the first element
+                let mut padded = vec![GoldilocksField(1); VL];
+                padded[1] = GoldilocksField(2);
+                padded[2] = GoldilocksField(3);
+                assert_eq!(
+                    get_self_entry_value(&pod, "coords").unwrap(),
+                    ScalarOrVec::Vector(padded)
+                );
+                assert_eq!(
+                    get_self_entry_value(&pod, "x").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(4))
+                );
+                pod_store.lock().unwrap().add_pod(pod);
+            }
+            _ => return Err(anyhow!("Expected PodRef")),
+        }
+
+        // Vectors can be matched exactly in queries
+        assert!(eval("[pod? [coords [vec 1 2 3]]]", env.clone())
+            .await
+            .is_ok());
+        assert!(eval("[pod? [coords [vec 1 2]]]", env.clone())
+            .await
+            .is_err());
+
+        let error = eval(
+            &format!("[createpod p v [vec {}]]", "1 ".repeat(VL + 1)),
+            env,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Vector entry v has {} elements, more than the maximum of {}",
+                VL + 1,
+                VL
+            )
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nth_vector() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[nth 2 [vec 5 6 7]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(7)));
+        let error = eval("[nth 3 [vec 5 6 7]]", env.clone()).await.unwrap_err();
+        assert_eq!(error.to_string(), "nth index 3 out of bounds for length 3");
+
+        // Vector entries can be read while creating the pod
+        match eval("[createpod p v [vec 5 6 7] second [nth 1 v]]", env.clone()).await? {
+            Value::PodRef(pod) => assert_eq!(
+                get_self_entry_value(&pod, "second").unwrap(),
+                ScalarOrVec::Scalar(GoldilocksField(6))
+            ),
+            _ => return Err(anyhow!("Expected PodRef")),
+        }
+        assert!(eval("[createpod p x 1 y [nth 0 x]]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_vec_arithmetic_fails() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[+ [vec 1 2] 3]", env.clone()).await;
+        assert!(result.unwrap_err().to_string().contains("vectors"));
+        assert!(eval("[createpod p x [* [vec 1] 2]]", env.clone())
+            .await
+            .is_err());
+        assert!(eval("[createpod p v [vec 1 2] w [+ v 1]]", env.clone())
+            .await
+            .is_err());
+        assert!(eval("[vec 1 [list 2]]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quote_atom() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[quote x]", env.clone()).await?;
+        assert!(matches!(result, Value::Quoted(Expr::Atom(_, ref a)) if a == "x"));
+        assert!(eval("[eval [quote x]]", env.clone()).await.is_err());
+
+        // eval of a quoted scalar, or of an already evaluated value, is the scalar
+        let result = eval("[eval [quote 5]]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(5)));
+        let result = eval("[eval 5]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(5)));
+
+        assert!(eval("[quote 1 2]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quote_list() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[quote [+ 1 2]]", env.clone()).await?;
+        match result {
+            Value::Quoted(Expr::List(_, exprs)) => {
+                let atoms = exprs
+                    .iter()
+                    .map(|expr| match expr {
+                        Expr::Atom(_, a) => a.as_str(),
+                        Expr::List(_, _) => "",
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(atoms, vec!["+", "1", "2"]);
+            }
+            _ => panic!("Expected quoted list"),
+        }
+
+        let result = eval("[eval [quote [+ 1 2]]]", env).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(3)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_quote_nested() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        // Each eval removes one level of quoting
+        let result = eval("[eval [quote [quote [+ 1 [* 2 3]]]]]", env.clone()).await?;
+        match result {
+            Value::Quoted(Expr::List(_, exprs)) => {
+                assert_eq!(exprs.len(), 3);
+                assert!(matches!(&exprs[2], Expr::List(_, inner) if inner.len() == 3));
+            }
+            _ => panic!("Expected quoted list"),
+        }
+
+        let result = eval("[eval [eval [quote [quote [+ 1 [* 2 3]]]]]]", env).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(7)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_defun() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        eval("[defun make-sum [a b] [+ a b]]", env.clone()).await?;
+        let result = eval("[make-sum 1 2]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(3)));
+        let result = eval("[[lambda [x] [* x 2]] 21]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(42)));
+
+        let error = eval("[make-sum 1 2 3]", env.clone()).await.unwrap_err();
+        assert_eq!(error.to_string(), "make-sum expects 2 arguments, got 3");
+        assert!(eval("[lambda [x x] x]", env.clone()).await.is_err());
+
+        // Named functions can recurse
+        eval(
+            "[defun fact [n] [if [= n 0] 1 [* n [fact [- n 1]]]]]",
+            env.clone(),
+        )
+        .await?;
+        let result = eval("[fact 4]", env).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(24)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_closures_nested_and_shadowing() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        eval(
+            "[define make-adder [lambda [n] [lambda [x] [+ x n]]]]",
+            env.clone(),
+        )
+        .await?;
+        eval("[define add5 [make-adder 5]]", env.clone()).await?;
+        eval("[define add7 [make-adder 7]]", env.clone()).await?;
+        let result = eval("[list [add5 10] [add7 10]]", env.clone()).await?;
+        match result {
+            Value::List(values) => {
+                assert!(matches!(values[0], Value::Scalar(s) if s == GoldilocksField(15)));
+                assert!(matches!(values[1], Value::Scalar(s) if s == GoldilocksField(17)));
+            }
+            _ => panic!("Expected list"),
+        }
+
+        // Parameters shadow outer bindings without changing them
+        eval("[define x 1]", env.clone()).await?;
+        eval("[defun double [x] [* x 2]]", env.clone()).await?;
+        let result = eval("[double 10]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(20)));
+        let result = eval("[+ x 0]", env.clone()).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(1)));
+
+        // Bindings are captured where the function is defined
+        eval("[define y 3]", env.clone()).await?;
+        eval("[defun get-y [] y]", env.clone()).await?;
+        eval("[define y 4]", env.clone()).await?;
+        let result = eval("[get-y]", env).await?;
+        assert!(matches!(result, Value::Scalar(s) if s == GoldilocksField(3)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_defun_in_createpod() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        eval("[defun make-sum [a b] [+ a b]]", env.clone()).await?;
+        let result = eval(
+            "[createpod receipt amount [make-sum 40 2] total [make-sum amount 8]]",
+            env,
+        )
+        .await?;
+
+        match result {
+            Value::PodRef(pod) => {
+                assert_eq!(
+                    get_self_entry_value(&pod, "amount").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(42))
+                );
+                assert_eq!(
+                    get_self_entry_value(&pod, "total").unwrap(),
+                    ScalarOrVec::Scalar(GoldilocksField(50))
+                );
+                assert!(pod.verify::<L, M, N, NS, VL>()?);
+            }
+            _ => panic!("Expected PodRef"),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_contains() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval("[contains [vec 1 2 3] 2]", env.clone()).await?;
+        assert!(matches!(result, Value::Bool(true)));
+        let result = eval("[!contains [vec 1 2 3] 2]", env.clone()).await?;
+        assert!(matches!(result, Value::Bool(false)));
+        assert!(eval("[contains 1 2]", env).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_contains_in_createpod() -> Result<()> {
+        let (env, _) = setup_env().await;
+
+        let result = eval(
+            "[createpod p signers [vec 1 2 3] signer 2 [contains signers signer] [!contains signers 5]]",
+            env.clone(),
+        )
+        .await?;
+        match result {
+            Value::PodRef(pod) => {
+                assert!(pod
+                    .payload
+                    .statements_list()
+                    .iter()
+                    .any(|(_, s)| matches!(s, Statement::Contains(v, x) if v.1 == "signers" && x.1 == "signer")));
+                assert!(pod
+                    .payload
+                    .statements_list()
+                    .iter()
+                    .any(|(_, s)| matches!(s, Statement::NotContains(v, _) if v.1 == "signers")));
+            }
+            _ => return Err(anyhow!("Expected PodRef")),
+        }
+
+        assert!(eval(
+            "[createpod p signers [vec 1 2 3] [contains signers 5]]",
+            env.clone()
+        )
+        .await
+        .is_err());
+        assert!(eval(
+            "[createpod p signers [vec 1 2 3] [!contains signers 1]]",
+            env
+        )
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_contains_query() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+
+        let mut pod_ids = Vec::new();
+        for script in [
+            "[createpod a signers [vec 1 2 3]]",
+            "[createpod b signers [vec 4 5 6]]",
+        ] {
+            if let Value::PodRef(pod) = eval(script, env.clone()).await? {
+                pod_ids.push(PodBuilder::pod_id(&pod));
+                pod_store.lock().unwrap().add_pod(pod);
+            }
+        }
+
+        for (query, pod_id) in [
+            ("[pod? [signers] [contains signers 5]]", &pod_ids[1]),
+            ("[pod? [signers] [contains signers 1]]", &pod_ids[0]),
+            ("[pod? [signers] [!contains signers 1]]", &pod_ids[1]),
+        ] {
+            match eval(query, env.clone()).await? {
+                Value::SRef(SRef(ORef::P(id), _)) => assert_eq!(&id, pod_id),
+                _ => return Err(anyhow!("Expected SRef")),
+            }
+        }
+        assert!(eval("[pod? [signers] [contains signers 7]]", env.clone())
+            .await
+            .is_err());
+
+        // Pods proving the membership with a statement match too
+        if let Value::PodRef(pod) = eval(
+            "[createpod c signers [vec 7 8 9] [contains signers 7]]",
+            env.clone(),
+        )
+        .await?
+        {
+            pod_store.lock().unwrap().add_pod(pod);
+        }
+        match eval("[pod? [signers] [contains signers 7]]", env).await? {
+            Value::SRef(_) => Ok(()),
+            _ => Err(anyhow!("Expected SRef")),
+        }
+    }
+
+    #[test]
+    fn test_membership_entry_origin() {
+        let own = Origin::auto(SELF_ORIGIN_NAME.to_string(), GadgetID::ORACLE);
+        let other = Origin::new(GoldilocksField(2), "other".to_string(), GadgetID::SCHNORR16);
+        let value_of = |origin: &Origin, key: &str, value: ScalarOrVec| {
+            (
+                format!("VALUEOF:{}.{}", origin.origin_name, key),
+                Statement::ValueOf(AnchoredKey(origin.clone(), key.to_string()), value),
+            )
+        };
+        let scalar = |value| ScalarOrVec::Scalar(GoldilocksField(value));
+        let pod = |statements: Vec<(String, Statement)>| POD {
+            payload: PODPayload::new(&statements.into_iter().collect()),
+            proof: PODProof::Oracle(SchnorrSignature { s: 0, e: 0 }),
+            proof_type: GadgetID::ORACLE,
+        };
+        let signers = value_of(
+            &own,
+            "signers",
+            ScalarOrVec::Vector(vec![GoldilocksField(1), GoldilocksField(2)]),
+        );
+        let matches = |pod: &POD, membership_type| {
+            matches_membership_entry(
+                pod,
+                membership_type,
+                "signers",
+                &OperandConstraint::EntryRef("signer".to_string()),
+            )
+        };
+
+        // Both pods have another origin's signer 2, which is in the pod's own signers
+        let with_own_signer = pod(vec![
+            signers.clone(),
+            value_of(&other, "signer", scalar(2)),
+            value_of(&own, "signer", scalar(5)),
+        ]);
+        assert!(!matches(&with_own_signer, MembershipType::Contains));
+        assert!(matches(&with_own_signer, MembershipType::NotContains));
+
+        let without_own_signer = pod(vec![signers, value_of(&other, "signer", scalar(2))]);
+        assert!(!matches(&without_own_signer, MembershipType::Contains));
+        assert!(!matches(&without_own_signer, MembershipType::NotContains));
+    }
+
+    #[tokio::test]
+    async fn test_pod_query_signer() -> Result<()> {
+        let (mut env, pod_store) = setup_env().await;
+        let signer = SchnorrSigner::new();
+        let mut pod_ids = Vec::new();
+        for (sk, script) in [(42, "[createpod a x 1]"), (7, "[createpod b x 2]")] {
+            env.set_identity(
+                "test_user".to_string(),
+                Some(SchnorrSecretKey::from_u64(sk)),
+            );
+            if let Value::PodRef(pod) = eval(script, env.clone()).await? {
+                pod_ids.push(PodBuilder::pod_id(&pod));
+                pod_store.lock().unwrap().add_pod(pod);
+            }
+        }
+        let pk = |sk| {
+            signer
+                .keygen(&SchnorrSecretKey::from_u64(sk))
+                .pk
+                .to_canonical_u64()
+        };
+
+        for (query, pod_id) in [
+            (format!("[pod? [x] [signer {}]]", pk(42)), &pod_ids[0]),
+            (format!("[pod? [x] [signer {}]]", pk(7)), &pod_ids[1]),
+            (
+                format!("[let [[key {}]] [pod? [x] [signer key]]]", pk(7)),
+                &pod_ids[1],
+            ),
+        ] {
+            match eval(&query, env.clone()).await? {
+                Value::SRef(SRef(ORef::P(id), _)) => assert_eq!(&id, pod_id),
+                _ => return Err(anyhow!("Expected SRef")),
+            }
+        }
+        let err = eval(&format!("[pod? [x] [signer {}]]", pk(1)), env.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<PexError>(),
+            Some(PexError::NoMatchingPod { .. })
+        ));
+
+        // The bound signer is the pod's signer entry
+        match eval("[pod? [x 2] [signer-of s]]", env).await? {
+            Value::List(values) => match &values[1] {
+                Value::SRef(SRef(ORef::P(id), statement)) => {
+                    assert_eq!(id, &pod_ids[1]);
+                    assert_eq!(statement, &StatementId::value_of(SIGNER_PK_KEY));
+                }
+                _ => return Err(anyhow!("Expected SRef")),
+            },
+            _ => return Err(anyhow!("Expected List")),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pod_query_same_signer() -> Result<()> {
+        let (env, pod_store) = setup_env().await;
+        for script in ["[createpod a x 1]", "[createpod b y 2]"] {
+            if let Value::PodRef(pod) = eval(script, env.clone()).await? {
+                pod_store.lock().unwrap().add_pod(pod);
+            }
+        }
+
+        let pod = match eval(
+            "[createpod c
+                [define [x s1] [pod? [x] [signer-of s1]]]
+                [define [y s2] [pod? [y] [signer-of s2]]]
+                [= s1 s2]
+                total [+ x y]]",
+            env.clone(),
+        )
+        .await?
+        {
+            Value::PodRef(pod) => pod,
+            _ => return Err(anyhow!("Expected PodRef")),
+        };
+        assert_eq!(
+            get_self_entry_value(&pod, "total"),
+            Some(ScalarOrVec::Scalar(GoldilocksField(3)))
+        );
+        assert!(pod
+            .payload
+            .statements_list()
+            .iter()
+            .any(|(_, statement)| matches!(statement, Statement::Equal(_, _))));
+
+        // The oracle pod isn't signed by the input pods' signer, even though it copies their entry
+        pod_store.lock().unwrap().pods = IndexMap::from([(PodBuilder::pod_id(&pod), pod)]);
+        let pk = SchnorrSigner::new()
+            .keygen(&SchnorrSecretKey::from_u64(42))
+            .pk
+            .to_canonical_u64();
+        assert!(eval(&format!("[pod? [total] [signer {}]]", pk), env)
+            .await
+            .is_err());
+        Ok(())
+    }
 }
@@ -1,22 +1,19 @@
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use colored::*;
-use constants::*;
 use eyre::{eyre, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use pex::{
+    keystore, podfile,
     repl::{
-        display::print_pod_details,
+        display::{print_plan, print_pod_details},
         reedline::{LispCompleter, LispHighlighter, LispValidator},
     },
     store::iroh::IrohStore,
 };
-use pex::{Env, MyPods, Value};
-use pod2::{
-    pod::gadget::PlonkyButNotPlonkyGadget,
-    recursion::traits::IntroducerCircuitTrait,
-    signature::schnorr::{SchnorrSecretKey, SchnorrSigner},
-};
+use pex::{Env, MyPods, ParseError, PexError, PodBuilder, ProverContext, SharedStore, Value};
+use pod2::signature::schnorr::{SchnorrSecretKey, SchnorrSigner};
 use reedline::{
     default_emacs_keybindings, ColumnarMenu, DefaultPrompt, DefaultPromptSegment, Emacs, KeyCode,
     KeyModifiers, MenuBuilder, Reedline, ReedlineEvent, ReedlineMenu, Signal,
@@ -38,6 +35,75 @@ fn create_spinner(msg: &str) -> ProgressBar {
     pb
 }
 
+/// Runs a script file in the env, printing the value of each form. Returns whether it ran
+/// without errors.
+async fn run_file(path: &str, env: &Env) -> bool {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            println!("{}: could not read {}: {}", "Error".red().bold(), path, e);
+            return false;
+        }
+    };
+    let spinner = create_spinner(&format!("Running {}...", path));
+    let result = pex::eval_script(&source, env.clone()).await;
+    spinner.finish_and_clear();
+    match result {
+        Ok(values) => {
+            for value in values {
+                match value {
+                    Value::PodRef(pod) => {
+                        println!("\n{}", "Created new POD:".green());
+                        print_pod_details(&pod, &env.pod_store.lock().unwrap());
+                    }
+                    value => println!("=> {:?}", value),
+                }
+            }
+            true
+        }
+        Err(e) => {
+            println!("{}: {}: {}", "Error".red().bold(), path, e);
+            false
+        }
+    }
+}
+
+fn print_pex_error(error: &PexError) {
+    match error {
+        PexError::UnknownIdentifier { name, pos } => println!(
+            "{}: {} is not defined (at {})",
+            "Error".red().bold(),
+            name.yellow(),
+            pos
+        ),
+        PexError::NoMatchingPod { constraints } => println!(
+            "{}: none of your PODs satisfies the {} constraints of the query",
+            "No matching POD".red().bold(),
+            constraints
+        ),
+        PexError::AssertionFailed {
+            assert,
+            left,
+            right,
+        } => println!(
+            "{}: {} doesn't hold (left is {}, right is {})",
+            "Assertion failed".red().bold(),
+            assert.yellow(),
+            left.to_string().bright_blue(),
+            right.to_string().bright_blue()
+        ),
+        PexError::RemoteUnavailable { aid, error } => println!(
+            "{}: couldn't get value {} from the other party: {}",
+            "Remote error".red().bold(),
+            aid.to_string().yellow(),
+            error
+        ),
+        PexError::PodFinalize(e) => {
+            println!("{}: {}", "Couldn't create the POD".red().bold(), e)
+        }
+    }
+}
+
 fn get_username_from_key(sk: &SchnorrSecretKey) -> String {
     let cosmic_prefixes = [
         "stellar",
@@ -86,8 +152,8 @@ fn get_username_from_key(sk: &SchnorrSecretKey) -> String {
     ];
 
     // Use the secret key to deterministically choose prefix and suffix
-    let prefix_index = sk.sk % cosmic_prefixes.len() as u64;
-    let suffix_index = (sk.sk >> 8) % cosmic_suffixes.len() as u64;
+    let prefix_index = sk.scalar() % cosmic_prefixes.len() as u64;
+    let suffix_index = (sk.scalar() >> 8) % cosmic_suffixes.len() as u64;
 
     format!(
         "{}_{}",
@@ -97,11 +163,27 @@ fn get_username_from_key(sk: &SchnorrSecretKey) -> String {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let random_sk = rand::thread_rng().gen::<u64>() % 10000;
-    let schnorr_key = SchnorrSecretKey { sk: random_sk };
+    // The key file comes from --key-file or PEX_KEY, otherwise we generate a fresh key
+    let args: Vec<String> = std::env::args().collect();
+    let key_file = args
+        .iter()
+        .position(|arg| arg == "--key-file")
+        .map(|i| {
+            args.get(i + 1)
+                .cloned()
+                .ok_or_else(|| eyre!("--key-file requires a path"))
+        })
+        .transpose()?
+        .or_else(|| std::env::var("PEX_KEY").ok());
+    let passphrase = std::env::var("PEX_KEY_PASSPHRASE").ok();
+    let mut schnorr_key = match &key_file {
+        Some(path) => keystore::load_key(Path::new(path), passphrase.as_deref())
+            .map_err(|e| eyre!("Could not load key: {}", e))?,
+        None => SchnorrSecretKey::from_seed(&rand::thread_rng().gen()),
+    };
     let signer = SchnorrSigner::new();
     let public_key = signer.keygen(&schnorr_key);
-    let username = get_username_from_key(&schnorr_key);
+    let mut username = get_username_from_key(&schnorr_key);
     let secret_key = iroh::net::key::SecretKey::generate();
     let shared = Arc::new(IrohStore::new(secret_key));
     let task_bound_shared = shared.clone();
@@ -130,42 +212,87 @@ async fn main() -> Result<()> {
     }
 
     let pod_store = Arc::new(Mutex::new(MyPods::default()));
-    let spinner = create_spinner("Generating prover parameters...");
-
-    let pod1_circuit_data =
-        pod2::recursion::traits_examples::ExampleIntroducer::circuit_data().unwrap();
-    let pod1_verifier_data = pod1_circuit_data.verifier_data();
-    let circuit_data =
-        PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::circuit_data(pod1_verifier_data).unwrap();
-    let prover_params = PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::build_prover_params(
-        pod1_circuit_data,
-        circuit_data,
-    )
-    .unwrap();
-    spinner.finish_and_clear();
-    println!("⚙️ Prover parameters generated");
-    let env = Env::new(
+    // Prover params are only needed by createpod!, build them in the background meanwhile
+    let prover = Arc::new(ProverContext::new());
+    let task_bound_prover = prover.clone();
+    tokio::task::spawn(async move {
+        match task_bound_prover.params().await {
+            Ok(_) => println!("⚙️ Prover parameters generated"),
+            Err(e) => println!("Failed to generate prover parameters: {}", e),
+        }
+    });
+    let mut env = Env::new(
         username.clone(),
         shared.clone(),
         pod_store.clone(),
         Some(schnorr_key),
         None,
-        Some(Arc::new(Mutex::new(prover_params))),
+        Some(prover),
     );
 
+    // `pex run file` runs a script and exits instead of starting the REPL
+    if args.get(1).map(String::as_str) == Some("run") {
+        let path = args.get(2).ok_or_else(|| eyre!("run requires a path"))?;
+        return if run_file(path, &env).await {
+            Ok(())
+        } else {
+            Err(eyre!("{} failed", path))
+        };
+    }
+
     let commands = vec![
         "createpod".into(),
+        "createpod!".into(),
         "define".into(),
+        "let".into(),
+        "lambda".into(),
+        "defun".into(),
+        "defschema".into(),
         "pod?".into(),
+        "pod?*".into(),
+        "exclude-inputs".into(),
+        "signer".into(),
+        "signer-of".into(),
+        "keep".into(),
+        "redact".into(),
         "list".into(),
         "car".into(),
         "cdr".into(),
         "cons".into(),
+        "length".into(),
+        "nth".into(),
+        "explain".into(),
+        "map".into(),
+        "fold".into(),
+        "quote".into(),
+        "eval".into(),
         "+".into(),
         "*".into(),
         "max".into(),
+        "min".into(),
+        "-".into(),
+        "/".into(),
+        "and".into(),
+        "or".into(),
+        "not".into(),
+        "cmp".into(),
+        "contains".into(),
+        "!contains".into(),
+        "if".into(),
         "exit".into(),
         "list-pods".into(),
+        "run".into(),
+        "peers".into(),
+        "connect".into(),
+        "forget-pod".into(),
+        "show-pod".into(),
+        "diff-pods".into(),
+        "export-pod".into(),
+        "import-pod".into(),
+        "export-pods".into(),
+        "import-pods".into(),
+        "export-key".into(),
+        "import-key".into(),
     ];
 
     let completer = Box::new(LispCompleter::new(commands.clone()));
@@ -205,6 +332,18 @@ async fn main() -> Result<()> {
     println!("Commands:");
     println!("  exit          - Exit the REPL");
     println!("  list-pods     - List all stored PODs");
+    println!("  peers         - Show the sync status and known peers");
+    println!("  connect addr  - Sync with a peer, given its node ticket or id");
+    println!("  forget-pod id - Remove a POD from the local and shared stores");
+    println!("  show-pod id   - Show a POD, given its id or a prefix of it");
+    println!("  diff-pods id1 id2  - Show how two PODs differ");
+    println!("  export-pod id path - Save a POD to a JSON file");
+    println!("  import-pod path    - Verify and add a POD from a JSON file");
+    println!("  export-pods [path] - Save all your PODs to a JSON file, or print them");
+    println!("  import-pods path   - Verify and add the PODs of a JSON file");
+    println!("  export-key path [passphrase] - Save your secret key to a file");
+    println!("  import-key path [passphrase] - Load a secret key from a file");
+    println!("  run path      - Run the forms of a script file one after the other");
     println!("\nExamples:");
     println!("  [+ 1 2]");
     println!("  [createpod test x 42 y [+ 2 10]]");
@@ -220,12 +359,209 @@ async fn main() -> Result<()> {
                     "list-pods" => {
                         let store = env.pod_store.lock().unwrap();
                         println!("\nStored PODs:");
-                        for (i, pod) in store.pods.iter().enumerate() {
-                            println!("POD #{}", i + 1);
+                        for (i, (id, pod)) in store.pods.iter().enumerate() {
+                            println!("POD #{} ({})", i + 1, id);
                             print_pod_details(pod, &store);
                         }
                         continue;
                     }
+                    "peers" => {
+                        println!("\nStatus: {}", shared.status());
+                        match shared.node_ticket().await {
+                            Ok(ticket) => println!("This node: {}", ticket.yellow()),
+                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                        }
+                        for peer in shared.list_peers().await {
+                            let latency = peer
+                                .latency
+                                .map_or("-".to_string(), |l| format!("{}ms", l.as_millis()));
+                            println!(
+                                "{} {} ({}, {}){}",
+                                "Peer".cyan(),
+                                peer.node_id,
+                                peer.connection,
+                                latency,
+                                if peer.syncing { ", syncing" } else { "" }
+                            );
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("connect") => {
+                        let addr = input["connect".len()..].trim().to_string();
+                        if addr.is_empty() {
+                            println!("{}: connect requires a node address", "Error".red().bold());
+                            continue;
+                        }
+                        match shared.add_peer(addr).await {
+                            Ok(()) => println!("Connecting to peer"),
+                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("export-key") || input.starts_with("import-key") => {
+                        let args: Vec<&str> = input.split_whitespace().collect();
+                        let (path, passphrase) = match args.as_slice() {
+                            [_, path] => (Path::new(path), None),
+                            [_, path, passphrase] => (Path::new(path), Some(*passphrase)),
+                            _ => {
+                                println!(
+                                    "{}: usage: {} path [passphrase]",
+                                    "Error".red().bold(),
+                                    args[0]
+                                );
+                                continue;
+                            }
+                        };
+                        if args[0] == "export-key" {
+                            match keystore::save_key(path, &schnorr_key, passphrase) {
+                                Ok(()) => println!("Exported key to {}", path.display()),
+                                Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                            }
+                        } else {
+                            match keystore::load_key(path, passphrase) {
+                                Ok(sk) => {
+                                    schnorr_key = sk;
+                                    username = get_username_from_key(&schnorr_key);
+                                    env.set_identity(username.clone(), Some(schnorr_key));
+                                    println!("Imported key, welcome {}!", username.cyan().bold());
+                                }
+                                Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                            }
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("forget-pod") => {
+                        let id = input["forget-pod".len()..].trim().to_string();
+                        if id.is_empty() {
+                            println!("{}: forget-pod requires a POD id", "Error".red().bold());
+                            continue;
+                        }
+                        let removed_local = env.pod_store.lock().unwrap().remove_pod(&id);
+                        let removed_shared = shared.remove_pod(&id);
+                        if removed_local || removed_shared {
+                            println!("Forgot POD {}", id.cyan());
+                        } else {
+                            println!("{}: no POD with id {}", "Error".red().bold(), id);
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("show-pod") => {
+                        let id = input["show-pod".len()..].trim();
+                        if id.is_empty() {
+                            println!("{}: show-pod requires a POD id", "Error".red().bold());
+                            continue;
+                        }
+                        let store = env.pod_store.lock().unwrap();
+                        match store.find_pod(id) {
+                            Ok(pod) => {
+                                println!("\nPOD {}", PodBuilder::pod_id(pod).cyan());
+                                print_pod_details(pod, &store);
+                            }
+                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("diff-pods") => {
+                        let args: Vec<&str> = input.split_whitespace().collect();
+                        let [_, id1, id2] = args.as_slice() else {
+                            println!("{}: usage: diff-pods id1 id2", "Error".red().bold());
+                            continue;
+                        };
+                        let store = env.pod_store.lock().unwrap();
+                        match (store.find_pod(id1), store.find_pod(id2)) {
+                            (Ok(pod1), Ok(pod2)) => {
+                                let diff = pod1.diff(pod2);
+                                if diff.is_empty() {
+                                    println!("PODs have the same statements");
+                                } else {
+                                    println!("{}", diff);
+                                }
+                            }
+                            (Err(e), _) | (_, Err(e)) => {
+                                println!("{}: {}", "Error".red().bold(), e)
+                            }
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("export-pods") => {
+                        let path = input["export-pods".len()..].trim();
+                        let pods: Vec<_> = env
+                            .pod_store
+                            .lock()
+                            .unwrap()
+                            .pods
+                            .values()
+                            .cloned()
+                            .collect();
+                        let result = if path.is_empty() {
+                            podfile::pods_to_json(&pods).map(|json| print!("{}", json))
+                        } else {
+                            podfile::save_pods(Path::new(path), &pods)
+                                .map(|()| println!("Exported {} PODs to {}", pods.len(), path))
+                        };
+                        if let Err(e) = result {
+                            println!("{}: {}", "Error".red().bold(), e);
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("export-pod") => {
+                        let args: Vec<&str> = input.split_whitespace().collect();
+                        let [_, id, path] = args.as_slice() else {
+                            println!("{}: usage: export-pod id path", "Error".red().bold());
+                            continue;
+                        };
+                        let path = Path::new(path);
+                        let store = env.pod_store.lock().unwrap();
+                        match store
+                            .find_pod(id)
+                            .and_then(|pod| podfile::save_pod(path, pod))
+                        {
+                            Ok(()) => println!("Exported POD to {}", path.display()),
+                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("import-pods") => {
+                        let path = input["import-pods".len()..].trim();
+                        if path.is_empty() {
+                            println!("{}: import-pods requires a path", "Error".red().bold());
+                            continue;
+                        }
+                        match podfile::load_pods(Path::new(path)) {
+                            Ok(pods) => {
+                                // Pods that fail verification or that we already have are
+                                // skipped, the others are still imported
+                                let total = pods.len();
+                                let mut imported = 0;
+                                for pod in pods {
+                                    match env.import_pod(pod) {
+                                        Ok(_) => imported += 1,
+                                        Err(e) => println!("{}: {}", "Skipped".yellow().bold(), e),
+                                    }
+                                }
+                                println!("Imported {} of {} PODs", imported, total);
+                            }
+                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("import-pod") => {
+                        let path = input["import-pod".len()..].trim();
+                        if path.is_empty() {
+                            println!("{}: import-pod requires a path", "Error".red().bold());
+                            continue;
+                        }
+                        match podfile::load_pod(Path::new(path)).and_then(|pod| env.import_pod(pod))
+                        {
+                            Ok(id) => println!("Imported POD {}", id.cyan()),
+                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                        }
+                        continue;
+                    }
+                    _ if input.starts_with("run ") => {
+                        run_file(input["run".len()..].trim(), &env).await;
+                        continue;
+                    }
                     "" => continue,
                     _ => {
                         let spinner = create_spinner("Generating ZKP...");
@@ -245,19 +581,22 @@ async fn main() -> Result<()> {
                                         println!("\n{}", "Participated in POD creation".green());
                                     };
                                 }
-                                value if input.trim().starts_with("[pod?") => {
+                                value
+                                    if input.trim().starts_with("[pod?")
+                                        && !input.trim().starts_with("[pod?*") =>
+                                {
                                     println!("\n{}", "Matching POD:".green());
                                     let store = env.pod_store.lock().unwrap();
 
                                     // Function to extract statement refs
                                     let get_statement_refs = |val: &Value| -> Vec<String> {
                                         match val {
-                                            Value::SRef(sref) => vec![sref.1.clone()],
+                                            Value::SRef(sref) => vec![sref.1.to_string()],
                                             Value::List(values) => values
                                                 .iter()
                                                 .filter_map(|v| {
                                                     if let Value::SRef(sref) = v {
-                                                        Some(sref.1.clone())
+                                                        Some(sref.1.to_string())
                                                     } else {
                                                         None
                                                     }
@@ -270,10 +609,10 @@ async fn main() -> Result<()> {
                                     let statement_refs = get_statement_refs(&value);
 
                                     // Find matching pod
-                                    if let Some(pod) = store.pods.iter().find(|pod| {
+                                    if let Some(pod) = store.pods.values().find(|pod| {
                                         statement_refs.iter().any(|ref_str| {
                                             pod.payload
-                                                .statements_list
+                                                .statements_list()
                                                 .iter()
                                                 .any(|(id, _)| id == ref_str)
                                         })
@@ -281,9 +620,24 @@ async fn main() -> Result<()> {
                                         print_pod_details(pod, &store);
                                     }
                                 }
+                                Value::Bool(b) => println!("=> {}", b),
+                                Value::Plan(plan) => print_plan(&plan),
                                 _ => println!("=> {:?}", result),
                             },
-                            Err(e) => println!("{}: {}", "Error".red().bold(), e),
+                            Err(e) => match e.downcast_ref::<ParseError>() {
+                                Some(parse_error) => println!(
+                                    "{}: {} at line {}, column {} ({})",
+                                    "Parse error".red().bold(),
+                                    parse_error.message,
+                                    parse_error.line,
+                                    parse_error.col,
+                                    parse_error.token.yellow()
+                                ),
+                                None => match e.downcast_ref::<PexError>() {
+                                    Some(pex_error) => print_pex_error(pex_error),
+                                    None => println!("{}: {}", "Error".red().bold(), e),
+                                },
+                            },
                         }
                     }
                 }
@@ -1,6 +1,6 @@
-pub const PREDICATE_VALUEOF: &str = "VALUEOF";
 pub const STATEMENT_PREFIX_CONSTANT: &str = "constant_";
 pub const STATEMENT_PREFIX_RESULT: &str = "result_";
 pub const STATEMENT_PREFIX_OTHER: &str = "statement_";
+pub const STATEMENT_PREFIX_KEPT: &str = "kept_";
 pub const POD_PREFIX: &str = "pod_";
 pub const SELF_ORIGIN_NAME: &str = "_SELF";
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use pod2::pod::POD;
+
+// POD files hold a single POD in the pod2 JSON wire format (see pod2::pod::json), so pods can be
+// handed to other people out of band. Loading only checks the format and the content ID, the
+// proof is checked when the pod is imported, see Env::import_pod. Files of several pods hold a
+// JSON array of them, in the same format.
+
+pub fn save_pod(path: &Path, pod: &POD) -> Result<()> {
+    std::fs::write(path, pod.to_json_v1()? + "\n")
+        .map_err(|e| anyhow!("Could not write POD file {}: {}", path.display(), e))
+}
+
+pub fn load_pod(path: &Path) -> Result<POD> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read POD file {}: {}", path.display(), e))?;
+    POD::from_json_v1(&contents).map_err(|e| anyhow!("Invalid POD file {}: {}", path.display(), e))
+}
+
+pub fn pods_to_json(pods: &[POD]) -> Result<String> {
+    let pods = pods
+        .iter()
+        .map(|pod| {
+            Ok(serde_json::from_str::<serde_json::Value>(
+                &pod.to_json_v1()?,
+            )?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(serde_json::to_string_pretty(&pods)? + "\n")
+}
+
+pub fn save_pods(path: &Path, pods: &[POD]) -> Result<()> {
+    std::fs::write(path, pods_to_json(pods)?)
+        .map_err(|e| anyhow!("Could not write PODs file {}: {}", path.display(), e))
+}
+
+pub fn load_pods(path: &Path) -> Result<Vec<POD>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read PODs file {}: {}", path.display(), e))?;
+    let invalid =
+        |e: &dyn std::fmt::Display| anyhow!("Invalid PODs file {}: {}", path.display(), e);
+    serde_json::from_str::<Vec<serde_json::Value>>(&contents)
+        .map_err(|e| invalid(&e))?
+        .iter()
+        .enumerate()
+        .map(|(i, pod)| {
+            POD::from_json_v1(&pod.to_string())
+                .map_err(|e| invalid(&format!("POD #{}: {}", i + 1, e)))
+        })
+        .collect()
+}
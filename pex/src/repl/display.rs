@@ -2,18 +2,23 @@ use std::collections::HashMap;
 
 use colored::Colorize;
 use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
-use pod2::pod::{statement::AnchoredKey, Statement, POD};
+use pod2::pod::{statement::AnchoredKey, value::ScalarOrVec, OpCmd, Statement, POD};
 
-use crate::{MyPods, PodBuilder};
+use crate::{FinalizePlan, MyPods, PodBuilder};
 
 pub fn get_pod_info(pod: &POD) -> HashMap<String, Vec<String>> {
     let mut origin_statements: HashMap<String, Vec<String>> = HashMap::new();
 
-    for (statement_id, statement) in pod.payload.statements_map.iter() {
+    for (statement_id, statement) in pod.payload.statements_map().iter() {
         let refs = match statement {
             Statement::SumOf(_, op1, op2)
             | Statement::ProductOf(_, op1, op2)
             | Statement::MaxOf(_, op1, op2)
+            | Statement::SubOf(_, op1, op2)
+            | Statement::DivOf(_, op1, op2)
+            | Statement::MinOf(_, op1, op2)
+            | Statement::QuotientOf(_, op1, op2)
+            | Statement::RemainderOf(_, op1, op2)
             | Statement::Equal(op1, op2) => vec![op1, op2]
                 .into_iter()
                 .filter(|r| !r.0.is_self())
@@ -51,13 +56,26 @@ fn format_ref(reference: &AnchoredKey) -> String {
 struct StatementFormatter<'a> {
     indent: &'a str,
     statement_id: &'a str,
+    pod_store: &'a MyPods,
 }
 
 impl<'a> StatementFormatter<'a> {
-    fn new(indent: &'a str, statement_id: &'a str) -> Self {
+    fn new(indent: &'a str, statement_id: &'a str, pod_store: &'a MyPods) -> Self {
         Self {
             indent,
             statement_id,
+            pod_store,
+        }
+    }
+
+    /// Values holding the hash of a string we know are shown as that string
+    fn format_value(&self, value: &ScalarOrVec) -> String {
+        match value {
+            ScalarOrVec::Scalar(s) => match self.pod_store.string_preimage(*s) {
+                Some(string) => format!("{:?}", string),
+                None => format!("{:?}", value),
+            },
+            _ => format!("{:?}", value),
         }
     }
 
@@ -103,6 +121,14 @@ impl<'a> StatementFormatter<'a> {
 
             Statement::ProductOf(result, op1, op2) => self.ternary_op(result, op1, "×", op2),
 
+            Statement::SubOf(result, op1, op2) => self.ternary_op(result, op1, "-", op2),
+
+            Statement::DivOf(result, op1, op2) => self.ternary_op(result, op1, "÷", op2),
+
+            Statement::QuotientOf(result, op1, op2) => self.ternary_op(result, op1, "div", op2),
+
+            Statement::RemainderOf(result, op1, op2) => self.ternary_op(result, op1, "mod", op2),
+
             Statement::MaxOf(result, op1, op2) => format!(
                 "{} max({}, {}) → {}",
                 self.prefix(),
@@ -111,16 +137,41 @@ impl<'a> StatementFormatter<'a> {
                 format_ref(result).bright_green()
             ),
 
+            Statement::MinOf(result, op1, op2) => format!(
+                "{} min({}, {}) → {}",
+                self.prefix(),
+                format_ref(op1).yellow(),
+                format_ref(op2).yellow(),
+                format_ref(result).bright_green()
+            ),
+
             Statement::Equal(op1, op2) => self.binary_op(op1, "=", op2),
             Statement::Gt(op1, op2) => self.binary_op(op1, ">", op2),
             Statement::Lt(op1, op2) => self.binary_op(op1, "<", op2),
             Statement::NotEqual(op1, op2) => self.binary_op(op1, "!=", op2),
+            Statement::Contains(vector, value) => self.binary_op(value, "∈", vector),
+            Statement::NotContains(vector, value) => self.binary_op(value, "∉", vector),
+            Statement::ElementAt(vector, index, value) => format!(
+                "{} {}[{}] = {}",
+                self.prefix(),
+                format_ref(vector).yellow(),
+                index,
+                self.format_value(&ScalarOrVec::Scalar(*value))
+                    .bright_blue()
+            ),
+            Statement::InRange(value, lo, hi) => format!(
+                "{} {} ≤ {} ≤ {}",
+                self.prefix(),
+                format_ref(lo).yellow(),
+                format_ref(value).yellow(),
+                format_ref(hi).yellow()
+            ),
 
             Statement::ValueOf(key, value) => format!(
                 "{} {} = {}",
                 self.prefix(),
                 format_ref(key).blue(),
-                format!("{:?}", value).bright_blue()
+                self.format_value(value).bright_blue()
             ),
 
             _ => format!("{} Other Operation", self.prefix()),
@@ -128,8 +179,13 @@ impl<'a> StatementFormatter<'a> {
     }
 }
 
-pub fn print_statement(statement_id: &str, statement: &Statement, indent: &str) {
-    let formatter = StatementFormatter::new(indent, statement_id);
+pub fn print_statement(
+    statement_id: &str,
+    statement: &Statement,
+    indent: &str,
+    pod_store: &MyPods,
+) {
+    let formatter = StatementFormatter::new(indent, statement_id, pod_store);
     println!("{}", formatter.format(statement));
 }
 pub fn print_section_header(title: &str, gadget_id: Option<&str>) {
@@ -150,8 +206,8 @@ pub fn print_pod_details(pod: &POD, pod_store: &MyPods) {
         for (origin, _) in origin_refs.iter() {
             let matching_pods: Vec<_> = pod_store
                 .pods
-                .iter()
-                .filter(|p| PodBuilder::pod_id(p) == *origin)
+                .values()
+                .filter(|p| PodBuilder::matches_pod_id(p, origin))
                 .collect();
 
             for matched_pod in matching_pods {
@@ -164,11 +220,11 @@ pub fn print_pod_details(pod: &POD, pod_store: &MyPods) {
 
                 for (stmt_id, stmt) in matched_pod
                     .payload
-                    .statements_map
+                    .statements_map()
                     .iter()
                     .filter(|(_, p)| p.code() != GoldilocksField::ZERO)
                 {
-                    print_statement(stmt_id, stmt, "    ");
+                    print_statement(stmt_id, stmt, "    ", pod_store);
                 }
                 println!();
             }
@@ -178,8 +234,8 @@ pub fn print_pod_details(pod: &POD, pod_store: &MyPods) {
         for (origin, statements) in origin_refs.iter() {
             let gadget_id = pod_store
                 .pods
-                .iter()
-                .find(|p| PodBuilder::pod_id(p) == *origin)
+                .values()
+                .find(|p| PodBuilder::matches_pod_id(p, origin))
                 .map(|p| p.proof_type.to_string())
                 .unwrap_or_else(|| "unknown".to_string());
 
@@ -192,15 +248,15 @@ pub fn print_pod_details(pod: &POD, pod_store: &MyPods) {
 
             let referenced_values: Vec<String> = pod_store
                 .pods
-                .iter()
-                .filter(|p| PodBuilder::pod_id(p) == *origin)
+                .values()
+                .filter(|p| PodBuilder::matches_pod_id(p, origin))
                 .flat_map(|p| {
                     statements
                         .iter()
                         .filter_map(|s| {
                             let key = s.split(": ").nth(1)?;
                             p.payload
-                                .statements_map
+                                .statements_map()
                                 .iter()
                                 .find(|(_, stmt)| {
                                     if let Statement::ValueOf(k, _) = stmt {
@@ -236,11 +292,54 @@ pub fn print_pod_details(pod: &POD, pod_store: &MyPods) {
     print_section_header("POD Statements", Some(&pod.proof_type.to_string()));
     for (statement_id, statement) in pod
         .payload
-        .statements_map
+        .statements_map()
         .iter()
         .filter(|(_, p)| p.code() != GoldilocksField::ZERO)
     {
-        print_statement(statement_id, statement, "  ");
+        print_statement(statement_id, statement, "  ", pod_store);
     }
     println!();
 }
+
+pub fn print_plan(plan: &FinalizePlan) {
+    if !plan.input_pods.is_empty() {
+        print_section_header("Input PODs", None);
+        for (pod_id, pod) in plan.input_pods.iter() {
+            println!(
+                "  {} {} ({})",
+                "POD".magenta(),
+                pod_id.yellow().bold(),
+                pod.proof_type.to_string().bright_cyan()
+            );
+        }
+        for ((pod_id, origin), renamed) in plan.origin_renaming_map.iter() {
+            println!(
+                "    └─ {}:{} → {}",
+                pod_id.bright_black(),
+                origin.blue(),
+                renamed.bright_green()
+            );
+        }
+    }
+
+    print_section_header("Operations", Some(&plan.gadget.to_string()));
+    for OpCmd(op, output_name) in plan.ops.iter() {
+        println!(
+            "  {} {} {}",
+            "└─".bright_black(),
+            output_name.bright_black(),
+            format!("{:?}", op).yellow()
+        );
+    }
+
+    let count = format!("{}/{}", plan.statement_count, plan.max_statements);
+    println!(
+        "\n{} {}",
+        "Statements".magenta().bold(),
+        if plan.statement_count > plan.max_statements {
+            count.red().bold()
+        } else {
+            count.bright_green()
+        }
+    );
+}
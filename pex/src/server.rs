@@ -0,0 +1,407 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use plonky2::field::types::PrimeField64;
+use pod2::{
+    pod::{Statement, POD},
+    signature::schnorr::SchnorrSecretKey,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+
+use crate::{eval, Env, MyPods, PodBuilder, SharedStore, Value};
+
+// HTTP API for running pex as a service:
+//   POST /eval {"script": ...}   evaluates a script as the authenticated user
+//   GET  /pods                   lists the ids of the user's pods
+//   GET  /pods/:id               returns a pod in the pod2 JSON wire format
+//   POST /pods                   verifies and imports a pod in that format
+// Requests authenticate with "Authorization: Bearer <token>", tokens are mapped to users in
+// the config file.
+
+/// Server config file, eg:
+/// {"users": [{"name": "alice", "token": "...", "secret_key": 42}]}
+#[derive(Debug, Deserialize)]
+pub struct ServerConfig {
+    pub users: Vec<UserConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserConfig {
+    pub name: String,
+    pub token: String,
+    pub secret_key: u64,
+}
+
+impl ServerConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Could not read config file {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid config file {}: {}", path.display(), e))
+    }
+}
+
+/// Every user gets their own env, with their own pods and bindings, over the shared store
+pub struct ServerState {
+    users: HashMap<String, Env>,
+}
+
+impl ServerState {
+    pub fn new(config: ServerConfig, shared: Arc<dyn SharedStore>) -> Result<Self> {
+        let mut users = HashMap::new();
+        for user in config.users {
+            let env = Env::new(
+                user.name.clone(),
+                shared.clone(),
+                Arc::new(Mutex::new(MyPods::default())),
+                Some(SchnorrSecretKey::from_u64(user.secret_key)),
+                None,
+                None,
+            );
+            if users.insert(user.token, env).is_some() {
+                return Err(anyhow!(
+                    "User {} reuses the token of another user",
+                    user.name
+                ));
+            }
+        }
+        Ok(Self { users })
+    }
+
+    fn user_env(&self, headers: &HeaderMap) -> Result<Env, ApiError> {
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.users.get(token))
+            .cloned()
+            .ok_or_else(|| ApiError(StatusCode::UNAUTHORIZED, "Invalid token".to_string()))
+    }
+}
+
+pub fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/eval", post(eval_script))
+        .route("/pods", get(list_pods).post(import_pod))
+        .route("/pods/:id", get(get_pod))
+        .with_state(state)
+}
+
+pub async fn serve(listener: TcpListener, state: Arc<ServerState>) -> Result<()> {
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct EvalRequest {
+    script: String,
+}
+
+async fn eval_script(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(request): Json<EvalRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let env = state.user_env(&headers)?;
+    let value = eval(&request.script, env.clone())
+        .await
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+    // Like in the REPL, pods are kept by the user creating them and not by the participants
+    if let Value::PodRef(pod) = &value {
+        if !request.script.contains(&env.user) {
+            env.pod_store.lock().unwrap().add_pod(pod.clone());
+        }
+    }
+    Ok(Json(json!({ "value": value_to_json(&value) })))
+}
+
+async fn list_pods(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let env = state.user_env(&headers)?;
+    let pods = env.pod_store.lock().unwrap();
+    Ok(Json(pods.pods.keys().cloned().collect()))
+}
+
+async fn get_pod(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Response, ApiError> {
+    let env = state.user_env(&headers)?;
+    let pods = env.pod_store.lock().unwrap();
+    let pod = pods
+        .find_pod(&id)
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e.to_string()))?;
+    let json = pod
+        .to_json_v1()
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
+}
+
+async fn import_pod(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let env = state.user_env(&headers)?;
+    let pod =
+        POD::from_json_v1(&body).map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let id = PodBuilder::pod_id(&pod);
+    if env.pod_store.lock().unwrap().get_pod(&id).is_some() {
+        return Err(ApiError(
+            StatusCode::CONFLICT,
+            format!("POD {} already exists", id),
+        ));
+    }
+    let id = env
+        .import_pod(pod)
+        .map_err(|e| ApiError(StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(json!({ "id": id })))
+}
+
+/// JSON encoding of values returned by the API. Every value is an object with a "type", field
+/// elements are decimal strings so they survive JavaScript numbers, and pods are summarized by
+/// their id and statements (the full pod is available from /pods/:id).
+pub fn value_to_json(value: &Value) -> serde_json::Value {
+    let field = |x: &plonky2::field::goldilocks_field::GoldilocksField| {
+        json!(x.to_canonical_u64().to_string())
+    };
+    match value {
+        Value::String(s) => json!({ "type": "string", "value": s }),
+        Value::Scalar(s) => json!({ "type": "scalar", "value": field(s) }),
+        Value::Bool(b) => json!({ "type": "bool", "value": b }),
+        Value::Vector(v) => json!({
+            "type": "vector",
+            "value": v.iter().map(field).collect::<Vec<_>>(),
+        }),
+        Value::PodRef(pod) => json!({
+            "type": "pod",
+            "id": PodBuilder::pod_id(pod),
+            "proof_type": pod.proof_type.to_string(),
+            "statements": pod
+                .payload
+                .statements_list()
+                .iter()
+                .filter(|(_, statement)| !matches!(statement, Statement::None))
+                .map(|(name, statement)| json!({ "name": name, "statement": statement.to_string() }))
+                .collect::<Vec<_>>(),
+        }),
+        Value::SRef(sref) => json!({
+            "type": "sref",
+            "origin": String::from(sref.0.clone()),
+            "statement": sref.1,
+        }),
+        Value::List(values) => json!({
+            "type": "list",
+            "value": values.iter().map(value_to_json).collect::<Vec<_>>(),
+        }),
+        Value::Operation(op) => json!({ "type": "operation", "value": format!("{:?}", op) }),
+        Value::Assert(assert) => json!({ "type": "assert", "value": format!("{:?}", assert) }),
+        Value::Quoted(expr) => json!({ "type": "quoted", "value": expr.to_string() }),
+        Value::Closure(closure) => json!({
+            "type": "closure",
+            "name": closure.name,
+            "params": closure.params,
+        }),
+        Value::Schema(schema) => json!({
+            "type": "schema",
+            "name": schema.name,
+            "fields": schema
+                .fields
+                .iter()
+                .map(|(key, kind)| json!({ "key": key, "kind": kind.to_string() }))
+                .collect::<Vec<_>>(),
+            "open": schema.open,
+        }),
+        Value::Plan(plan) => json!({
+            "type": "plan",
+            "gadget": plan.gadget.to_string(),
+            "statements": plan.statement_count,
+            "max_statements": plan.max_statements,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryStore;
+
+    const ALICE: &str = "alice-token";
+    const BOB: &str = "bob-token";
+
+    async fn spawn_server() -> Result<String> {
+        let config: ServerConfig = serde_json::from_value(json!({
+            "users": [
+                { "name": "alice", "token": ALICE, "secret_key": 1 },
+                { "name": "bob", "token": BOB, "secret_key": 2 },
+            ]
+        }))?;
+        let state = ServerState::new(config, Arc::new(InMemoryStore::new()))?;
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(serve(listener, Arc::new(state)));
+        Ok(format!("http://{}", addr))
+    }
+
+    async fn post_eval(
+        client: &reqwest::Client,
+        url: &str,
+        token: &str,
+        script: &str,
+    ) -> Result<reqwest::Response> {
+        Ok(client
+            .post(format!("{}/eval", url))
+            .bearer_auth(token)
+            .json(&json!({ "script": script }))
+            .send()
+            .await?)
+    }
+
+    #[tokio::test]
+    async fn test_server_eval() -> Result<()> {
+        let url = spawn_server().await?;
+        let client = reqwest::Client::new();
+
+        let response = post_eval(&client, &url, ALICE, "[list 1 [vec 2 3] [> 2 1]]").await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response.json().await?;
+        assert_eq!(
+            body["value"],
+            json!({ "type": "list", "value": [
+                { "type": "scalar", "value": "1" },
+                { "type": "vector", "value": ["2", "3"] },
+                { "type": "bool", "value": true },
+            ]})
+        );
+
+        let response = post_eval(&client, &url, ALICE, "[+ 1").await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = post_eval(&client, &url, "nobody", "[+ 1 2]").await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_server_cross_user_pods() -> Result<()> {
+        let url = spawn_server().await?;
+        let client = reqwest::Client::new();
+
+        let response = post_eval(&client, &url, ALICE, "[createpod test x 40]").await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response.json().await?;
+        let alice_id = body["value"]["id"].as_str().unwrap().to_string();
+
+        // Alice shares a query result that Bob builds on
+        let script = "[createpod shared
+            [define result [from alice [pod? [x]]]]
+            new_value [+ result 10]]";
+        let response = post_eval(&client, &url, ALICE, script).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = post_eval(&client, &url, BOB, script).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response.json().await?;
+        assert_eq!(body["value"]["type"], "pod");
+        let id = body["value"]["id"].as_str().unwrap().to_string();
+
+        // The pod belongs to Bob only
+        let pods = |token: &'static str| {
+            client
+                .get(format!("{}/pods", url))
+                .bearer_auth(token)
+                .send()
+        };
+        assert_eq!(
+            pods(BOB).await?.json::<Vec<String>>().await?,
+            vec![id.clone()]
+        );
+        assert!(!pods(ALICE)
+            .await?
+            .json::<Vec<String>>()
+            .await?
+            .contains(&id));
+
+        let response = client
+            .get(format!("{}/pods/{}", url, &id[..12]))
+            .bearer_auth(BOB)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let pod_json = response.text().await?;
+        let pod = POD::from_json_v1(&pod_json)?;
+        assert_eq!(PodBuilder::pod_id(&pod), id);
+        let response = client
+            .get(format!("{}/pods/{}", url, id))
+            .bearer_auth(ALICE)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Alice can import it, once
+        let import = |body: String| {
+            client
+                .post(format!("{}/pods", url))
+                .bearer_auth(ALICE)
+                .body(body)
+                .send()
+        };
+        let response = import(pod_json.clone()).await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(pods(ALICE)
+            .await?
+            .json::<Vec<String>>()
+            .await?
+            .contains(&id));
+        assert_eq!(import(pod_json).await?.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            import("not a pod".to_string()).await?.status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        // Alice's pod reached the shared store through her query, which doesn't stop Bob
+        // importing it
+        let pod_json = client
+            .get(format!("{}/pods/{}", url, alice_id))
+            .bearer_auth(ALICE)
+            .send()
+            .await?
+            .text()
+            .await?;
+        let response = client
+            .post(format!("{}/pods", url))
+            .bearer_auth(BOB)
+            .body(pod_json)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(pods(BOB)
+            .await?
+            .json::<Vec<String>>()
+            .await?
+            .contains(&alice_id));
+        Ok(())
+    }
+}
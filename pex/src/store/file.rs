@@ -0,0 +1,236 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use pod2::pod::POD;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::{
+    InMemoryStore, PodBuilder, ScriptId, SetOptions, SharedStore, SharedValue, User, Value,
+    WaitError,
+};
+
+/// A SharedStore kept in a JSON file, for a single user who wants their values and pods back in
+/// the next session. Everything is loaded when the store is opened and read from memory after
+/// that, the whole file is rewritten on every change.
+pub struct FileStore {
+    path: PathBuf,
+    memory: InMemoryStore,
+    // Held while writing the file, so that an older snapshot can't overwrite a newer one
+    saving: Mutex<()>,
+}
+
+/// Contents of the file. Pods are keyed by their id, which is derived from the pod itself.
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    values: Vec<StoredValue>,
+    pods: Vec<POD>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredValue {
+    script_id: String,
+    id: u64,
+    value: SharedValue,
+}
+
+impl FileStore {
+    /// Opens the store kept in `path`, which is created on the first change if it doesn't exist
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Could not read store file {}: {}", path.display(), e))?;
+            serde_json::from_str::<StoreFile>(&contents)
+                .map_err(|e| anyhow!("Invalid store file {}: {}", path.display(), e))?
+        } else {
+            StoreFile::default()
+        };
+
+        let values = file
+            .values
+            .into_iter()
+            .filter(|stored| !stored.value.is_expired())
+            .map(|stored| ((ScriptId(stored.script_id), stored.id), stored.value))
+            .collect();
+        let pods = file
+            .pods
+            .into_iter()
+            .map(|pod| (PodBuilder::pod_id(&pod), pod))
+            .collect();
+        Ok(Self {
+            path,
+            memory: InMemoryStore::with_contents(values, pods),
+            saving: Mutex::new(()),
+        })
+    }
+
+    /// Writes the current values and pods to the file, through a temporary file so that a
+    /// failed write doesn't lose what was saved before
+    fn save(&self) -> Result<()> {
+        let _saving = self.saving.lock().unwrap();
+        let file = StoreFile {
+            values: self
+                .memory
+                .values()
+                .into_iter()
+                .map(|((script_id, id), value)| StoredValue {
+                    script_id: script_id.0,
+                    id,
+                    value,
+                })
+                .collect(),
+            pods: self.memory.pods(),
+        };
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_string(&file)?)
+            .and_then(|_| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|e| anyhow!("Could not write store file {}: {}", self.path.display(), e))
+    }
+
+    /// The SharedStore methods can't fail, so a failed save is only logged. The change is
+    /// still visible until the store is dropped.
+    fn persist(&self) {
+        if let Err(e) = self.save() {
+            warn!("{}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl SharedStore for FileStore {
+    async fn get_value(
+        &self,
+        script_id: &ScriptId,
+        id: u64,
+        reader: &User,
+        timeout: Duration,
+    ) -> Result<Value, WaitError> {
+        self.memory.get_value(script_id, id, reader, timeout).await
+    }
+
+    fn set_value(&self, script_id: &ScriptId, id: u64, value: Value, options: SetOptions) {
+        self.memory.set_value(script_id, id, value, options);
+        self.persist();
+    }
+
+    fn purge_expired(&self) -> usize {
+        let purged = self.memory.purge_expired();
+        if purged > 0 {
+            self.persist();
+        }
+        purged
+    }
+
+    async fn get_pod(&self, id: &String, timeout: Duration) -> Result<POD, WaitError> {
+        self.memory.get_pod(id, timeout).await
+    }
+
+    fn store_pod(&self, pod: POD) -> String {
+        let (id, new) = self.memory.insert_pod(pod);
+        if new {
+            self.persist();
+        }
+        id
+    }
+
+    fn remove_pod(&self, id: &String) -> bool {
+        let removed = self.memory.remove_pod(id);
+        if removed {
+            self.persist();
+        }
+        removed
+    }
+
+    fn clear_script(&self, script_id: &ScriptId) {
+        self.memory.clear_script(script_id);
+        self.persist();
+    }
+
+    fn list_pod_ids(&self) -> Vec<String> {
+        self.memory.list_pod_ids()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use constants::{NS, VL};
+    use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
+    use pod2::pod::entry::Entry;
+    use pod2::signature::schnorr::SchnorrSecretKey;
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("pex-store-{}.json", rand::random::<u64>()))
+    }
+
+    #[tokio::test]
+    async fn test_file_store_reopen() -> Result<()> {
+        let path = temp_path();
+        let pod = POD::execute_schnorr_gadget::<NS, VL>(
+            &[Entry::new_from_scalar(
+                "x",
+                GoldilocksField::from_canonical_u64(1),
+            )],
+            &SchnorrSecretKey::from_u64(42),
+        )?;
+        let script_id = ScriptId::from_script("[from alice 1]");
+        let timeout = Duration::from_millis(10);
+        let reader = "bob".to_string();
+
+        let store = FileStore::open(&path)?;
+        let id = store.store_pod(pod.clone());
+        store.set_value(
+            &script_id,
+            1,
+            Value::String("hello".to_string()),
+            SetOptions::default(),
+        );
+        // Expired values aren't loaded back
+        store.set_value(
+            &script_id,
+            2,
+            Value::String("gone".to_string()),
+            SetOptions {
+                ttl: Some(Duration::ZERO),
+                ..Default::default()
+            },
+        );
+        drop(store);
+
+        let store = FileStore::open(&path)?;
+        let reopened = (
+            store.list_pod_ids(),
+            store.get_pod(&id, timeout).await,
+            store.get_value(&script_id, 1, &reader, timeout).await,
+            store.get_value(&script_id, 2, &reader, timeout).await,
+        );
+        let removed = store.remove_pod(&id);
+        let after_remove = FileStore::open(&path).map(|store| store.list_pod_ids());
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(reopened.0, vec![id.clone()]);
+        assert_eq!(reopened.1, Ok(pod));
+        assert!(matches!(reopened.2, Ok(Value::String(s)) if s == "hello"));
+        assert_eq!(reopened.3.unwrap_err(), WaitError::Timeout(timeout));
+        assert!(removed);
+        assert!(after_remove?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_store_invalid_file() -> Result<()> {
+        let path = temp_path();
+        std::fs::write(&path, "not json")?;
+        let store = FileStore::open(&path);
+        std::fs::remove_file(&path)?;
+        assert!(store
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Invalid store file"));
+        Ok(())
+    }
+}
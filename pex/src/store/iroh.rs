@@ -1,37 +1,65 @@
+use anyhow::anyhow;
 use anyhow::Result;
 use async_trait::async_trait;
 use futures::StreamExt;
+use iroh::base::ticket::NodeTicket;
 use iroh::client::Doc;
 use iroh::docs::DocTicket;
 use iroh::net::discovery::pkarr::dht::DhtDiscovery;
 use iroh::net::endpoint::{TransportConfig, VarInt};
 use iroh::net::key::SecretKey;
+use iroh::net::{NodeAddr, NodeId};
 use pod2::pod::POD;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tokio::sync::oneshot; // Add this import
 use tokio::sync::RwLock;
 
 type IrohNode = iroh::node::MemNode;
 
-use crate::{ScriptId, SharedStore, Value};
+use crate::{
+    InMemoryStore, ScriptId, SetOptions, SharedStore, SharedValue, StoreStatus, User, Value,
+    WaitError,
+};
 
 pub struct IrohStore {
     iroh: Arc<tokio::sync::RwLock<Option<IrohNode>>>,
     doc: Arc<RwLock<Option<Doc>>>,
-    values: Arc<Mutex<HashMap<(ScriptId, u64), Value>>>,
-    pods: Arc<Mutex<HashMap<String, POD>>>,
+    memory: InMemoryStore,
     secret_key: SecretKey,
+    // Peers we are syncing the document with, and the outcome of the last sync
+    neighbors: Arc<Mutex<HashSet<NodeId>>>,
+    last_sync: Arc<Mutex<Option<SystemTime>>>,
+    last_sync_failed: Arc<Mutex<bool>>,
 }
+
+/// A peer known to the iroh node
+#[derive(Clone, Debug)]
+pub struct PeerInfo {
+    pub node_id: String,
+    pub addrs: Vec<String>,
+    pub relay_url: Option<String>,
+    pub connection: String,
+    pub latency: Option<Duration>,
+    /// Whether we are currently syncing the document with it
+    pub syncing: bool,
+}
+
 const DOC_TICKET: &str = "docaaacb6cej4lpglwuuya5tecmiflfmnkeprhubm6nk7lhdhj4vwnobficahswyqlad2rachperq7aesmyhoxycbsn7djsqwrn4m7yd7pkr3rxwaaa";
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 enum PodOrValue {
     Pod(String, POD),
-    Value(String, Value),
+    Value(String, SharedValue),
+}
+
+/// Key of a value in the document. The id is zero padded so that no key is a prefix of
+/// another, as entries are deleted by prefix.
+fn value_key(script_id: &ScriptId, id: u64) -> String {
+    format!("value:{}:{:020}", script_id.0, id)
 }
 
 impl IrohStore {
@@ -39,9 +67,11 @@ impl IrohStore {
         Self {
             iroh: Arc::new(RwLock::new(None)),
             doc: Arc::new(RwLock::new(None)),
-            values: Arc::new(Mutex::new(HashMap::new())),
-            pods: Arc::new(Mutex::new(HashMap::new())),
+            memory: InMemoryStore::new(),
             secret_key,
+            neighbors: Arc::new(Mutex::new(HashSet::new())),
+            last_sync: Arc::new(Mutex::new(None)),
+            last_sync_failed: Arc::new(Mutex::new(false)),
         }
     }
 
@@ -84,19 +114,25 @@ impl IrohStore {
         if let (Some(doc), Some(iroh)) = (doc.as_ref(), iroh.as_ref()) {
             let mut events = doc.subscribe().await.unwrap();
 
-            let values = self.values.clone();
-            let pods = self.pods.clone();
-
             while let Some(Ok(event)) = events.next().await {
                 match event {
+                    iroh::client::docs::LiveEvent::NeighborUp(node_id) => {
+                        self.neighbors.lock().unwrap().insert(node_id);
+                    }
+                    iroh::client::docs::LiveEvent::NeighborDown(node_id) => {
+                        self.neighbors.lock().unwrap().remove(&node_id);
+                    }
+                    iroh::client::docs::LiveEvent::SyncFinished(sync) => {
+                        *self.last_sync_failed.lock().unwrap() = sync.result.is_err();
+                        if sync.result.is_ok() {
+                            *self.last_sync.lock().unwrap() = Some(sync.finished);
+                        }
+                    }
                     iroh::client::docs::LiveEvent::ContentReady { hash } => {
                         if let Ok(content) = iroh.blobs().read_to_bytes(hash).await {
                             if let Ok(pod_or_value) = postcard::from_bytes::<PodOrValue>(&content) {
                                 match pod_or_value {
-                                    PodOrValue::Pod(_, pod) => {
-                                        let id = crate::PodBuilder::pod_id(&pod);
-                                        pods.lock().unwrap().insert(id, pod);
-                                    }
+                                    PodOrValue::Pod(_, pod) => self.memory.sync_pod(pod),
                                     PodOrValue::Value(key, value) => {
                                         let parts: Vec<&str> = key.split(':').collect();
                                         if parts.len() == 3 && parts[0] == "value" {
@@ -104,10 +140,11 @@ impl IrohStore {
                                                 parts[1].parse::<String>(),
                                                 parts[2].parse::<u64>(),
                                             ) {
-                                                values
-                                                    .lock()
-                                                    .unwrap()
-                                                    .insert((ScriptId(script_id), value_id), value);
+                                                self.memory.sync_value(
+                                                    ScriptId(script_id),
+                                                    value_id,
+                                                    value,
+                                                );
                                             }
                                         }
                                     }
@@ -122,6 +159,77 @@ impl IrohStore {
         Ok(())
     }
 
+    fn delete_prefix(&self, prefix: String) {
+        let doc = self.doc.clone();
+        let iroh = self.iroh.clone();
+
+        tokio::spawn(async move {
+            if let (Some(doc), Some(iroh)) = (doc.read().await.as_ref(), iroh.read().await.as_ref())
+            {
+                let author = iroh.authors().default().await?;
+                doc.del(author, prefix).await?;
+                Ok::<(), anyhow::Error>(())
+            } else {
+                Ok(())
+            }
+        });
+    }
+
+    /// Address other nodes can connect to, as a node ticket.
+    pub async fn node_ticket(&self) -> Result<String> {
+        let iroh = self.iroh.read().await;
+        let iroh = iroh
+            .as_ref()
+            .ok_or_else(|| anyhow!("Iroh node is not initialized yet"))?;
+        let node_addr = iroh.net().node_addr().await?;
+        Ok(NodeTicket::new(node_addr).to_string())
+    }
+
+    /// Connects to a peer, given as a node ticket or a node id (whose address is then found
+    /// through discovery), and starts syncing the document with it.
+    pub async fn add_peer(&self, node_addr: String) -> Result<()> {
+        let node_addr = match NodeTicket::from_str(&node_addr) {
+            Ok(ticket) => ticket.node_addr().clone(),
+            Err(_) => NodeAddr::new(
+                NodeId::from_str(&node_addr)
+                    .map_err(|_| anyhow!("Invalid node address: {}", node_addr))?,
+            ),
+        };
+        let (iroh, doc) = (self.iroh.read().await, self.doc.read().await);
+        match (iroh.as_ref(), doc.as_ref()) {
+            (Some(iroh), Some(doc)) => {
+                if !node_addr.info.is_empty() {
+                    iroh.net().add_node_addr(node_addr.clone()).await?;
+                }
+                doc.start_sync(vec![node_addr]).await
+            }
+            _ => Err(anyhow!("Iroh node is not initialized yet")),
+        }
+    }
+
+    pub async fn list_peers(&self) -> Vec<PeerInfo> {
+        let iroh = self.iroh.read().await;
+        let Some(iroh) = iroh.as_ref() else {
+            return vec![];
+        };
+        let Ok(remote_infos) = iroh.net().remote_info_iter().await else {
+            return vec![];
+        };
+        let neighbors = self.neighbors.lock().unwrap().clone();
+        remote_infos
+            .filter_map(|info| async move { info.ok() })
+            .map(|info| PeerInfo {
+                node_id: info.node_id.to_string(),
+                addrs: info.addrs.iter().map(|a| a.addr.to_string()).collect(),
+                relay_url: info.relay_url.map(|r| r.relay_url.to_string()),
+                connection: info.conn_type.to_string(),
+                latency: info.latency,
+                syncing: neighbors.contains(&info.node_id),
+            })
+            .collect::<Vec<_>>()
+            .await
+    }
+
     pub async fn cleanup(&self) -> Result<()> {
         if let Some(iroh) = self.iroh.write().await.take() {
             iroh.shutdown().await?;
@@ -132,37 +240,25 @@ impl IrohStore {
 
 #[async_trait]
 impl SharedStore for IrohStore {
-    async fn get_value(&self, script_id: &ScriptId, id: u64) -> Option<Value> {
-        let mut counter = 0;
-        loop {
-            if let Some(v) = self
-                .values
-                .lock()
-                .unwrap()
-                .get(&(script_id.clone(), id))
-                .cloned()
-            {
-                return Some(v);
-            }
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            counter += 1;
-            if counter > 100 {
-                return None;
-            }
-        }
+    async fn get_value(
+        &self,
+        script_id: &ScriptId,
+        id: u64,
+        reader: &User,
+        timeout: Duration,
+    ) -> Result<Value, WaitError> {
+        self.memory.get_value(script_id, id, reader, timeout).await
     }
 
-    fn set_value(&self, script_id: &ScriptId, id: u64, value: Value) {
-        self.values
-            .lock()
-            .unwrap()
-            .insert((script_id.clone(), id), value.clone());
+    fn set_value(&self, script_id: &ScriptId, id: u64, value: Value, options: SetOptions) {
+        let value = SharedValue::new(value, options);
+        self.memory.insert_value(script_id, id, value.clone());
 
-        // Sync to iroh network
+        // Sync to iroh network, along with the options so that peers enforce them too
         let doc = self.doc.clone();
         let iroh = self.iroh.clone();
-        let key = format!("value:{}:{}", script_id.0, id);
-        let value = PodOrValue::Value(key.clone(), value.clone());
+        let key = value_key(script_id, id);
+        let value = PodOrValue::Value(key.clone(), value);
 
         tokio::spawn(async move {
             if let (Some(doc), Some(iroh)) = (doc.read().await.as_ref(), iroh.read().await.as_ref())
@@ -177,19 +273,33 @@ impl SharedStore for IrohStore {
         });
     }
 
-    async fn get_pod(&self, id: &String) -> Option<POD> {
-        self.pods.lock().unwrap().get(id).cloned()
+    fn purge_expired(&self) -> usize {
+        let expired = self.memory.take_expired();
+
+        // Only our own entries can be deleted from the document, expired ones of peers are
+        // ignored when synced or read
+        for (script_id, id) in &expired {
+            self.delete_prefix(value_key(script_id, *id));
+        }
+        expired.len()
+    }
+
+    async fn get_pod(&self, id: &String, timeout: Duration) -> Result<POD, WaitError> {
+        self.memory.get_pod(id, timeout).await
     }
 
     fn store_pod(&self, pod: POD) -> String {
-        let id = crate::PodBuilder::pod_id(&pod);
-        self.pods.lock().unwrap().insert(id.clone(), pod.clone());
+        let (id, new) = self.memory.insert_pod(pod.clone());
+        // Peers already got it when it was first stored
+        if !new {
+            return id;
+        }
 
         // Sync to iroh network
         let doc = self.doc.clone();
         let iroh = self.iroh.clone();
         let key = format!("pod:{}", id);
-        let pod = PodOrValue::Pod(key.clone(), pod.clone());
+        let pod = PodOrValue::Pod(key.clone(), pod);
 
         tokio::spawn(async move {
             if let (Some(doc), Some(iroh)) = (doc.read().await.as_ref(), iroh.read().await.as_ref())
@@ -205,4 +315,94 @@ impl SharedStore for IrohStore {
 
         id
     }
+
+    fn remove_pod(&self, id: &String) -> bool {
+        let removed = self.memory.remove_pod(id);
+
+        // Remove from the iroh document too
+        self.delete_prefix(format!("pod:{}", id));
+        removed
+    }
+
+    fn clear_script(&self, script_id: &ScriptId) {
+        self.memory.clear_script(script_id);
+
+        // Remove from the iroh document too
+        self.delete_prefix(format!("value:{}:", script_id.0));
+    }
+
+    fn list_pod_ids(&self) -> Vec<String> {
+        self.memory.list_pod_ids()
+    }
+
+    fn status(&self) -> StoreStatus {
+        // The node and document are only written once, when initialization is done
+        if self.doc.try_read().map_or(true, |doc| doc.is_none()) {
+            return StoreStatus::Initializing;
+        }
+        let peers = self.neighbors.lock().unwrap().len();
+        let last_sync = *self.last_sync.lock().unwrap();
+        if peers == 0 || *self.last_sync_failed.lock().unwrap() {
+            StoreStatus::Degraded { peers, last_sync }
+        } else {
+            StoreStatus::Synced { peers, last_sync }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn spawn_store() -> Result<Arc<IrohStore>> {
+        let store = Arc::new(IrohStore::new(SecretKey::generate()));
+        let (sync_tx, sync_rx) = oneshot::channel();
+        let task_bound_store = store.clone();
+        tokio::spawn(async move { task_bound_store.initialize(sync_tx).await });
+        sync_rx.await?;
+        // The node is stored right after the sync signal
+        while store.status() == StoreStatus::Initializing {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(store)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_add_peer() -> Result<()> {
+        let alice = spawn_store().await?;
+        let bob = spawn_store().await?;
+        assert!(alice.add_peer("not an address".to_string()).await.is_err());
+
+        bob.add_peer(alice.node_ticket().await?).await?;
+        let script_id = ScriptId::from_script(&format!("[test {}]", alice.secret_key.public()));
+        alice.set_value(
+            &script_id,
+            1,
+            Value::String("hello".to_string()),
+            SetOptions::default(),
+        );
+
+        match bob
+            .get_value(&script_id, 1, &"bob".to_string(), Duration::from_secs(10))
+            .await
+        {
+            Ok(Value::String(s)) => assert_eq!(s, "hello"),
+            _ => panic!("Expected the value set by the peer"),
+        }
+        let alice_id = alice.secret_key.public().to_string();
+        assert!(bob
+            .list_peers()
+            .await
+            .iter()
+            .any(|peer| peer.node_id == alice_id));
+        // Sync events may still be on their way
+        for _ in 0..100 {
+            if matches!(bob.status(), StoreStatus::Synced { .. }) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(matches!(bob.status(), StoreStatus::Synced { peers, .. } if peers > 0));
+        Ok(())
+    }
 }
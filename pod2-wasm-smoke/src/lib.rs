@@ -0,0 +1,17 @@
+//! Checks that pod2 without its `prover` feature builds for wasm32-unknown-unknown and verifies
+//! PODs there. The tests also run natively:
+//!
+//! ```sh
+//! cargo test -p pod2-wasm-smoke
+//! wasm-pack test --node pod2-wasm-smoke
+//! ```
+
+/// Verifying a POD must not need randomness, so on wasm the entropy source getrandom falls back
+/// to fails every call instead of reaching for `crypto.getRandomValues`.
+#[cfg(target_arch = "wasm32")]
+getrandom::register_custom_getrandom!(no_randomness);
+
+#[cfg(target_arch = "wasm32")]
+fn no_randomness(_: &mut [u8]) -> Result<(), getrandom::Error> {
+    Err(getrandom::Error::UNSUPPORTED)
+}
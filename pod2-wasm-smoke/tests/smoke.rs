@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use pod2::pod::{entry::Entry, gadget::GadgetID, payload::PODPayload, Statement, SIGNER_PK_KEY};
+use pod2::signature::schnorr::{SchnorrSecretKey, SchnorrSigner};
+use pod2::{PODProof, POD};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+/// The JSON of a Schnorr POD, signed with a fixed nonce since the build has no rng.
+fn schnorr_pod_json() -> Result<String> {
+    let sk = SchnorrSecretKey::from_u64(25);
+    let protocol = SchnorrSigner::new();
+    let mut statements = HashMap::new();
+    for entry in [
+        Entry::new_from_scalar("apple", GoldilocksField(36)),
+        Entry::new_from_scalar(SIGNER_PK_KEY, protocol.keygen(&sk).pk),
+    ] {
+        statements.insert(
+            format!("VALUEOF:{}", entry.key),
+            Statement::from_entry(&entry, GadgetID::SCHNORR16),
+        );
+    }
+    let payload = PODPayload::new(&statements);
+    let signature = protocol.sign_with_nonce(payload.hash_payload().elements.as_ref(), &sk, 1234);
+    POD {
+        payload,
+        proof: PODProof::Schnorr(signature),
+        proof_type: GadgetID::SCHNORR16,
+    }
+    .to_json_v1()
+}
+
+#[test]
+fn verify_schnorr_pod() {
+    let pod = POD::from_json_v1(&schnorr_pod_json().unwrap()).unwrap();
+    assert!(pod.verify::<0, 1, 0, 2, 0>().unwrap());
+    assert_eq!(
+        POD::verify_batch_schnorr::<0, 1, 0, 2, 0>(&[&pod]).unwrap(),
+        [true]
+    );
+}
+
+#[test]
+fn reject_forged_schnorr_pod() {
+    // A signature by another key doesn't verify
+    let mut pod = POD::from_json_v1(&schnorr_pod_json().unwrap()).unwrap();
+    pod.proof = PODProof::Schnorr(SchnorrSigner::new().sign_with_nonce(
+        pod.payload.hash_payload().elements.as_ref(),
+        &SchnorrSecretKey::from_u64(26),
+        1234,
+    ));
+    assert!(!pod.verify::<0, 1, 0, 2, 0>().unwrap());
+}
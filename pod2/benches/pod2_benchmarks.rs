@@ -0,0 +1,189 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use plonky2::field::goldilocks_field::GoldilocksField;
+use pod2::pod::{
+    entry::Entry,
+    gadget::PlonkyButNotPlonkyGadget,
+    payload::{HashablePayload, PODPayload},
+    statement::StatementRef,
+    GPGInput, Op, OpCmd, VerifierContext, POD,
+};
+use pod2::recursion::{traits_examples::ExampleIntroducer, IntroducerCircuitTrait};
+use pod2::signature::schnorr::{SchnorrSecretKey, SchnorrSigner};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::HashMap;
+
+/// Entries x0, x1, ... with values 1, 2, ...
+fn entries(n: usize) -> Vec<Entry> {
+    (0..n)
+        .map(|i| Entry::new_from_scalar(&format!("x{}", i), GoldilocksField(i as u64 + 1)))
+        .collect()
+}
+
+fn schnorr_pod<const NS: usize>(n: usize) -> POD {
+    POD::execute_schnorr_gadget_with_rng::<NS, 0>(
+        &entries(n),
+        &SchnorrSecretKey::from_u64(25),
+        &mut StdRng::seed_from_u64(0),
+    )
+    .expect("can't create schnorr pod")
+}
+
+fn benchmark_schnorr_gadget(c: &mut Criterion) {
+    let sk = SchnorrSecretKey::from_u64(25);
+    // Fill every statement slot but the one taken by the signer key
+    macro_rules! bench_ns {
+        ($ns:literal) => {
+            let entries = entries($ns - 1);
+            c.bench_function(concat!("schnorr gadget NS=", $ns), |b| {
+                let mut rng = StdRng::seed_from_u64(0);
+                b.iter(|| {
+                    POD::execute_schnorr_gadget_with_rng::<$ns, 0>(
+                        black_box(&entries),
+                        &sk,
+                        &mut rng,
+                    )
+                })
+            });
+        };
+    }
+    bench_ns!(3);
+    bench_ns!(16);
+    bench_ns!(64);
+}
+
+fn benchmark_oracle_gadget(c: &mut Criterion) {
+    const NUM_ENTRIES: usize = 8;
+    let input = GPGInput::new(
+        HashMap::from([("p1".to_string(), schnorr_pod::<16>(NUM_ENTRIES))]),
+        HashMap::new(),
+    );
+    let value_of = |i: usize| StatementRef::new("p1", format!("VALUEOF:x{}", i % NUM_ENTRIES));
+
+    for num_ops in [10, 50] {
+        // Alternate copies with comparisons, which x0 loses against every other entry
+        let cmds: Vec<OpCmd> = (0..num_ops)
+            .map(|i| {
+                let op = if i % 2 == 0 {
+                    Op::CopyStatement(value_of(i))
+                } else {
+                    Op::GtFromEntries(value_of(i % (NUM_ENTRIES - 1) + 1), value_of(0))
+                };
+                OpCmd::new(op, format!("out{}", i))
+            })
+            .collect();
+        c.bench_function(&format!("oracle gadget {} ops", num_ops), |b| {
+            b.iter(|| POD::execute_oracle_gadget(black_box(&input), black_box(&cmds)))
+        });
+    }
+}
+
+fn benchmark_payload_hash(c: &mut Criterion) {
+    let statements = schnorr_pod::<64>(63).payload.statements_map().clone();
+    // A fresh payload per iteration, so the cached hash is never reused
+    c.bench_function("payload hash 64 statements", |b| {
+        b.iter_batched(
+            || PODPayload::new(&statements),
+            |payload| payload.hash_payload(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn benchmark_schnorr_verify(c: &mut Criterion) {
+    let signer = SchnorrSigner::new();
+    let sk = SchnorrSecretKey::from_u64(25);
+    let pk = signer.keygen(&sk);
+    let msg = signer.u64_into_goldilocks_vec(vec![1, 2, 3, 4]);
+    let sig = signer.sign(&msg, &sk, &mut StdRng::seed_from_u64(0));
+
+    c.bench_function("schnorr verify", |b| {
+        b.iter(|| signer.verify(black_box(&sig), black_box(&msg), black_box(&pk)))
+    });
+}
+
+fn benchmark_schnorr_batch_verify(c: &mut Criterion) {
+    const NS: usize = 3;
+    let pods: Vec<POD> = (0..100)
+        .map(|i| {
+            POD::execute_schnorr_gadget_with_rng::<NS, 0>(
+                &[Entry::new_from_scalar("x", GoldilocksField(i))],
+                &SchnorrSecretKey::from_u64(25 + i),
+                &mut StdRng::seed_from_u64(i),
+            )
+            .expect("can't create schnorr pod")
+        })
+        .collect();
+    let pods: Vec<&POD> = pods.iter().collect();
+
+    let mut group = c.benchmark_group("schnorr verify 100 PODs");
+    group.bench_function("batched", |b| {
+        b.iter(|| POD::verify_batch_schnorr::<0, 3, 2, NS, 0>(black_box(&pods)))
+    });
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            black_box(&pods)
+                .iter()
+                .map(|pod| pod.verify::<0, 3, 2, NS, 0>().unwrap_or(false))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+fn benchmark_plonky_verify(c: &mut Criterion) {
+    const L: usize = 0;
+    const M: usize = 2;
+    const N: usize = 1;
+    const NS: usize = 3;
+    const VL: usize = 2;
+
+    let pod1_circuit_data = ExampleIntroducer::circuit_data().unwrap();
+    let circuit_data = PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::circuit_data(
+        pod1_circuit_data.verifier_data(),
+    )
+    .unwrap();
+    let mut prover_params = PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::build_prover_params(
+        pod1_circuit_data,
+        circuit_data,
+    )
+    .unwrap();
+    let input = GPGInput::new(
+        HashMap::from([("p1".to_string(), schnorr_pod::<NS>(2))]),
+        HashMap::new(),
+    );
+    let cmds = [
+        OpCmd::new(
+            Op::CopyStatement(StatementRef::new("p1", "VALUEOF:x0")),
+            "x0",
+        ),
+        OpCmd::new(
+            Op::CopyStatement(StatementRef::new("p1", "VALUEOF:x1")),
+            "x1",
+        ),
+    ];
+    let pod = POD::execute_plonky_gadget::<L, M, N, NS, VL>(&mut prover_params, &input, &cmds)
+        .expect("can't create plonky pod");
+
+    // `verify` builds the verifier data every time, `verify_with_context` reuses it
+    let mut group = c.benchmark_group("plonky verify");
+    group.sample_size(10);
+    group.bench_function("without context", |b| {
+        b.iter(|| black_box(&pod).verify::<L, M, N, NS, VL>())
+    });
+    let ctx = VerifierContext::<L, M, N, NS, VL>::new().unwrap();
+    group.bench_function("with context", |b| {
+        b.iter(|| black_box(&pod).verify_with_context(&ctx))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_schnorr_gadget,
+    benchmark_oracle_gadget,
+    benchmark_payload_hash,
+    benchmark_schnorr_verify,
+    benchmark_schnorr_batch_verify,
+    benchmark_plonky_verify
+);
+criterion_main!(benches);
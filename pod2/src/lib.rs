@@ -19,8 +19,10 @@ pub type PlonkyProof = Proof<F, PoseidonGoldilocksConfig, D>;
 // u32.
 pub const NUM_BITS: usize = 32;
 
+#[cfg(feature = "prover")]
 pub mod plonky2_u32;
 pub mod pod;
+#[cfg(feature = "prover")]
 pub mod recursion;
 pub mod signature;
 
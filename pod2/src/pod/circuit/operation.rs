@@ -154,6 +154,25 @@ impl<const VL: usize> OperationTarget<VL> {
             ), // TODO: MaxOf
             StatementTarget::lt(builder, statement1_target, statement2_target), // TODO: Lt
             StatementTarget::not_equal(builder, statement1_target, statement2_target), // LtToNonequality. TODO.
+            StatementTarget::sub_of(
+                builder,
+                statement1_target,
+                statement2_target,
+                statement3_target,
+            ), // SubOf
+            StatementTarget::div_of(
+                builder,
+                statement1_target,
+                statement2_target,
+                statement3_target,
+            ), // DivOf
+            StatementTarget::min_of(
+                builder,
+                statement1_target,
+                statement2_target,
+                statement3_target,
+            ), // MinOf
+            StatementTarget::not_contains(builder, statement1_target, statement2_target), // NotContainsFromEntries
         ];
 
         // Type indicators
@@ -287,6 +306,78 @@ impl<const VL: usize> OperationTarget<VL> {
             }, // MaxOf
             builder._true(),                                    // TODO: Lt
             builder._true(),                                    // TODO: LtToNonequality
+            {
+                let conditions = &[
+                    // Types
+                    statement_is_valueof[0],
+                    statement_is_valueof[1],
+                    statement_is_valueof[2],
+                    // s1 = s2 - s3 <=> s1 + s3 = s2 (in the field)
+                    {
+                        let lhs = builder.add(statement1_target.value, statement3_target.value);
+                        builder.is_equal(lhs, statement2_target.value)
+                    },
+                ];
+                and(builder, conditions)
+            }, // SubOf
+            {
+                let conditions = &[
+                    // Types
+                    statement_is_valueof[0],
+                    statement_is_valueof[1],
+                    statement_is_valueof[2],
+                    // s1 = s2 / s3 <=> s1 * s3 = s2 and s3 != 0
+                    {
+                        let lhs = builder.mul(statement1_target.value, statement3_target.value);
+                        builder.is_equal(lhs, statement2_target.value)
+                    },
+                    {
+                        let zero_target = builder.zero();
+                        let s3_is_zero = builder.is_equal(statement3_target.value, zero_target);
+                        builder.not(s3_is_zero)
+                    },
+                ];
+                and(builder, conditions)
+            }, // DivOf
+            {
+                let conditions = &[
+                    // Types
+                    statement_is_valueof[0],
+                    statement_is_valueof[1],
+                    statement_is_valueof[2],
+                    // s1 = min(s2, s3) <=> s1 <= s2, s3 and (s1 = s2 or s1 = s3)
+                    {
+                        // TODO: Replace assertions.
+                        let minof_opcode_target = builder.constant(Op::<Statement>::MIN_OF);
+                        let one_target = builder.one();
+                        let op_is_minof = builder.is_equal(self.op, minof_opcode_target);
+                        let s1 = statement1_target.value;
+                        let s2 = statement2_target.value;
+                        let s3 = statement3_target.value;
+                        let s2_plus_one = builder.add(s2, one_target);
+                        let s3_plus_one = builder.add(s3, one_target);
+                        assert_less_if::<NUM_BITS>(builder, op_is_minof, s1, s2_plus_one);
+                        assert_less_if::<NUM_BITS>(builder, op_is_minof, s1, s3_plus_one);
+
+                        let s1_eq_s2 = builder.is_equal(s1, s2);
+                        let s1_eq_s3 = builder.is_equal(s1, s3);
+
+                        builder.or(s1_eq_s2, s1_eq_s3)
+                    },
+                ];
+                and(builder, conditions)
+            }, // MinOf
+            {
+                let conditions = &[
+                    // Types
+                    statement_is_valueof[0],
+                    statement_is_valueof[1],
+                    // The proof is the whole vector, so the scalar is absent if it is none of its elements
+                    builder.not(scalar_is_member),
+                    root_is_valid,
+                ];
+                and(builder, conditions)
+            }, // NotContainsFromEntries
         ]
         .iter()
         .enumerate()
@@ -348,7 +439,7 @@ impl<const NS: usize, const VL: usize> OpListTarget<NS, VL> {
         let statement_table: <StatementRef as StatementOrRef>::StatementTable =
             input_and_output_pod_list
                 .iter()
-                .map(|(pod_name, pod)| (pod_name.clone(), pod.payload.statements_map.clone()))
+                .map(|(pod_name, pod)| (pod_name.clone(), pod.payload.statements_map().clone()))
                 .collect();
 
         zip(&self.0, op_list.sort(&input_and_output_pod_list).0).try_for_each(
@@ -12,7 +12,9 @@ use std::iter::zip;
 
 use super::{statement::StatementTarget, util::vector_ref};
 use crate::{
-    pod::{util::hash_string_to_field, PODProof, Statement, POD, SIGNER_PK_KEY},
+    pod::{
+        statement::StatementId, util::hash_string_to_field, PODProof, Statement, POD, SIGNER_PK_KEY,
+    },
     signature::schnorr_prover::{
         MessageTarget, SchnorrBuilder, SchnorrPublicKeyTarget, SchnorrSignatureTarget,
         SignatureVerifierBuilder,
@@ -128,13 +130,13 @@ impl SchnorrPODTarget {
     }
     pub fn set_witness(&self, pw: &mut PartialWitness<GoldilocksField>, pod: &POD) -> Result<()> {
         // Assign payload witness.
-        zip(&self.payload, &pod.payload.statements_list)
+        zip(&self.payload, pod.payload.statements_list())
             .try_for_each(|(s_target, (_, s))| s_target.set_witness(pw, s))?;
         // Assign signer's public key index witness.
-        let pk_statement_name = format!("VALUEOF:{}", SIGNER_PK_KEY);
+        let pk_statement_name = StatementId::value_of(SIGNER_PK_KEY).to_string();
         let pk_index = pod
             .payload
-            .statements_list
+            .statements_list()
             .iter()
             .enumerate()
             .filter(|(_, (s_name, _))| s_name == &pk_statement_name)
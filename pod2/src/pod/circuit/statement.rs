@@ -212,6 +212,23 @@ impl StatementTarget {
         }
     }
 
+    pub fn not_contains(
+        builder: &mut CircuitBuilder<F, D>,
+        statement1_target: StatementTarget,
+        statement2_target: StatementTarget,
+    ) -> Self {
+        Self {
+            predicate: builder.constant(Statement::NOT_CONTAINS),
+            origin1: statement1_target.origin1,
+            key1: statement1_target.key1,
+            origin2: statement2_target.origin1,
+            key2: statement2_target.key1,
+            origin3: OriginTarget::none(builder),
+            key3: builder.zero(),
+            value: builder.zero(),
+        }
+    }
+
     pub fn rename_contained_by(
         builder: &mut CircuitBuilder<F, D>,
         statement1_target: StatementTarget,
@@ -283,6 +300,60 @@ impl StatementTarget {
         }
     }
 
+    pub fn sub_of(
+        builder: &mut CircuitBuilder<F, D>,
+        statement1_target: StatementTarget,
+        statement2_target: StatementTarget,
+        statement3_target: StatementTarget,
+    ) -> Self {
+        Self {
+            predicate: builder.constant(Statement::SUB_OF),
+            origin1: statement1_target.origin1,
+            key1: statement1_target.key1,
+            origin2: statement2_target.origin1,
+            key2: statement2_target.key1,
+            origin3: statement3_target.origin1,
+            key3: statement3_target.key1,
+            value: builder.zero(),
+        }
+    }
+
+    pub fn div_of(
+        builder: &mut CircuitBuilder<F, D>,
+        statement1_target: StatementTarget,
+        statement2_target: StatementTarget,
+        statement3_target: StatementTarget,
+    ) -> Self {
+        Self {
+            predicate: builder.constant(Statement::DIV_OF),
+            origin1: statement1_target.origin1,
+            key1: statement1_target.key1,
+            origin2: statement2_target.origin1,
+            key2: statement2_target.key1,
+            origin3: statement3_target.origin1,
+            key3: statement3_target.key1,
+            value: builder.zero(),
+        }
+    }
+
+    pub fn min_of(
+        builder: &mut CircuitBuilder<F, D>,
+        statement1_target: StatementTarget,
+        statement2_target: StatementTarget,
+        statement3_target: StatementTarget,
+    ) -> Self {
+        Self {
+            predicate: builder.constant(Statement::MIN_OF),
+            origin1: statement1_target.origin1,
+            key1: statement1_target.key1,
+            origin2: statement2_target.origin1,
+            key2: statement2_target.key1,
+            origin3: statement3_target.origin1,
+            key3: statement3_target.key1,
+            value: builder.zero(),
+        }
+    }
+
     pub fn lt(
         builder: &mut CircuitBuilder<F, D>,
         statement1_target: StatementTarget,
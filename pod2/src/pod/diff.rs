@@ -0,0 +1,129 @@
+use std::{collections::BTreeMap, fmt};
+
+use super::{Statement, POD};
+
+/// Differences between the statements of two PODs, matched by statement name. Padding
+/// statements are ignored, so PODs with a different number of them can still be equal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PodDiff {
+    pub only_in_self: Vec<(String, Statement)>,
+    pub only_in_other: Vec<(String, Statement)>,
+    /// Name and the statement of each POD, for names both PODs have.
+    pub mismatches: Vec<(String, Statement, Statement)>,
+}
+
+impl PodDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() && self.mismatches.is_empty()
+    }
+}
+
+impl POD {
+    /// Statements only in `self`, only in `other`, and those with the same name that differ.
+    /// Each list is sorted by name.
+    pub fn diff(&self, other: &POD) -> PodDiff {
+        let statements = |pod: &POD| {
+            pod.payload
+                .statements_map()
+                .iter()
+                .filter(|(_, statement)| **statement != Statement::None)
+                .map(|(name, statement)| (name.clone(), statement.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let (ours, theirs) = (statements(self), statements(other));
+
+        let mut diff = PodDiff::default();
+        for (name, statement) in &ours {
+            match theirs.get(name) {
+                None => diff.only_in_self.push((name.clone(), statement.clone())),
+                Some(other_statement) if other_statement != statement => {
+                    diff.mismatches
+                        .push((name.clone(), statement.clone(), other_statement.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        diff.only_in_other = theirs
+            .into_iter()
+            .filter(|(name, _)| !ours.contains_key(name))
+            .collect();
+        diff
+    }
+}
+
+/// `-` for statements only in the first POD, `+` for those only in the second and `~` for
+/// mismatches, e.g. `~ VALUEOF:age: VALUEOF: _SELF.age = 30 → VALUEOF: _SELF.age = 31`.
+impl fmt::Display for PodDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = self
+            .only_in_self
+            .iter()
+            .map(|(name, statement)| format!("- {}: {}", name, statement))
+            .chain(
+                self.only_in_other
+                    .iter()
+                    .map(|(name, statement)| format!("+ {}: {}", name, statement)),
+            )
+            .chain(
+                self.mismatches
+                    .iter()
+                    .map(|(name, s1, s2)| format!("~ {}: {} → {}", name, s1, s2)),
+            );
+        if let Some(first) = lines.next() {
+            write!(f, "{}", first)?;
+        }
+        lines.try_for_each(|line| write!(f, "\n{}", line))
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::*;
+    use crate::pod::{entry::Entry, gadget::GadgetID};
+    use crate::signature::schnorr::SchnorrSecretKey;
+
+    #[test]
+    fn diff_test() -> Result<()> {
+        let sk = SchnorrSecretKey::from_u64(25);
+        let entry = |key: &str, value: u64| Entry::new_from_scalar(key, GoldilocksField(value));
+        let pod1 = POD::execute_schnorr_gadget::<4, 0>(&[entry("age", 30), entry("id", 7)], &sk)?;
+        let pod2 = POD::execute_schnorr_gadget::<4, 0>(
+            &[entry("age", 31), entry("id", 7), entry("zip", 1234)],
+            &sk,
+        )?;
+
+        assert!(pod1.diff(&pod1).is_empty());
+        assert_eq!(pod1.diff(&pod1).to_string(), "");
+
+        let diff = pod1.diff(&pod2);
+        let value_of =
+            |key: &str, value: u64| Statement::from_entry(&entry(key, value), GadgetID::SCHNORR16);
+        assert_eq!(
+            diff,
+            PodDiff {
+                only_in_self: vec![],
+                only_in_other: vec![("VALUEOF:zip".to_string(), value_of("zip", 1234))],
+                mismatches: vec![(
+                    "VALUEOF:age".to_string(),
+                    value_of("age", 30),
+                    value_of("age", 31)
+                )],
+            }
+        );
+        assert_eq!(
+            diff.to_string(),
+            "+ VALUEOF:zip: VALUEOF: _SELF.zip = 1234\n\
+             ~ VALUEOF:age: VALUEOF: _SELF.age = 30 → VALUEOF: _SELF.age = 31"
+        );
+
+        // The other way around, the extra statement is only in self
+        let diff = pod2.diff(&pod1);
+        assert_eq!(diff.only_in_self.len(), 1);
+        assert!(diff.only_in_other.is_empty());
+        assert_eq!(diff.mismatches[0].1, value_of("age", 31));
+        Ok(())
+    }
+}
@@ -1,10 +1,28 @@
 use anyhow::{anyhow, Result};
+#[cfg(feature = "prover")]
 use parcnet_pod::pod::PodValue;
 use plonky2::field::goldilocks_field::GoldilocksField;
 
 use super::value::ScalarOrVec;
 use crate::pod::{util::hash_string_to_field, value::HashableEntryValue};
 
+/// Prefix of the entries recording the type of the values introduced from POD1s, eg:
+/// `_type:owner` for `owner`, so that the string "5" and the integer 5 can be told apart.
+pub const POD1_TYPE_PREFIX: &str = "_type:";
+
+/// Names of the POD1 value types, as recorded (hashed) in type entries.
+pub const POD1_VALUE_TYPES: [&str; 9] = [
+    "string",
+    "int",
+    "cryptographic",
+    "eddsa_pubkey",
+    "boolean",
+    "bytes",
+    "date",
+    "null",
+    "dictionary",
+];
+
 /// An Entry, which is just a key-value pair.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Entry {
@@ -27,6 +45,7 @@ impl Entry {
         }
     }
 
+    #[cfg(feature = "prover")]
     pub fn new_from_pod_value(key: &str, pod_value: &PodValue) -> Self {
         Self {
             key: key.to_string(),
@@ -34,6 +53,48 @@ impl Entry {
         }
     }
 
+    /// Converts a POD1 value, along with the entry recording its type. Unlike
+    /// `new_from_pod_value`, errors on values that can't be converted instead of panicking.
+    #[cfg(feature = "prover")]
+    pub fn new_typed_from_pod_value(key: &str, pod_value: &PodValue) -> Result<[Self; 2]> {
+        let value_type = match pod_value {
+            PodValue::String(_) => "string",
+            PodValue::Int(_) => "int",
+            PodValue::Cryptographic(_) => "cryptographic",
+            PodValue::EdDSAPublicKey(_) => "eddsa_pubkey",
+            PodValue::Boolean(_) => "boolean",
+            PodValue::Bytes(_) => "bytes",
+            PodValue::Date(_) => "date",
+            PodValue::Null => "null",
+            PodValue::Dictionary(_) => "dictionary",
+        };
+        match pod_value {
+            PodValue::Int(_) | PodValue::Cryptographic(_) | PodValue::Boolean(_) => Ok(()),
+            PodValue::Date(d) => i64::try_from(d.unix_timestamp_nanos())
+                .map(|_| ())
+                .map_err(|_| anyhow!("date {} is out of range", d)),
+            _ => pod_value
+                .hash()
+                .map(|_| ())
+                .map_err(|e| anyhow!("{} value could not be hashed: {}", value_type, e)),
+        }?;
+
+        Ok([
+            Self::new_from_pod_value(key, pod_value),
+            Self::new_from_scalar(
+                &format!("{}{}", POD1_TYPE_PREFIX, key),
+                hash_string_to_field(value_type),
+            ),
+        ])
+    }
+
+    /// Inverse of the type recording done by `new_typed_from_pod_value`.
+    pub fn pod1_value_type(type_value: &ScalarOrVec) -> Option<&'static str> {
+        POD1_VALUE_TYPES
+            .into_iter()
+            .find(|value_type| *type_value == ScalarOrVec::Scalar(hash_string_to_field(value_type)))
+    }
+
     /// Pads an entry's value if it is a vector. Padding is chosen so
     /// as to define the same set as the original vector.
     pub fn pad_if_vec<const VL: usize>(&self) -> Result<Self> {
@@ -1,14 +1,22 @@
 use core::fmt;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "prover")]
 pub mod introducer;
+#[cfg(feature = "prover")]
 pub mod opexecutor;
+#[cfg(feature = "prover")]
 pub mod plonky_pod;
+#[cfg(feature = "prover")]
 pub mod schnorr_pod;
 
+#[cfg(feature = "prover")]
 pub use introducer::IntroducerCircuit;
+#[cfg(feature = "prover")]
 pub use opexecutor::OpExecutorGadget;
+#[cfg(feature = "prover")]
 pub use plonky_pod::PlonkyButNotPlonkyGadget;
+#[cfg(feature = "prover")]
 pub use schnorr_pod::SchnorrPODGadget;
 
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Default)]
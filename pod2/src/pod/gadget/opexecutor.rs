@@ -136,7 +136,7 @@ impl<const NP: usize, const NS: usize, const VL: usize> OpsExecutorTrait
         // Set POD targets.
         // TODO: Connect these to the POD targets that go into the inner and recursion circuits instead!
         zip(&targets.0, &input.0.pods_list).try_for_each(|(s_targets, (_, pod))| {
-            let pod_statements = &pod.payload.statements_list;
+            let pod_statements = pod.payload.statements_list();
             zip(s_targets, pod_statements)
                 .try_for_each(|(s_target, (_, s))| s_target.set_witness(pw, s))
         })?;
@@ -197,7 +197,7 @@ mod tests {
                 Entry::new_from_scalar("s1", GoldilocksField(55)),
                 Entry::new_from_scalar("s2", GoldilocksField(56)),
             ],
-            &SchnorrSecretKey { sk: 27 },
+            &SchnorrSecretKey::from_u64(27),
         )?;
         let schnorr_pod2_name = "Test POD 2".to_string();
         let schnorr_pod2 = POD::execute_schnorr_gadget::<NS, VL>(
@@ -205,7 +205,7 @@ mod tests {
                 Entry::new_from_scalar("s3", GoldilocksField(57)),
                 Entry::new_from_scalar("s4", GoldilocksField(55)),
             ],
-            &SchnorrSecretKey { sk: 29 },
+            &SchnorrSecretKey::from_u64(29),
         )?;
 
         let schnorr_pod3_name = "Test POD 3".to_string();
@@ -214,7 +214,7 @@ mod tests {
                 Entry::new_from_scalar("s0", GoldilocksField(57)),
                 Entry::new_from_scalar("s-1", GoldilocksField(55)),
             ],
-            &SchnorrSecretKey { sk: 24 },
+            &SchnorrSecretKey::from_u64(24),
         )?;
 
         let schnorr_pod4_name = "Test POD 4".to_string();
@@ -226,7 +226,7 @@ mod tests {
                 ),
                 Entry::new_from_scalar("what", GoldilocksField(5)),
             ],
-            &SchnorrSecretKey { sk: 20 },
+            &SchnorrSecretKey::from_u64(20),
         )?;
 
         let schnorr_pod5_name = "Test POD 5".to_string();
@@ -235,7 +235,7 @@ mod tests {
                 Entry::new_from_scalar("who", GoldilocksField(111)),
                 Entry::new_from_scalar("what", GoldilocksField(55 * 57)),
             ],
-            &SchnorrSecretKey { sk: 20 },
+            &SchnorrSecretKey::from_u64(20),
         )?;
 
         let schnorr_pod6_name = "Test POD 6".to_string();
@@ -247,7 +247,7 @@ mod tests {
                 ),
                 Entry::new_from_scalar("why", GoldilocksField(0)),
             ],
-            &SchnorrSecretKey { sk: 20 },
+            &SchnorrSecretKey::from_u64(20),
         )?;
 
         let oracle_pod_name = "Oracle POD".to_string();
@@ -417,7 +417,7 @@ mod tests {
                 &mut pw,
                 &targets,
                 &(gpg_input.clone(), op_list),
-                &oracle_pod.payload.statements_list,
+                &oracle_pod.payload.statements_list().to_vec(),
             )?;
 
             let data = builder.build::<C>();
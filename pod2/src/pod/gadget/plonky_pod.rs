@@ -204,7 +204,7 @@ where
 
         let statement_check = input_pods
             .iter()
-            .all(|(_, pod)| pod.payload.statements_list.len() == NS);
+            .all(|(_, pod)| pod.payload.statements_list().len() == NS);
         if !statement_check {
             return Err(anyhow!(
                 "All input PODs must contain exactly {} statements.",
@@ -221,12 +221,12 @@ where
 
         // TODO: Constructor
         let dummy_plonky_pod = POD {
-            payload: PODPayload {
-                statements_list: (0..NS)
+            payload: PODPayload::from_parts(
+                (0..NS)
                     .map(|i| (format!("Dummy statement {}", i), Statement::None))
                     .collect(),
-                statements_map: std::collections::HashMap::new(),
-            },
+                std::collections::HashMap::new(),
+            ),
             proof: crate::pod::PODProof::Plonky(prover_params.dummy_proof.clone()),
             proof_type: GadgetID::PLONKY,
         };
@@ -236,7 +236,7 @@ where
             &(0..(NS - 1))
                 .map(|i| Entry::new_from_scalar(&format!("Dummy entry {}", i), GoldilocksField(0)))
                 .collect::<Vec<_>>(),
-            &SchnorrSecretKey { sk: 0 },
+            &SchnorrSecretKey::from_u64(0),
         )?;
 
         // TODO do the same with L POD1-Introducer PODs
@@ -296,7 +296,8 @@ where
         // Output Plonky POD should have this as its statement_list in its payload.
         let output_statements: StatementList = POD::execute_oracle_gadget(&gpg_input, &op_list.0)?
             .payload
-            .statements_list;
+            .statements_list()
+            .to_vec();
 
         // Verify SchnorrPODs in circuit by routing the first `M` elements of `padded_pod_list`
         // (ignoring the string part of the tuple) and the first `M` elements of `selectors` into the
@@ -372,17 +373,17 @@ where
         // );
 
         Ok(POD {
-            payload: PODPayload {
-                statements_list: output_statements.clone(),
-                statements_map: output_statements.into_iter().collect(),
-            },
+            payload: PODPayload::from_parts(
+                output_statements.clone(),
+                output_statements.into_iter().collect(),
+            ),
             proof: PODProof::Plonky(plonky_proof.proof),
             proof_type: GadgetID::PLONKY,
         })
     }
 
     /// This is a helper method that just verifies the PlonkyProof contained inside the POD
-    pub fn verify_plonky_pod(verifier_data: VerifierCircuitData<F, C, D>, pod: POD) -> Result<()> {
+    pub fn verify_plonky_pod(verifier_data: &VerifierCircuitData<F, C, D>, pod: POD) -> Result<()> {
         // get the PlonkyProof from the pod.proof
         let proof = match pod.proof.clone() {
             PODProof::Plonky(p) => Ok(p),
@@ -393,8 +394,8 @@ where
         // `pod.payload.statements_list:Vec<(String,Statement)>`
         let public_inputs: Vec<F> = pod
             .payload
-            .statements_list
-            .into_iter()
+            .statements_list()
+            .iter()
             .flat_map(|v| v.1.to_fields())
             .collect();
 
@@ -403,7 +404,7 @@ where
 
     /// This is a helper method that just verifies the given PlonkyProof
     pub fn verify_plonky_proof(
-        verifier_data: VerifierCircuitData<F, C, D>,
+        verifier_data: &VerifierCircuitData<F, C, D>,
         proof: PlonkyProof,
         public_inputs: Vec<F>,
     ) -> Result<()> {
@@ -458,7 +459,7 @@ mod tests {
                 Entry::new_from_scalar("s1", GoldilocksField(55)),
                 Entry::new_from_scalar("s2", GoldilocksField(56)),
             ],
-            &SchnorrSecretKey { sk: 27 },
+            &SchnorrSecretKey::from_u64(27),
         )?;
         let schnorr_pod2_name = "Test POD 2".to_string();
         let schnorr_pod2 = POD::execute_schnorr_gadget::<NS, VL>(
@@ -466,7 +467,7 @@ mod tests {
                 Entry::new_from_scalar("s3", GoldilocksField(57)),
                 Entry::new_from_scalar("s4", GoldilocksField(55)),
             ],
-            &SchnorrSecretKey { sk: 29 },
+            &SchnorrSecretKey::from_u64(29),
         )?;
 
         let pods_list = vec![
@@ -549,7 +550,7 @@ mod tests {
         println!("PlonkyButNotPlonkyGadget::execute(): {:?}", start.elapsed());
 
         // verify the new_pod's plonky2 proof
-        PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::verify_plonky_pod(verifier_data, new_pod)?;
+        PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::verify_plonky_pod(&verifier_data, new_pod)?;
 
         // TODO do a 2nd iteration where the generated plonky2-pod is (recursively) verified
         Ok(())
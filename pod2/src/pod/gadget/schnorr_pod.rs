@@ -74,8 +74,10 @@ mod tests {
 
         let scalar1 = GoldilocksField(36);
         let entry1 = Entry::new_from_scalar("some key", scalar1);
-        let schnorr_pod3 =
-            POD::execute_schnorr_gadget::<NS, VL>(&[entry1.clone()], &SchnorrSecretKey { sk: 25 })?;
+        let schnorr_pod3 = POD::execute_schnorr_gadget::<NS, VL>(
+            &[entry1.clone()],
+            &SchnorrSecretKey::from_u64(25),
+        )?;
         let payload_hash = schnorr_pod3.payload.hash_payload();
 
         let config = CircuitConfig::standard_recursion_config();
@@ -128,7 +130,7 @@ mod tests {
                 .map(|i| {
                     let scalar1 = GoldilocksField(36 + (i as u64));
                     let entry1 = Entry::new_from_scalar("some key", scalar1);
-                    POD::execute_schnorr_gadget(&vec![entry1.clone()], &SchnorrSecretKey { sk: 25 })
+                    POD::execute_schnorr_gadget(&vec![entry1.clone()], &SchnorrSecretKey::from_u64(25))
                 })
                 .collect();
 
@@ -0,0 +1,450 @@
+use anyhow::{anyhow, Result};
+use plonky2::field::{
+    goldilocks_field::GoldilocksField,
+    types::{Field, Field64, PrimeField64},
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use super::{
+    gadget::GadgetID,
+    origin::Origin,
+    payload::{HashablePayload, PODPayload},
+    statement::{AnchoredKey, Statement},
+    value::ScalarOrVec,
+    PODProof, POD,
+};
+use crate::signature::schnorr::SchnorrSignature;
+
+// JSON wire format for PODs.
+//
+// Version 1 looks like
+// {
+//   "version": 1,
+//   "schema": "schnorr16" | "oracle" | "plonky2",
+//   "content_id": "<64 hex digits>",
+//   "statements": [{ "name": ..., "predicate": ..., "args": [anchored keys], "value": ... }],
+//   "proof": ...
+// }
+// where field elements are decimal strings, anchored keys are
+// { "origin": { "id": ..., "name": ..., "gadget": ... }, "key": ... } and values are
+// { "scalar": ... } or { "vector": [...] }. Schnorr and oracle proofs are { "s": ..., "e": ... },
+// while plonky2 proofs are kept in their serde form.
+
+pub const POD_JSON_VERSION: u32 = 1;
+
+impl POD {
+    /// Content ID of the POD, i.e. its payload hash as a hex string.
+    pub fn content_id(&self) -> String {
+        self.payload
+            .hash_payload()
+            .elements
+            .iter()
+            .map(|x| format!("{:016x}", x.to_canonical_u64()))
+            .collect()
+    }
+
+    pub fn to_json_v1(&self) -> Result<String> {
+        let (schema, proof) = match (&self.proof, self.proof_type) {
+            (PODProof::Schnorr(sig), GadgetID::SCHNORR16) => ("schnorr16", signature_to_json(sig)),
+            (PODProof::Oracle(sig), GadgetID::ORACLE) => ("oracle", signature_to_json(sig)),
+            (PODProof::Plonky(proof), GadgetID::PLONKY) => {
+                ("plonky2", serde_json::to_value(proof)?)
+            }
+            (_, proof_type) => {
+                return Err(anyhow!(
+                    "Proof does not match proof type {} of POD.",
+                    proof_type
+                ))
+            }
+        };
+        let statements = self
+            .payload
+            .statements_list()
+            .iter()
+            .map(|(name, statement)| statement_to_json(name, statement))
+            .collect::<Vec<_>>();
+        Ok(serde_json::to_string_pretty(&json!({
+            "version": POD_JSON_VERSION,
+            "schema": schema,
+            "content_id": self.content_id(),
+            "statements": statements,
+            "proof": proof,
+        }))?)
+    }
+
+    pub fn from_json_v1(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        let envelope = as_object(&value, "POD")?;
+        let version = get(envelope, "version")?
+            .as_u64()
+            .ok_or(anyhow!("POD version must be a number."))?;
+        if version != POD_JSON_VERSION as u64 {
+            return Err(anyhow!(
+                "Unsupported POD JSON version {}; expected version {}.",
+                version,
+                POD_JSON_VERSION
+            ));
+        }
+
+        let statements_list = as_array(get(envelope, "statements")?, "statements")?
+            .iter()
+            .map(statement_from_json)
+            .collect::<Result<Vec<_>>>()?;
+        let statements_map = statements_list.iter().cloned().collect::<HashMap<_, _>>();
+        if statements_map.len() != statements_list.len() {
+            return Err(anyhow!("POD JSON contains duplicate statement names."));
+        }
+
+        let proof = get(envelope, "proof")?;
+        let (proof, proof_type) = match get_str(envelope, "schema")? {
+            "schnorr16" => (
+                PODProof::Schnorr(signature_from_json(proof)?),
+                GadgetID::SCHNORR16,
+            ),
+            "oracle" => (
+                PODProof::Oracle(signature_from_json(proof)?),
+                GadgetID::ORACLE,
+            ),
+            "plonky2" => (
+                PODProof::Plonky(serde_json::from_value(proof.clone())?),
+                GadgetID::PLONKY,
+            ),
+            schema => return Err(anyhow!("Unknown POD schema {}.", schema)),
+        };
+
+        let pod = Self {
+            payload: PODPayload::from_parts(statements_list, statements_map),
+            proof,
+            proof_type,
+        };
+        let content_id = get_str(envelope, "content_id")?;
+        if content_id != pod.content_id() {
+            return Err(anyhow!(
+                "Content ID {} does not match payload hash {}.",
+                content_id,
+                pod.content_id()
+            ));
+        }
+        Ok(pod)
+    }
+}
+
+fn field_to_json(x: &GoldilocksField) -> Value {
+    Value::String(x.to_canonical_u64().to_string())
+}
+
+fn field_from_json(value: &Value) -> Result<GoldilocksField> {
+    let x = value
+        .as_str()
+        .ok_or(anyhow!("Field element {} must be a decimal string.", value))?
+        .parse::<u64>()
+        .map_err(|e| anyhow!("Invalid field element {}: {}", value, e))?;
+    if x >= GoldilocksField::ORDER {
+        return Err(anyhow!("Field element {} is not canonical.", x));
+    }
+    Ok(GoldilocksField::from_canonical_u64(x))
+}
+
+fn signature_to_json(sig: &SchnorrSignature) -> Value {
+    json!({ "s": sig.s.to_string(), "e": sig.e.to_string() })
+}
+
+fn signature_from_json(value: &Value) -> Result<SchnorrSignature> {
+    let sig = as_object(value, "signature")?;
+    let parse = |name: &str| {
+        get_str(sig, name)?
+            .parse::<u64>()
+            .map_err(|e| anyhow!("Invalid signature component {}: {}", name, e))
+    };
+    Ok(SchnorrSignature {
+        s: parse("s")?,
+        e: parse("e")?,
+    })
+}
+
+fn anchored_key_to_json(AnchoredKey(origin, key): &AnchoredKey) -> Value {
+    json!({
+        "origin": {
+            "id": field_to_json(&origin.origin_id),
+            "name": origin.origin_name,
+            "gadget": origin.gadget_id.to_string(),
+        },
+        "key": key,
+    })
+}
+
+fn anchored_key_from_json(value: &Value) -> Result<AnchoredKey> {
+    let anchkey = as_object(value, "anchored key")?;
+    let origin = as_object(get(anchkey, "origin")?, "origin")?;
+    let gadget_id = match get_str(origin, "gadget")? {
+        "NONE" => GadgetID::NONE,
+        "SCHNORR16" => GadgetID::SCHNORR16,
+        "ORACLE" => GadgetID::ORACLE,
+        "PLONKY" => GadgetID::PLONKY,
+        gadget => return Err(anyhow!("Unknown gadget ID {}.", gadget)),
+    };
+    Ok(AnchoredKey(
+        Origin::new(
+            field_from_json(get(origin, "id")?)?,
+            get_str(origin, "name")?.to_string(),
+            gadget_id,
+        ),
+        get_str(anchkey, "key")?.to_string(),
+    ))
+}
+
+fn statement_to_json(name: &str, statement: &Statement) -> Value {
+    let mut value = json!({
+        "name": name,
+        "predicate": statement.predicate(),
+        "args": statement
+            .anchored_keys()
+            .iter()
+            .map(anchored_key_to_json)
+            .collect::<Vec<_>>(),
+    });
+    if let Statement::ElementAt(_, index, x) = statement {
+        value["index"] = json!(index);
+        value["value"] = json!({ "scalar": field_to_json(x) });
+    }
+    if let Statement::ValueOf(_, v) = statement {
+        value["value"] = match v {
+            ScalarOrVec::Scalar(x) => json!({ "scalar": field_to_json(x) }),
+            ScalarOrVec::Vector(xs) => {
+                json!({ "vector": xs.iter().map(field_to_json).collect::<Vec<_>>() })
+            }
+        };
+    }
+    value
+}
+
+fn statement_from_json(value: &Value) -> Result<(String, Statement)> {
+    let statement = as_object(value, "statement")?;
+    let name = get_str(statement, "name")?.to_string();
+    let predicate = get_str(statement, "predicate")?;
+    let args = as_array(get(statement, "args")?, "args")?
+        .iter()
+        .map(anchored_key_from_json)
+        .collect::<Result<Vec<_>>>()?;
+    let statement = match (predicate, args.as_slice()) {
+        ("NONE", []) => Statement::None,
+        ("VALUEOF", [ak]) => {
+            let value = as_object(get(statement, "value")?, "value")?;
+            let value = match (value.get("scalar"), value.get("vector")) {
+                (Some(x), None) => ScalarOrVec::Scalar(field_from_json(x)?),
+                (None, Some(xs)) => ScalarOrVec::Vector(
+                    as_array(xs, "vector")?
+                        .iter()
+                        .map(field_from_json)
+                        .collect::<Result<Vec<_>>>()?,
+                ),
+                _ => return Err(anyhow!("Value of {} must be a scalar or a vector.", name)),
+            };
+            Statement::ValueOf(ak.clone(), value)
+        }
+        ("EQUAL", [ak1, ak2]) => Statement::Equal(ak1.clone(), ak2.clone()),
+        ("NOTEQUAL", [ak1, ak2]) => Statement::NotEqual(ak1.clone(), ak2.clone()),
+        ("GT", [ak1, ak2]) => Statement::Gt(ak1.clone(), ak2.clone()),
+        ("LT", [ak1, ak2]) => Statement::Lt(ak1.clone(), ak2.clone()),
+        ("CONTAINS", [ak1, ak2]) => Statement::Contains(ak1.clone(), ak2.clone()),
+        ("NOTCONTAINS", [ak1, ak2]) => Statement::NotContains(ak1.clone(), ak2.clone()),
+        ("SUMOF", [ak1, ak2, ak3]) => Statement::SumOf(ak1.clone(), ak2.clone(), ak3.clone()),
+        ("PRODUCTOF", [ak1, ak2, ak3]) => {
+            Statement::ProductOf(ak1.clone(), ak2.clone(), ak3.clone())
+        }
+        ("MAXOF", [ak1, ak2, ak3]) => Statement::MaxOf(ak1.clone(), ak2.clone(), ak3.clone()),
+        ("SUBOF", [ak1, ak2, ak3]) => Statement::SubOf(ak1.clone(), ak2.clone(), ak3.clone()),
+        ("DIVOF", [ak1, ak2, ak3]) => Statement::DivOf(ak1.clone(), ak2.clone(), ak3.clone()),
+        ("MINOF", [ak1, ak2, ak3]) => Statement::MinOf(ak1.clone(), ak2.clone(), ak3.clone()),
+        ("INRANGE", [ak1, ak2, ak3]) => Statement::InRange(ak1.clone(), ak2.clone(), ak3.clone()),
+        ("QUOTIENTOF", [ak1, ak2, ak3]) => {
+            Statement::QuotientOf(ak1.clone(), ak2.clone(), ak3.clone())
+        }
+        ("REMAINDEROF", [ak1, ak2, ak3]) => {
+            Statement::RemainderOf(ak1.clone(), ak2.clone(), ak3.clone())
+        }
+        ("ELEMENTAT", [ak]) => {
+            let index = get(statement, "index")?
+                .as_u64()
+                .ok_or(anyhow!("Index of {} must be a number.", name))?;
+            let value = as_object(get(statement, "value")?, "value")?;
+            let x = field_from_json(
+                value
+                    .get("scalar")
+                    .ok_or(anyhow!("Value of {} must be a scalar.", name))?,
+            )?;
+            Statement::ElementAt(ak.clone(), index as usize, x)
+        }
+        _ => {
+            return Err(anyhow!(
+                "Invalid statement {}: predicate {} with {} arguments.",
+                name,
+                predicate,
+                args.len()
+            ))
+        }
+    };
+    Ok((name, statement))
+}
+
+fn get<'a>(object: &'a Map<String, Value>, field: &str) -> Result<&'a Value> {
+    object
+        .get(field)
+        .ok_or(anyhow!("Missing field {} in POD JSON.", field))
+}
+
+fn get_str<'a>(object: &'a Map<String, Value>, field: &str) -> Result<&'a str> {
+    get(object, field)?
+        .as_str()
+        .ok_or(anyhow!("Field {} in POD JSON must be a string.", field))
+}
+
+fn as_object<'a>(value: &'a Value, what: &str) -> Result<&'a Map<String, Value>> {
+    value
+        .as_object()
+        .ok_or(anyhow!("Expected {} to be a JSON object.", what))
+}
+
+fn as_array<'a>(value: &'a Value, what: &str) -> Result<&'a Vec<Value>> {
+    value
+        .as_array()
+        .ok_or(anyhow!("Expected {} to be a JSON array.", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pod::entry::Entry;
+    use crate::pod::SIGNER_PK_KEY;
+    use crate::signature::schnorr::{SchnorrSecretKey, SchnorrSigner};
+
+    const NS: usize = 3;
+    const VL: usize = 10;
+
+    #[cfg(feature = "prover")]
+    fn schnorr_pod() -> Result<POD> {
+        POD::execute_schnorr_gadget::<NS, VL>(
+            &[
+                Entry::new_from_scalar("apple", GoldilocksField(36)),
+                Entry::new_from_vec("basket", vec![GoldilocksField(36), GoldilocksField(52)]),
+            ],
+            &SchnorrSecretKey::from_u64(25),
+        )
+    }
+
+    #[test]
+    fn json_v1_golden_test() -> Result<()> {
+        // A POD with a fixed signature, so that its JSON is deterministic.
+        let mut statements = HashMap::new();
+        for entry in [
+            Entry::new_from_scalar("apple", GoldilocksField(36)),
+            Entry::new_from_vec("basket", vec![GoldilocksField(36), GoldilocksField(52)]),
+        ] {
+            statements.insert(
+                format!("VALUEOF:{}", entry.key),
+                Statement::from_entry(&entry, GadgetID::SCHNORR16),
+            );
+        }
+        let pod = POD {
+            payload: PODPayload::new(&statements),
+            proof: PODProof::Schnorr(SchnorrSignature { s: 1234, e: 5678 }),
+            proof_type: GadgetID::SCHNORR16,
+        };
+
+        let json = pod.to_json_v1()?;
+        assert_eq!(POD::from_json_v1(&json)?, pod);
+
+        // The fixture pins the content ID too, so that a change to the payload hash, which
+        // would make stored PODs fail to load, is caught.
+        let golden = include_str!("fixtures/schnorr_pod_v1.json");
+        assert_eq!(
+            serde_json::from_str::<Value>(&json)?,
+            serde_json::from_str::<Value>(golden)?
+        );
+        assert_eq!(POD::from_json_v1(golden)?, pod);
+
+        Ok(())
+    }
+
+    /// Doesn't use the prover, so that it also runs with `--no-default-features`.
+    #[test]
+    fn json_v1_verify_without_prover() -> Result<()> {
+        let sk = SchnorrSecretKey::from_u64(25);
+        let protocol = SchnorrSigner::new();
+        let mut statements = HashMap::new();
+        for entry in [
+            Entry::new_from_scalar("apple", GoldilocksField(36)),
+            Entry::new_from_scalar(SIGNER_PK_KEY, protocol.keygen(&sk).pk),
+        ] {
+            statements.insert(
+                format!("VALUEOF:{}", entry.key),
+                Statement::from_entry(&entry, GadgetID::SCHNORR16),
+            );
+        }
+        let payload = PODPayload::new(&statements);
+        let signature =
+            protocol.sign_with_nonce(payload.hash_payload().elements.as_ref(), &sk, 1234);
+        let pod = POD {
+            payload,
+            proof: PODProof::Schnorr(signature),
+            proof_type: GadgetID::SCHNORR16,
+        };
+
+        let deserialized = POD::from_json_v1(&pod.to_json_v1()?)?;
+        assert_eq!(deserialized, pod);
+        assert!(deserialized.verify::<0, 1, 0, 2, 0>()?);
+        assert_eq!(
+            POD::verify_batch_schnorr::<0, 1, 0, 2, 0>(&[&deserialized])?,
+            [true]
+        );
+
+        // A signature by another key doesn't verify
+        let mut forged = deserialized.clone();
+        forged.proof = PODProof::Schnorr(protocol.sign_with_nonce(
+            forged.payload.hash_payload().elements.as_ref(),
+            &SchnorrSecretKey::from_u64(26),
+            1234,
+        ));
+        assert!(!forged.verify::<0, 1, 0, 2, 0>()?);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn json_v1_rejects_unknown_version() -> Result<()> {
+        let mut value: Value = serde_json::from_str(&schnorr_pod()?.to_json_v1()?)?;
+        value["version"] = json!(2);
+        let err = POD::from_json_v1(&value.to_string()).unwrap_err();
+        assert!(err.to_string().contains("Unsupported POD JSON version 2"));
+
+        value.as_object_mut().unwrap().remove("version");
+        assert!(POD::from_json_v1(&value.to_string()).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn json_v1_rejects_tampered_payload() -> Result<()> {
+        let pod = schnorr_pod()?;
+        let mut value: Value = serde_json::from_str(&pod.to_json_v1()?)?;
+        let statements = value["statements"].as_array_mut().unwrap();
+        let apple = statements
+            .iter_mut()
+            .find(|s| s["name"] == "VALUEOF:apple")
+            .unwrap();
+        apple["value"]["scalar"] = json!("37");
+        let err = POD::from_json_v1(&value.to_string()).unwrap_err();
+        assert!(err.to_string().contains("does not match payload hash"));
+
+        // Field elements must be canonical.
+        let mut value: Value = serde_json::from_str(&pod.to_json_v1()?)?;
+        value["statements"][0]["args"][0]["origin"]["id"] =
+            json!(GoldilocksField::ORDER.to_string());
+        assert!(POD::from_json_v1(&value.to_string()).is_err());
+
+        Ok(())
+    }
+}
@@ -1,33 +1,54 @@
 use anyhow::anyhow;
 use anyhow::Result;
-use parcnet_pod::pod::{Pod, PodValue};
-use plonky2::field::goldilocks_field::GoldilocksField;
-use plonky2::field::types::Field;
 use serde::Deserialize;
 use serde::Serialize;
 
-use plonky2::field::types::PrimeField64;
-use std::collections::HashMap;
+#[cfg(feature = "prover")]
+use parcnet_pod::pod::{Pod, PodValue};
+#[cfg(feature = "prover")]
+use plonky2::field::{
+    goldilocks_field::GoldilocksField,
+    types::{Field, PrimeField64},
+};
+#[cfg(feature = "prover")]
+use plonky2::plonk::circuit_data::VerifierCircuitData;
+#[cfg(feature = "prover")]
+use rayon::prelude::*;
+#[cfg(feature = "prover")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "prover")]
+use std::sync::Arc;
 
-use crate::pod::gadget::{IntroducerCircuit, PlonkyButNotPlonkyGadget};
 use crate::pod::{
-    entry::Entry,
     gadget::GadgetID,
     payload::{HashablePayload, PODPayload},
+    statement::StatementId,
     value::ScalarOrVec,
 };
-use crate::recursion::{traits_examples::ExampleIntroducer, IntroducerCircuitTrait};
 use crate::signature::schnorr::{
     SchnorrPublicKey, SchnorrSecretKey, SchnorrSignature, SchnorrSigner,
 };
 use crate::PlonkyProof;
+#[cfg(feature = "prover")]
+use crate::{
+    pod::{
+        entry::Entry,
+        gadget::{IntroducerCircuit, PlonkyButNotPlonkyGadget},
+        statement::StatementRef,
+    },
+    recursion::{traits_examples::ExampleIntroducer, IntroducerCircuitTrait},
+    C, D, F,
+};
 
+pub use diff::PodDiff;
 pub use operation::Operation as Op;
 pub use operation::OperationCmd as OpCmd;
 pub use statement::Statement;
 
+pub mod diff;
 pub mod entry;
 pub mod gadget;
+pub mod json;
 pub mod operation;
 pub mod origin;
 pub mod payload;
@@ -36,6 +57,7 @@ pub mod util;
 pub mod value;
 
 // submodule
+#[cfg(feature = "prover")]
 pub mod circuit;
 pub use origin::Origin;
 
@@ -55,6 +77,49 @@ pub struct POD {
     pub proof_type: GadgetID,
 }
 
+#[cfg(all(test, feature = "prover"))]
+thread_local! {
+    /// Number of VerifierContexts built on the current thread, to check they get reused.
+    static VERIFIER_CONTEXT_BUILDS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// Verifier data for Plonky PODs. Building it requires computing the circuit_data, which takes
+/// a considerable amount of time, so it is built once and shared (cloning only bumps an Arc).
+#[cfg(feature = "prover")]
+#[derive(Clone)]
+pub struct VerifierContext<
+    const L: usize,
+    const M: usize,
+    const N: usize,
+    const NS: usize,
+    const VL: usize,
+> {
+    verifier_data: Arc<VerifierCircuitData<F, C, D>>,
+}
+
+#[cfg(feature = "prover")]
+impl<const L: usize, const M: usize, const N: usize, const NS: usize, const VL: usize>
+    VerifierContext<L, M, N, NS, VL>
+where
+    [(); L + M + N]:,
+    [(); L + N]:,
+{
+    pub fn new() -> Result<Self> {
+        // let pod1_circuit_data = IntroducerCircuit::circuit_data()?; // TODO
+        let pod1_circuit_data = ExampleIntroducer::circuit_data()?;
+        let pod1_verifier_data = pod1_circuit_data.verifier_data();
+        let circuit_data =
+            PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::circuit_data(pod1_verifier_data)?;
+
+        #[cfg(test)]
+        VERIFIER_CONTEXT_BUILDS.with(|builds| builds.set(builds.get() + 1));
+
+        Ok(Self {
+            verifier_data: Arc::new(circuit_data.verifier_data()),
+        })
+    }
+}
+
 impl POD {
     /// L: number of POD1-Introducer PODs
     /// M: number of PODs
@@ -82,18 +147,9 @@ impl POD {
 
                 let payload_hash = self.payload.hash_payload();
                 let protocol = SchnorrSigner::new();
+                let pk = self.signer_pk()?;
 
-                let pk: GoldilocksField = self
-                    .payload
-                    .statements_map
-                    .get(&format!("VALUEOF:{}", SIGNER_PK_KEY))
-                    .ok_or(anyhow!("No signer found in payload"))
-                    .and_then(|s| match s {
-                        Statement::ValueOf(_, ScalarOrVec::Scalar(v)) => Ok(*v),
-                        _ => Err(anyhow!("Invalid signer entry in payload")),
-                    })?;
-
-                Ok(protocol.verify(p, &payload_hash.elements.to_vec(), &SchnorrPublicKey { pk }))
+                Ok(protocol.verify(p, &payload_hash.elements.to_vec(), &pk))
             }
 
             PODProof::Oracle(p) => {
@@ -107,30 +163,142 @@ impl POD {
                 Ok(protocol.verify(
                     p,
                     &payload_hash.elements.to_vec(),
-                    &protocol.keygen(&SchnorrSecretKey { sk: 0 }), // hardcoded secret key
+                    &protocol.keygen(&SchnorrSecretKey::from_u64(0)), // hardcoded secret key
                 ))
             }
-            PODProof::Plonky(_p) => {
+            #[cfg(feature = "prover")]
+            PODProof::Plonky(_) => {
+                let ctx = VerifierContext::<L, M, N, NS, VL>::new()?;
+                self.verify_with_context(&ctx)
+            }
+            #[cfg(not(feature = "prover"))]
+            PODProof::Plonky(_) => Err(anyhow!(
+                "Verifying Plonky PODs requires the prover feature."
+            )),
+        }
+    }
+
+    /// Public key of the signer of a Schnorr POD, taken from its payload
+    fn signer_pk(&self) -> Result<SchnorrPublicKey> {
+        self.payload
+            .statements_map()
+            .get(&StatementId::value_of(SIGNER_PK_KEY).to_string())
+            .ok_or(anyhow!("No signer found in payload"))
+            .and_then(|s| match s {
+                Statement::ValueOf(_, ScalarOrVec::Scalar(v)) => Ok(SchnorrPublicKey { pk: *v }),
+                _ => Err(anyhow!("Invalid signer entry in payload")),
+            })
+    }
+
+    /// Verifies many PODs at once, returning whether each one is valid, in the same order.
+    /// Schnorr and Oracle PODs get their payloads hashed and their signatures checked in
+    /// parallel, while Plonky PODs are verified one after the other sharing a single
+    /// VerifierContext. Malformed PODs (eg: a Schnorr POD without a signer) are reported as
+    /// invalid rather than failing the whole batch. Without the prover feature, signatures are
+    /// checked one after the other and a batch containing Plonky PODs is an error.
+    pub fn verify_batch_schnorr<
+        const L: usize,
+        const M: usize,
+        const N: usize,
+        const NS: usize,
+        const VL: usize,
+    >(
+        pods: &[&POD],
+    ) -> Result<Vec<bool>>
+    where
+        [(); L + M + N]:,
+        [(); L + N]:,
+    {
+        let protocol = SchnorrSigner::new();
+        let oracle_pk = protocol.keygen(&SchnorrSecretKey::from_u64(0)); // hardcoded secret key
+
+        // Position in `pods` and (signature, payload hash, public key) of every well-formed
+        // signed POD. Plonky PODs are verified below, the rest stay invalid.
+        #[cfg(feature = "prover")]
+        let signed_pods = pods.par_iter();
+        #[cfg(not(feature = "prover"))]
+        let signed_pods = pods.iter();
+        let (indices, batch): (Vec<usize>, Vec<_>) = signed_pods
+            .enumerate()
+            .filter_map(|(i, pod)| {
+                let (sig, pk) = match (&pod.proof, pod.proof_type) {
+                    (PODProof::Schnorr(sig), GadgetID::SCHNORR16) => (*sig, pod.signer_pk().ok()?),
+                    (PODProof::Oracle(sig), GadgetID::ORACLE) => (*sig, oracle_pk),
+                    _ => return None,
+                };
+                Some((i, (sig, pod.payload.hash_payload().elements.to_vec(), pk)))
+            })
+            .unzip();
+
+        let mut valid = vec![false; pods.len()];
+        for (i, ok) in indices.into_iter().zip(protocol.verify_batch(&batch)) {
+            valid[i] = ok;
+        }
+
+        #[cfg(not(feature = "prover"))]
+        if pods
+            .iter()
+            .any(|pod| matches!(pod.proof, PODProof::Plonky(_)))
+        {
+            return Err(anyhow!(
+                "Verifying Plonky PODs requires the prover feature."
+            ));
+        }
+        #[cfg(feature = "prover")]
+        let mut ctx = None;
+        #[cfg(feature = "prover")]
+        for (i, pod) in pods.iter().enumerate() {
+            if let PODProof::Plonky(_) = pod.proof {
+                if ctx.is_none() {
+                    ctx = Some(VerifierContext::<L, M, N, NS, VL>::new()?);
+                }
+                valid[i] = pod
+                    .verify_with_context(ctx.as_ref().unwrap())
+                    .unwrap_or(false);
+            }
+        }
+        Ok(valid)
+    }
+}
+
+/// Creation of PODs, along with the verification of Plonky PODs, which needs the prover's
+/// circuit_data.
+#[cfg(feature = "prover")]
+impl POD {
+    /// Verifies the POD reusing the verifier data cached in `ctx`, which avoids rebuilding the
+    /// Plonky circuit on every call. Non-Plonky PODs don't need the context.
+    pub fn verify_with_context<
+        const L: usize,
+        const M: usize,
+        const N: usize,
+        const NS: usize,
+        const VL: usize,
+    >(
+        &self,
+        ctx: &VerifierContext<L, M, N, NS, VL>,
+    ) -> Result<bool>
+    where
+        [(); L + M + N]:,
+        [(); L + N]:,
+    {
+        match &self.proof {
+            PODProof::Plonky(_) => {
                 // ensure that the amount of statements match the NS parameter
-                assert_eq!(NS, self.payload.statements_list.len());
-
-                // TODO the verifier_data currently is computed here on the fly, but it will not be
-                // computed here and will be passed as parameter, bcs the circuit_data (needed to
-                // get the verifier_data on the fly) takes a considerable amount of time to
-                // compute. Need to think how we modify the interface to pass the verifier_data to
-                // this method.
-                // let pod1_circuit_data = IntroducerCircuit::circuit_data()?; // TODO
-                let pod1_circuit_data = ExampleIntroducer::circuit_data()?;
-                let pod1_verifier_data = pod1_circuit_data.verifier_data();
-                let circuit_data =
-                    PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::circuit_data(pod1_verifier_data)?;
-                let verifier_data = circuit_data.verifier_data();
+                if self.payload.statements_list().len() != NS {
+                    return Err(anyhow!(
+                        "Plonky POD has {} statements, expected {}.",
+                        self.payload.statements_list().len(),
+                        NS
+                    ));
+                }
+
                 PlonkyButNotPlonkyGadget::<L, M, N, NS, VL>::verify_plonky_pod(
-                    verifier_data,
+                    &ctx.verifier_data,
                     self.clone(),
                 )?;
                 Ok(true)
             }
+            _ => self.verify::<L, M, N, NS, VL>(),
         }
     }
 
@@ -138,7 +306,16 @@ impl POD {
         entries: &[Entry],
         sk: &SchnorrSecretKey,
     ) -> Result<Self> {
-        let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
+        Self::execute_schnorr_gadget_with_rng::<NS, VL>(entries, sk, &mut rand::thread_rng())
+    }
+
+    /// Same as `execute_schnorr_gadget`, drawing the signature nonce from `rng`, eg: a seeded
+    /// one for reproducible pods.
+    pub fn execute_schnorr_gadget_with_rng<const NS: usize, const VL: usize>(
+        entries: &[Entry],
+        sk: &SchnorrSecretKey,
+        rng: &mut impl rand::Rng,
+    ) -> Result<Self> {
         let protocol = SchnorrSigner::new();
 
         let kv_pairs = [
@@ -158,7 +335,7 @@ impl POD {
                 .iter()
                 .map(|e| {
                     (
-                        format!("VALUEOF:{}", e.key),
+                        StatementId::value_of(&e.key).to_string(),
                         Statement::from_entry(e, GadgetID::SCHNORR16),
                     )
                 })
@@ -170,7 +347,7 @@ impl POD {
 
         let payload = PODPayload::new(&statement_map);
         let payload_hash = payload.hash_payload();
-        let proof = protocol.sign(payload_hash.elements.as_ref(), sk, &mut rng);
+        let proof = protocol.sign(payload_hash.elements.as_ref(), sk, rng);
         Ok(Self {
             payload,
             proof: PODProof::Schnorr(proof),
@@ -178,7 +355,79 @@ impl POD {
         })
     }
 
+    /// Same as `execute_schnorr_gadget`, with `NS` the next power of two that fits the entries
+    /// and the signer key, and `VL` the next power of two that fits the longest vector entry
+    /// (0 without vectors). Supports up to 63 entries and vectors of up to 64 elements.
+    pub fn sign_schnorr(entries: &[Entry], sk: &SchnorrSecretKey) -> Result<Self> {
+        match (entries.len() + 1).next_power_of_two() {
+            2 => Self::sign_schnorr_with_ns::<2>(entries, sk),
+            4 => Self::sign_schnorr_with_ns::<4>(entries, sk),
+            8 => Self::sign_schnorr_with_ns::<8>(entries, sk),
+            16 => Self::sign_schnorr_with_ns::<16>(entries, sk),
+            32 => Self::sign_schnorr_with_ns::<32>(entries, sk),
+            64 => Self::sign_schnorr_with_ns::<64>(entries, sk),
+            _ => Err(anyhow!(
+                "Can't sign {} entries, the maximum is 63.",
+                entries.len()
+            )),
+        }
+    }
+
+    fn sign_schnorr_with_ns<const NS: usize>(
+        entries: &[Entry],
+        sk: &SchnorrSecretKey,
+    ) -> Result<Self> {
+        let max_vector_len = entries
+            .iter()
+            .filter_map(|e| match &e.value {
+                ScalarOrVec::Vector(v) => Some(v.len()),
+                ScalarOrVec::Scalar(_) => None,
+            })
+            .max();
+        match max_vector_len.map(usize::next_power_of_two) {
+            None => Self::execute_schnorr_gadget::<NS, 0>(entries, sk),
+            Some(1) => Self::execute_schnorr_gadget::<NS, 1>(entries, sk),
+            Some(2) => Self::execute_schnorr_gadget::<NS, 2>(entries, sk),
+            Some(4) => Self::execute_schnorr_gadget::<NS, 4>(entries, sk),
+            Some(8) => Self::execute_schnorr_gadget::<NS, 8>(entries, sk),
+            Some(16) => Self::execute_schnorr_gadget::<NS, 16>(entries, sk),
+            Some(32) => Self::execute_schnorr_gadget::<NS, 32>(entries, sk),
+            Some(64) => Self::execute_schnorr_gadget::<NS, 64>(entries, sk),
+            Some(_) => Err(anyhow!(
+                "Vectors of more than 64 elements aren't supported."
+            )),
+        }
+    }
+
     pub fn introduce_pod1<const NS: usize, const VL: usize>(pod: Pod) -> Result<Self> {
+        Self::execute_schnorr_gadget::<NS, VL>(
+            &Self::pod1_entries(&pod)?,
+            &SchnorrSecretKey::from_u64(0),
+        )
+    }
+
+    /// Same as `introduce_pod1` for each of `pods`. All of them are checked before any is
+    /// signed, and errors give the index of the offending POD.
+    pub fn introduce_pod1_batch<const NS: usize, const VL: usize>(
+        pods: &[Pod],
+    ) -> Result<Vec<Self>> {
+        let entries = pods
+            .iter()
+            .enumerate()
+            .map(|(i, pod)| Self::pod1_entries(pod).map_err(|e| anyhow!("POD {}: {}", i, e)))
+            .collect::<Result<Vec<_>>>()?;
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, entries)| {
+                Self::execute_schnorr_gadget::<NS, VL>(entries, &SchnorrSecretKey::from_u64(0))
+                    .map_err(|e| anyhow!("POD {}: {}", i, e))
+            })
+            .collect()
+    }
+
+    /// Entries of a verified POD1, along with its signer as `_pod1_signer`
+    fn pod1_entries(pod: &Pod) -> Result<Vec<Entry>> {
         // Check input POD.
         let pod_is_valid = pod
             .verify()
@@ -191,33 +440,47 @@ impl POD {
         // Form POD entries.
         let signer_key: ScalarOrVec =
             Into::<ScalarOrVec>::into(PodValue::EdDSAPublicKey(pod.signer_public_key()));
-        let entries = pod
-            .entries()
-            .iter()
-            .map(|(s, pod_value)| Entry::new_from_pod_value(s, pod_value))
-            .chain([Entry {
-                key: "_pod1_signer".to_string(),
-                value: signer_key,
-            }])
-            .collect::<Vec<_>>();
-
-        Self::execute_schnorr_gadget::<NS, VL>(&entries, &SchnorrSecretKey { sk: 0 })
+        let mut entries = Vec::new();
+        let mut unsupported = Vec::new();
+        for (key, pod_value) in pod.entries().iter() {
+            match Entry::new_typed_from_pod_value(key, pod_value) {
+                Ok(typed_entries) => entries.extend(typed_entries),
+                Err(e) => unsupported.push(format!("{} ({})", key, e)),
+            }
+        }
+        if !unsupported.is_empty() {
+            return Err(anyhow!(
+                "Unsupported POD values: {}",
+                unsupported.join(", ")
+            ));
+        }
+        entries.push(Entry {
+            key: "_pod1_signer".to_string(),
+            value: signer_key,
+        });
+        Ok(entries)
     }
 
     pub fn execute_oracle_gadget(input: &GPGInput, cmds: &[OpCmd]) -> Result<Self> {
         let mut statements = input.remap_origin_ids_by_name()?;
+        Self::check_statement_refs(&statements, cmds)?;
         statements.insert("_SELF".to_string(), HashMap::new());
-        for cmd in cmds {
-            let OpCmd(op, output_name) = cmd;
-            let new_statement = op.execute(GadgetID::ORACLE, &statements)?;
-            statements.get_mut("_SELF").unwrap().insert(
-                format!(
-                    "{}:{}",
-                    new_statement.predicate(),
-                    Into::<String>::into(output_name.clone())
-                ),
-                new_statement,
-            );
+        // Operations of a level only depend on statements of previous levels, so they can be
+        // executed in parallel. Merging them back in cmd order gives the same statements as
+        // executing the cmds one after the other.
+        for level in Self::oracle_dependency_levels(cmds) {
+            let new_statements = level
+                .par_iter()
+                .map(|&i| {
+                    let OpCmd(op, output_name) = &cmds[i];
+                    let new_statement = op.execute(GadgetID::ORACLE, &statements)?;
+                    Ok((
+                        StatementId::new((&new_statement).into(), output_name).to_string(),
+                        new_statement,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            statements.get_mut("_SELF").unwrap().extend(new_statements);
         }
         let out_statements = statements.get("_SELF").unwrap();
         let out_payload = PODPayload::new(out_statements);
@@ -233,7 +496,7 @@ impl POD {
         // max number of statements for output POD, and some max number of each type of operation
         let proof = protocol.sign(
             payload_hash.elements.as_ref(),
-            &SchnorrSecretKey { sk: 0 },
+            &SchnorrSecretKey::from_u64(0),
             &mut rng,
         );
         Ok(Self {
@@ -242,6 +505,77 @@ impl POD {
             proof_type: GadgetID::ORACLE,
         })
     }
+    /// Checks that the statements `cmds` refer to are in the input PODs or, for _SELF, are the
+    /// output of an earlier cmd (both its predicate and name), so that a typo is reported by name
+    /// rather than when executing.
+    fn check_statement_refs(
+        statements: &HashMap<String, HashMap<String, Statement>>,
+        cmds: &[OpCmd],
+    ) -> Result<()> {
+        for (i, OpCmd(op, output_name)) in cmds.iter().enumerate() {
+            for StatementRef(pod_name, statement_id) in op.operands() {
+                let found = if pod_name == "_SELF" {
+                    cmds[..i].iter().any(|OpCmd(op, name)| {
+                        &StatementId::new(op.output_predicate(), name) == statement_id
+                    })
+                } else {
+                    statements
+                        .get(pod_name)
+                        .ok_or(anyhow!(
+                            "Operation {} refers to POD {}, which isn't an input POD.",
+                            output_name,
+                            pod_name
+                        ))?
+                        .contains_key(&statement_id.to_string())
+                };
+                if !found {
+                    return Err(anyhow!(
+                        "Operation {} refers to statement {}, which POD {} doesn't have.",
+                        output_name,
+                        statement_id,
+                        pod_name
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Groups the indices of `cmds` into levels, in order. A cmd comes after the cmds producing the
+    /// _SELF statements it refers to (or with the same output name), and after the cmds referring
+    /// to its output name, so that each cmd sees the same _SELF statements as when executed in order.
+    /// Statements are tracked by output name only, which can add dependencies but never miss one.
+    fn oracle_dependency_levels(cmds: &[OpCmd]) -> Vec<Vec<usize>> {
+        let mut last_write: HashMap<&str, usize> = HashMap::new();
+        let mut last_read: HashMap<&str, usize> = HashMap::new();
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+        for (i, OpCmd(op, output_name)) in cmds.iter().enumerate() {
+            let self_refs = op
+                .operands()
+                .into_iter()
+                .filter(|s_ref| s_ref.0 == "_SELF")
+                .map(|s_ref| s_ref.1.name.as_str())
+                .collect::<Vec<_>>();
+            let level = self_refs
+                .iter()
+                .filter_map(|name| last_write.get(name))
+                .chain(last_write.get(output_name.as_str()))
+                .chain(last_read.get(output_name.as_str()))
+                .map(|level| level + 1)
+                .max()
+                .unwrap_or(0);
+            for name in self_refs {
+                let read_level = last_read.entry(name).or_insert(level);
+                *read_level = (*read_level).max(level);
+            }
+            last_write.insert(output_name.as_str(), level);
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            levels[level].push(i);
+        }
+        levels
+    }
     // the prover_params is passed as parameter, because compunting it depends on first computing
     // the circuit_data, which takes a considerable amount of time to compute. So we compute it
     // once at the beginning and just reuse it through all the calls to execute_plonky_gadget.
@@ -290,6 +624,7 @@ impl POD {
     }
 }
 
+#[cfg(feature = "prover")]
 #[derive(Clone, Debug)]
 pub struct GPGInput {
     /// ORDERED list of pods, ordered by names
@@ -299,6 +634,7 @@ pub struct GPGInput {
     pub origin_renaming_map: HashMap<(String, String), String>,
 }
 
+#[cfg(feature = "prover")]
 impl GPGInput {
     pub fn new(
         named_pods: HashMap<String, POD>,
@@ -317,6 +653,58 @@ impl GPGInput {
             origin_renaming_map: origin_renaming_map_clone,
         }
     }
+
+    /// Like `new`, but every origin of the pods named in `hidden_origins`
+    /// (their _SELF as well as the origins they copied from) is renamed to
+    /// an opaque origin_1, origin_2, ... regardless of `origin_renaming_map`,
+    /// so the output pod doesn't reveal which pods its statements came from.
+    pub fn new_redacted(
+        named_pods: HashMap<String, POD>,
+        origin_renaming_map: HashMap<(String, String), String>,
+        hidden_origins: &HashSet<String>,
+    ) -> Self {
+        let mut input = Self::new(named_pods, origin_renaming_map);
+        // Sorted so that the same inputs always get the same opaque names
+        let mut hidden_keys = input
+            .pods_list
+            .iter()
+            .filter(|(pod_name, _)| hidden_origins.contains(pod_name))
+            .flat_map(|(pod_name, pod)| {
+                std::iter::once("_SELF".to_string())
+                    .chain(
+                        pod.payload
+                            .statements_list()
+                            .iter()
+                            .flat_map(|(_, s)| s.anchored_keys())
+                            .filter(|anchkey| !anchkey.0.is_self())
+                            .map(|anchkey| anchkey.0.origin_name),
+                    )
+                    .map(move |origin_name| (pod_name.clone(), origin_name))
+            })
+            .collect::<Vec<_>>();
+        hidden_keys.sort();
+        hidden_keys.dedup();
+
+        for key in &hidden_keys {
+            input.origin_renaming_map.remove(key);
+        }
+        let used_names = input
+            .origin_renaming_map
+            .values()
+            .cloned()
+            .collect::<HashSet<_>>();
+        let mut next_id = 1;
+        for key in hidden_keys {
+            while used_names.contains(&format!("origin_{}", next_id)) {
+                next_id += 1;
+            }
+            input
+                .origin_renaming_map
+                .insert(key, format!("origin_{}", next_id));
+            next_id += 1;
+        }
+        input
+    }
     /// New origin name -> new origin ID map
     fn origin_name_to_new_id_map(&self) -> HashMap<&String, usize> {
         // Sorted new origin name list
@@ -350,7 +738,7 @@ impl GPGInput {
                     GoldilocksField(1)
                 } else {
                     pod.payload
-                        .statements_list
+                        .statements_list()
                         .iter()
                         .flat_map(|(_, s)| {
                             s.anchored_keys()
@@ -392,7 +780,7 @@ impl GPGInput {
         let num_statements = self
             .pods_list
             .iter()
-            .map(|(_, p)| p.payload.statements_list.len())
+            .map(|(_, p)| p.payload.statements_list().len())
             .max()
             .ok_or(anyhow!("POD with empty statement list encountered!"))?;
         Ok((0..num_pods)
@@ -447,7 +835,7 @@ impl GPGInput {
                 Ok((
                     pod_name.to_string(),
                     pod.payload
-                        .statements_map
+                        .statements_map()
                         .iter()
                         .map(|(statement_name, statement)| {
                             Ok((
@@ -464,7 +852,7 @@ impl GPGInput {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "prover"))]
 mod tests {
     use crate::recursion::{traits_examples::ExampleIntroducer, IntroducerCircuitTrait};
     use operation::Operation as Op;
@@ -603,6 +991,413 @@ mod tests {
         Ok(())
     }
 
+    /// ValueOf statement of the scalar entry `key`, outside of any gadget
+    fn entry_statement(key: &str, value: u64) -> Statement {
+        Statement::from_entry(
+            &Entry::new_from_scalar(key, GoldilocksField(value)),
+            GadgetID::NONE,
+        )
+    }
+
+    #[test]
+    fn sub_div_op_test() -> Result<()> {
+        let two = entry_statement("two", 2);
+        let five = entry_statement("five", 5);
+        let six = entry_statement("six", 6);
+        let three = entry_statement("three", 3);
+        let zero = entry_statement("zero", 0);
+
+        // 5 - 2 = 3
+        assert!(
+            Op::SubOf(three.clone(), five.clone(), two.clone())
+                .eval_with_gadget_id(GadgetID::NONE)?
+                == Statement::SubOf(
+                    three.anchored_keys()[0].clone(),
+                    five.anchored_keys()[0].clone(),
+                    two.anchored_keys()[0].clone()
+                )
+        );
+        assert!(Op::SubOf(three.clone(), two.clone(), five.clone())
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_err());
+
+        // 2 - 5 wraps around the Goldilocks modulus p = 2^64 - 2^32 + 1 to p - 3.
+        let wrapped = entry_statement("wrapped", 18446744069414584318);
+        assert!(Op::SubOf(wrapped, two.clone(), five.clone())
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_ok());
+
+        // 6 / 2 = 3
+        assert!(
+            Op::DivOf(three.clone(), six.clone(), two.clone())
+                .eval_with_gadget_id(GadgetID::NONE)?
+                == Statement::DivOf(
+                    three.anchored_keys()[0].clone(),
+                    six.anchored_keys()[0].clone(),
+                    two.anchored_keys()[0].clone()
+                )
+        );
+        // Division by zero is rejected, even when 0 * 0 = 0.
+        assert!(Op::DivOf(zero.clone(), zero.clone(), zero.clone())
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn sum_product_num_bits_test() -> Result<()> {
+        let max = (1u64 << crate::NUM_BITS) - 1;
+        let one = entry_statement("one", 1);
+
+        // 2^32 - 1 = (2^32 - 2) + 1, right at the boundary
+        assert!(Op::SumOf(
+            entry_statement("max", max),
+            entry_statement("almost", max - 1),
+            one.clone()
+        )
+        .eval_with_gadget_id(GadgetID::NONE)
+        .is_ok());
+        // (2^32 - 1) + 1 is just over it, even though the field holds it fine
+        assert!(Op::SumOf(
+            entry_statement("over", max + 1),
+            entry_statement("max", max),
+            one.clone()
+        )
+        .eval_with_gadget_id(GadgetID::NONE)
+        .is_err());
+
+        // 65535 * 65537 = 2^32 - 1
+        let (a, b) = (entry_statement("a", 65535), entry_statement("b", 65537));
+        assert!(Op::ProductOf(entry_statement("max", max), a, b.clone())
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_ok());
+        // Both operands fit, their product doesn't
+        assert!(
+            Op::ProductOf(entry_statement("over", 65537 * 65537), b.clone(), b)
+                .eval_with_gadget_id(GadgetID::NONE)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn range_check_op_test() -> Result<()> {
+        let range_check = |value: &Statement, lo: &Statement, hi: &Statement| {
+            Op::RangeCheckFromEntries(value.clone(), lo.clone(), hi.clone())
+                .eval_with_gadget_id(GadgetID::NONE)
+        };
+        let age = entry_statement("age", 30);
+        let adult = entry_statement("adult", 18);
+        let retired = entry_statement("retired", 65);
+
+        // 18 <= 30 <= 65
+        assert!(
+            range_check(&age, &adult, &retired)?
+                == Statement::InRange(
+                    age.anchored_keys()[0].clone(),
+                    adult.anchored_keys()[0].clone(),
+                    retired.anchored_keys()[0].clone()
+                )
+        );
+        // Both bounds are inclusive
+        assert!(range_check(&adult, &adult, &retired).is_ok());
+        assert!(range_check(&retired, &adult, &retired).is_ok());
+        assert!(range_check(&age, &age, &age).is_ok());
+
+        assert!(range_check(&adult, &age, &retired).is_err());
+        assert!(range_check(&retired, &adult, &age).is_err());
+        // An empty range
+        assert!(range_check(&age, &retired, &adult).is_err());
+
+        // Values must fit in NUM_BITS bits
+        let big = entry_statement("big", 1 << crate::NUM_BITS);
+        let bigger = entry_statement("bigger", (1 << crate::NUM_BITS) + 1);
+        assert!(range_check(&big, &adult, &bigger).is_err());
+
+        let vector = Statement::from_entry(
+            &Entry::new_from_vec("vector", vec![GoldilocksField(30)]),
+            GadgetID::NONE,
+        );
+        assert!(range_check(&vector, &adult, &retired).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn quotient_remainder_op_test() -> Result<()> {
+        let quotient = |q: u64, a: u64, b: u64| {
+            Op::QuotientOf(
+                entry_statement("q", q),
+                entry_statement("a", a),
+                entry_statement("b", b),
+            )
+            .eval_with_gadget_id(GadgetID::NONE)
+        };
+        let remainder = |r: u64, a: u64, b: u64| {
+            Op::RemainderOf(
+                entry_statement("r", r),
+                entry_statement("a", a),
+                entry_statement("b", b),
+            )
+            .eval_with_gadget_id(GadgetID::NONE)
+        };
+
+        // Exact division
+        assert!(
+            quotient(7, 42, 6)?
+                == Statement::QuotientOf(
+                    entry_statement("q", 7).anchored_keys()[0].clone(),
+                    entry_statement("a", 42).anchored_keys()[0].clone(),
+                    entry_statement("b", 6).anchored_keys()[0].clone()
+                )
+        );
+        assert!(remainder(0, 42, 6).is_ok());
+
+        // 43 = 6 * 7 + 1
+        assert!(quotient(7, 43, 6).is_ok());
+        assert!(remainder(1, 43, 6).is_ok());
+        assert!(quotient(8, 43, 6).is_err());
+        assert!(remainder(7, 43, 6).is_err());
+        // The remainder is less than the divisor
+        assert!(remainder(6, 42, 6).is_err());
+
+        // Field division would give the inverse of 6 times 43 instead
+        let field_quotient = (GoldilocksField(43) / GoldilocksField(6)).to_canonical_u64();
+        assert!(quotient(field_quotient, 43, 6).is_err());
+        // and negative values are large canonical ones, not negative integers
+        let minus_one = GoldilocksField::NEG_ONE.to_canonical_u64();
+        assert!(quotient(minus_one / 2, minus_one, 2).is_ok());
+
+        // Division by zero is never a valid claim
+        assert!(quotient(0, 42, 0).is_err());
+        assert!(remainder(42, 42, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn element_at_op_test() -> Result<()> {
+        let vector = Statement::from_entry(
+            &Entry::new_from_vec("pair", vec![GoldilocksField(7), GoldilocksField(9)]),
+            GadgetID::NONE,
+        );
+        let index = |i: u64| {
+            Statement::from_entry(
+                &Entry::new_from_scalar("index", GoldilocksField(i)),
+                GadgetID::NONE,
+            )
+        };
+        let element_at = |vector: &Statement, index: &Statement| {
+            Op::ElementAtFromEntries(vector.clone(), index.clone())
+                .eval_with_gadget_id(GadgetID::NONE)
+        };
+        let pair = vector.anchored_keys()[0].clone();
+
+        assert!(
+            element_at(&vector, &index(0))?
+                == Statement::ElementAt(pair.clone(), 0, GoldilocksField(7))
+        );
+        assert!(
+            element_at(&vector, &index(1))? == Statement::ElementAt(pair, 1, GoldilocksField(9))
+        );
+        assert!(element_at(&vector, &index(2)).is_err());
+        // The index must be a scalar and the vector a vector
+        assert!(element_at(&vector, &vector).is_err());
+        assert!(element_at(&index(0), &index(0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn transitive_gt_op_test() -> Result<()> {
+        let (a, b, c) = (
+            entry_statement("a", 30),
+            entry_statement("b", 20),
+            entry_statement("c", 10),
+        );
+        let gt = |s1: &Statement, s2: &Statement| {
+            Op::GtFromEntries(s1.clone(), s2.clone()).eval_with_gadget_id(GadgetID::NONE)
+        };
+        let (a_gt_b, b_gt_c, a_gt_c) = (gt(&a, &b)?, gt(&b, &c)?, gt(&a, &c)?);
+
+        assert!(
+            Op::TransitiveGtFromStatements(a_gt_b.clone(), b_gt_c.clone())
+                .eval_with_gadget_id(GadgetID::NONE)?
+                == a_gt_c
+        );
+
+        // The right key of the first statement must be the left key of the second
+        assert!(
+            Op::TransitiveGtFromStatements(b_gt_c.clone(), a_gt_b.clone())
+                .eval_with_gadget_id(GadgetID::NONE)
+                .is_err()
+        );
+        assert!(
+            Op::TransitiveGtFromStatements(a_gt_b.clone(), a_gt_c.clone())
+                .eval_with_gadget_id(GadgetID::NONE)
+                .is_err()
+        );
+        // Both statements must be Gt
+        let b_eq_b =
+            Op::EqualityFromEntries(b.clone(), b.clone()).eval_with_gadget_id(GadgetID::NONE)?;
+        assert!(Op::TransitiveGtFromStatements(a_gt_b, b_eq_b)
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn oracle_parallel_execution_test() -> Result<()> {
+        const NS: usize = 3;
+        const VL: usize = 0;
+
+        let schnorr_pod = POD::execute_schnorr_gadget::<NS, VL>(
+            &[Entry::new_from_scalar("x", GoldilocksField(7))],
+            &SchnorrSecretKey::from_u64(25),
+        )?;
+        let gpg_input = GPGInput::new(
+            HashMap::from([("p1".to_string(), schnorr_pod)]),
+            HashMap::new(),
+        );
+
+        // Independent entries, each proven equal to x so the equalities depend
+        // on entries created earlier in the batch
+        let mut ops = vec![OpCmd::new(
+            Op::CopyStatement(StatementRef::new("p1", "VALUEOF:x")),
+            "copied x",
+        )];
+        for i in 0..25 {
+            ops.push(OpCmd::new(
+                Op::NewEntry(Entry::new_from_scalar(
+                    &format!("entry{}", i),
+                    GoldilocksField(7),
+                )),
+                format!("entry{}", i),
+            ));
+            ops.push(OpCmd::new(
+                Op::EqualityFromEntries(
+                    StatementRef::new("p1", "VALUEOF:x"),
+                    StatementRef::new("_SELF", &format!("VALUEOF:entry{}", i)),
+                ),
+                format!("eq{}", i),
+            ));
+        }
+        assert_eq!(POD::oracle_dependency_levels(&ops).len(), 2);
+
+        // Execute the same cmds one after the other
+        let mut statements = gpg_input.remap_origin_ids_by_name()?;
+        statements.insert("_SELF".to_string(), HashMap::new());
+        for OpCmd(op, output_name) in &ops {
+            let new_statement = op.execute(GadgetID::ORACLE, &statements)?;
+            statements.get_mut("_SELF").unwrap().insert(
+                StatementId::new((&new_statement).into(), output_name).to_string(),
+                new_statement,
+            );
+        }
+        let sequential_payload = PODPayload::new(statements.get("_SELF").unwrap());
+
+        let oracle_pod = POD::execute_oracle_gadget(&gpg_input, &ops)?;
+        assert_eq!(oracle_pod.payload, sequential_payload);
+        assert!(oracle_pod.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
+
+        // A cmd referring to an entry created later in the batch still fails
+        let out_of_order = [ops[2].clone(), ops[1].clone()];
+        assert!(POD::execute_oracle_gadget(&gpg_input, &out_of_order).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn contains_op_test() -> Result<()> {
+        let vector = Statement::from_entry(
+            &Entry::new_from_vec("vector", vec![GoldilocksField(36), GoldilocksField(52)]),
+            GadgetID::NONE,
+        );
+        let member = Statement::from_entry(
+            &Entry::new_from_scalar("member", GoldilocksField(52)),
+            GadgetID::NONE,
+        );
+        let non_member = Statement::from_entry(
+            &Entry::new_from_scalar("non member", GoldilocksField(16)),
+            GadgetID::NONE,
+        );
+
+        assert!(
+            Op::ContainsFromEntries(vector.clone(), member.clone())
+                .eval_with_gadget_id(GadgetID::NONE)?
+                == Statement::Contains(
+                    vector.anchored_keys()[0].clone(),
+                    member.anchored_keys()[0].clone()
+                )
+        );
+        assert!(Op::ContainsFromEntries(vector.clone(), non_member.clone())
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_err());
+
+        assert!(
+            Op::NotContainsFromEntries(vector.clone(), non_member.clone())
+                .eval_with_gadget_id(GadgetID::NONE)?
+                == Statement::NotContains(
+                    vector.anchored_keys()[0].clone(),
+                    non_member.anchored_keys()[0].clone()
+                )
+        );
+        assert!(Op::NotContainsFromEntries(vector.clone(), member.clone())
+            .eval_with_gadget_id(GadgetID::NONE)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn oracle_missing_statement_test() -> Result<()> {
+        let pod = POD::execute_schnorr_gadget::<3, 0>(
+            &[Entry::new_from_scalar("apple", GoldilocksField(36))],
+            &SchnorrSecretKey::from_u64(25),
+        )?;
+        let input = GPGInput::new(HashMap::from([("p1".to_string(), pod)]), HashMap::new());
+        let error = |ops: &[OpCmd]| {
+            POD::execute_oracle_gadget(&input, ops)
+                .unwrap_err()
+                .to_string()
+        };
+        let copy = |pod: &str, statement: &str| {
+            OpCmd::new(
+                Op::CopyStatement(StatementRef::new(pod, statement)),
+                "apple",
+            )
+        };
+
+        assert_eq!(
+            error(&[copy("p1", "VALUEOF:aple")]),
+            "Operation apple refers to statement VALUEOF:aple, which POD p1 doesn't have."
+        );
+        assert_eq!(
+            error(&[copy("p2", "VALUEOF:apple")]),
+            "Operation apple refers to POD p2, which isn't an input POD."
+        );
+
+        // _SELF statements must be the output of an earlier operation
+        let eq = OpCmd::new(
+            Op::EqualityFromEntries(
+                StatementRef::new("_SELF", "VALUEOF:apple"),
+                StatementRef::new("p1", "VALUEOF:apple"),
+            ),
+            "eq",
+        );
+        assert_eq!(
+            error(&[eq.clone(), copy("p1", "VALUEOF:apple")]),
+            "Operation eq refers to statement VALUEOF:apple, which POD _SELF doesn't have."
+        );
+        assert!(POD::execute_oracle_gadget(&input, &[copy("p1", "VALUEOF:apple"), eq]).is_ok());
+
+        // ... with the same predicate, not only the same name
+        let gt_to_neq = OpCmd::new(
+            Op::GtToNonequality(StatementRef::new("_SELF", "GT:apple")),
+            "neq",
+        );
+        assert_eq!(
+            error(&[copy("p1", "VALUEOF:apple"), gt_to_neq]),
+            "Operation neq refers to statement GT:apple, which POD _SELF doesn't have."
+        );
+        Ok(())
+    }
+
     #[test]
     fn schnorr_pod_test() -> Result<()> {
         const NS: usize = 3;
@@ -622,26 +1417,31 @@ mod tests {
 
         let schnorr_pod1 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry1.clone(), entry2.clone()],
-            &SchnorrSecretKey { sk: 25 },
+            &SchnorrSecretKey::from_u64(25),
         )?;
 
         let schnorr_pod2 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry2.clone(), entry3.clone()],
-            &SchnorrSecretKey { sk: 42 },
+            &SchnorrSecretKey::from_u64(42),
         )?;
 
         assert!(schnorr_pod1.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
         assert!(schnorr_pod2.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
 
-        let mut schnorr_pod3 =
-            POD::execute_schnorr_gadget::<NS, VL>(&[entry1.clone()], &SchnorrSecretKey { sk: 25 })?;
+        // PODs survive a round trip through JSON.
+        let schnorr_pod1_json = POD::from_json_v1(&schnorr_pod1.to_json_v1()?)?;
+        assert_eq!(schnorr_pod1_json, schnorr_pod1);
+        assert!(schnorr_pod1_json.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
+
+        let mut schnorr_pod3 = POD::execute_schnorr_gadget::<NS, VL>(
+            &[entry1.clone()],
+            &SchnorrSecretKey::from_u64(25),
+        )?;
 
         // modify the internal value of the valueOf statement in schnorrPOD3
         schnorr_pod3
             .payload
-            .statements_map
-            .insert("VALUEOF:some key".to_string(), other_statement.clone());
-        schnorr_pod3.payload.statements_list[1].1 = other_statement;
+            .set_statement("VALUEOF:some key".to_string(), other_statement);
 
         // now signature shouldn't verify
         assert!(!(schnorr_pod3.verify::<0, 3, 2, 2, 0>()?)); // TODO use L!=0
@@ -649,8 +1449,114 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn schnorr_pod_seeded_rng_test() -> Result<()> {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let entries = [
+            Entry::new_from_scalar("some key", GoldilocksField(36)),
+            Entry::new_from_vec("vector entry", vec![GoldilocksField(52)]),
+        ];
+        let sk = SchnorrSecretKey::from_u64(25);
+        let sign = |seed| {
+            POD::execute_schnorr_gadget_with_rng::<3, 10>(
+                &entries,
+                &sk,
+                &mut StdRng::seed_from_u64(seed),
+            )
+        };
+
+        let pod1 = sign(1)?;
+        let pod2 = sign(1)?;
+        assert!(pod1.verify::<0, 3, 2, 2, 0>()?);
+        assert_eq!(pod1, pod2);
+
+        // The payload doesn't depend on the rng, only the signature does
+        let pod3 = POD::execute_schnorr_gadget::<3, 10>(&entries, &sk)?;
+        assert_eq!(pod3.payload, pod1.payload);
+        assert_eq!(pod3.payload.hash_payload(), pod1.payload.hash_payload());
+        assert!(pod3.verify::<0, 3, 2, 2, 0>()?);
+        Ok(())
+    }
+
+    #[test]
+    fn sign_schnorr_test() -> Result<()> {
+        let sk = SchnorrSecretKey::from_u64(25);
+        let entries = |n: u64| {
+            (0..n)
+                .map(|i| Entry::new_from_scalar(&format!("key{}", i), GoldilocksField(i)))
+                .collect::<Vec<_>>()
+        };
+
+        // The signer key takes up a statement too, so 7 entries fill 8 statements
+        for (n, statements) in [(1, 2), (3, 4), (7, 8)] {
+            let pod = POD::sign_schnorr(&entries(n), &sk)?;
+            assert_eq!(pod.payload.statements_list().len(), statements);
+            assert!(pod.verify::<0, 3, 2, 2, 0>()?);
+        }
+
+        // Vectors are padded to the next power of two
+        let vector = Entry::new_from_vec("v", (1..=3).map(GoldilocksField).collect());
+        let pod = POD::sign_schnorr(&[vector], &sk)?;
+        match &pod.payload.statements_map()["VALUEOF:v"] {
+            Statement::ValueOf(_, ScalarOrVec::Vector(v)) => assert_eq!(v.len(), 4),
+            statement => panic!("Expected a vector, got {}", statement),
+        }
+        assert!(pod.verify::<0, 3, 2, 2, 0>()?);
+
+        assert!(POD::sign_schnorr(&entries(64), &sk).is_err());
+        Ok(())
+    }
+
     // i haven't written asserts yet to check the correctness of oracle and oracle2 pods
     // but i've manually inspected output and it looks good
+    #[test]
+    fn batch_verify_test() -> Result<()> {
+        const NS: usize = 3;
+        const VL: usize = 0;
+
+        let schnorr_pods = (0..100)
+            .map(|i| {
+                POD::execute_schnorr_gadget::<NS, VL>(
+                    &[Entry::new_from_scalar("x", GoldilocksField(i))],
+                    &SchnorrSecretKey::from_u64(25 + i),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let gpg_input = GPGInput::new(
+            HashMap::from([("p1".to_string(), schnorr_pods[0].clone())]),
+            HashMap::new(),
+        );
+        let oracle_pod = POD::execute_oracle_gadget(
+            &gpg_input,
+            &[OpCmd::new(
+                Op::CopyStatement(StatementRef::new("p1", "VALUEOF:x")),
+                "copied x",
+            )],
+        )?;
+
+        // Signed by another key, and claiming the wrong proof type
+        let mut swapped_proof = schnorr_pods[1].clone();
+        swapped_proof.proof = schnorr_pods[2].proof.clone();
+        let mut wrong_type = schnorr_pods[3].clone();
+        wrong_type.proof_type = GadgetID::ORACLE;
+
+        let mut pods: Vec<&POD> = schnorr_pods.iter().collect();
+        pods.extend([&oracle_pod, &swapped_proof, &wrong_type]);
+
+        let batch = POD::verify_batch_schnorr::<0, 3, 2, 2, 0>(&pods)?; // TODO use L!=0
+        let sequential = pods
+            .iter()
+            .map(|pod| pod.verify::<0, 3, 2, 2, 0>().unwrap_or(false))
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![true; 101];
+        expected.extend([false, false]);
+        assert_eq!(batch, expected);
+        assert_eq!(batch, sequential);
+        Ok(())
+    }
+
     #[test]
     fn oracle_pod_from_schnorr_test() -> Result<()> {
         const NS: usize = 4;
@@ -676,12 +1582,12 @@ mod tests {
         // three schnorr pods
         let schnorr_pod1 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry1.clone(), entry2.clone()],
-            &SchnorrSecretKey { sk: 25 },
+            &SchnorrSecretKey::from_u64(25),
         )?;
 
         let schnorr_pod2 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry3.clone(), entry4.clone()],
-            &SchnorrSecretKey { sk: 42 },
+            &SchnorrSecretKey::from_u64(42),
         )?;
         // make an OraclePOD using from_pods called on the two schnorr PODs
 
@@ -759,12 +1665,20 @@ mod tests {
 
         let oracle_pod = POD::execute_oracle_gadget(&gpg_input, &ops).unwrap();
         assert!(oracle_pod.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
+        let oracle_pod_json = POD::from_json_v1(&oracle_pod.to_json_v1()?)?;
+        assert_eq!(oracle_pod_json, oracle_pod);
+        assert!(oracle_pod_json.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
+
+        // The derived serde representation round-trips as well
+        let oracle_pod_serde: POD = serde_json::from_str(&serde_json::to_string(&oracle_pod)?)?;
+        assert_eq!(oracle_pod_serde, oracle_pod);
+        assert!(oracle_pod_serde.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
 
         // make another oracle POD which takes that oracle POD and a schnorr POD
 
         let schnorr_pod3 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry5.clone(), entry6.clone(), entry7.clone()],
-            &SchnorrSecretKey { sk: 83 },
+            &SchnorrSecretKey::from_u64(83),
         )?;
 
         // make the GPG input
@@ -838,7 +1752,7 @@ mod tests {
         ];
 
         let oracle_pod2 = POD::execute_oracle_gadget(&gpg_input, &ops).unwrap();
-        for statement in oracle_pod2.payload.statements_list.iter() {
+        for statement in oracle_pod2.payload.statements_list().iter() {
             println!("{:?}", statement);
         }
         assert!(oracle_pod2.verify::<0, 3, 2, 2, 0>()?); // TODO use L!=0
@@ -868,12 +1782,12 @@ mod tests {
         // two schnorr pods
         let schnorr_pod1 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry1.clone(), entry2.clone()],
-            &SchnorrSecretKey { sk: 25 },
+            &SchnorrSecretKey::from_u64(25),
         )?;
 
         let schnorr_pod2 = POD::execute_schnorr_gadget::<NS, VL>(
             &[entry3.clone(), entry4.clone()],
-            &SchnorrSecretKey { sk: 42 },
+            &SchnorrSecretKey::from_u64(42),
         )?;
         // make a PlonkyPOD using from_pods called on the two schnorr PODs
 
@@ -919,6 +1833,18 @@ mod tests {
         let plonky_pod =
             POD::execute_plonky_gadget::<L, M, N, NS, VL>(&mut prover_params, &gpg_input, &ops)?;
         assert!(plonky_pod.verify::<L, M, N, NS, VL>()?);
+        let plonky_pod_json = POD::from_json_v1(&plonky_pod.to_json_v1()?)?;
+        assert_eq!(plonky_pod_json, plonky_pod);
+        assert!(plonky_pod_json.verify::<L, M, N, NS, VL>()?);
+
+        // Proofs are expensive to generate, so they get cached, e.g. in a file: the derived serde
+        // representation, Plonky proof included, round-trips and still verifies
+        let path = std::env::temp_dir().join(format!("plonky_pod_{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_vec(&plonky_pod)?)?;
+        let plonky_pod_serde: POD = serde_json::from_slice(&std::fs::read(&path)?)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(plonky_pod_serde, plonky_pod);
+        assert!(plonky_pod_serde.verify::<L, M, N, NS, VL>()?);
 
         // make another oracle POD which takes that oracle POD and a schnorr POD
 
@@ -958,11 +1884,122 @@ mod tests {
 
         let plonky_pod2 =
             POD::execute_plonky_gadget::<L, M, N, NS, VL>(&mut prover_params, &gpg_input, &ops)?;
-        for statement in plonky_pod2.payload.statements_list.iter() {
+        for statement in plonky_pod2.payload.statements_list().iter() {
             println!("{:?}", statement);
         }
         assert!(plonky_pod2.verify::<L, M, N, NS, VL>()?);
 
+        // both plonky PODs can be verified reusing a single context
+        let builds = VERIFIER_CONTEXT_BUILDS.with(|builds| builds.get());
+        let ctx = VerifierContext::<L, M, N, NS, VL>::new()?;
+        assert!(plonky_pod.verify_with_context(&ctx)?);
+        assert!(plonky_pod2.verify_with_context(&ctx.clone())?);
+        assert_eq!(
+            VERIFIER_CONTEXT_BUILDS.with(|builds| builds.get()),
+            builds + 1
+        );
+
+        // a Plonky POD with the wrong number of statements is invalid rather than a panic
+        let mut statements = plonky_pod.payload.statements_list().to_vec();
+        statements.pop();
+        let truncated = POD {
+            payload: PODPayload::from_parts(statements.clone(), statements.into_iter().collect()),
+            ..plonky_pod.clone()
+        };
+        assert!(truncated.verify_with_context(&ctx).is_err());
+        assert_eq!(
+            POD::verify_batch_schnorr::<L, M, N, NS, VL>(&[&plonky_pod, &truncated])?,
+            [true, false]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn redacted_origins_test() -> Result<()> {
+        const NS: usize = 4;
+        const VL: usize = 10;
+
+        let age_pod = POD::execute_schnorr_gadget::<NS, VL>(
+            &[Entry::new_from_scalar("age", GoldilocksField(30))],
+            &SchnorrSecretKey::from_u64(25),
+        )?;
+        let min_age_pod = POD::execute_schnorr_gadget::<NS, VL>(
+            &[Entry::new_from_scalar("min", GoldilocksField(18))],
+            &SchnorrSecretKey::from_u64(42),
+        )?;
+        // The visible pod is called origin_1, so the hidden one must skip that name
+        let named_input_pods = HashMap::from([
+            ("alice_id".to_string(), age_pod),
+            ("origin_1".to_string(), min_age_pod),
+        ]);
+        let origin_renaming_map = HashMap::from([(
+            ("alice_id".to_string(), "_SELF".to_string()),
+            "alice_id".to_string(),
+        )]);
+        let gpg_input = GPGInput::new_redacted(
+            named_input_pods,
+            origin_renaming_map,
+            &HashSet::from(["alice_id".to_string()]),
+        );
+        assert_eq!(
+            gpg_input.origin_renaming_map[&("alice_id".to_string(), "_SELF".to_string())],
+            "origin_2"
+        );
+
+        let ops = vec![
+            OpCmd::new(
+                Op::CopyStatement(StatementRef::new("alice_id", "VALUEOF:age")),
+                "age",
+            ),
+            OpCmd::new(
+                Op::GtFromEntries(
+                    StatementRef::new("alice_id", "VALUEOF:age"),
+                    StatementRef::new("origin_1", "VALUEOF:min"),
+                ),
+                "adult",
+            ),
+        ];
+        let pod = POD::execute_oracle_gadget(&gpg_input, &ops)?;
+        assert!(pod.verify::<0, 3, 2, 2, 0>()?);
+        let origin_names = |pod: &POD| {
+            pod.payload
+                .statements_list()
+                .iter()
+                .flat_map(|(_, s)| s.anchored_keys())
+                .filter(|anchkey| !anchkey.0.is_self())
+                .map(|anchkey| anchkey.0.origin_name)
+                .collect::<HashSet<_>>()
+        };
+        assert_eq!(
+            origin_names(&pod),
+            HashSet::from(["origin_1".to_string(), "origin_2".to_string()])
+        );
+
+        // Hiding a derived pod also hides the origins it copied from
+        let gpg_input = GPGInput::new_redacted(
+            HashMap::from([("derived".to_string(), pod)]),
+            HashMap::from([
+                (
+                    ("derived".to_string(), "origin_1".to_string()),
+                    "min_age".to_string(),
+                ),
+                (
+                    ("derived".to_string(), "origin_2".to_string()),
+                    "alice_id".to_string(),
+                ),
+            ]),
+            &HashSet::from(["derived".to_string()]),
+        );
+        let ops = vec![OpCmd::new(
+            Op::CopyStatement(StatementRef::new("derived", "GT:adult")),
+            "adult",
+        )];
+        let pod = POD::execute_oracle_gadget(&gpg_input, &ops)?;
+        assert!(pod.verify::<0, 3, 2, 2, 0>()?);
+        assert!(origin_names(&pod)
+            .iter()
+            .all(|name| name.starts_with("origin_")));
         Ok(())
     }
 
@@ -975,18 +2012,18 @@ mod tests {
 
         let protocol = SchnorrSigner::new();
 
-        let alice_sk = SchnorrSecretKey { sk: 25 };
+        let alice_sk = SchnorrSecretKey::from_u64(25);
         let alice_pk = protocol.keygen(&alice_sk).pk;
-        let bob_sk = SchnorrSecretKey { sk: 26 };
+        let bob_sk = SchnorrSecretKey::from_u64(26);
         let bob_pk = protocol.keygen(&bob_sk).pk;
-        let charlie_sk = SchnorrSecretKey { sk: 27 };
+        let charlie_sk = SchnorrSecretKey::from_u64(27);
         let charlie_pk = protocol.keygen(&charlie_sk).pk;
 
-        let goog_sk = SchnorrSecretKey { sk: 28 };
+        let goog_sk = SchnorrSecretKey::from_u64(28);
         let goog_pk = protocol.keygen(&goog_sk).pk;
-        let msft_sk = SchnorrSecretKey { sk: 29 };
+        let msft_sk = SchnorrSecretKey::from_u64(29);
         let msft_pk = protocol.keygen(&msft_sk).pk;
-        let fb_sk = SchnorrSecretKey { sk: 30 };
+        let fb_sk = SchnorrSecretKey::from_u64(30);
         let fb_pk = protocol.keygen(&fb_sk).pk;
 
         let known_attestors = vec![goog_pk, msft_pk, fb_pk];
@@ -1315,7 +2352,7 @@ mod tests {
         let alice_grb = POD::execute_oracle_gadget(&grb_input, &grb_ops).unwrap();
         assert!(alice_grb.verify::<0, 3, 2, 2, 0>()?); // TODO L=0, use L!=0
 
-        for statement in alice_grb.payload.statements_list {
+        for statement in alice_grb.payload.statements_list() {
             println!("{:?}", statement);
         }
 
@@ -1338,9 +2375,9 @@ mod tests {
         //  overall-max [max remote-max
         //                   local-sum]]
 
-        let alice_sk = SchnorrSecretKey { sk: 25 };
-        let bob_sk = SchnorrSecretKey { sk: 26 };
-        let charlie_sk = SchnorrSecretKey { sk: 27 };
+        let alice_sk = SchnorrSecretKey::from_u64(25);
+        let bob_sk = SchnorrSecretKey::from_u64(26);
+        let charlie_sk = SchnorrSecretKey::from_u64(27);
         // Let's create simple-pod-1
 
         // [createpod simple-pod-1  ; Alice's first pod
@@ -1630,16 +2667,35 @@ mod tests {
             pod_entries![
             "speed" => 5,
             "jump" => 10,
-            "owner" => "gub"
+            "owner" => "gub",
+            "label" => "5",
+            "vip" => true,
+            "issued" => time::OffsetDateTime::from_unix_timestamp(1_700_000_000)?
             ],
         )?;
 
-        let introduced_pod = POD::introduce_pod1::<5, 8>(test_pod)?;
+        let introduced_pod = POD::introduce_pod1::<14, 8>(test_pod)?;
+
+        // The type of each value survives the introduction
+        let value_type = |key: &str| -> Result<&'static str> {
+            let type_value = introduced_pod
+                .payload
+                .statements_map()
+                .get(&format!("VALUEOF:{}{}", entry::POD1_TYPE_PREFIX, key))
+                .ok_or(anyhow!("Missing type entry for {}", key))?
+                .value()?;
+            Entry::pod1_value_type(&type_value).ok_or(anyhow!("Unknown type for {}", key))
+        };
+        assert_eq!(value_type("speed")?, "int");
+        assert_eq!(value_type("owner")?, "string");
+        assert_eq!(value_type("label")?, "string");
+        assert_eq!(value_type("vip")?, "boolean");
+        assert_eq!(value_type("issued")?, "date");
 
         assert!(
             introduced_pod
                 .payload
-                .statements_map
+                .statements_map()
                 .get("VALUEOF:speed")
                 .ok_or(anyhow!(""))?
                 .value()?
@@ -1657,7 +2713,7 @@ mod tests {
         assert!(
             introduced_pod
                 .payload
-                .statements_map
+                .statements_map()
                 .get("VALUEOF:jump")
                 .ok_or(anyhow!(""))?
                 .value()?
@@ -1675,4 +2731,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn pod1_intro_batch_test() -> Result<()> {
+        let pod = |speed: i64| create_pod(&[0u8; 32], pod_entries!["speed" => speed]);
+        let mut tampered = serde_json::to_value(pod(5)?)?;
+        *tampered.pointer_mut("/claim/entries/speed").unwrap() =
+            serde_json::to_value(PodValue::Int(6))?;
+        let tampered: Pod = serde_json::from_value(tampered)?;
+
+        let introduced = POD::introduce_pod1_batch::<4, 8>(&[pod(5)?, pod(10)?])?;
+        assert_eq!(introduced.len(), 2);
+        for (introduced_pod, speed) in introduced.iter().zip([5, 10]) {
+            assert!(introduced_pod.verify::<0, 3, 2, 2, 0>()?);
+            assert_eq!(
+                introduced_pod.payload.statements_map()["VALUEOF:speed"].value()?,
+                ScalarOrVec::Vector(
+                    [speed, 0, speed, speed, speed, speed, speed, speed]
+                        .map(GoldilocksField)
+                        .to_vec()
+                )
+            );
+        }
+
+        let error = POD::introduce_pod1_batch::<4, 8>(&[pod(5)?, tampered, pod(10)?]).unwrap_err();
+        assert_eq!(error.to_string(), "POD 1: POD verification failed.");
+        Ok(())
+    }
 }
@@ -8,10 +8,11 @@ use std::{collections::HashMap, fmt::Debug};
 use super::{
     entry::Entry,
     gadget::GadgetID,
-    statement::{Statement, StatementOrRef, StatementRef},
+    statement::{Predicate, Statement, StatementOrRef, StatementRef},
     value::ScalarOrVec,
     POD,
 };
+use crate::NUM_BITS;
 
 #[derive(Clone, Debug)]
 pub enum Operation<S: StatementOrRef> {
@@ -30,6 +31,22 @@ pub enum Operation<S: StatementOrRef> {
     SumOf(S, S, S),
     ProductOf(S, S, S),
     MaxOf(S, S, S),
+    SubOf(S, S, S),
+    DivOf(S, S, S),
+    MinOf(S, S, S),
+    NotContainsFromEntries(S, S),
+    /// Value, lower bound and upper bound entries, both bounds inclusive. Only the oracle
+    /// gadget supports it so far.
+    RangeCheckFromEntries(S, S, S),
+    /// Vector entry and index entry. Only the oracle gadget supports it so far.
+    ElementAtFromEntries(S, S),
+    /// Result, dividend and divisor entries of an integer division. Only the oracle gadget
+    /// supports these so far.
+    QuotientOf(S, S, S),
+    RemainderOf(S, S, S),
+    /// `a > c` from `a > b` and `b > c`, without revealing `b`. Only the oracle gadget
+    /// supports it so far.
+    TransitiveGtFromStatements(S, S),
 }
 
 impl Operation<Statement> {
@@ -63,6 +80,10 @@ impl Operation<Statement> {
                 Statement::Equal(anchkey1, anchkey2),
                 Statement::Equal(anchkey3, anchkey4),
             ) if anchkey2.eq(anchkey3) => Ok(Statement::Equal(anchkey1.clone(), anchkey4.clone())),
+            Self::TransitiveGtFromStatements(
+                Statement::Gt(anchkey1, anchkey2),
+                Statement::Gt(anchkey3, anchkey4),
+            ) if anchkey2.eq(anchkey3) => Ok(Statement::Gt(anchkey1.clone(), anchkey4.clone())),
             Self::GtToNonequality(Statement::Gt(anchkey1, anchkey2)) => {
                 Ok(Statement::NotEqual(anchkey1.clone(), anchkey2.clone()))
             }
@@ -73,38 +94,140 @@ impl Operation<Statement> {
                 Statement::ValueOf(anchkey1, ScalarOrVec::Vector(vec)),
                 Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(scal)),
             ) if vec.contains(scal) => Ok(Statement::Contains(anchkey1.clone(), anchkey2.clone())),
+            Self::NotContainsFromEntries(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Vector(vec)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(scal)),
+            ) if !vec.contains(scal) => {
+                Ok(Statement::NotContains(anchkey1.clone(), anchkey2.clone()))
+            }
             Self::RenameContainedBy(
                 Statement::Contains(anchkey1, anchkey2),
                 Statement::Equal(anchkey3, anchkey4),
             ) if anchkey1.eq(anchkey3) => {
                 Ok(Statement::Contains(anchkey4.clone(), anchkey2.clone()))
             }
+            // Sums and products are over NUM_BITS-bit integers, so they can't wrap around the
+            // Goldilocks modulus
             Self::SumOf(
                 Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
                 Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
                 Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
-            ) if *x1 == *x2 + *x3 => Ok(Statement::SumOf(
+            ) if fit_in_num_bits(&[x1, x2, x3])
+                && x1.to_canonical_u64() == x2.to_canonical_u64() + x3.to_canonical_u64() =>
+            {
+                Ok(Statement::SumOf(
+                    anchkey1.clone(),
+                    anchkey2.clone(),
+                    anchkey3.clone(),
+                ))
+            }
+            Self::ProductOf(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
+                Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
+            ) if fit_in_num_bits(&[x1, x2, x3])
+                && x1.to_canonical_u64() == x2.to_canonical_u64() * x3.to_canonical_u64() =>
+            {
+                Ok(Statement::ProductOf(
+                    anchkey1.clone(),
+                    anchkey2.clone(),
+                    anchkey3.clone(),
+                ))
+            }
+            Self::MaxOf(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
+                Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
+            ) if x1.to_canonical_u64()
+                == Ord::max(x2.to_canonical_u64(), x3.to_canonical_u64()) =>
+            {
+                Ok(Statement::MaxOf(
+                    anchkey1.clone(),
+                    anchkey2.clone(),
+                    anchkey3.clone(),
+                ))
+            }
+            // Subtraction is field subtraction, so a result that would be negative wraps
+            // around the Goldilocks modulus, e.g. 2 - 5 = p - 3.
+            Self::SubOf(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
+                Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
+            ) if *x1 == *x2 - *x3 => Ok(Statement::SubOf(
                 anchkey1.clone(),
                 anchkey2.clone(),
                 anchkey3.clone(),
             )),
-            Self::ProductOf(
+            // Division is field division, i.e. x1 is the unique element such that
+            // x1 * x3 = x2. This agrees with integer division whenever x3 divides x2
+            // exactly. Division by zero is never a valid claim.
+            Self::DivOf(
                 Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
                 Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
                 Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
-            ) if *x1 == *x2 * *x3 => Ok(Statement::ProductOf(
+            ) if !x3.is_zero() && *x1 * *x3 == *x2 => Ok(Statement::DivOf(
                 anchkey1.clone(),
                 anchkey2.clone(),
                 anchkey3.clone(),
             )),
-            Self::MaxOf(
+            Self::MinOf(
                 Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
                 Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
                 Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
             ) if x1.to_canonical_u64()
-                == Ord::max(x2.to_canonical_u64(), x3.to_canonical_u64()) =>
+                == Ord::min(x2.to_canonical_u64(), x3.to_canonical_u64()) =>
             {
-                Ok(Statement::MaxOf(
+                Ok(Statement::MinOf(
+                    anchkey1.clone(),
+                    anchkey2.clone(),
+                    anchkey3.clone(),
+                ))
+            }
+            // Like the circuit's comparisons, values are assumed to fit in NUM_BITS bits
+            Self::RangeCheckFromEntries(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(lo)),
+                Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(hi)),
+            ) if fit_in_num_bits(&[x, lo, hi])
+                && (lo.to_canonical_u64()..=hi.to_canonical_u64())
+                    .contains(&x.to_canonical_u64()) =>
+            {
+                Ok(Statement::InRange(
+                    anchkey1.clone(),
+                    anchkey2.clone(),
+                    anchkey3.clone(),
+                ))
+            }
+            Self::ElementAtFromEntries(
+                Statement::ValueOf(anchkey, ScalarOrVec::Vector(vec)),
+                Statement::ValueOf(_, ScalarOrVec::Scalar(index)),
+            ) if index.to_canonical_u64() < vec.len() as u64 => {
+                let index = index.to_canonical_u64() as usize;
+                Ok(Statement::ElementAt(anchkey.clone(), index, vec[index]))
+            }
+            // Integer division over canonical values, i.e. x2 = x3 * q + r with 0 <= r < x3,
+            // unlike DivOf's field division
+            Self::QuotientOf(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
+                Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
+            ) if x2.to_canonical_u64().checked_div(x3.to_canonical_u64())
+                == Some(x1.to_canonical_u64()) =>
+            {
+                Ok(Statement::QuotientOf(
+                    anchkey1.clone(),
+                    anchkey2.clone(),
+                    anchkey3.clone(),
+                ))
+            }
+            Self::RemainderOf(
+                Statement::ValueOf(anchkey1, ScalarOrVec::Scalar(x1)),
+                Statement::ValueOf(anchkey2, ScalarOrVec::Scalar(x2)),
+                Statement::ValueOf(anchkey3, ScalarOrVec::Scalar(x3)),
+            ) if x2.to_canonical_u64().checked_rem(x3.to_canonical_u64())
+                == Some(x1.to_canonical_u64()) =>
+            {
+                Ok(Statement::RemainderOf(
                     anchkey1.clone(),
                     anchkey2.clone(),
                     anchkey3.clone(),
@@ -115,6 +238,11 @@ impl Operation<Statement> {
     }
 }
 
+/// Whether the values are below 2^NUM_BITS, the range comparisons and arithmetic assume.
+pub fn fit_in_num_bits(values: &[&GoldilocksField]) -> bool {
+    values.iter().all(|v| v.to_canonical_u64() < 1 << NUM_BITS)
+}
+
 impl<S: StatementOrRef> Operation<S> {
     /// Resolution of indirect operation specification.
     pub fn deref_args(&self, table: &S::StatementTable) -> Result<Operation<Statement>> {
@@ -170,6 +298,48 @@ impl<S: StatementOrRef> Operation<S> {
                 s2.deref_cloned(table)?,
                 s3.deref_cloned(table)?,
             )),
+            Self::SubOf(s1, s2, s3) => Ok(Op::SubOf(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+                s3.deref_cloned(table)?,
+            )),
+            Self::DivOf(s1, s2, s3) => Ok(Op::DivOf(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+                s3.deref_cloned(table)?,
+            )),
+            Self::MinOf(s1, s2, s3) => Ok(Op::MinOf(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+                s3.deref_cloned(table)?,
+            )),
+            Self::NotContainsFromEntries(s1, s2) => Ok(Op::NotContainsFromEntries(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+            )),
+            Self::RangeCheckFromEntries(s1, s2, s3) => Ok(Op::RangeCheckFromEntries(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+                s3.deref_cloned(table)?,
+            )),
+            Self::ElementAtFromEntries(s1, s2) => Ok(Op::ElementAtFromEntries(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+            )),
+            Self::QuotientOf(s1, s2, s3) => Ok(Op::QuotientOf(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+                s3.deref_cloned(table)?,
+            )),
+            Self::RemainderOf(s1, s2, s3) => Ok(Op::RemainderOf(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+                s3.deref_cloned(table)?,
+            )),
+            Self::TransitiveGtFromStatements(s1, s2) => Ok(Op::TransitiveGtFromStatements(
+                s1.deref_cloned(table)?,
+                s2.deref_cloned(table)?,
+            )),
         }
     }
     /// Opcodes
@@ -188,6 +358,15 @@ impl<S: StatementOrRef> Operation<S> {
     pub const MAX_OF: GoldilocksField = GoldilocksField(12);
     pub const LT_FROM_ENTRIES: GoldilocksField = GoldilocksField(13);
     pub const LT_TO_NONEQUALITY: GoldilocksField = GoldilocksField(14);
+    pub const SUB_OF: GoldilocksField = GoldilocksField(15);
+    pub const DIV_OF: GoldilocksField = GoldilocksField(16);
+    pub const MIN_OF: GoldilocksField = GoldilocksField(17);
+    pub const NOT_CONTAINS_FROM_ENTRIES: GoldilocksField = GoldilocksField(18);
+    pub const RANGE_CHECK_FROM_ENTRIES: GoldilocksField = GoldilocksField(19);
+    pub const ELEMENT_AT_FROM_ENTRIES: GoldilocksField = GoldilocksField(20);
+    pub const QUOTIENT_OF: GoldilocksField = GoldilocksField(21);
+    pub const REMAINDER_OF: GoldilocksField = GoldilocksField(22);
+    pub const TRANSITIVE_GT_FROM_STATEMENTS: GoldilocksField = GoldilocksField(23);
 
     /// Method specifying opcodes.
     pub fn code(&self) -> GoldilocksField {
@@ -209,6 +388,15 @@ impl<S: StatementOrRef> Operation<S> {
             Self::MaxOf(_, _, _) => Self::MAX_OF,
             Self::LtFromEntries(_, _) => Self::LT_FROM_ENTRIES,
             Self::LtToNonequality(_) => Self::LT_TO_NONEQUALITY,
+            Self::SubOf(_, _, _) => Self::SUB_OF,
+            Self::DivOf(_, _, _) => Self::DIV_OF,
+            Self::MinOf(_, _, _) => Self::MIN_OF,
+            Self::NotContainsFromEntries(_, _) => Self::NOT_CONTAINS_FROM_ENTRIES,
+            Self::RangeCheckFromEntries(_, _, _) => Self::RANGE_CHECK_FROM_ENTRIES,
+            Self::ElementAtFromEntries(_, _) => Self::ELEMENT_AT_FROM_ENTRIES,
+            Self::QuotientOf(_, _, _) => Self::QUOTIENT_OF,
+            Self::RemainderOf(_, _, _) => Self::REMAINDER_OF,
+            Self::TransitiveGtFromStatements(_, _) => Self::TRANSITIVE_GT_FROM_STATEMENTS,
         }
     }
     /// Method specifying operands.
@@ -225,6 +413,15 @@ impl<S: StatementOrRef> Operation<S> {
             Self::SumOf(s1, s2, s3) => vec![s1, s2, s3],
             Self::ProductOf(s1, s2, s3) => vec![s1, s2, s3],
             Self::MaxOf(s1, s2, s3) => vec![s1, s2, s3],
+            Self::SubOf(s1, s2, s3) => vec![s1, s2, s3],
+            Self::DivOf(s1, s2, s3) => vec![s1, s2, s3],
+            Self::MinOf(s1, s2, s3) => vec![s1, s2, s3],
+            Self::NotContainsFromEntries(s1, s2) => vec![s1, s2],
+            Self::RangeCheckFromEntries(s1, s2, s3) => vec![s1, s2, s3],
+            Self::ElementAtFromEntries(s1, s2) => vec![s1, s2],
+            Self::QuotientOf(s1, s2, s3) => vec![s1, s2, s3],
+            Self::RemainderOf(s1, s2, s3) => vec![s1, s2, s3],
+            Self::TransitiveGtFromStatements(s1, s2) => vec![s1, s2],
             _ => vec![],
         }
     }
@@ -251,6 +448,38 @@ impl OperationCmd {
 }
 
 impl Operation<StatementRef> {
+    /// Predicate of the statement this operation outputs, which is known without executing it:
+    /// a copy has the predicate of the statement it refers to.
+    pub fn output_predicate(&self) -> Predicate {
+        match self {
+            Self::None => Predicate::None,
+            Self::NewEntry(_) => Predicate::ValueOf,
+            Self::CopyStatement(StatementRef(_, statement_id)) => {
+                statement_id.predicate.unwrap_or(Predicate::None)
+            }
+            Self::EqualityFromEntries(_, _) => Predicate::Equal,
+            Self::NonequalityFromEntries(_, _) => Predicate::NotEqual,
+            Self::GtFromEntries(_, _) => Predicate::Gt,
+            Self::TransitiveEqualityFromStatements(_, _) => Predicate::Equal,
+            Self::GtToNonequality(_) => Predicate::NotEqual,
+            Self::ContainsFromEntries(_, _) => Predicate::Contains,
+            Self::RenameContainedBy(_, _) => Predicate::Contains,
+            Self::SumOf(_, _, _) => Predicate::SumOf,
+            Self::ProductOf(_, _, _) => Predicate::ProductOf,
+            Self::MaxOf(_, _, _) => Predicate::MaxOf,
+            Self::LtFromEntries(_, _) => Predicate::Lt,
+            Self::LtToNonequality(_) => Predicate::NotEqual,
+            Self::SubOf(_, _, _) => Predicate::SubOf,
+            Self::DivOf(_, _, _) => Predicate::DivOf,
+            Self::MinOf(_, _, _) => Predicate::MinOf,
+            Self::NotContainsFromEntries(_, _) => Predicate::NotContains,
+            Self::RangeCheckFromEntries(_, _, _) => Predicate::InRange,
+            Self::ElementAtFromEntries(_, _) => Predicate::ElementAt,
+            Self::QuotientOf(_, _, _) => Predicate::QuotientOf,
+            Self::RemainderOf(_, _, _) => Predicate::RemainderOf,
+            Self::TransitiveGtFromStatements(_, _) => Predicate::Gt,
+        }
+    }
     /// Representation of operation command as field vector of length
     /// 9 + VL of the form
     /// [code] ++ [pod_num1, statement_num1] ++ [pod_num2,
@@ -293,7 +522,7 @@ impl Operation<StatementRef> {
 
         // Check for `contains` op.
         let contains_proof = match self {
-            Self::ContainsFromEntries(s_ref, _) => {
+            Self::ContainsFromEntries(s_ref, _) | Self::NotContainsFromEntries(s_ref, _) => {
                 // Look up statement
                 let statement = s_ref.deref_cloned(statement_table)?;
                 match statement {
@@ -339,7 +568,7 @@ impl OpList {
                 Operation::NewEntry(_) => 1,
                 Operation::CopyStatement(s_ref) => {
                     let (pod_index, statement_index) = ref_index_map.get(&s_ref).unwrap();
-                    pods_list[*pod_index].1.payload.statements_list[*statement_index]
+                    pods_list[*pod_index].1.payload.statements_list()[*statement_index]
                         .1
                         .code()
                         .to_canonical_u64()
@@ -356,6 +585,15 @@ impl OpList {
                 Operation::MaxOf(_, _, _) => 8,
                 Operation::LtFromEntries(_, _) => 9,
                 Operation::LtToNonequality(_) => 3,
+                Operation::SubOf(_, _, _) => 10,
+                Operation::DivOf(_, _, _) => 11,
+                Operation::MinOf(_, _, _) => 12,
+                Operation::NotContainsFromEntries(_, _) => 13,
+                Operation::RangeCheckFromEntries(_, _, _) => 14,
+                Operation::ElementAtFromEntries(_, _) => 15,
+                Operation::QuotientOf(_, _, _) => 16,
+                Operation::RemainderOf(_, _, _) => 17,
+                Operation::TransitiveGtFromStatements(_, _) => 4,
             }))
         };
 
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use super::gadget::GadgetID;
 
@@ -53,3 +54,9 @@ impl Origin {
         Ok(Self::new(new_origin_id, new_origin_name, self.gadget_id))
     }
 }
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.origin_name)
+    }
+}
@@ -4,7 +4,7 @@ use plonky2::{
     plonk::config::Hasher,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, sync::OnceLock};
 
 use super::statement::Statement;
 use crate::F;
@@ -21,10 +21,14 @@ pub trait HashablePayload: Clone + PartialEq {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// The payload hash is cached once computed, so the statements are only modified through
+/// `set_statement`, which clears the cache. The cache is neither serialised nor compared.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PODPayload {
-    pub statements_list: Vec<(String, Statement)>, // ORDERED list of statements, ordered by names
-    pub statements_map: HashMap<String, Statement>,
+    statements_list: Vec<(String, Statement)>, // ORDERED list of statements, ordered by names
+    statements_map: HashMap<String, Statement>,
+    #[serde(skip)]
+    hash: OnceLock<HashOut<F>>,
 }
 
 impl PODPayload {
@@ -34,10 +38,57 @@ impl PODPayload {
             statements_and_names_list.push((name.clone(), statement.clone()));
         }
         statements_and_names_list.sort_by(|a, b| a.0.cmp(&b.0));
+        Self::from_parts(statements_and_names_list, statements.clone())
+    }
+
+    /// Payload with the statements in the given order, which is the order they are hashed in.
+    pub fn from_parts(
+        statements_list: Vec<(String, Statement)>,
+        statements_map: HashMap<String, Statement>,
+    ) -> Self {
         Self {
-            statements_list: statements_and_names_list,
-            statements_map: statements.clone(),
+            statements_list,
+            statements_map,
+            hash: OnceLock::new(),
+        }
+    }
+
+    /// The statements in the order they are hashed in.
+    pub fn statements_list(&self) -> &[(String, Statement)] {
+        &self.statements_list
+    }
+
+    pub fn statements_map(&self) -> &HashMap<String, Statement> {
+        &self.statements_map
+    }
+
+    /// Replaces the statement with the given name, or adds it in name order if there is none.
+    pub fn set_statement(&mut self, name: String, statement: Statement) {
+        match self.statements_list.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, s)) => *s = statement.clone(),
+            None => {
+                let index = self.statements_list.partition_point(|(n, _)| *n < name);
+                self.statements_list
+                    .insert(index, (name.clone(), statement.clone()));
+            }
         }
+        self.statements_map.insert(name, statement);
+        self.hash = OnceLock::new();
+    }
+}
+
+impl PartialEq for PODPayload {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements_list == other.statements_list && self.statements_map == other.statements_map
+    }
+}
+
+impl fmt::Debug for PODPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PODPayload")
+            .field("statements_list", &self.statements_list)
+            .field("statements_map", &self.statements_map)
+            .finish()
     }
 }
 
@@ -58,4 +109,62 @@ impl HashablePayload for PODPayload {
         }
         statements_vec.to_field_vec()
     }
+
+    fn hash_payload(&self) -> HashOut<F> {
+        *self
+            .hash
+            .get_or_init(|| PoseidonHash::hash_no_pad(&self.to_field_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pod::{entry::Entry, gadget::GadgetID};
+
+    fn value_of(key: &str, value: u64) -> (String, Statement) {
+        (
+            format!("VALUEOF:{}", key),
+            Statement::from_entry(
+                &Entry::new_from_scalar(key, GoldilocksField(value)),
+                GadgetID::SCHNORR16,
+            ),
+        )
+    }
+
+    #[test]
+    fn test_payload_hash_cache() -> anyhow::Result<()> {
+        let mut payload = PODPayload::new(&[value_of("a", 1), value_of("b", 2)].into());
+        let hash = payload.hash_payload();
+        assert_eq!(payload.hash_payload(), hash);
+        assert_eq!(hash, PoseidonHash::hash_no_pad(&payload.to_field_vec()));
+
+        // The cache is left out of serialisation and comparisons
+        let serialised = serde_json::to_string(&payload)?;
+        assert!(!serialised.contains("hash"));
+        let deserialised: PODPayload = serde_json::from_str(&serialised)?;
+        assert_eq!(deserialised, payload);
+        assert_eq!(deserialised.hash_payload(), hash);
+
+        // Changing a statement changes the hash
+        let (name, statement) = value_of("a", 3);
+        payload.set_statement(name, statement);
+        let changed_hash = payload.hash_payload();
+        assert_ne!(changed_hash, hash);
+        assert_eq!(
+            changed_hash,
+            PODPayload::new(&[value_of("a", 3), value_of("b", 2)].into()).hash_payload()
+        );
+
+        // New statements are added in name order
+        let (name, statement) = value_of("aa", 4);
+        payload.set_statement(name, statement);
+        assert_ne!(payload.hash_payload(), changed_hash);
+        assert_eq!(
+            payload,
+            PODPayload::new(&[value_of("a", 3), value_of("aa", 4), value_of("b", 2)].into())
+        );
+
+        Ok(())
+    }
 }
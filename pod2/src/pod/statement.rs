@@ -4,7 +4,7 @@ use plonky2::field::{
     types::{Field, PrimeField64},
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt, fmt::Debug};
+use std::{collections::HashMap, convert::Infallible, fmt, fmt::Debug, str::FromStr};
 
 use super::{
     entry::Entry,
@@ -44,32 +44,62 @@ impl AnchoredKey {
 impl fmt::Display for AnchoredKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let AnchoredKey(origin, key) = self;
-        if origin.origin_name == "_SELF" {
-            write!(f, "{}", key)
-        } else {
-            write!(f, "{}:{}", origin.origin_name, key)
-        }
+        write!(f, "{}.{}", origin, key)
     }
 }
 
+/// One line per statement, the predicate followed by its arguments, e.g.
+/// `GT: pod-1.age > _SELF.age_bound` or `VALUEOF: _SELF.age = 30`.
 impl fmt::Display for Statement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let predicate = self.predicate();
         match self {
-            Statement::None => write!(f, "None"),
-            Statement::ValueOf(key, value) => write!(f, "ValueOf({} = {:?})", key, value),
-            Statement::Equal(op1, op2) => write!(f, "Equal({} = {})", op1, op2),
-            Statement::NotEqual(op1, op2) => write!(f, "NotEqual({} ≠ {})", op1, op2),
-            Statement::Gt(op1, op2) => write!(f, "Gt({} > {})", op1, op2),
-            Statement::Lt(op1, op2) => write!(f, "Lt({} > {})", op1, op2),
-            Statement::Contains(op1, op2) => write!(f, "Contains({} ∈ {})", op1, op2),
+            Statement::None => write!(f, "{}", predicate),
+            Statement::ValueOf(key, value) => write!(f, "{}: {} = {}", predicate, key, value),
+            Statement::Equal(op1, op2) => write!(f, "{}: {} = {}", predicate, op1, op2),
+            Statement::NotEqual(op1, op2) => write!(f, "{}: {} ≠ {}", predicate, op1, op2),
+            Statement::Gt(op1, op2) => write!(f, "{}: {} > {}", predicate, op1, op2),
+            Statement::Lt(op1, op2) => write!(f, "{}: {} < {}", predicate, op1, op2),
+            Statement::Contains(vector, value) => {
+                write!(f, "{}: {} ∈ {}", predicate, value, vector)
+            }
+            Statement::NotContains(vector, value) => {
+                write!(f, "{}: {} ∉ {}", predicate, value, vector)
+            }
             Statement::SumOf(result, op1, op2) => {
-                write!(f, "SumOf({} = {} + {})", result, op1, op2)
+                write!(f, "{}: {} = {} + {}", predicate, result, op1, op2)
             }
             Statement::ProductOf(result, op1, op2) => {
-                write!(f, "ProductOf({} = {} × {})", result, op1, op2)
+                write!(f, "{}: {} = {} × {}", predicate, result, op1, op2)
             }
             Statement::MaxOf(result, op1, op2) => {
-                write!(f, "MaxOf({} = max({}, {}))", result, op1, op2)
+                write!(f, "{}: {} = max({}, {})", predicate, result, op1, op2)
+            }
+            Statement::SubOf(result, op1, op2) => {
+                write!(f, "{}: {} = {} - {}", predicate, result, op1, op2)
+            }
+            Statement::DivOf(result, op1, op2) => {
+                write!(f, "{}: {} = {} / {}", predicate, result, op1, op2)
+            }
+            Statement::MinOf(result, op1, op2) => {
+                write!(f, "{}: {} = min({}, {})", predicate, result, op1, op2)
+            }
+            Statement::InRange(value, lo, hi) => {
+                write!(f, "{}: {} ≤ {} ≤ {}", predicate, lo, value, hi)
+            }
+            Statement::ElementAt(vector, index, value) => write!(
+                f,
+                "{}: {}[{}] = {}",
+                predicate,
+                vector,
+                index,
+                value.to_canonical_u64()
+            ),
+            Statement::QuotientOf(result, op1, op2) => {
+                write!(f, "{}: {} = {} div {}", predicate, result, op1, op2)
+            }
+            Statement::RemainderOf(result, op1, op2) => {
+                write!(f, "{}: {} = {} mod {}", predicate, result, op1, op2)
             }
         }
     }
@@ -87,22 +117,23 @@ pub enum Statement {
     SumOf(AnchoredKey, AnchoredKey, AnchoredKey),
     ProductOf(AnchoredKey, AnchoredKey, AnchoredKey),
     MaxOf(AnchoredKey, AnchoredKey, AnchoredKey),
+    SubOf(AnchoredKey, AnchoredKey, AnchoredKey),
+    DivOf(AnchoredKey, AnchoredKey, AnchoredKey),
+    MinOf(AnchoredKey, AnchoredKey, AnchoredKey),
+    NotContains(AnchoredKey, AnchoredKey),
+    /// Value, lower bound and upper bound, both bounds inclusive.
+    InRange(AnchoredKey, AnchoredKey, AnchoredKey),
+    /// Vector entry, index and the element at that index, so the element can be referred to
+    /// without the rest of the vector.
+    ElementAt(AnchoredKey, usize, GoldilocksField),
+    /// Result, dividend and divisor of an integer division.
+    QuotientOf(AnchoredKey, AnchoredKey, AnchoredKey),
+    RemainderOf(AnchoredKey, AnchoredKey, AnchoredKey),
 }
 
 impl Statement {
     pub fn predicate(&self) -> &'static str {
-        match self {
-            Statement::None => "NONE",
-            Statement::ValueOf(_, _) => "VALUEOF",
-            Statement::Equal(_, _) => "EQUAL",
-            Statement::NotEqual(_, _) => "NOTEQUAL",
-            Statement::Gt(_, _) => "GT",
-            Statement::Lt(_, _) => "LT",
-            Statement::Contains(_, _) => "CONTAINS",
-            Statement::SumOf(_, _, _) => "SUMOF",
-            Statement::ProductOf(_, _, _) => "PRODUCTOF",
-            Statement::MaxOf(_, _, _) => "MAXOF",
-        }
+        Predicate::from(self).name()
     }
     pub fn code_to_predicate(code: GoldilocksField) -> &'static str {
         match code.to_canonical_u64() {
@@ -116,6 +147,14 @@ impl Statement {
             7 => "PRODUCTOF",
             8 => "MAXOF",
             9 => "LT",
+            10 => "SUBOF",
+            11 => "DIVOF",
+            12 => "MINOF",
+            13 => "NOTCONTAINS",
+            14 => "INRANGE",
+            15 => "ELEMENTAT",
+            16 => "QUOTIENTOF",
+            17 => "REMAINDEROF",
             _ => "",
         }
     }
@@ -139,6 +178,14 @@ impl Statement {
     pub const PRODUCT_OF: GoldilocksField = GoldilocksField(7);
     pub const MAX_OF: GoldilocksField = GoldilocksField(8);
     pub const LT: GoldilocksField = GoldilocksField(9);
+    pub const SUB_OF: GoldilocksField = GoldilocksField(10);
+    pub const DIV_OF: GoldilocksField = GoldilocksField(11);
+    pub const MIN_OF: GoldilocksField = GoldilocksField(12);
+    pub const NOT_CONTAINS: GoldilocksField = GoldilocksField(13);
+    pub const IN_RANGE: GoldilocksField = GoldilocksField(14);
+    pub const ELEMENT_AT: GoldilocksField = GoldilocksField(15);
+    pub const QUOTIENT_OF: GoldilocksField = GoldilocksField(16);
+    pub const REMAINDER_OF: GoldilocksField = GoldilocksField(17);
     pub fn code(&self) -> GoldilocksField {
         match self {
             Self::None => Self::NONE,
@@ -151,6 +198,14 @@ impl Statement {
             Self::ProductOf(_, _, _) => Self::PRODUCT_OF,
             Self::MaxOf(_, _, _) => Self::MAX_OF,
             Self::Lt(_, _) => Self::LT,
+            Self::SubOf(_, _, _) => Self::SUB_OF,
+            Self::DivOf(_, _, _) => Self::DIV_OF,
+            Self::MinOf(_, _, _) => Self::MIN_OF,
+            Self::NotContains(_, _) => Self::NOT_CONTAINS,
+            Self::InRange(_, _, _) => Self::IN_RANGE,
+            Self::ElementAt(_, _, _) => Self::ELEMENT_AT,
+            Self::QuotientOf(_, _, _) => Self::QUOTIENT_OF,
+            Self::RemainderOf(_, _, _) => Self::REMAINDER_OF,
         }
     }
     /// Field representation as a vector of length 11.
@@ -220,6 +275,50 @@ impl Statement {
                     vec![GoldilocksField::ZERO],
                 ]
                 .concat(),
+                Self::SubOf(anchkey1, anchkey2, anchkey3) => [
+                    anchkey1.to_fields(),
+                    anchkey2.to_fields(),
+                    anchkey3.to_fields(),
+                    vec![GoldilocksField::ZERO],
+                ]
+                .concat(),
+                Self::DivOf(anchkey1, anchkey2, anchkey3) => [
+                    anchkey1.to_fields(),
+                    anchkey2.to_fields(),
+                    anchkey3.to_fields(),
+                    vec![GoldilocksField::ZERO],
+                ]
+                .concat(),
+                Self::MinOf(anchkey1, anchkey2, anchkey3) => [
+                    anchkey1.to_fields(),
+                    anchkey2.to_fields(),
+                    anchkey3.to_fields(),
+                    vec![GoldilocksField::ZERO],
+                ]
+                .concat(),
+                Self::NotContains(anchkey1, anchkey2) => [
+                    anchkey1.to_fields(),
+                    anchkey2.to_fields(),
+                    vec![GoldilocksField::ZERO; 4],
+                ]
+                .concat(),
+                Self::InRange(anchkey1, anchkey2, anchkey3)
+                | Self::QuotientOf(anchkey1, anchkey2, anchkey3)
+                | Self::RemainderOf(anchkey1, anchkey2, anchkey3) => [
+                    anchkey1.to_fields(),
+                    anchkey2.to_fields(),
+                    anchkey3.to_fields(),
+                    vec![GoldilocksField::ZERO],
+                ]
+                .concat(),
+                // The index takes the place of the second key
+                Self::ElementAt(anchkey, index, value) => [
+                    anchkey.to_fields(),
+                    vec![GoldilocksField::from_canonical_usize(*index)],
+                    vec![GoldilocksField::ZERO; 5],
+                    vec![*value],
+                ]
+                .concat(),
             },
         ]
         .concat()
@@ -266,6 +365,43 @@ impl Statement {
                 anchkey2.remap_origin(f)?,
                 anchkey3.remap_origin(f)?,
             )),
+            Self::SubOf(anchkey1, anchkey2, anchkey3) => Ok(Self::SubOf(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+                anchkey3.remap_origin(f)?,
+            )),
+            Self::DivOf(anchkey1, anchkey2, anchkey3) => Ok(Self::DivOf(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+                anchkey3.remap_origin(f)?,
+            )),
+            Self::MinOf(anchkey1, anchkey2, anchkey3) => Ok(Self::MinOf(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+                anchkey3.remap_origin(f)?,
+            )),
+            Self::NotContains(anchkey1, anchkey2) => Ok(Self::NotContains(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+            )),
+            Self::InRange(anchkey1, anchkey2, anchkey3) => Ok(Self::InRange(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+                anchkey3.remap_origin(f)?,
+            )),
+            Self::ElementAt(anchkey, index, value) => {
+                Ok(Self::ElementAt(anchkey.remap_origin(f)?, *index, *value))
+            }
+            Self::QuotientOf(anchkey1, anchkey2, anchkey3) => Ok(Self::QuotientOf(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+                anchkey3.remap_origin(f)?,
+            )),
+            Self::RemainderOf(anchkey1, anchkey2, anchkey3) => Ok(Self::RemainderOf(
+                anchkey1.remap_origin(f)?,
+                anchkey2.remap_origin(f)?,
+                anchkey3.remap_origin(f)?,
+            )),
         }
     }
     // Misc helpers
@@ -293,6 +429,26 @@ impl Statement {
             Self::MaxOf(anchkey1, anchkey2, anchkey3) => {
                 vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
             }
+            Self::SubOf(anchkey1, anchkey2, anchkey3) => {
+                vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
+            }
+            Self::DivOf(anchkey1, anchkey2, anchkey3) => {
+                vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
+            }
+            Self::MinOf(anchkey1, anchkey2, anchkey3) => {
+                vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
+            }
+            Self::NotContains(anchkey1, anchkey2) => vec![anchkey1.clone(), anchkey2.clone()],
+            Self::InRange(anchkey1, anchkey2, anchkey3) => {
+                vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
+            }
+            Self::ElementAt(anchkey, _, _) => vec![anchkey.clone()],
+            Self::QuotientOf(anchkey1, anchkey2, anchkey3) => {
+                vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
+            }
+            Self::RemainderOf(anchkey1, anchkey2, anchkey3) => {
+                vec![anchkey1.clone(), anchkey2.clone(), anchkey3.clone()]
+            }
         }
     }
     // Helper to get the anchoredkey of a value of statement
@@ -307,7 +463,12 @@ impl Statement {
         match self {
             Statement::SumOf(result, _, _)
             | Statement::ProductOf(result, _, _)
-            | Statement::MaxOf(result, _, _) => Some(result.clone()),
+            | Statement::MaxOf(result, _, _)
+            | Statement::SubOf(result, _, _)
+            | Statement::DivOf(result, _, _)
+            | Statement::MinOf(result, _, _)
+            | Statement::QuotientOf(result, _, _)
+            | Statement::RemainderOf(result, _, _) => Some(result.clone()),
             _ => None,
         }
     }
@@ -330,24 +491,197 @@ impl StatementOrRef for Statement {
     }
 }
 
+/// Predicate of a statement, without its arguments.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Predicate {
+    None,
+    ValueOf,
+    Equal,
+    NotEqual,
+    Gt,
+    Lt,
+    Contains,
+    SumOf,
+    ProductOf,
+    MaxOf,
+    SubOf,
+    DivOf,
+    MinOf,
+    NotContains,
+    InRange,
+    ElementAt,
+    QuotientOf,
+    RemainderOf,
+}
+
+impl Predicate {
+    pub const ALL: [Self; 18] = [
+        Self::None,
+        Self::ValueOf,
+        Self::Equal,
+        Self::NotEqual,
+        Self::Gt,
+        Self::Lt,
+        Self::Contains,
+        Self::SumOf,
+        Self::ProductOf,
+        Self::MaxOf,
+        Self::SubOf,
+        Self::DivOf,
+        Self::MinOf,
+        Self::NotContains,
+        Self::InRange,
+        Self::ElementAt,
+        Self::QuotientOf,
+        Self::RemainderOf,
+    ];
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "NONE",
+            Self::ValueOf => "VALUEOF",
+            Self::Equal => "EQUAL",
+            Self::NotEqual => "NOTEQUAL",
+            Self::Gt => "GT",
+            Self::Lt => "LT",
+            Self::Contains => "CONTAINS",
+            Self::SumOf => "SUMOF",
+            Self::ProductOf => "PRODUCTOF",
+            Self::MaxOf => "MAXOF",
+            Self::SubOf => "SUBOF",
+            Self::DivOf => "DIVOF",
+            Self::MinOf => "MINOF",
+            Self::NotContains => "NOTCONTAINS",
+            Self::InRange => "INRANGE",
+            Self::ElementAt => "ELEMENTAT",
+            Self::QuotientOf => "QUOTIENTOF",
+            Self::RemainderOf => "REMAINDEROF",
+        }
+    }
+}
+
+impl From<&Statement> for Predicate {
+    fn from(statement: &Statement) -> Self {
+        match statement {
+            Statement::None => Self::None,
+            Statement::ValueOf(_, _) => Self::ValueOf,
+            Statement::Equal(_, _) => Self::Equal,
+            Statement::NotEqual(_, _) => Self::NotEqual,
+            Statement::Gt(_, _) => Self::Gt,
+            Statement::Lt(_, _) => Self::Lt,
+            Statement::Contains(_, _) => Self::Contains,
+            Statement::SumOf(_, _, _) => Self::SumOf,
+            Statement::ProductOf(_, _, _) => Self::ProductOf,
+            Statement::MaxOf(_, _, _) => Self::MaxOf,
+            Statement::SubOf(_, _, _) => Self::SubOf,
+            Statement::DivOf(_, _, _) => Self::DivOf,
+            Statement::MinOf(_, _, _) => Self::MinOf,
+            Statement::NotContains(_, _) => Self::NotContains,
+            Statement::InRange(_, _, _) => Self::InRange,
+            Statement::ElementAt(_, _, _) => Self::ElementAt,
+            Statement::QuotientOf(_, _, _) => Self::QuotientOf,
+            Statement::RemainderOf(_, _, _) => Self::RemainderOf,
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|predicate| predicate.name() == s)
+            .ok_or(anyhow!("Unknown predicate {}", s))
+    }
+}
+
+/// Name of a statement in a POD: the predicate and output name of the operation producing it,
+/// e.g. `VALUEOF:age` for the entry `age`. Padding statements only have a name. Names may
+/// contain ':', as only a known predicate before the first ':' is split off.
+///
+/// Statements maps are keyed by the string form, which is also how ids are serialised.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", from = "String")]
+pub struct StatementId {
+    pub predicate: Option<Predicate>,
+    pub name: String,
+}
+
+impl StatementId {
+    pub fn new(predicate: Predicate, name: impl Into<String>) -> Self {
+        Self {
+            predicate: Some(predicate),
+            name: name.into(),
+        }
+    }
+    /// Id of the statement of the entry `key`.
+    pub fn value_of(key: impl Into<String>) -> Self {
+        Self::new(Predicate::ValueOf, key)
+    }
+}
+
+impl fmt::Display for StatementId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.predicate {
+            Some(predicate) => write!(f, "{}:{}", predicate, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl FromStr for StatementId {
+    type Err = Infallible;
+    fn from_str(s: &str) -> Result<Self, Infallible> {
+        Ok(s.into())
+    }
+}
+
+impl From<&str> for StatementId {
+    fn from(s: &str) -> Self {
+        s.split_once(':')
+            .and_then(|(predicate, name)| Some(Self::new(predicate.parse().ok()?, name)))
+            .unwrap_or_else(|| Self {
+                predicate: None,
+                name: s.to_string(),
+            })
+    }
+}
+
+impl From<String> for StatementId {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<StatementId> for String {
+    fn from(id: StatementId) -> Self {
+        id.to_string()
+    }
+}
+
 /// Typical statement ref type.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct StatementRef(pub String, pub String);
+pub struct StatementRef(pub String, pub StatementId);
 
 impl StatementOrRef for StatementRef {
     type StatementTable = HashMap<String, HashMap<String, Statement>>;
     fn deref_cloned(&self, table: &Self::StatementTable) -> Result<Statement> {
-        let StatementRef(parent_name, statement_name) = self;
+        let StatementRef(parent_name, statement_id) = self;
         table
             .get(parent_name)
             .ok_or(anyhow!(
                 "Statement parent {} missing from statement table!",
                 parent_name
             ))?
-            .get(statement_name)
+            .get(&statement_id.to_string())
             .ok_or(anyhow!(
                 "Statement {} with parent {} missing from statement table!",
-                statement_name,
+                statement_id,
                 parent_name
             ))
             .cloned()
@@ -355,18 +689,18 @@ impl StatementOrRef for StatementRef {
 }
 
 impl StatementRef {
-    pub fn new(pod_name: impl Into<String>, statement_name: impl Into<String>) -> Self {
-        Self(pod_name.into(), statement_name.into())
+    pub fn new(pod_name: impl Into<String>, statement_id: impl Into<StatementId>) -> Self {
+        Self(pod_name.into(), statement_id.into())
     }
     pub fn index_map(pods_list: &[(String, POD)]) -> HashMap<Self, (usize, usize)> {
         pods_list
             .iter()
             .enumerate()
             .flat_map(|(pod_num, (pod_name, pod))| {
-                pod.payload.statements_list.iter().enumerate().map(
+                pod.payload.statements_list().iter().enumerate().map(
                     move |(statement_num, (statement_name, _))| {
                         (
-                            StatementRef(pod_name.clone(), statement_name.clone()),
+                            StatementRef(pod_name.clone(), statement_name.as_str().into()),
                             (pod_num, statement_num),
                         )
                     },
@@ -375,3 +709,133 @@ impl StatementRef {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_test() {
+        let key = |origin: &str, key: &str| {
+            let origin = if origin == "_SELF" {
+                Origin::auto(origin.to_string(), GadgetID::ORACLE)
+            } else {
+                Origin::new(GoldilocksField(2), origin.to_string(), GadgetID::SCHNORR16)
+            };
+            AnchoredKey(origin, key.to_string())
+        };
+        let (a, b, c) = (
+            key("pod-1", "age"),
+            key("_SELF", "age_bound"),
+            key("_SELF", "x"),
+        );
+        let vector = |n: u64| ScalarOrVec::Vector((1..=n).map(GoldilocksField).collect());
+
+        let cases = [
+            (Statement::None, "NONE"),
+            (
+                Statement::ValueOf(a.clone(), ScalarOrVec::Scalar(GoldilocksField(30))),
+                "VALUEOF: pod-1.age = 30",
+            ),
+            (
+                Statement::ValueOf(c.clone(), vector(3)),
+                "VALUEOF: _SELF.x = [1, 2, 3]",
+            ),
+            (
+                Statement::ValueOf(c.clone(), vector(10)),
+                "VALUEOF: _SELF.x = [1, 2, 3, 4, … (10 elements)]",
+            ),
+            (
+                Statement::Equal(a.clone(), b.clone()),
+                "EQUAL: pod-1.age = _SELF.age_bound",
+            ),
+            (
+                Statement::NotEqual(a.clone(), b.clone()),
+                "NOTEQUAL: pod-1.age ≠ _SELF.age_bound",
+            ),
+            (
+                Statement::Gt(a.clone(), b.clone()),
+                "GT: pod-1.age > _SELF.age_bound",
+            ),
+            (
+                Statement::Lt(a.clone(), b.clone()),
+                "LT: pod-1.age < _SELF.age_bound",
+            ),
+            (
+                Statement::Contains(c.clone(), a.clone()),
+                "CONTAINS: pod-1.age ∈ _SELF.x",
+            ),
+            (
+                Statement::NotContains(c.clone(), a.clone()),
+                "NOTCONTAINS: pod-1.age ∉ _SELF.x",
+            ),
+            (
+                Statement::SumOf(c.clone(), a.clone(), b.clone()),
+                "SUMOF: _SELF.x = pod-1.age + _SELF.age_bound",
+            ),
+            (
+                Statement::ProductOf(c.clone(), a.clone(), b.clone()),
+                "PRODUCTOF: _SELF.x = pod-1.age × _SELF.age_bound",
+            ),
+            (
+                Statement::MaxOf(c.clone(), a.clone(), b.clone()),
+                "MAXOF: _SELF.x = max(pod-1.age, _SELF.age_bound)",
+            ),
+            (
+                Statement::SubOf(c.clone(), a.clone(), b.clone()),
+                "SUBOF: _SELF.x = pod-1.age - _SELF.age_bound",
+            ),
+            (
+                Statement::DivOf(c.clone(), a.clone(), b.clone()),
+                "DIVOF: _SELF.x = pod-1.age / _SELF.age_bound",
+            ),
+            (
+                Statement::MinOf(c.clone(), a.clone(), b.clone()),
+                "MINOF: _SELF.x = min(pod-1.age, _SELF.age_bound)",
+            ),
+            (
+                Statement::InRange(a.clone(), b.clone(), c.clone()),
+                "INRANGE: _SELF.age_bound ≤ pod-1.age ≤ _SELF.x",
+            ),
+            (
+                Statement::ElementAt(c.clone(), 1, GoldilocksField(2)),
+                "ELEMENTAT: _SELF.x[1] = 2",
+            ),
+            (
+                Statement::QuotientOf(c.clone(), a.clone(), b.clone()),
+                "QUOTIENTOF: _SELF.x = pod-1.age div _SELF.age_bound",
+            ),
+            (
+                Statement::RemainderOf(c, a, b),
+                "REMAINDEROF: _SELF.x = pod-1.age mod _SELF.age_bound",
+            ),
+        ];
+        for (statement, expected) in cases {
+            assert_eq!(statement.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn statement_id_test() -> Result<()> {
+        let cases = [
+            ("VALUEOF:age", Some(Predicate::ValueOf), "age"),
+            ("VALUEOF:a:b", Some(Predicate::ValueOf), "a:b"),
+            ("VALUEOF:âge ∈ 🎂", Some(Predicate::ValueOf), "âge ∈ 🎂"),
+            ("GT:statement_3", Some(Predicate::Gt), "statement_3"),
+            ("_DUMMY_STATEMENT2", None, "_DUMMY_STATEMENT2"),
+            // Not a predicate, so it's all name
+            ("UNKNOWN:x", None, "UNKNOWN:x"),
+        ];
+        for (s, predicate, name) in cases {
+            let id = StatementId::from(s);
+            assert_eq!((id.predicate, id.name.as_str()), (predicate, name));
+            assert_eq!(id.to_string(), s);
+        }
+
+        // Ids are (de)serialised as their string form, as statement names were before
+        let id = StatementId::value_of("a:b");
+        assert_eq!(serde_json::to_string(&id)?, "\"VALUEOF:a:b\"");
+        assert_eq!(serde_json::from_str::<StatementId>("\"VALUEOF:a:b\"")?, id);
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "prover")]
 use parcnet_pod::pod::Fq;
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::hash::poseidon::PoseidonHash;
@@ -39,6 +40,7 @@ fn str_to_fields(s: &str) -> Vec<GoldilocksField> {
         .collect()
 }
 
+#[cfg(feature = "prover")]
 pub fn bn254_fq_to_goldilocks_fq(s: Fq) -> Vec<GoldilocksField> {
     todo!()
 }
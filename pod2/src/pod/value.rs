@@ -1,12 +1,14 @@
-use ark_ff::BigInteger;
-use ark_ff::PrimeField;
+#[cfg(feature = "prover")]
+use ark_ff::{BigInteger, PrimeField};
+#[cfg(feature = "prover")]
 use parcnet_pod::pod::{Fq, PodValue};
 use plonky2::{
-    field::goldilocks_field::GoldilocksField,
+    field::{goldilocks_field::GoldilocksField, types::PrimeField64},
     hash::poseidon::PoseidonHash,
     plonk::config::{GenericHashOut, Hasher},
 };
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 // EntryValue trait, and ScalarOrVec type which implements it.
 // This is a field element or array of field elements.
@@ -47,6 +49,7 @@ impl From<i64> for ScalarOrVec {
     }
 }
 
+#[cfg(feature = "prover")]
 impl From<Fq> for ScalarOrVec {
     fn from(x: Fq) -> Self {
         let x_bytes = &x.into_bigint().to_bytes_le();
@@ -61,6 +64,7 @@ impl From<Fq> for ScalarOrVec {
     }
 }
 
+#[cfg(feature = "prover")]
 impl From<PodValue> for ScalarOrVec {
     fn from(pod_value: PodValue) -> Self {
         match pod_value {
@@ -99,3 +103,28 @@ impl HashableEntryValue for ScalarOrVec {
         }
     }
 }
+
+/// Number of elements of a vector shown before it gets truncated.
+const DISPLAYED_VECTOR_ELEMENTS: usize = 4;
+
+impl fmt::Display for ScalarOrVec {
+    /// Values in decimal, e.g. `30` or `[1, 2, 3, 4, … (10 elements)]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scalar(s) => write!(f, "{}", s.to_canonical_u64()),
+            Self::Vector(v) => {
+                let shown = v
+                    .iter()
+                    .take(DISPLAYED_VECTOR_ELEMENTS)
+                    .map(|x| x.to_canonical_u64().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if v.len() > DISPLAYED_VECTOR_ELEMENTS {
+                    write!(f, "[{}, … ({} elements)]", shown, v.len())
+                } else {
+                    write!(f, "[{}]", shown)
+                }
+            }
+        }
+    }
+}
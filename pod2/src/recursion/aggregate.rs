@@ -0,0 +1,248 @@
+//! Aggregation of many InnerCircuit inputs (eg. schnorr signatures) into a single recursive proof,
+//! choosing the shape of the RecursionTree from the number of inputs.
+use anyhow::{anyhow, Result};
+use plonky2::field::types::Field;
+use plonky2::plonk::circuit_builder::CircuitBuilder;
+use plonky2::plonk::circuit_data::{CircuitConfig, VerifierCircuitData};
+use plonky2::plonk::proof::ProofWithPublicInputs;
+use plonky2::recursion::cyclic_recursion::check_cyclic_proof_verifier_data;
+use std::array;
+
+use super::{
+    InnerCircuitTrait, IntroducerCircuitTrait, OpsExecutorTrait, RecursionCircuit, RecursionTree,
+};
+use crate::{PlonkyProof, C, D, F};
+
+/// Root proof returned by `aggregate`, together with what is needed to verify it.
+pub struct Aggregate {
+    /// proof of the root node. Its public inputs are the ones of the root node's InnerCircuits
+    /// and OpsExecutor, followed by the verifier data of the recursive circuit (see
+    /// `RecursionCircuit::prepare_public_inputs`).
+    pub proof: ProofWithPublicInputs<F, C, D>,
+    pub verifier_data: VerifierCircuitData<F, C, D>,
+    /// number of aggregated leaves
+    pub num_leaves: usize,
+    /// number of levels of the tree, 1 when a single node verifies all the leaves
+    pub levels: usize,
+}
+
+/// Generates a single recursive proof attesting that all the given `leaves` verify in the
+/// InnerCircuit `I`.
+///
+/// The nodes at the base level verify M leaves each, and the nodes of each level above verify N
+/// proofs of the level below, until a single proof is left. With N=1 a node can not merge
+/// proofs, so the tree becomes a chain where each node verifies the next M leaves and the proof
+/// of the previous node. Missing leaves and proofs are disabled through their selectors, taking
+/// a copy of the first leaf and a dummy proof as placeholders. The L POD1-Introducer proofs are
+/// disabled too, using dummy proofs of `P`.
+///
+/// The same circuit_data, prover and circuit targets are reused across all the nodes.
+pub fn aggregate<
+    I,
+    O,
+    P,
+    const L: usize,
+    const M: usize,
+    const N: usize,
+    const NS: usize,
+    const VL: usize,
+>(
+    leaves: Vec<I::Input>,
+) -> Result<Aggregate>
+where
+    I: InnerCircuitTrait,
+    I::Input: Clone,
+    O: OpsExecutorTrait,
+    O::Input: Default,
+    O::Output: Default,
+    P: IntroducerCircuitTrait,
+    [(); L + M + N]:,
+    [(); L + N]:,
+{
+    let num_leaves = leaves.len();
+    let padding = leaves
+        .first()
+        .cloned()
+        .ok_or(anyhow!("No leaves to aggregate"))?;
+    if M == 0 {
+        return Err(anyhow!("Can not aggregate leaves with M=0 InnerCircuits"));
+    }
+    if N == 0 && num_leaves > M {
+        return Err(anyhow!(
+            "Can not aggregate {} leaves with N=0 recursive proofs, at most M={}",
+            num_leaves,
+            M
+        ));
+    }
+
+    // POD1 introducer proofs are not used, fill them with dummy proofs
+    let pod1_circuit_data = P::circuit_data()?;
+    let pod1_verifier_data = pod1_circuit_data.verifier_data();
+    let pod1_dummy_proof: PlonkyProof = P::dummy_proof(pod1_circuit_data)?;
+    let pod1_proofs: [PlonkyProof; L] = array::from_fn(|_| pod1_dummy_proof.clone());
+    let pod1_public_inputs: [Vec<F>; L] = array::from_fn(|_| vec![]);
+
+    // build the circuit_data, prover and targets once for all the nodes
+    let circuit_data =
+        RecursionCircuit::<I, O, L, M, N, NS, VL>::circuit_data(pod1_verifier_data.clone())?;
+    let verifier_data = circuit_data.verifier_data();
+    let prover = RecursionCircuit::<I, O, L, M, N, NS, VL>::build_prover(
+        pod1_verifier_data.clone(),
+        verifier_data.clone(),
+    )?;
+    let dummy_proof = RecursionCircuit::<I, O, L, M, N, NS, VL>::dummy_proof(circuit_data);
+    let mut builder = CircuitBuilder::new(CircuitConfig::standard_recursion_config());
+    let mut circuit = RecursionCircuit::<I, O, L, M, N, NS, VL>::add_targets(
+        &mut builder,
+        pod1_verifier_data,
+        verifier_data.clone(),
+    )?;
+
+    let mut leaves = leaves.into_iter().peekable();
+    let mut proofs: Vec<ProofWithPublicInputs<F, C, D>> = vec![];
+    let mut levels = 0;
+    while leaves.peek().is_some() || proofs.len() > 1 {
+        // with N=1 each level takes the next M leaves, otherwise all of them go to the base level
+        let level_leaves: Vec<I::Input> = if N == 1 {
+            leaves.by_ref().take(M).collect()
+        } else {
+            leaves.by_ref().collect()
+        };
+        let num_nodes = level_leaves
+            .len()
+            .div_ceil(M)
+            .max(proofs.len().div_ceil(N.max(1)));
+
+        let mut level_leaves = level_leaves.into_iter();
+        let mut children = std::mem::take(&mut proofs).into_iter();
+        for _ in 0..num_nodes {
+            let node_leaves: Vec<I::Input> = level_leaves.by_ref().take(M).collect();
+            let node_proofs: Vec<PlonkyProof> =
+                children.by_ref().take(N).map(|p| p.proof).collect();
+
+            // enable only the selectors of the actual leaves and proofs of this node
+            let mut selectors: [F; L + M + N] = [F::ZERO; L + M + N];
+            selectors[L..L + node_leaves.len()].fill(F::ONE);
+            selectors[L + M..L + M + node_proofs.len()].fill(F::ONE);
+
+            let inner_circuits_input: [I::Input; M] =
+                array::from_fn(|k| node_leaves.get(k).unwrap_or(&padding).clone());
+            let recursive_proofs: [PlonkyProof; N] =
+                array::from_fn(|k| node_proofs.get(k).unwrap_or(&dummy_proof).clone());
+
+            proofs.push(
+                RecursionTree::<I, O, L, M, N, NS, VL>::prove_node_with_public_inputs(
+                    &prover,
+                    &mut circuit,
+                    selectors,
+                    O::Input::default(),
+                    O::Output::default(),
+                    &pod1_public_inputs,
+                    &pod1_proofs,
+                    inner_circuits_input,
+                    &recursive_proofs,
+                )?,
+            );
+        }
+        levels += 1;
+    }
+
+    let proof = proofs
+        .pop()
+        .ok_or(anyhow!("Aggregation did not produce a root proof"))?;
+    Ok(Aggregate {
+        proof,
+        verifier_data,
+        num_leaves,
+        levels,
+    })
+}
+
+/// Verifies the root proof of `aggregate`, checking also that the verifier data contained in its
+/// public inputs is the given one.
+pub fn verify_aggregate(
+    proof: &ProofWithPublicInputs<F, C, D>,
+    verifier_data: &VerifierCircuitData<F, C, D>,
+) -> Result<()> {
+    check_cyclic_proof_verifier_data(proof, &verifier_data.verifier_only, &verifier_data.common)?;
+    verifier_data.verify(proof.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use plonky2::field::types::Sample;
+    use rand;
+
+    use super::*;
+    use crate::recursion::traits_examples::{
+        ExampleGadget, ExampleGadgetInput, ExampleIntroducer, ExampleOpsExecutor,
+    };
+    use crate::signature::schnorr::*;
+
+    /// generates `n` signatures over random messages of length 4
+    fn example_leaves(n: usize) -> Vec<ExampleGadgetInput> {
+        let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
+        let schnorr = SchnorrSigner::new();
+        (0..n)
+            .map(|i| {
+                let msg: Vec<F> = std::iter::repeat_with(|| F::sample(&mut rng))
+                    .take(4)
+                    .collect();
+                let sk = SchnorrSecretKey::from_u64(i as u64);
+                ExampleGadgetInput {
+                    pk: schnorr.keygen(&sk),
+                    sig: schnorr.sign(&msg, &sk, &mut rng),
+                    msg,
+                }
+            })
+            .collect()
+    }
+
+    fn aggregate_example<const L: usize, const M: usize, const N: usize>(
+        leaves: Vec<ExampleGadgetInput>,
+    ) -> Result<Aggregate>
+    where
+        [(); L + M + N]:,
+        [(); L + N]:,
+    {
+        aggregate::<ExampleGadget, ExampleOpsExecutor<1, 0>, ExampleIntroducer, L, M, N, 2, 0>(
+            leaves,
+        )
+    }
+
+    #[test]
+    fn test_aggregate() -> Result<()> {
+        // 1 leaf: a single node
+        let agg = aggregate_example::<1, 2, 2>(example_leaves(1))?;
+        assert_eq!((agg.num_leaves, agg.levels), (1, 1));
+        verify_aggregate(&agg.proof, &agg.verifier_data)?;
+
+        // M leaves: a single full node
+        let agg = aggregate_example::<1, 2, 2>(example_leaves(2))?;
+        assert_eq!((agg.num_leaves, agg.levels), (2, 1));
+        verify_aggregate(&agg.proof, &agg.verifier_data)?;
+
+        // M+1 leaves: two base nodes, the second one padded, and a root merging them
+        let agg = aggregate_example::<1, 2, 2>(example_leaves(3))?;
+        assert_eq!((agg.num_leaves, agg.levels), (3, 2));
+        verify_aggregate(&agg.proof, &agg.verifier_data)?;
+
+        // the proof doesn't verify against another circuit's verifier data
+        let other = aggregate_example::<1, 2, 1>(example_leaves(1))?;
+        assert!(verify_aggregate(&agg.proof, &other.verifier_data).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_invalid_leaf() -> Result<()> {
+        assert!(aggregate_example::<1, 2, 2>(vec![]).is_err());
+
+        let mut leaves = example_leaves(3);
+        leaves[2].msg[0] += F::ONE;
+        assert!(aggregate_example::<1, 2, 2>(leaves).is_err());
+
+        Ok(())
+    }
+}
@@ -111,6 +111,32 @@ where
         inner_circuits_input: [I::Input; M],
         recursive_proofs: &[PlonkyProof; N],
     ) -> Result<PlonkyProof> {
+        Ok(Self::prove_node_with_public_inputs(
+            prover,
+            circuit,
+            selectors,
+            ops_executor_input,
+            ops_executor_output,
+            pod1_public_inputs,
+            pod1_proofs,
+            inner_circuits_input,
+            recursive_proofs,
+        )?
+        .proof)
+    }
+
+    /// same as `prove_node`, but keeping the public inputs of the new proof
+    pub fn prove_node_with_public_inputs(
+        prover: &ProverCircuitData<F, C, D>,
+        circuit: &mut RecursionCircuit<I, O, L, M, N, NS, VL>,
+        selectors: [F; L + M + N],
+        ops_executor_input: O::Input,
+        ops_executor_output: O::Output,
+        pod1_public_inputs: &[Vec<F>; L],
+        pod1_proofs: &[PlonkyProof; L],
+        inner_circuits_input: [I::Input; M],
+        recursive_proofs: &[PlonkyProof; N],
+    ) -> Result<ProofWithPublicInputs<F, C, D>> {
         println!("prove_node with L={}, M={}, N={}:", L, M, N);
         for i in 0..L + M + N {
             let what = if i < L {
@@ -155,7 +181,7 @@ where
         let new_proof = prover.prove(pw)?;
         println!("generate new_proof: {:?}", start.elapsed());
 
-        Ok(new_proof.proof)
+        Ok(new_proof)
     }
 }
 
@@ -540,17 +566,13 @@ where
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
-    use plonky2::field::types::{Field, Sample};
-    use plonky2::plonk::proof::ProofWithPublicInputs;
-    use plonky2::recursion::dummy_circuit::dummy_proof;
+    use plonky2::field::types::Sample;
     use rand;
-    use std::array;
     use std::time::Instant;
 
     use super::*;
 
-    use crate::pod::gadget::SchnorrPODGadget;
-    use crate::recursion::traits::IntroducerCircuitTrait;
+    use crate::recursion::aggregate::{aggregate, verify_aggregate};
     use crate::recursion::traits_examples::{
         ExampleGadget, ExampleGadgetInput, ExampleIntroducer, ExampleOpsExecutor,
     };
@@ -592,16 +614,17 @@ mod tests {
             L, M, N
         );
 
-        let l: u32 = 2; // levels of the recursion (binary) tree
+        // 2*M leaves, so that the tree has 2 levels for any N
+        let num_leaves = 2 * M;
         println!(
-            "Testing {} recursive iterations, where each iteration checks:\n    L={} POD1-Introducer plonky2 proofs\n    M={} InnerCircuits\n    N={} cyclic plonky2 proofs",
-            l, L, M, N
+            "Aggregating {} InnerCircuits, where each recursive node checks:\n    L={} POD1-Introducer plonky2 proofs\n    M={} InnerCircuits\n    N={} cyclic plonky2 proofs",
+            num_leaves, L, M, N
         );
 
         let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
         let schnorr = SchnorrSigner::new();
-        // generate M random messages, each of length 4
-        let msg_vec: Vec<Vec<F>> = (0..M)
+        // generate the random messages, each of length 4
+        let msg_vec: Vec<Vec<F>> = (0..num_leaves)
             .map(|_| {
                 std::iter::repeat_with(|| F::sample(&mut rng))
                     .take(4)
@@ -609,131 +632,45 @@ mod tests {
             })
             .collect();
 
-        // generate M*N key pairs (M for each of the N recursive nodes at the base level)
-        let sk_vec: Vec<SchnorrSecretKey> =
-            (0..M).map(|i| SchnorrSecretKey { sk: i as u64 }).collect();
+        // generate a key pair for each message
+        let sk_vec: Vec<SchnorrSecretKey> = (0..num_leaves)
+            .map(|i| SchnorrSecretKey::from_u64(i as u64))
+            .collect();
         let pk_vec: Vec<SchnorrPublicKey> = sk_vec.iter().map(|&sk| schnorr.keygen(&sk)).collect();
 
         // sign the messages
-        let sig_vec: Vec<SchnorrSignature> = sk_vec
+        let leaves: Vec<ExampleGadgetInput> = sk_vec
             .iter()
-            .zip(msg_vec.clone())
-            .map(|(&sk, msg)| schnorr.sign(&msg.to_vec(), &sk, &mut rng))
+            .zip(pk_vec)
+            .zip(msg_vec)
+            .map(|((&sk, pk), msg)| ExampleGadgetInput {
+                pk,
+                sig: schnorr.sign(&msg.to_vec(), &sk, &mut rng),
+                msg,
+            })
             .collect();
-        assert_eq!(sig_vec.len(), M);
-
-        // POD1 introducer logic:
-        let pod1_circuit_data = ExampleIntroducer::circuit_data()?;
-        let pod1_verifier_data = pod1_circuit_data.verifier_data();
-        let pod1_dummy_proof: PlonkyProof = ExampleIntroducer::dummy_proof(pod1_circuit_data)?;
-        let pod1_proofs: [PlonkyProof; L] = array::from_fn(|_| pod1_dummy_proof.clone());
-        let pod1_public_inputs: [Vec<F>; L] = array::from_fn(|_| vec![]);
-
-        type RC<const L: usize, const M: usize, const N: usize, const NS: usize, const VL: usize> =
-            RecursionCircuit<ExampleGadget, ExampleOpsExecutor<1, VL>, L, M, N, NS, VL>;
-        type RT<const L: usize, const M: usize, const N: usize, const NS: usize, const VL: usize> =
-            RecursionTree<ExampleGadget, ExampleOpsExecutor<1, VL>, L, M, N, NS, VL>;
-
-        // build the circuit_data & verifier_data for the recursive circuit
-        let circuit_data = RC::<L, M, N, NS, VL>::circuit_data(pod1_verifier_data.clone())?;
-        let verifier_data = circuit_data.verifier_data();
-        let prover =
-            RC::<L, M, N, NS, VL>::build_prover(pod1_verifier_data.clone(), verifier_data.clone())?;
-
-        let dummy_proof = RC::<L, M, N, NS, VL>::dummy_proof(circuit_data);
 
-        // we start with k dummy proofs, since at the leafs level we don't have proofs yet and we
-        // just verify the signatures. At each level we divide the amount of proofs by N. At the
-        // root level there is a single proof.
-        let mut proofs_at_level_i: Vec<PlonkyProof> =
-            (0..(N * N)).map(|_| dummy_proof.clone()).collect();
-
-        let config = CircuitConfig::standard_recursion_config();
-        let mut builder = CircuitBuilder::new(config);
         let start = Instant::now();
-        let mut circuit = RC::<L, M, N, NS, VL>::add_targets(
-            &mut builder,
-            pod1_verifier_data.clone(),
-            verifier_data.clone(),
-        )?;
-        println!("RecursionCircuit::add_targets(): {:?}", start.elapsed());
-
-        // loop over the recursion levels
-        for i in 0..l {
-            println!("\n--- recursion level i={}", i);
-            let mut next_level_proofs: Vec<PlonkyProof> = vec![];
-
-            // loop over the nodes of each recursion tree level
-            for j in (0..proofs_at_level_i.len()).step_by(N) {
-                println!(
-                    "\n------ recursion node: (level) i={}, (node in level) j={}",
-                    i, j
-                );
-
-                // prepare the inputs for the `RecursionTree::prove_node` call
-                let mut selectors: [F; L + M + N] = [F::ONE; L + M + N];
-                if i == 0 {
-                    // if we're at the base level, set to 0 (=disable) the L & N selectors of the
-                    // proofs verifications
-                    selectors[0..L].fill(F::ZERO); // pod1 proof verifications
-                    selectors[L + M..L + M + N].fill(F::ZERO); // recursive proof verifications
-                }
-                let innercircuits_input: [ExampleGadgetInput; M] =
-                    array::from_fn(|k| ExampleGadgetInput {
-                        pk: pk_vec[k],
-                        sig: sig_vec[k],
-                        msg: msg_vec[k].clone(),
-                    });
-                // let ops_executor_input = array::from_fn(|_| ());
-                let ops_executor_input = ();
-                let ops_executor_output = ();
-
-                // let pod1_proofs: [PlonkyProof; M] = array::from_fn(|k| pod1_proofs[j + k].clone());
-                let proofs: [PlonkyProof; N] = array::from_fn(|k| proofs_at_level_i[j + k].clone());
-
-                // do the recursive step
-                let start = Instant::now();
-                let new_proof = RT::<L, M, N, NS, VL>::prove_node(
-                    &prover,
-                    &mut circuit,
-                    selectors,
-                    ops_executor_input,
-                    ops_executor_output,
-                    &pod1_public_inputs,
-                    &pod1_proofs,
-                    innercircuits_input,
-                    &proofs,
-                )?;
-                println!(
-                    "RecursionTree::prove_node (level: i={}, node: j={}) took: {:?}",
-                    i,
-                    j,
-                    start.elapsed()
-                );
-
-                // verify the recursive proof
-                let public_inputs =
-                    RT::<L, M, N, NS, VL>::prepare_public_inputs(vec![], verifier_data.clone());
-                verifier_data.clone().verify(ProofWithPublicInputs {
-                    proof: new_proof.clone(),
-                    public_inputs: public_inputs.clone(),
-                })?;
+        let agg = aggregate::<
+            ExampleGadget,
+            ExampleOpsExecutor<1, VL>,
+            ExampleIntroducer,
+            L,
+            M,
+            N,
+            NS,
+            VL,
+        >(leaves)?;
+        println!(
+            "aggregate ({} leaves, {} levels) took: {:?}",
+            agg.num_leaves,
+            agg.levels,
+            start.elapsed()
+        );
+        assert_eq!(agg.levels, 2);
 
-                // set new_proof for next iteration
-                next_level_proofs.push(new_proof);
-            }
-            proofs_at_level_i = next_level_proofs.clone();
-        }
-        assert_eq!(proofs_at_level_i.len(), 1);
-        let last_proof = proofs_at_level_i[0].clone();
-
-        // verify the last proof
-        let public_inputs =
-            RT::<L, M, N, NS, VL>::prepare_public_inputs(vec![], verifier_data.clone());
-        verifier_data.clone().verify(ProofWithPublicInputs {
-            proof: last_proof.clone(),
-            public_inputs: public_inputs.clone(),
-        })?;
+        // verify the root proof
+        verify_aggregate(&agg.proof, &agg.verifier_data)?;
 
         Ok(())
     }
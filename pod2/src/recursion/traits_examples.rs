@@ -30,7 +30,7 @@ impl IntroducerCircuitTrait for ExampleIntroducer {
         let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
         let schnorr = SchnorrSigner::new();
         let msg: Vec<F> = vec![F::ZERO, F::ZERO, F::ZERO, F::ZERO];
-        let sk: SchnorrSecretKey = SchnorrSecretKey { sk: 0u64 };
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(0u64);
         let pk: SchnorrPublicKey = schnorr.keygen(&sk);
         let sig: SchnorrSignature = schnorr.sign(&msg.to_vec(), &sk, &mut rng);
         Ok(Self::Input { pk, sig, msg })
@@ -76,6 +76,7 @@ impl IntroducerCircuitTrait for ExampleIntroducer {
     }
 }
 
+#[derive(Clone)]
 pub struct ExampleGadgetInput {
     pub pk: SchnorrPublicKey,
     pub sig: SchnorrSignature,
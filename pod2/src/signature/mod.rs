@@ -1,9 +1,16 @@
 /// code forked from https://github.com/tideofwords/schnorr
+#[cfg(feature = "prover")]
 pub mod biguint;
+#[cfg(feature = "prover")]
 pub mod eddsa;
+#[cfg(feature = "prover")]
 pub mod jubjubcurve;
+#[cfg(feature = "prover")]
 pub mod jubjubfield;
+#[cfg(feature = "prover")]
 pub mod mod65537;
 pub mod schnorr;
+#[cfg(feature = "prover")]
 pub mod schnorr_prover;
+#[cfg(feature = "prover")]
 pub mod serialization;
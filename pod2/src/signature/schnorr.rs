@@ -5,7 +5,10 @@ use plonky2::field::types::Field;
 use plonky2::field::types::PrimeField64;
 use plonky2::hash::poseidon::PoseidonHash;
 use plonky2::plonk::config::Hasher;
+#[cfg(feature = "prover")]
 use rand::Rng;
+#[cfg(feature = "prover")]
+use rayon::prelude::*;
 use serde::Deserialize;
 use serde::Serialize;
 
@@ -24,12 +27,27 @@ pub struct SchnorrSigner {
     prime_group_order: u64,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-
+/// A secret key is stored as a 256-bit little-endian integer, so that it can be read from and
+/// written to 32 bytes of key material. This does not widen the key space: only its residue
+/// modulo the group order 2^16+1 is used for signing (see [`SchnorrSecretKey::scalar`]), so
+/// there are only 65537 distinct keys and any of them can be recovered from its public key by
+/// brute force. Security is bounded by the group order, not by the stored width.
+///
+/// Equality is on that residue as well, so two keys that sign identically compare equal
+/// whatever their stored limbs.
+#[derive(Copy, Clone, Debug)]
 pub struct SchnorrSecretKey {
-    pub sk: u64,
+    pub sk: [u64; 4],
 }
 
+impl PartialEq for SchnorrSecretKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.scalar() == other.scalar()
+    }
+}
+
+impl Eq for SchnorrSecretKey {}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SchnorrPublicKey {
     pub pk: GoldilocksField,
@@ -41,14 +59,56 @@ pub struct SchnorrSignature {
     pub e: u64,
 }
 
+impl SchnorrSecretKey {
+    /// Compatibility constructor for the old numeric keys, which sign exactly as before.
+    pub fn from_u64(sk: u64) -> Self {
+        Self { sk: [sk, 0, 0, 0] }
+    }
+
+    /// Derives a key from 32 bytes of key material, read as a little-endian integer and
+    /// reduced into the scalar field. The result is one of only 2^16+1 keys whatever the seed,
+    /// see [`SchnorrSecretKey`].
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self::from_u64(Self::from_le_bytes(seed).scalar())
+    }
+
+    pub fn from_le_bytes(bytes: &[u8; 32]) -> Self {
+        Self {
+            sk: std::array::from_fn(|i| {
+                u64::from_le_bytes(std::array::from_fn(|j| bytes[8 * i + j]))
+            }),
+        }
+    }
+
+    pub fn to_le_bytes(&self) -> [u8; 32] {
+        std::array::from_fn(|i| self.sk[i / 8].to_le_bytes()[i % 8])
+    }
+
+    /// The key reduced modulo the group order.
+    pub fn scalar(&self) -> u64 {
+        let order = SchnorrSigner::PRIME_GROUP_ORDER as u128;
+        self.sk
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &limb| ((acc << 64) | limb as u128) % order) as u64
+    }
+}
+
+impl From<u64> for SchnorrSecretKey {
+    fn from(sk: u64) -> Self {
+        Self::from_u64(sk)
+    }
+}
+
 impl SchnorrSigner {
+    const PRIME_GROUP_ORDER: u64 = (1 << 16) + 1;
+
     pub fn new() -> Self {
         let quotient_order: u64 = (1 << 48) - (1 << 32);
         let prime_group_gen: GoldilocksField = Self::pow(BIG_GROUP_GEN, quotient_order);
-        let prime_group_order: u64 = (1 << 16) + 1;
         SchnorrSigner {
             prime_group_gen,
-            prime_group_order,
+            prime_group_order: Self::PRIME_GROUP_ORDER,
         }
     }
 
@@ -67,7 +127,7 @@ impl SchnorrSigner {
     }
 
     pub fn keygen(&self, sk: &SchnorrSecretKey) -> SchnorrPublicKey {
-        let pk: GoldilocksField = Self::pow(self.prime_group_gen, sk.sk).inverse();
+        let pk: GoldilocksField = Self::pow(self.prime_group_gen, sk.scalar()).inverse();
         // self.PRIME_GROUP_GEN is 6612579038192137166
         SchnorrPublicKey { pk }
     }
@@ -80,9 +140,9 @@ impl SchnorrSigner {
         h.elements[0].to_canonical_u64() % self.prime_group_order
     }
 
-    pub fn rand_group_multiplier(&self, rng: &mut rand::rngs::ThreadRng) -> u64 {
-        let group_order: u64 = (1 << 16) + 1;
-        rng.gen_range(0..group_order)
+    #[cfg(feature = "prover")]
+    pub fn rand_group_multiplier(&self, rng: &mut impl Rng) -> u64 {
+        rng.gen_range(0..self.prime_group_order)
     }
 
     pub fn u64_into_goldilocks_vec(&self, msg: Vec<u64>) -> Vec<GoldilocksField> {
@@ -91,20 +151,30 @@ impl SchnorrSigner {
             .collect()
     }
 
+    #[cfg(feature = "prover")]
     pub fn sign(
         &self,
         msg: &[GoldilocksField],
         sk: &SchnorrSecretKey,
-        rng: &mut rand::rngs::ThreadRng,
+        rng: &mut impl Rng,
+    ) -> SchnorrSignature {
+        self.sign_with_nonce(msg, sk, self.rand_group_multiplier(rng))
+    }
+
+    /// Same as `sign`, with the nonce `k` given instead of drawn from an rng. Reusing `k` for
+    /// two messages leaks the secret key, this is meant for fixtures and tests.
+    pub fn sign_with_nonce(
+        &self,
+        msg: &[GoldilocksField],
+        sk: &SchnorrSecretKey,
+        k: u64,
     ) -> SchnorrSignature {
-        let k: u64 = self.rand_group_multiplier(rng);
         let r: GoldilocksField = Self::pow(self.prime_group_gen, k);
         let e: u64 = self.hash_insecure(&r, msg);
         info!("[SIGN] msg is: {:?}", msg);
         assert!(k < self.prime_group_order);
-        assert!(sk.sk < self.prime_group_order);
         assert!(e < self.prime_group_order);
-        let mut s128: u128 = (k as u128) + (sk.sk as u128) * (e as u128);
+        let mut s128: u128 = (k as u128) + (sk.scalar() as u128) * (e as u128);
         s128 %= self.prime_group_order as u128;
         let s: u64 = s128 as u64;
         SchnorrSignature { e, s }
@@ -121,6 +191,23 @@ impl SchnorrSigner {
         info!("[VERIFY] msg is: {:?}", msg);
         e_v == sig.e
     }
+
+    /// Verifies many (signature, message, public key) triples, returning whether each one is
+    /// valid. Since a signature is (s, e) with e = H(r, msg), r is only known after verifying,
+    /// so the verification equations can't be merged into a random linear combination; the
+    /// signatures are checked independently, in parallel.
+    pub fn verify_batch(
+        &self,
+        batch: &[(SchnorrSignature, Vec<GoldilocksField>, SchnorrPublicKey)],
+    ) -> Vec<bool> {
+        #[cfg(feature = "prover")]
+        let batch = batch.par_iter();
+        #[cfg(not(feature = "prover"))]
+        let batch = batch.iter();
+        batch
+            .map(|(sig, msg, pk)| self.verify(sig, msg, pk))
+            .collect()
+    }
 }
 
 impl Default for SchnorrSigner {
@@ -129,9 +216,10 @@ impl Default for SchnorrSigner {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "prover"))]
 mod tests {
     use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
 
     use super::{SchnorrPublicKey, SchnorrSecretKey, SchnorrSignature, SchnorrSigner};
 
@@ -148,7 +236,7 @@ mod tests {
     fn test_sig() {
         let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
         let ss = SchnorrSigner::new();
-        let sk: SchnorrSecretKey = SchnorrSecretKey { sk: 1422 };
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(1422);
         let pk: SchnorrPublicKey = ss.keygen(&sk);
 
         let msg0_u64: Vec<u64> = vec![17, 123985, 3, 12];
@@ -162,12 +250,81 @@ mod tests {
         assert!(res);
     }
 
+    #[test]
+    fn test_seed_keys() {
+        let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
+        let ss = SchnorrSigner::new();
+        let sk = SchnorrSecretKey::from_seed(&[0xab; 32]);
+        assert!(sk.sk[0] < ss.prime_group_order && sk.sk[1..] == [0, 0, 0]);
+        let pk: SchnorrPublicKey = ss.keygen(&sk);
+
+        let msg: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![17, 123985, 3, 12]);
+        let sig: SchnorrSignature = ss.sign(&msg, &sk, &mut rng);
+        assert!(ss.verify(&sig, &msg, &pk));
+        assert!(!ss.verify(
+            &sig,
+            &msg,
+            &ss.keygen(&SchnorrSecretKey::from_seed(&[1; 32]))
+        ));
+
+        // 2^192 = (2^16)^12 = 1 mod 2^16 + 1
+        let mut seed = [0u8; 32];
+        seed[24] = 1;
+        assert_eq!(
+            SchnorrSecretKey::from_seed(&seed),
+            SchnorrSecretKey::from_u64(1)
+        );
+        let sk = SchnorrSecretKey::from_le_bytes(&[0xab; 32]);
+        assert_eq!(SchnorrSecretKey::from_le_bytes(&sk.to_le_bytes()).sk, sk.sk);
+        assert_eq!(ss.keygen(&sk), pk);
+        // Unreduced keys equal their reduced form
+        assert_eq!(sk, SchnorrSecretKey::from_seed(&[0xab; 32]));
+        assert_eq!(
+            SchnorrSecretKey::from_u64(65538),
+            SchnorrSecretKey::from_u64(1)
+        );
+        assert_ne!(SchnorrSecretKey::from_u64(2), SchnorrSecretKey::from_u64(1));
+    }
+
+    #[test]
+    fn test_small_key_compat() {
+        let ss = SchnorrSigner::new();
+        assert_eq!(ss.prime_group_gen, GoldilocksField(6612579038192137166));
+        for sk in [0, 1, 25, 1422, 65536, 65537, 1 << 40, u64::MAX - 7] {
+            // Public keys as computed before keys were widened
+            let old_pk = SchnorrSigner::pow(ss.prime_group_gen, sk).inverse();
+            assert_eq!(ss.keygen(&SchnorrSecretKey::from_u64(sk)).pk, old_pk);
+        }
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
+        let ss = SchnorrSigner::new();
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(1422);
+        let pk: SchnorrPublicKey = ss.keygen(&sk);
+        let other_pk: SchnorrPublicKey = ss.keygen(&SchnorrSecretKey::from_u64(25));
+
+        let msg0: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![17, 123985, 3, 12]);
+        let msg1: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![1, 2, 3]);
+        let sig0: SchnorrSignature = ss.sign(&msg0, &sk, &mut rng);
+        let sig1: SchnorrSignature = ss.sign(&msg1, &sk, &mut rng);
+
+        let res = ss.verify_batch(&[
+            (sig0, msg0.clone(), pk),
+            (sig1, msg1.clone(), pk),
+            (sig0, msg1, pk),
+            (sig0, msg0, other_pk),
+        ]);
+        assert_eq!(res, vec![true, true, false, false]);
+    }
+
     #[test]
     fn test_sig_2() {
         info!("=================TEST SIG 2=================");
         let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
         let ss = SchnorrSigner::new();
-        let sk: SchnorrSecretKey = SchnorrSecretKey { sk: 25 };
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(25);
         let pk: SchnorrPublicKey = ss.keygen(&sk);
 
         let msg0_u64: Vec<u64> = vec![
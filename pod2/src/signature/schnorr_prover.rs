@@ -43,6 +43,8 @@ impl MessageTarget {
     }
 }
 
+// The secret key never enters the circuit: however wide the key, s and e are reduced modulo
+// the group order 2^16 + 1, so they fit in the exponent bits used below.
 pub struct SchnorrSignatureTarget {
     pub s: Target,
     pub e: Target,
@@ -183,7 +185,7 @@ mod tests {
         let sb: SchnorrBuilder = SchnorrBuilder;
 
         // create keypair, message, signature
-        let sk: SchnorrSecretKey = SchnorrSecretKey { sk: 133 };
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(133);
         let ss = SchnorrSigner::new();
         let pk: SchnorrPublicKey = ss.keygen(&sk);
         let msg: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![1500, 1600, 0, 0, 0]);
@@ -206,6 +208,42 @@ mod tests {
         let _proof = data.prove(pw).unwrap();
     }
 
+    #[test]
+    fn test_schnorr_seed_key() {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+
+        let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
+
+        let config = CircuitConfig::standard_recursion_config();
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+
+        let sb: SchnorrBuilder = SchnorrBuilder;
+
+        // create keypair from 32 bytes of key material, message, signature
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_seed(&[0x5c; 32]);
+        let ss = SchnorrSigner::new();
+        let pk: SchnorrPublicKey = ss.keygen(&sk);
+        let msg: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![1500, 1600, 0, 0, 0]);
+        let sig: SchnorrSignature = ss.sign(&msg, &sk, &mut rng);
+
+        let pk_targ = SchnorrPublicKeyTarget::new_virtual(&mut builder);
+        let sig_targ = SchnorrSignatureTarget::new_virtual(&mut builder);
+        let msg_targ = MessageTarget::new_with_size(&mut builder, msg.len());
+
+        sb.constrain_sig::<PoseidonGoldilocksConfig>(&mut builder, &sig_targ, &msg_targ, &pk_targ);
+
+        let mut pw: PartialWitness<F> = PartialWitness::new();
+        pk_targ.set_witness(&mut pw, &pk).unwrap();
+        sig_targ.set_witness(&mut pw, &sig).unwrap();
+        msg_targ.set_witness(&mut pw, &msg).unwrap();
+
+        let data = builder.build::<C>();
+        let proof = data.prove(pw).unwrap();
+        data.verify(proof).unwrap();
+    }
+
     #[test]
     fn test_schnorr_fails() {
         const D: usize = 2;
@@ -220,7 +258,7 @@ mod tests {
         let sb: SchnorrBuilder = SchnorrBuilder;
 
         // create keypair, message, signature
-        let sk: SchnorrSecretKey = SchnorrSecretKey { sk: 133 };
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(133);
         let ss = SchnorrSigner::new();
         let pk: SchnorrPublicKey = ss.keygen(&sk);
         let msg0: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![1500, 1600, 0, 0, 0]);
@@ -265,7 +303,7 @@ mod tests {
         let sb: SchnorrBuilder = SchnorrBuilder;
 
         // create keypair, message, signature
-        let sk: SchnorrSecretKey = SchnorrSecretKey { sk: 133 };
+        let sk: SchnorrSecretKey = SchnorrSecretKey::from_u64(133);
         let ss = SchnorrSigner::new();
         let pk: SchnorrPublicKey = ss.keygen(&sk);
         let msg0: Vec<GoldilocksField> = ss.u64_into_goldilocks_vec(vec![1500, 1600, 0, 0, 0]);